@@ -0,0 +1,223 @@
+use {AmcAtNloLine, Attributes, Clustering, ClusteringStep, CommentLines, Extensions, MgRwt};
+use {ParticleScale, PdfLine, PdfRwt, PdfRwtEntry};
+use {RScale, Scale, Scales, Weight, Weights, HEPEUP, HEPRUP};
+
+/// Tolerance for comparing floating-point numbers
+///
+/// Two numbers `a` and `b` are considered equal if
+/// `|a - b| <= max(absolute, relative * max(|a|, |b|))`. Two NaN values
+/// are considered equal as well.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # let (old, new): (lhef::HEPEUP, lhef::HEPEUP) = unimplemented!();
+/// let tolerance = lhef::Tolerance::new(1e-12, 1e-6);
+/// assert!(old.approx_eq(&new, &tolerance));
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct Tolerance {
+    /// Absolute tolerance
+    pub absolute: f64,
+    /// Tolerance relative to the larger of the compared numbers
+    pub relative: f64,
+}
+
+impl Tolerance {
+    /// Create a tolerance from an absolute and a relative part
+    pub fn new(absolute: f64, relative: f64) -> Tolerance {
+        Tolerance { absolute, relative }
+    }
+
+    /// Check whether two numbers are equal within the tolerance
+    pub fn close(&self, a: f64, b: f64) -> bool {
+        if a == b || (a.is_nan() && b.is_nan()) {
+            return true;
+        }
+        let allowed = self.absolute.max(self.relative * a.abs().max(b.abs()));
+        (a - b).abs() <= allowed
+    }
+}
+
+/// Comparison of floating-point numbers within a tolerance, and of
+/// text up to whitespace
+pub(crate) trait ApproxEq {
+    fn approx_eq(&self, other: &Self, tolerance: &Tolerance) -> bool;
+}
+
+impl ApproxEq for f64 {
+    fn approx_eq(&self, other: &f64, tolerance: &Tolerance) -> bool {
+        tolerance.close(*self, *other)
+    }
+}
+
+impl ApproxEq for String {
+    fn approx_eq(&self, other: &String, _: &Tolerance) -> bool {
+        self.split_whitespace().eq(other.split_whitespace())
+    }
+}
+
+impl<T: ApproxEq> ApproxEq for [T] {
+    fn approx_eq(&self, other: &[T], tolerance: &Tolerance) -> bool {
+        self.len() == other.len()
+            && self.iter().zip(other).all(|(a, b)| a.approx_eq(b, tolerance))
+    }
+}
+
+impl<T: ApproxEq> ApproxEq for Vec<T> {
+    fn approx_eq(&self, other: &Vec<T>, tolerance: &Tolerance) -> bool {
+        self[..].approx_eq(&other[..], tolerance)
+    }
+}
+
+impl<T: ApproxEq, const N: usize> ApproxEq for [T; N] {
+    fn approx_eq(&self, other: &[T; N], tolerance: &Tolerance) -> bool {
+        self[..].approx_eq(&other[..], tolerance)
+    }
+}
+
+impl<T: ApproxEq> ApproxEq for Option<T> {
+    fn approx_eq(&self, other: &Option<T>, tolerance: &Tolerance) -> bool {
+        match (self, other) {
+            (Some(a), Some(b)) => a.approx_eq(b, tolerance),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Types without floating-point numbers are compared exactly
+macro_rules! approx_eq_exact {
+    ($($type:ty),*) => {
+        $(
+            impl ApproxEq for $type {
+                fn approx_eq(&self, other: &$type, _: &Tolerance) -> bool {
+                    self == other
+                }
+            }
+        )*
+    };
+}
+
+approx_eq_exact!(i32, u32, usize, Attributes, Extensions);
+
+/// Structs are compared field by field
+macro_rules! approx_eq_fields {
+    ($type:ty; $($field:ident),*) => {
+        impl ApproxEq for $type {
+            fn approx_eq(&self, other: &$type, tolerance: &Tolerance) -> bool {
+                $(self.$field.approx_eq(&other.$field, tolerance))&&*
+            }
+        }
+    };
+}
+
+approx_eq_fields!(Scales; muf, mur, mups, particle_scales, scales, attr);
+approx_eq_fields!(ParticleScale; name, particle, value);
+approx_eq_fields!(Scale; stype, pos, etype, value);
+approx_eq_fields!(Clustering; steps);
+approx_eq_fields!(ClusteringStep; p1, p2, p0, scale, alphas);
+approx_eq_fields!(MgRwt; rscale, asrwt, pdfrwt, totfact);
+approx_eq_fields!(RScale; n_alphas, scale);
+approx_eq_fields!(PdfRwt; beam, entries);
+approx_eq_fields!(PdfRwtEntry; id, x, scale);
+approx_eq_fields!(CommentLines; pdf, scales, amcatnlo);
+approx_eq_fields!(PdfLine; id, x, scale, xpdf);
+approx_eq_fields!(AmcAtNloLine; event_type, fks, fks_mother, partner, shower_scales, extra);
+approx_eq_fields!(Weights; weights);
+approx_eq_fields!(Weight; id, value);
+approx_eq_fields!(
+    HEPRUP;
+    IDBMUP, EBMUP, PDFGUP, PDFSUP, IDWTUP, NPRUP, XSECUP, XERRUP, XMAXUP, LPRUP, info, extensions
+);
+approx_eq_fields!(
+    HEPEUP;
+    NUP, IDRUP, XWGTUP, SCALUP, AQEDUP, AQCDUP, IDUP, ISTUP, MOTHUP, ICOLUP, PUP, VTIMUP, SPINUP,
+    info, attr, scales, clustering, mgrwt, weights, comment_lines, extensions
+);
+
+impl HEPRUP {
+    /// Compare with other run information, allowing for small
+    /// differences in floating-point numbers
+    ///
+    /// Whitespace differences in `info` are ignored.
+    pub fn approx_eq(&self, other: &HEPRUP, tolerance: &Tolerance) -> bool {
+        ApproxEq::approx_eq(self, other, tolerance)
+    }
+}
+
+impl HEPEUP {
+    /// Compare with another event, allowing for small differences in
+    /// floating-point numbers
+    ///
+    /// Whitespace differences in `info` and the encoding of the
+    /// additional weights are ignored.
+    pub fn approx_eq(&self, other: &HEPEUP, tolerance: &Tolerance) -> bool {
+        ApproxEq::approx_eq(self, other, tolerance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Reader;
+
+    const EVENTS: &str = r#"<LesHouchesEvents version="3.0">
+<init>
+2212 2212 6500 6500 0 0 0 0 3 1
+1 0 1 1
+</init>
+<event>
+1 1 2 10 0 0.1
+21 -1 0 0 0 0 0 0 1e2 1e2 0 0 9
+<scales muf="10"/>
+<weights>1 2</weights>
+# some  info
+</event>
+</LesHouchesEvents>
+"#;
+
+    const REFORMATTED: &str = r#"<LesHouchesEvents version="3.0">
+<init>
+2212 2212 6.5e3 6.50000001e3 0 0 0 0 3 1
+1.0 0.0 1.0 1
+</init>
+<event>
+1 1 2.0000000001 10 0 1.0e-1
+21 -1 0 0 0 0 0.0 0.0 100.0 100.0 0 0 9
+<scales muf="1.0e1"/>
+<rwgt>
+<wgt>1</wgt>
+<wgt>2.0000001</wgt>
+</rwgt>
+# some info
+</event>
+</LesHouchesEvents>
+"#;
+
+    #[test]
+    fn approx() {
+        let tolerance = Tolerance::new(0., 1e-6);
+        let mut a = Reader::new(EVENTS.as_bytes()).unwrap();
+        let mut b = Reader::new(REFORMATTED.as_bytes()).unwrap();
+        assert!(a.heprup().approx_eq(b.heprup(), &tolerance));
+        assert!(!a.heprup().approx_eq(b.heprup(), &Tolerance::new(0., 0.)));
+        let a = a.event().unwrap().unwrap();
+        let b = b.event().unwrap().unwrap();
+        assert_ne!(a, b);
+        assert!(a.approx_eq(&b, &tolerance));
+        assert!(!a.approx_eq(&b, &Tolerance::new(1e-12, 0.)));
+
+        let mut c = b.clone();
+        c.PUP[0][3] += 1e-3;
+        assert!(!a.approx_eq(&c, &tolerance));
+        assert!(a.approx_eq(&c, &Tolerance::new(1e-2, 0.)));
+        c.IDUP[0] = 1;
+        assert!(!a.approx_eq(&c, &Tolerance::new(1., 1.)));
+
+        assert!(tolerance.close(f64::NAN, f64::NAN));
+        assert!(!tolerance.close(f64::NAN, 1.));
+        assert!(tolerance.close(f64::INFINITY, f64::INFINITY));
+    }
+}
@@ -0,0 +1,104 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Attributes of a tag, in the order in which they appear
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Attributes(Vec<(String, String)>);
+
+impl Attributes {
+    /// Create an empty set of attributes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the value of the attribute with the given name
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Get the value of the attribute with the given name
+    /// converted to type `T`
+    ///
+    /// Returns `None` if the attribute is absent or cannot be converted.
+    pub fn parse<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.get(name).and_then(|value| value.trim().parse().ok())
+    }
+
+    /// Set the attribute with the given name
+    ///
+    /// An existing value is replaced, otherwise the attribute is appended.
+    pub fn set(&mut self, name: &str, value: &str) {
+        match self.0.iter_mut().find(|(key, _)| key == name) {
+            Some(entry) => entry.1 = value.to_owned(),
+            None => self.0.push((name.to_owned(), value.to_owned())),
+        }
+    }
+
+    /// Remove the attribute with the given name, returning its value
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let pos = self.0.iter().position(|(key, _)| key == name)?;
+        Some(self.0.remove(pos).1)
+    }
+
+    /// Iterate over all attribute names and values
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// Number of attributes
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Check whether there are no attributes
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<'a> From<Vec<(&'a str, &'a str)>> for Attributes {
+    fn from(attributes: Vec<(&'a str, &'a str)>) -> Self {
+        Attributes(
+            attributes
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value.to_owned()))
+                .collect(),
+        )
+    }
+}
+
+/// Formats the attributes as they appear inside a tag,
+/// each preceded by a space
+impl fmt::Display for Attributes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (key, value) in self.iter() {
+            if value.contains('"') {
+                write!(f, " {}='{}'", key, value)?;
+            } else {
+                write!(f, " {}=\"{}\"", key, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_and_format() {
+        let mut attr = Attributes::new();
+        attr.set("npLO", "2");
+        attr.set("quote", "\"");
+        attr.set("npLO", "1");
+        assert_eq!(attr.parse::<i32>("npLO"), Some(1));
+        assert_eq!(attr.to_string(), " npLO=\"1\" quote='\"'");
+        assert_eq!(attr.remove("quote"), Some("\"".to_owned()));
+        assert_eq!(attr.len(), 1);
+    }
+}
@@ -0,0 +1,154 @@
+use std::io::{self, BufRead, Read};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+/// Size in bytes of the chunks passed from the background thread
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Default number of chunks that can be read ahead
+const DEFAULT_CHUNKS: usize = 16;
+
+/// Reader running the underlying stream on a dedicated thread
+///
+/// The stream is read in chunks of 64 KiB, which are passed on through
+/// a bounded channel. This is mainly useful for compressed input: the
+/// decompression then overlaps with the parsing of the events, which
+/// can reduce the time to read large files considerably. At most
+/// `chunks` chunks are read ahead, so memory usage stays bounded.
+///
+/// `ReaderBuilder::background_decompression` uses this for compressed
+/// files opened with `ReaderBuilder::open`. Errors of the underlying
+/// stream are passed on when the corresponding chunk would have been
+/// read. Dropping the reader stops the thread after its current read.
+///
+/// # Example
+///
+/// Using the `zstd` crate:
+///
+/// ```rust,ignore
+/// let file = std::fs::File::open("events.lhe.zst").unwrap();
+/// let decoder = zstd::Decoder::new(file).unwrap();
+/// let input = lhef::BackgroundReader::new(decoder);
+/// let reader = lhef::Reader::new(input).unwrap();
+/// ```
+pub struct BackgroundReader {
+    chunks: Receiver<io::Result<Vec<u8>>>,
+    chunk: Vec<u8>,
+    pos: usize,
+}
+
+impl BackgroundReader {
+    /// Start reading `stream` on a new thread
+    pub fn new<R: Read + Send + 'static>(stream: R) -> BackgroundReader {
+        Self::with_chunks(stream, DEFAULT_CHUNKS)
+    }
+
+    /// Start reading `stream` on a new thread, reading at most `chunks`
+    /// chunks ahead
+    pub fn with_chunks<R>(mut stream: R, chunks: usize) -> BackgroundReader
+    where
+        R: Read + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::sync_channel(chunks);
+        thread::spawn(move || loop {
+            let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+            let result = stream.by_ref().take(CHUNK_SIZE as u64).read_to_end(&mut chunk);
+            if !chunk.is_empty() && sender.send(Ok(chunk)).is_err() {
+                break;
+            }
+            match result {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(err) => {
+                    let _ = sender.send(Err(err));
+                    break;
+                }
+            }
+        });
+        BackgroundReader {
+            chunks: receiver,
+            chunk: Vec::new(),
+            pos: 0,
+        }
+    }
+}
+
+impl Read for BackgroundReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl BufRead for BackgroundReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos == self.chunk.len() {
+            // a closed channel means that the stream has ended
+            if let Ok(chunk) = self.chunks.recv() {
+                self.chunk = chunk?;
+                self.pos = 0;
+            }
+        }
+        Ok(&self.chunk[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.chunk.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::{GzDecoder, GzEncoder};
+    use flate2::Compression;
+    use test_util::SyntheticEvents;
+    use Reader;
+
+    #[test]
+    fn decompress() {
+        let sample = SyntheticEvents::new().num_events(500).to_bytes();
+        assert!(sample.len() > 2 * CHUNK_SIZE);
+        let mut compressed = Vec::new();
+        GzEncoder::new(sample.as_slice(), Compression::fast())
+            .read_to_end(&mut compressed)
+            .unwrap();
+
+        let decoder = GzDecoder::new(io::Cursor::new(compressed));
+        let input = BackgroundReader::with_chunks(decoder, 1);
+        let mut reader = Reader::new(input).unwrap();
+        let mut expected = Reader::new(sample.as_slice()).unwrap();
+        while let Some(block) = expected.block().unwrap() {
+            assert_eq!(reader.block().unwrap(), Some(block));
+        }
+        assert_eq!(reader.block().unwrap(), None);
+    }
+
+    struct Failing(usize);
+
+    impl Read for Failing {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0 == 0 {
+                return Err(io::Error::other("broken"));
+            }
+            let len = self.0.min(buf.len());
+            buf[..len].iter_mut().for_each(|b| *b = b'x');
+            self.0 -= len;
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn error() {
+        let mut input = BackgroundReader::new(Failing(10));
+        let mut data = Vec::new();
+        let err = input.read_to_end(&mut data).unwrap_err();
+        assert_eq!(err.to_string(), "broken");
+        assert_eq!(data, b"xxxxxxxxxx");
+        let mut empty = BackgroundReader::new(io::empty());
+        assert_eq!(empty.read_to_end(&mut data).unwrap(), 0);
+    }
+}
@@ -0,0 +1,226 @@
+use runcard;
+use tags;
+
+const MG_VERSION_TAG: &str = "MGVersion";
+const POWHEG_NAME: &str = "POWHEG-BOX";
+const POWHEG_INPUT_START: &str = "Input file powheg.input contained:";
+const POWHEG_INPUT_END: &str = "End of powheg.input content";
+
+/// Generator name and settings recognised in the header
+///
+/// This gives a common view of the banners written by MadGraph,
+/// POWHEG-BOX, and Pythia.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct GeneratorMetadata {
+    /// Name of the generator
+    pub name: String,
+    /// Version of the generator
+    pub version: Option<String>,
+    /// Input parameters as key-value pairs, in the order in which they
+    /// appear
+    pub parameters: Vec<(String, String)>,
+}
+
+impl GeneratorMetadata {
+    /// Get the value of an input parameter
+    pub fn parameter(&self, key: &str) -> Option<&str> {
+        self.parameters
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Recognise the generators that wrote the header
+pub(crate) fn parse_generator_metadata(header: &str) -> Vec<GeneratorMetadata> {
+    let mut result = Vec::new();
+    result.extend(parse_madgraph(header));
+    result.extend(parse_powheg(header));
+    result.extend(parse_pythia(header));
+    result
+}
+
+fn parse_madgraph(header: &str) -> Option<GeneratorMetadata> {
+    let version = tags::header_tag(header, &[MG_VERSION_TAG])
+        .map(|tag| tag.content().trim().trim_start_matches('#').trim().to_owned());
+    let run_card = runcard::parse_run_card(header);
+    if version.is_none() && run_card.is_none() {
+        return None;
+    }
+    let parameters = run_card
+        .map(|card| {
+            card.entries
+                .into_iter()
+                .map(|entry| (entry.key, entry.value))
+                .collect()
+        })
+        .unwrap_or_default();
+    Some(GeneratorMetadata {
+        name: "MadGraph5_aMC@NLO".to_owned(),
+        version: version.filter(|v| !v.is_empty()),
+        parameters,
+    })
+}
+
+/// Recognise the POWHEG-BOX banner
+///
+/// POWHEG-BOX echoes its `powheg.input` file into a comment in the
+/// header, with one `key value ! comment` setting per line.
+fn parse_powheg(header: &str) -> Option<GeneratorMetadata> {
+    let start = header.find(POWHEG_NAME)?;
+    let version = header[start + POWHEG_NAME.len()..]
+        .split_whitespace()
+        .next()
+        .map(|v| v.trim_start_matches('-'))
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_owned());
+    let mut parameters = Vec::new();
+    if let Some(input_start) = header.find(POWHEG_INPUT_START) {
+        let input = &header[input_start + POWHEG_INPUT_START.len()..];
+        let input = match input.find(POWHEG_INPUT_END) {
+            Some(end) => &input[..end],
+            None => input,
+        };
+        for line in input.lines() {
+            let line = strip_comment(line, &['!', '#']);
+            let mut words = line.split_whitespace();
+            if let (Some(key), Some(value)) = (words.next(), words.next()) {
+                parameters.push((key.to_owned(), value.to_owned()));
+            }
+        }
+    }
+    Some(GeneratorMetadata {
+        name: POWHEG_NAME.to_owned(),
+        version,
+        parameters,
+    })
+}
+
+/// Recognise a Pythia banner
+///
+/// The banner is recognised by a mention of "Pythia 8", "Pythia8", or
+/// "Pythia version", since other generators refer to Pythia in their
+/// comments. Pythia settings are echoed in the form
+/// `Group:name = value`.
+fn parse_pythia(header: &str) -> Option<GeneratorMetadata> {
+    let lower = header.to_ascii_lowercase();
+    let start = ["pythia8", "pythia 8", "pythia version"]
+        .iter()
+        .filter_map(|marker| lower.find(marker))
+        .min()?;
+    let mut words = header[start + "pythia".len()..].split_whitespace();
+    let version = match words.next() {
+        Some(word) if word.eq_ignore_ascii_case("version") => words.next(),
+        word => word,
+    };
+    let version = version
+        .map(|v| {
+            let end = v
+                .find(|c: char| !c.is_ascii_digit() && c != '.')
+                .unwrap_or(v.len());
+            v[..end].trim_end_matches('.')
+        })
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_owned());
+    let parameters = header
+        .lines()
+        .filter_map(|line| {
+            let line = strip_comment(line, &['!', '#']);
+            let eq = line.find('=')?;
+            let key = line[..eq].trim();
+            let value = line[eq + 1..].trim();
+            let is_setting = key.contains(':')
+                && !key.contains(char::is_whitespace)
+                && !value.is_empty();
+            if is_setting {
+                Some((key.to_owned(), value.to_owned()))
+            } else {
+                None
+            }
+        })
+        .collect();
+    Some(GeneratorMetadata {
+        name: "Pythia".to_owned(),
+        version,
+        parameters,
+    })
+}
+
+fn strip_comment<'a>(line: &'a str, markers: &[char]) -> &'a str {
+    match line.find(markers) {
+        Some(pos) => &line[..pos],
+        None => line,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn powheg() {
+        let header = "<!--\n\
+                      file generated with POWHEG-BOX-V2\n\
+                      Input file powheg.input contained:\n\
+                      numevts 10000    ! number of events to be generated\n\
+                      ih1   1           ! hadron 1\n\
+                      ebeam1 6500d0     ! energy of beam 1\n\
+                      ! a comment\n\
+                      End of powheg.input content\n\
+                      random seed 12\n\
+                      -->\n";
+        let metadata = parse_generator_metadata(header);
+        assert_eq!(metadata.len(), 1);
+        assert_eq!(metadata[0].name, "POWHEG-BOX");
+        assert_eq!(metadata[0].version, Some("V2".to_owned()));
+        assert_eq!(metadata[0].parameters.len(), 3);
+        assert_eq!(metadata[0].parameter("ebeam1"), Some("6500d0"));
+        assert_eq!(metadata[0].parameter("random"), None);
+    }
+
+    #[test]
+    fn pythia() {
+        let header = "<!--\n\
+                      File written by PYTHIA version 8.306\n\
+                      Beams:eCM = 13000.  ! CM energy\n\
+                      HardQCD:all = on\n\
+                      not a setting = 1\n\
+                      -->\n";
+        let metadata = parse_generator_metadata(header);
+        assert_eq!(metadata.len(), 1);
+        assert_eq!(metadata[0].name, "Pythia");
+        assert_eq!(metadata[0].version, Some("8.306".to_owned()));
+        assert_eq!(
+            metadata[0].parameters,
+            [
+                ("Beams:eCM".to_owned(), "13000.".to_owned()),
+                ("HardQCD:all".to_owned(), "on".to_owned()),
+            ]
+        );
+        let metadata = parse_generator_metadata("<!--\nFile written by Pythia8::LHAup\n-->\n");
+        assert_eq!(metadata[0].version, Some("8".to_owned()));
+    }
+
+    #[test]
+    fn madgraph() {
+        let header = "<header>\n\
+                      <MGVersion>\n#2.6.5\n</MGVersion>\n\
+                      <MGRunCard>\n 1000 = nevents ! events\n</MGRunCard>\n\
+                      </header>\n";
+        let metadata = parse_generator_metadata(header);
+        assert_eq!(metadata.len(), 1);
+        assert_eq!(metadata[0].version, Some("2.6.5".to_owned()));
+        assert_eq!(metadata[0].parameter("nevents"), Some("1000"));
+        assert!(parse_generator_metadata("<header>\n</header>\n").is_empty());
+    }
+
+    #[test]
+    fn read_file() {
+        let reader = ::Reader::open("test_data/2j.lhe.gz").unwrap();
+        let metadata = reader.generator_metadata();
+        assert_eq!(metadata.len(), 1);
+        assert_eq!(metadata[0].version, Some("5.2.3.3".to_owned()));
+        assert_eq!(metadata[0].parameter("ebeam2"), Some("7000.0"));
+    }
+}
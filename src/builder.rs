@@ -0,0 +1,258 @@
+use std::error;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use file;
+use progress::CountingReader;
+use reweight::WeightHook;
+use {parse_header, parse_init, parse_version};
+use {Checkpoint, ExtensionRegistry, InfoRetention, Limits, NonFinitePolicy, Reader, Weights};
+use {HEPEUP, HEPRUP};
+
+/// Builder for a `Reader` with custom settings
+///
+/// # Example
+///
+/// ```rust,no_run
+/// let file = std::fs::File::open("events.lhe").unwrap();
+/// let file = std::io::BufReader::new(file);
+/// let reader = lhef::ReaderBuilder::new()
+///     .limits(lhef::Limits::unlimited())
+///     .build(file)
+///     .unwrap();
+/// ```
+#[derive(Default)]
+pub struct ReaderBuilder {
+    extensions: ExtensionRegistry,
+    limits: Limits,
+    default_version: Option<String>,
+    lenient_tags: bool,
+    buffer_capacity: Option<usize>,
+    background_decompression: bool,
+    info_retention: InfoRetention,
+    non_finite: NonFinitePolicy,
+    weight_hook: Option<WeightHook>,
+}
+
+impl ReaderBuilder {
+    /// Create a builder with the default settings
+    pub fn new() -> ReaderBuilder {
+        ReaderBuilder::default()
+    }
+
+    /// Use custom parsers for extension tags
+    ///
+    /// See `ExtensionRegistry` for an example.
+    pub fn extensions(mut self, extensions: ExtensionRegistry) -> ReaderBuilder {
+        self.extensions = extensions;
+        self
+    }
+
+    /// Set the limits on the accepted input
+    pub fn limits(mut self, limits: Limits) -> ReaderBuilder {
+        self.limits = limits;
+        self
+    }
+
+    /// Assume `version` if the `<LesHouchesEvents>` tag has no version
+    /// attribute or declares an unsupported version
+    ///
+    /// By default, such files are rejected. The version found in the
+    /// input is available from `Reader::declared_version`. Building the
+    /// reader fails if `version` itself is not supported.
+    pub fn default_version(mut self, version: &str) -> ReaderBuilder {
+        self.default_version = Some(version.to_owned());
+        self
+    }
+
+    /// Match the `<event>`, `<eventgroup>`, and closing
+    /// `</LesHouchesEvents>` tags case-insensitively and ignore
+    /// whitespace around the tag names
+    ///
+    /// This accepts for example `<Event>` or `< /event >`. By default,
+    /// the tags have to be written exactly as in the standard.
+    pub fn lenient_tags(mut self, lenient: bool) -> ReaderBuilder {
+        self.lenient_tags = lenient;
+        self
+    }
+
+    /// Set the capacity in bytes of the buffer used by
+    /// `build_from_read`
+    ///
+    /// By default, the capacity of `BufReader::new` is used.
+    pub fn buffer_capacity(mut self, capacity: usize) -> ReaderBuilder {
+        self.buffer_capacity = Some(capacity);
+        self
+    }
+
+    /// Decompress files opened with `open` on a separate thread
+    ///
+    /// This overlaps decompression and parsing, which speeds up reading
+    /// large compressed files on machines with several cores. See
+    /// `BackgroundReader`. Uncompressed files are not affected.
+    pub fn background_decompression(mut self, background: bool) -> ReaderBuilder {
+        self.background_decompression = background;
+        self
+    }
+
+    /// Set how the optional information `HEPEUP::info` of the events
+    /// is retained
+    ///
+    /// By default, it is copied into each event, see `InfoRetention`.
+    pub fn info_retention(mut self, retention: InfoRetention) -> ReaderBuilder {
+        self.info_retention = retention;
+        self
+    }
+
+    /// Set what happens with weights and momenta that are NaN or
+    /// infinite
+    ///
+    /// By default, they are kept, see `NonFinitePolicy`. The number of
+    /// kept or replaced values is available from
+    /// `Reader::non_finite_values`.
+    pub fn non_finite(mut self, policy: NonFinitePolicy) -> ReaderBuilder {
+        self.non_finite = policy;
+        self
+    }
+
+    /// Call `hook` with each event and its additional weights
+    ///
+    /// The hook can add or modify weights, for example to reweight
+    /// events to a different PDF, before the event is returned by the
+    /// reader. Events without weights are passed an empty set of
+    /// weights. Errors returned by the hook are passed on by the
+    /// reader.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # let file: std::io::BufReader<std::fs::File> = unimplemented!();
+    /// let reader = lhef::ReaderBuilder::new()
+    ///     .weight_hook(|event, weights| {
+    ///         weights.set("half", 0.5 * event.XWGTUP);
+    ///         Ok(())
+    ///     })
+    ///     .build(file)
+    ///     .unwrap();
+    /// ```
+    pub fn weight_hook<F>(mut self, hook: F) -> ReaderBuilder
+    where
+        F: FnMut(&HEPEUP, &mut Weights) -> Result<(), Box<dyn error::Error>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.weight_hook = Some(Box::new(hook));
+        self
+    }
+
+    /// Create a reader, parsing the header and init block of `stream`
+    pub fn build<Stream: BufRead>(
+        self,
+        mut stream: Stream
+    ) -> Result<Reader<Stream>, Box<dyn error::Error>> {
+        let limits = self.limits;
+        let default_version = match self.default_version {
+            Some(ref version) => Some(::version(version)?),
+            None => None
+        };
+        let mut counting = CountingReader{stream: &mut stream, count: 0};
+        let (version, declared_version, root_attributes, header) = {
+            span!(DEBUG, "lhef_header");
+            let (version, declared_version, root_attributes) =
+                parse_version(&mut counting, &limits, default_version)?;
+            let header = parse_header(&mut counting, &limits)?;
+            event!(DEBUG, version, bytes = header.len(), "read header");
+            (version, declared_version, root_attributes, header)
+        };
+        let heprup = {
+            span!(DEBUG, "lhef_init");
+            let heprup = parse_init(&mut counting, &self.extensions, &limits)?;
+            event!(DEBUG, nprup = heprup.NPRUP, "read init block");
+            heprup
+        };
+        let bytes_read = counting.count;
+        let mut reader = Reader::from_parts(
+            stream, version, header, heprup, self.extensions, limits, self.info_retention
+        )?;
+        reader.bytes_read = bytes_read;
+        reader.declared_version = declared_version;
+        reader.root_attributes = root_attributes;
+        reader.lenient_tags = self.lenient_tags;
+        reader.weight_hook = self.weight_hook;
+        reader.set_non_finite_policy(self.non_finite);
+        Ok(reader)
+    }
+
+    /// Create a reader for an unbuffered stream, wrapping it in a
+    /// `BufReader`
+    ///
+    /// See `Reader::from_read`.
+    pub fn build_from_read<Stream: Read>(
+        self,
+        stream: Stream
+    ) -> Result<Reader<BufReader<Stream>>, Box<dyn error::Error>> {
+        let stream = match self.buffer_capacity {
+            Some(capacity) => BufReader::with_capacity(capacity, stream),
+            None => BufReader::new(stream)
+        };
+        self.build(stream)
+    }
+
+    /// Create a reader for the file at `path`
+    ///
+    /// See `Reader::open`.
+    pub fn open<P: AsRef<Path>>(
+        self,
+        path: P
+    ) -> Result<Reader<Box<dyn BufRead + Send>>, Box<dyn error::Error>> {
+        let stream = file::open(path.as_ref(), self.background_decompression)?;
+        self.build(stream)
+    }
+
+    /// Create a reader for a stream that only contains events
+    ///
+    /// See `Reader::from_fragments`.
+    pub fn build_fragments<Stream: BufRead>(
+        self,
+        stream: Stream,
+        heprup: HEPRUP
+    ) -> Result<Reader<Stream>, Box<dyn error::Error>> {
+        let mut reader = Reader::from_parts(
+            stream, "3.0", String::new(), heprup, self.extensions, self.limits,
+            self.info_retention
+        )?;
+        reader.fragments = true;
+        reader.lenient_tags = self.lenient_tags;
+        reader.weight_hook = self.weight_hook;
+        reader.set_non_finite_policy(self.non_finite);
+        Ok(reader)
+    }
+
+    /// Create a reader continuing from a checkpoint
+    ///
+    /// The declared version and lenient tag matching are restored from
+    /// the checkpoint. See `Reader::resume`.
+    pub fn resume<Stream: BufRead + Seek>(
+        self,
+        mut stream: Stream,
+        checkpoint: Checkpoint,
+    ) -> Result<Reader<Stream>, Box<dyn error::Error>> {
+        let version = ::version(&checkpoint.version)?;
+        stream.seek(SeekFrom::Start(checkpoint.offset))?;
+        let mut reader = Reader::from_parts(
+            stream, version, checkpoint.header, checkpoint.heprup,
+            self.extensions, self.limits, self.info_retention
+        )?;
+        reader.declared_version = checkpoint.declared_version;
+        reader.root_attributes = checkpoint.root_attributes;
+        reader.bytes_read = checkpoint.offset;
+        reader.nevents = checkpoint.events;
+        reader.pending = checkpoint.pending.into();
+        reader.fragments = checkpoint.fragments;
+        reader.lenient_tags = checkpoint.lenient_tags || self.lenient_tags;
+        reader.weight_hook = self.weight_hook;
+        reader.set_non_finite_policy(self.non_finite);
+        Ok(reader)
+    }
+}
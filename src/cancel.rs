@@ -0,0 +1,107 @@
+use std::error;
+use std::fmt;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use {LimitExceeded, ParseError};
+
+/// Token to cancel reading from another thread
+///
+/// Clones of a token share the same state, so one clone can be given
+/// to a `Reader` with `Reader::set_cancellation` and another one kept to
+/// cancel reading, e.g. when a user presses a button. Once cancelled,
+/// reading fails with a `Cancelled` error. This also applies to
+/// operations that read events internally, such as the conversions.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// let file = std::fs::File::open("events.lhe").unwrap();
+/// let file = std::io::BufReader::new(file);
+/// let mut reader = lhef::Reader::new(file).unwrap();
+/// let token = lhef::CancellationToken::new();
+/// reader.set_cancellation(Some(token.clone()));
+///
+/// let handle = std::thread::spawn(move || {
+///     let mut nevents = 0;
+///     loop {
+///         match reader.event() {
+///             Ok(Some(_)) => nevents += 1,
+///             Ok(None) => return Some(nevents),
+///             Err(err) if err.is::<lhef::Cancelled>() => return None,
+///             Err(err) => panic!("{}", err),
+///         }
+///     }
+/// });
+/// token.cancel();
+/// let nevents = handle.join().unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Create a new token that is not cancelled
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Cancel all operations using this token or one of its clones
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed)
+    }
+
+    /// Check whether the token has been cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Error returned when an operation is cancelled with a
+/// `CancellationToken`
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Operation was cancelled")
+    }
+}
+
+impl error::Error for Cancelled {}
+
+/// Check a token, returning an I/O error if it is cancelled
+pub(crate) fn check(token: Option<&CancellationToken>) -> io::Result<()> {
+    match token {
+        Some(token) if token.is_cancelled() => {
+            Err(io::Error::new(io::ErrorKind::Interrupted, Cancelled))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Convert an I/O error, extracting a `Cancelled` error or a parse
+/// error wrapped inside
+pub(crate) fn from_io_error(err: io::Error) -> Box<dyn error::Error> {
+    let is_cancelled = err.get_ref().is_some_and(|inner| inner.is::<Cancelled>());
+    let is_parse_error = err
+        .get_ref()
+        .is_some_and(|inner| inner.is::<ParseError>() || inner.is::<LimitExceeded>());
+    if is_cancelled {
+        Box::new(Cancelled)
+    } else if is_parse_error {
+        err.into_inner().unwrap()
+    } else {
+        Box::new(err)
+    }
+}
+
+/// Check whether reading can be resumed after an I/O error
+///
+/// This is the case if following a stream timed out or was cancelled.
+pub(crate) fn is_resumable(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::TimedOut | io::ErrorKind::Interrupted
+    )
+}
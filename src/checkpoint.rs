@@ -0,0 +1,169 @@
+use {Attributes, HEPEUP, HEPRUP};
+
+/// The state of a `Reader`, from which reading can be resumed later
+///
+/// A checkpoint is obtained with `Reader::checkpoint` and passed to
+/// `Reader::resume` together with the same input, for example to
+/// continue a long conversion after the job was interrupted. With the
+/// `serde` feature, checkpoints can be serialised and stored alongside
+/// the output.
+///
+/// The offset counts all bytes consumed by the reader, so checkpoints
+/// are only meaningful for readers without continuations (see
+/// `Reader::attach`) and partitions (see `Reader::into_partition`).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub struct Checkpoint {
+    /// Byte offset of the first block that has not been read yet
+    pub offset: u64,
+    /// Number of events read so far, see `Progress::events`
+    pub events: u64,
+    /// The LHEF version
+    pub version: String,
+    /// The version attribute as found in the input, see
+    /// `Reader::declared_version`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub declared_version: Option<String>,
+    /// The attributes of the `<LesHouchesEvents>` tag other than the
+    /// version
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub root_attributes: Attributes,
+    /// The header
+    pub header: String,
+    /// The run information
+    pub heprup: HEPRUP,
+    /// Remaining events of a partially consumed `<eventgroup>`
+    pub pending: Vec<HEPEUP>,
+    /// Whether the input only contains events, see
+    /// `Reader::from_fragments`
+    pub fragments: bool,
+    /// Whether block tags are matched case-insensitively, see
+    /// `ReaderBuilder::lenient_tags`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub lenient_tags: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use test_util::SyntheticEvents;
+    use {Block, Reader, ReaderBuilder};
+
+    const EVENTGROUP: &str = r#"<LesHouchesEvents version="3.0">
+<init>
+2212 2212 6500 6500 0 0 0 0 3 1
+1 0 1 1
+</init>
+<event>
+1 1 1 10 0 0.1
+21 -1 0 0 0 0 0 0 1e2 1e2 0 0 9
+</event>
+<eventgroup>
+<event>
+1 1 2 10 0 0.1
+21 -1 0 0 0 0 0 0 1e2 1e2 0 0 9
+</event>
+<event>
+1 1 3 10 0 0.1
+21 -1 0 0 0 0 0 0 1e2 1e2 0 0 9
+</event>
+</eventgroup>
+<event>
+1 1 4 10 0 0.1
+21 -1 0 0 0 0 0 0 1e2 1e2 0 0 9
+</event>
+</LesHouchesEvents>
+"#;
+
+    const LATIN1: &[u8] = b"<LesHouchesEvents version=\"0.9\">
+<init>
+2212 2212 6500 6500 0 0 0 0 3 1
+1 0 1 1
+</init>
+<Event>
+1 1 1 10 0 0.1
+21 -1 0 0 0 0 0 0 1e2 1e2 0 0 9
+# r\xe9sum\xe9
+</Event>
+<event>
+1 1 2 10 0 0.1
+21 -1 0 0 0 0 0 0 1e2 1e2 0 0 9
+< Event comment=\"r\xe9sum\xe9\" >
+1 1 3 10 0 0.1
+21 -1 0 0 0 0 0 0 1e2 1e2 0 0 9
+</event>
+</LesHouchesEvents>
+";
+
+    #[test]
+    fn resume() {
+        let sample = SyntheticEvents::new().num_events(10).num_weights(2).to_bytes();
+        let mut reader = Reader::new(Cursor::new(&sample)).unwrap();
+        let mut expected = Vec::new();
+        while expected.len() < 4 {
+            expected.push(reader.event().unwrap().unwrap());
+        }
+        let checkpoint = reader.checkpoint();
+        assert_eq!(checkpoint.events, 4);
+        assert_eq!(&checkpoint.heprup, reader.heprup());
+        while let Some(event) = reader.event().unwrap() {
+            expected.push(event);
+        }
+
+        let mut reader = Reader::resume(Cursor::new(&sample), checkpoint).unwrap();
+        assert_eq!(reader.initrwgt().unwrap().weights().len(), 2);
+        let mut events = Vec::new();
+        while let Some(event) = reader.event().unwrap() {
+            events.push(event);
+        }
+        assert_eq!(events, &expected[4..]);
+        assert_eq!(reader.progress().events, 10);
+        assert_eq!(reader.progress().bytes, sample.len() as u64);
+    }
+
+    #[test]
+    fn inside_eventgroup() {
+        let mut reader = Reader::new(Cursor::new(EVENTGROUP)).unwrap();
+        reader.event().unwrap();
+        reader.event().unwrap();
+        let checkpoint = reader.checkpoint();
+        assert_eq!(checkpoint.pending.len(), 1);
+        assert_eq!(checkpoint.events, 3);
+
+        let mut reader = ReaderBuilder::new()
+            .resume(Cursor::new(EVENTGROUP), checkpoint.clone())
+            .unwrap();
+        match reader.block().unwrap() {
+            Some(Block::EventGroup(events)) => assert_eq!(events, checkpoint.pending),
+            _ => panic!("expected remaining events of group"),
+        }
+        assert_eq!(reader.event().unwrap().unwrap().XWGTUP, 4.);
+        assert_eq!(reader.event().unwrap(), None);
+
+        let mut bad = checkpoint;
+        bad.version = "4.0".to_owned();
+        assert!(Reader::resume(Cursor::new(EVENTGROUP), bad).is_err());
+    }
+
+    #[test]
+    fn raw_offset() {
+        let mut reader = ReaderBuilder::new()
+            .default_version("3.0")
+            .lenient_tags(true)
+            .build(Cursor::new(LATIN1))
+            .unwrap();
+        assert_eq!(reader.event().unwrap().unwrap().info, "# r\u{e9}sum\u{e9}\n");
+        assert!(reader.event().is_err());
+        let checkpoint = reader.checkpoint();
+        let next = LATIN1.windows(7).position(|w| w == b"< Event").unwrap();
+        assert_eq!(checkpoint.offset, next as u64);
+        assert_eq!(checkpoint.declared_version.as_deref(), Some("0.9"));
+        assert!(checkpoint.lenient_tags);
+
+        let mut reader = Reader::resume(Cursor::new(LATIN1), checkpoint).unwrap();
+        assert_eq!(reader.declared_version(), Some("0.9"));
+        assert_eq!(reader.event().unwrap().unwrap().XWGTUP, 3.);
+        assert_eq!(reader.event().unwrap(), None);
+        assert_eq!(reader.progress().bytes, LATIN1.len() as u64);
+    }
+}
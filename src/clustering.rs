@@ -0,0 +1,125 @@
+use std::error;
+use std::fmt;
+
+use tags::{self, Tag};
+use {parse, ParseError};
+
+pub(crate) const CLUSTERING_TAG: &str = "clustering";
+const CLUS_TAG: &str = "clus";
+
+/// Clustering history from the LHEF 3.0 `<clustering>` tag
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Clustering {
+    /// Clustering steps in the order in which they appear
+    pub steps: Vec<ClusteringStep>,
+}
+
+/// A single clustering step from a `<clus>` tag
+///
+/// Particles are labelled by their index in the event, starting from 1.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub struct ClusteringStep {
+    /// First clustered particle
+    pub p1: usize,
+    /// Second clustered particle
+    pub p2: usize,
+    /// Particle resulting from the clustering
+    ///
+    /// If not given explicitly, this is the same as `p1`.
+    pub p0: usize,
+    /// Scale of the clustering in GeV
+    pub scale: Option<f64>,
+    /// Value of the strong coupling to be used for this step
+    pub alphas: Option<f64>,
+}
+
+impl Clustering {
+    pub(crate) fn from_tag(tag: &Tag) -> Result<Clustering, Box<dyn error::Error>> {
+        let mut steps = Vec::new();
+        for (_, clus) in tags::tags(tag.content()) {
+            if clus.name() != CLUS_TAG {
+                continue;
+            }
+            let mut entries = clus.content().split_whitespace();
+            let p1 = parse::<usize>("clus p1", entries.next())?;
+            let p2 = parse::<usize>("clus p2", entries.next())?;
+            let p0 = match entries.next() {
+                Some(p0) => parse::<usize>("clus p0", Some(p0))?,
+                None => p1,
+            };
+            if let Some(extra) = entries.next() {
+                return Err(Box::new(ParseError::ConversionError(extra.to_owned())));
+            }
+            let attr_f64 = |name| {
+                clus.attribute(name)
+                    .map(|value| parse::<f64>(name, Some(value.trim())))
+                    .transpose()
+            };
+            steps.push(ClusteringStep {
+                p1,
+                p2,
+                p0,
+                scale: attr_f64("scale")?,
+                alphas: attr_f64("alphas")?,
+            });
+        }
+        Ok(Clustering { steps })
+    }
+}
+
+/// Formats the clustering history as a `<clustering>` tag
+impl fmt::Display for Clustering {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "<{}>", CLUSTERING_TAG)?;
+        for step in &self.steps {
+            write!(f, "<{}", CLUS_TAG)?;
+            if let Some(scale) = step.scale {
+                write!(f, " scale=\"{:e}\"", scale)?;
+            }
+            if let Some(alphas) = step.alphas {
+                write!(f, " alphas=\"{:e}\"", alphas)?;
+            }
+            write!(f, ">{} {}", step.p1, step.p2)?;
+            if step.p0 != step.p1 {
+                write!(f, " {}", step.p0)?;
+            }
+            writeln!(f, "</{}>", CLUS_TAG)?;
+        }
+        write!(f, "</{}>", CLUSTERING_TAG)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_clustering(text: &str) -> Result<Clustering, Box<dyn error::Error>> {
+        let (_, tag) = tags::tags(text).next().unwrap();
+        Clustering::from_tag(&tag)
+    }
+
+    #[test]
+    fn parse_and_format() {
+        let text = "<clustering>\n\
+                    <clus scale=\"30.5\" alphas=\"0.12\"> 3 4 </clus>\n\
+                    <clus>1 5 6</clus>\n\
+                    </clustering>";
+        let clustering = parse_clustering(text).unwrap();
+        assert_eq!(
+            clustering.steps,
+            [
+                ClusteringStep { p1: 3, p2: 4, p0: 3, scale: Some(30.5), alphas: Some(0.12) },
+                ClusteringStep { p1: 1, p2: 5, p0: 6, scale: None, alphas: None },
+            ]
+        );
+        assert_eq!(parse_clustering(&clustering.to_string()).unwrap(), clustering);
+    }
+
+    #[test]
+    fn bad_step() {
+        assert!(parse_clustering("<clustering><clus>1</clus></clustering>").is_err());
+        assert!(parse_clustering("<clustering><clus>1 2 3 4</clus></clustering>").is_err());
+    }
+}
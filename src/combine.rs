@@ -0,0 +1,244 @@
+use std::error;
+use std::fmt;
+use std::io::BufRead;
+
+use {Reader, HEPEUP, HEPRUP};
+
+/// Combination of several samples into a single weighted sample
+///
+/// The weights of the events in each sample are rescaled such that
+/// they add up to the cross section of the sample. The combined events
+/// then add up to the total cross section, regardless of how many
+/// events were generated for each sample. Events are returned sample
+/// by sample.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # let (signal, background): (lhef::Reader<std::io::Empty>, lhef::Reader<std::io::Empty>) = unimplemented!();
+/// # let mut writer: lhef::Writer<std::io::Sink> = unimplemented!();
+/// let mut combiner = lhef::Combiner::new();
+/// // cross sections in pb and sums of weights
+/// combiner.add(signal, 0.3, 10_000.);
+/// combiner.add(background, 120., 50_000.);
+/// writer.heprup(&combiner.heprup().unwrap()).unwrap();
+/// while let Some(event) = combiner.event().unwrap() {
+///     writer.event(&event).unwrap();
+/// }
+/// ```
+pub struct Combiner<Stream> {
+    inputs: Vec<Input<Stream>>,
+    current: usize,
+}
+
+struct Input<Stream> {
+    reader: Reader<Stream>,
+    cross_section: f64,
+    /// factor for converting weights to the combined sample
+    factor: f64,
+}
+
+impl<Stream: BufRead> Default for Combiner<Stream> {
+    fn default() -> Self {
+        Combiner {
+            inputs: Vec::new(),
+            current: 0,
+        }
+    }
+}
+
+impl<Stream: BufRead> Combiner<Stream> {
+    /// Create a combiner without any samples
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a sample
+    ///
+    /// `sum_weights` is the sum of the weights `XWGTUP` of all events
+    /// in the sample. For unweighted samples, this is the number of
+    /// events times the common weight.
+    pub fn add(&mut self, reader: Reader<Stream>, cross_section: f64, sum_weights: f64) {
+        self.inputs.push(Input {
+            reader,
+            cross_section,
+            factor: cross_section / sum_weights,
+        })
+    }
+
+    /// Number of samples
+    pub fn len(&self) -> usize {
+        self.inputs.len()
+    }
+
+    /// Whether there are no samples
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    /// Run information for the combined sample
+    ///
+    /// Beams, PDFs, and run information are taken from the first
+    /// sample. The subprocesses of all samples are listed with their
+    /// cross sections rescaled to the one given for the sample, and
+    /// subprocesses with the same ID in several samples are merged. The
+    /// weights are marked as summing up to the cross section, i.e.
+    /// `IDWTUP` is 4, or -4 if any sample has negative weights.
+    ///
+    /// Fails if there are no samples or if the beams differ between
+    /// samples.
+    pub fn heprup(&self) -> Result<HEPRUP, Box<dyn error::Error>> {
+        let first = self.inputs.first().ok_or(CombineError::NoSamples)?.reader.heprup();
+        let mut heprup = HEPRUP {
+            IDWTUP: 4,
+            NPRUP: 0,
+            XSECUP: Vec::new(),
+            XERRUP: Vec::new(),
+            XMAXUP: Vec::new(),
+            LPRUP: Vec::new(),
+            ..first.clone()
+        };
+        for (n, input) in self.inputs.iter().enumerate() {
+            let run = input.reader.heprup();
+            if run.IDBMUP != first.IDBMUP || run.EBMUP != first.EBMUP {
+                return Err(Box::new(CombineError::BeamMismatch(n)));
+            }
+            if run.IDWTUP < 0 {
+                heprup.IDWTUP = -4;
+            }
+            let total: f64 = run.XSECUP.iter().sum();
+            let scale = if total != 0. { input.cross_section / total } else { 0. };
+            for i in 0..run.LPRUP.len() {
+                let xsec = scale * run.XSECUP[i];
+                let xerr = scale * run.XERRUP[i];
+                let xmax = input.factor * run.XMAXUP[i];
+                match heprup.LPRUP.iter().position(|&id| id == run.LPRUP[i]) {
+                    Some(j) => {
+                        heprup.XSECUP[j] += xsec;
+                        heprup.XERRUP[j] = heprup.XERRUP[j].hypot(xerr);
+                        heprup.XMAXUP[j] = heprup.XMAXUP[j].max(xmax);
+                    }
+                    None => {
+                        heprup.XSECUP.push(xsec);
+                        heprup.XERRUP.push(xerr);
+                        heprup.XMAXUP.push(xmax);
+                        heprup.LPRUP.push(run.LPRUP[i]);
+                    }
+                }
+            }
+        }
+        heprup.NPRUP = heprup.LPRUP.len() as i32;
+        Ok(heprup)
+    }
+
+    /// Get the next event with rescaled weight
+    ///
+    /// The event weight `XWGTUP` and all additional weights are
+    /// rescaled.
+    pub fn event(&mut self) -> Result<Option<HEPEUP>, Box<dyn error::Error>> {
+        while let Some(input) = self.inputs.get_mut(self.current) {
+            if let Some(mut event) = input.reader.event()? {
+                event.XWGTUP *= input.factor;
+                if let Some(ref mut weights) = event.weights {
+                    for weight in weights.weights.iter_mut() {
+                        weight.value *= input.factor;
+                    }
+                }
+                return Ok(Some(event));
+            }
+            self.current += 1;
+        }
+        Ok(None)
+    }
+}
+
+#[derive(Debug)]
+enum CombineError {
+    NoSamples,
+    BeamMismatch(usize),
+}
+
+impl fmt::Display for CombineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::CombineError::*;
+        match *self {
+            NoSamples => write!(f, "No samples to combine"),
+            BeamMismatch(n) => write!(
+                f,
+                "Beams of sample {} differ from those of the first sample",
+                n + 1
+            ),
+        }
+    }
+}
+
+impl error::Error for CombineError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIGNAL: &str = r#"<LesHouchesEvents version="3.0">
+<init>
+2212 2212 6500 6500 0 0 0 0 3 1
+2 0.1 3 1
+</init>
+<event>
+1 1 2 10 0 0.1
+21 -1 0 0 0 0 0 0 1e2 1e2 0 0 9
+<rwgt>
+<wgt id="a">4</wgt>
+</rwgt>
+</event>
+<event>
+1 1 2 10 0 0.1
+21 -1 0 0 0 0 0 0 1e2 1e2 0 0 9
+</event>
+</LesHouchesEvents>
+"#;
+
+    const BACKGROUND: &str = r#"<LesHouchesEvents version="1.0">
+<init>
+2212 2212 6500 6500 0 0 0 0 -3 2
+10 1 1 1
+30 1 1 2
+</init>
+<event>
+1 2 -1 10 0 0.1
+21 -1 0 0 0 0 0 0 1e2 1e2 0 0 9
+</event>
+</LesHouchesEvents>
+"#;
+
+    #[test]
+    fn combine() {
+        let mut combiner = Combiner::new();
+        assert!(combiner.heprup().is_err());
+        combiner.add(Reader::new(SIGNAL.as_bytes()).unwrap(), 3., 4.);
+        combiner.add(Reader::new(BACKGROUND.as_bytes()).unwrap(), 80., 1.);
+        assert_eq!(combiner.len(), 2);
+        let heprup = combiner.heprup().unwrap();
+        assert_eq!(heprup.IDWTUP, -4);
+        assert_eq!(heprup.NPRUP, 2);
+        assert_eq!(heprup.LPRUP, [1, 2]);
+        assert_eq!(heprup.XSECUP, [3. + 20., 60.]);
+        assert_eq!(heprup.XMAXUP, [80., 80.]);
+
+        let first = combiner.event().unwrap().unwrap();
+        assert_eq!(first.XWGTUP, 1.5);
+        assert_eq!(first.weights.unwrap().get("a"), Some(3.));
+        assert_eq!(combiner.event().unwrap().unwrap().XWGTUP, 1.5);
+        assert_eq!(combiner.event().unwrap().unwrap().XWGTUP, -80.);
+        assert_eq!(combiner.event().unwrap(), None);
+    }
+
+    #[test]
+    fn beam_mismatch() {
+        let other_beams = SIGNAL.replace("2212 2212 6500 6500", "2212 -2212 980 980");
+        let mut combiner = Combiner::new();
+        combiner.add(Reader::new(SIGNAL.as_bytes()).unwrap(), 1., 1.);
+        combiner.add(Reader::new(other_beams.as_bytes()).unwrap(), 1., 1.);
+        let err = combiner.heprup().unwrap_err();
+        assert_eq!(err.to_string(), "Beams of sample 2 differ from those of the first sample");
+    }
+}
@@ -0,0 +1,245 @@
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+const PDF_PREFIX: &str = "#pdf";
+const SCALE_PREFIX: &str = "#scale";
+const AMCATNLO_PREFIX: &str = "#aMCatNLO";
+
+/// Generator-specific comment lines in the optional event information
+///
+/// Pythia and MadGraph5_aMC@NLO write some information as lines starting
+/// with `#` after the particle lines. The recognised lines are parsed
+/// into this struct and removed from `HEPEUP::info`. Lines that cannot
+/// be parsed are left in `info`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct CommentLines {
+    /// PDF information from the `#pdf` line
+    pub pdf: Option<PdfLine>,
+    /// Scales in GeV from the `#scale` line
+    pub scales: Option<Vec<f64>>,
+    /// Matching information from the `#aMCatNLO` line
+    pub amcatnlo: Option<AmcAtNloLine>,
+}
+
+/// PDF information in the format `#pdf id1 id2 x1 x2 scalePDF xpdf1 xpdf2`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub struct PdfLine {
+    /// IDs of the incoming partons
+    pub id: [i32; 2],
+    /// Momentum fractions of the incoming partons
+    pub x: [f64; 2],
+    /// Factorisation scale in GeV
+    pub scale: f64,
+    /// PDF values `x f(x)` of the incoming partons
+    pub xpdf: [f64; 2],
+}
+
+/// Matching information from MadGraph5_aMC@NLO for the parton shower
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub struct AmcAtNloLine {
+    /// 1 for S events and 2 for H events
+    pub event_type: i32,
+    /// Positions of the FKS partons i and j, counting from 1
+    pub fks: [i32; 2],
+    /// Position of the FKS mother, counting from 1
+    pub fks_mother: i32,
+    /// Position of the colour partner, counting from 1
+    pub partner: i32,
+    /// Shower starting scales in GeV
+    pub shower_scales: [f64; 2],
+    /// Remaining values, whose meaning depends on the version of
+    /// MadGraph5_aMC@NLO
+    pub extra: Vec<f64>,
+}
+
+impl CommentLines {
+    /// Parse the recognised comment lines in `info`
+    ///
+    /// Lines inside the byte ranges `skip`, which are sorted, are
+    /// ignored. Returns the parsed lines, if any, and the byte ranges
+    /// of the lines to remove from `info`.
+    pub(crate) fn parse(
+        info: &str,
+        skip: &[Range<usize>],
+    ) -> (Option<CommentLines>, Vec<Range<usize>>) {
+        let mut lines = CommentLines::default();
+        let mut ranges = Vec::new();
+        let mut skip = skip.iter().peekable();
+        let mut pos = 0;
+        for line in info.split_inclusive('\n') {
+            let range = pos..pos + line.len();
+            pos = range.end;
+            while skip.peek().is_some_and(|s| s.end <= range.start) {
+                skip.next();
+            }
+            if skip.peek().is_some_and(|s| s.start < range.end) {
+                continue;
+            }
+            if lines.parse_line(line.trim()) {
+                ranges.push(range);
+            }
+        }
+        if ranges.is_empty() {
+            (None, ranges)
+        } else {
+            (Some(lines), ranges)
+        }
+    }
+
+    /// Parse a recognised line that has not been seen yet
+    fn parse_line(&mut self, line: &str) -> bool {
+        if let Some(values) = values(line, PDF_PREFIX) {
+            if self.pdf.is_none() {
+                self.pdf = PdfLine::parse(&values);
+                return self.pdf.is_some();
+            }
+        } else if let Some(values) = values(line, SCALE_PREFIX) {
+            if self.scales.is_none() {
+                self.scales = parse_all(&values);
+                return self.scales.is_some();
+            }
+        } else if let Some(values) = values(line, AMCATNLO_PREFIX) {
+            if self.amcatnlo.is_none() {
+                self.amcatnlo = AmcAtNloLine::parse(&values);
+                return self.amcatnlo.is_some();
+            }
+        }
+        false
+    }
+}
+
+impl PdfLine {
+    fn parse(values: &[&str]) -> Option<PdfLine> {
+        if values.len() != 7 {
+            return None;
+        }
+        Some(PdfLine {
+            id: [values[0].parse().ok()?, values[1].parse().ok()?],
+            x: [values[2].parse().ok()?, values[3].parse().ok()?],
+            scale: values[4].parse().ok()?,
+            xpdf: [values[5].parse().ok()?, values[6].parse().ok()?],
+        })
+    }
+}
+
+impl AmcAtNloLine {
+    fn parse(values: &[&str]) -> Option<AmcAtNloLine> {
+        if values.len() < 7 {
+            return None;
+        }
+        Some(AmcAtNloLine {
+            event_type: values[0].parse().ok()?,
+            fks: [values[1].parse().ok()?, values[2].parse().ok()?],
+            fks_mother: values[3].parse().ok()?,
+            partner: values[4].parse().ok()?,
+            shower_scales: [values[5].parse().ok()?, values[6].parse().ok()?],
+            extra: parse_all(&values[7..])?,
+        })
+    }
+}
+
+/// Get the values of a line starting with `prefix`
+fn values<'a>(line: &'a str, prefix: &str) -> Option<Vec<&'a str>> {
+    let rest = line.strip_prefix(prefix)?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some(rest.split_whitespace().collect())
+}
+
+fn parse_all<T: FromStr>(values: &[&str]) -> Option<Vec<T>> {
+    values.iter().map(|value| value.parse().ok()).collect()
+}
+
+/// Formats the lines as found in the optional event information,
+/// without a final line break
+impl fmt::Display for CommentLines {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut separator = "";
+        if let Some(ref pdf) = self.pdf {
+            write!(
+                f,
+                "{} {} {} {:e} {:e} {:e} {:e} {:e}",
+                PDF_PREFIX,
+                pdf.id[0],
+                pdf.id[1],
+                pdf.x[0],
+                pdf.x[1],
+                pdf.scale,
+                pdf.xpdf[0],
+                pdf.xpdf[1]
+            )?;
+            separator = "\n";
+        }
+        if let Some(ref scales) = self.scales {
+            write!(f, "{}{}", separator, SCALE_PREFIX)?;
+            for scale in scales {
+                write!(f, " {:e}", scale)?;
+            }
+            separator = "\n";
+        }
+        if let Some(ref amcatnlo) = self.amcatnlo {
+            write!(
+                f,
+                "{}{} {} {} {} {} {} {:e} {:e}",
+                separator,
+                AMCATNLO_PREFIX,
+                amcatnlo.event_type,
+                amcatnlo.fks[0],
+                amcatnlo.fks[1],
+                amcatnlo.fks_mother,
+                amcatnlo.partner,
+                amcatnlo.shower_scales[0],
+                amcatnlo.shower_scales[1]
+            )?;
+            for value in &amcatnlo.extra {
+                write!(f, " {:e}", value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INFO: &str = "\
+#pdf 21 2 0.1 0.25 91.188 2.5 0.6
+<mgrwt>
+#scale 1 2
+</mgrwt>
+#scale 45.6 91.2
+#aMCatNLO 2 5 3 3 1 0.12E+03 0.11E+03 9 0 0 0.99E+00
+#pdf 1 2 3
+# other comment
+";
+
+    #[test]
+    fn parse() {
+        let mgrwt = INFO.find("<mgrwt>").unwrap()..INFO.find("#scale 45").unwrap() - 1;
+        let (lines, ranges) = CommentLines::parse(INFO, &[mgrwt]);
+        let lines = lines.unwrap();
+        let pdf = lines.pdf.as_ref().unwrap();
+        assert_eq!(pdf.id, [21, 2]);
+        assert_eq!(pdf.scale, 91.188);
+        assert_eq!(pdf.xpdf, [2.5, 0.6]);
+        assert_eq!(lines.scales, Some(vec![45.6, 91.2]));
+        let amcatnlo = lines.amcatnlo.as_ref().unwrap();
+        assert_eq!(amcatnlo.event_type, 2);
+        assert_eq!(amcatnlo.fks, [5, 3]);
+        assert_eq!(amcatnlo.shower_scales, [120., 110.]);
+        assert_eq!(amcatnlo.extra, [9., 0., 0., 0.99]);
+        assert_eq!(ranges.len(), 3);
+        let rest = ::tags::remove_ranges(INFO, &ranges);
+        assert_eq!(rest, "<mgrwt>\n#scale 1 2\n</mgrwt>\n#pdf 1 2 3\n# other comment\n");
+
+        let formatted = format!("{}\n", lines);
+        assert_eq!(CommentLines::parse(&formatted, &[]).0, Some(lines));
+        assert_eq!(CommentLines::parse("# comment\n#pdfs 1\n", &[]), (None, vec![]));
+    }
+}
@@ -0,0 +1,773 @@
+use std::collections::HashSet;
+use std::error;
+use std::fmt;
+use std::io::BufRead;
+use std::str::FromStr;
+
+use cutsinfo::CUTSINFO_TAG;
+use fields::Fields;
+use generator::GENERATOR_TAG;
+use initrwgt;
+use procinfo::{MERGEINFO_TAG, PROCINFO_TAG};
+use scales::SCALES_TAG;
+use tags::{self, Tag, HEADER_TAG};
+use weights::{RWGT_TAG, WEIGHTS_TAG};
+use xsecinfo::XSECINFO_TAG;
+use {ends_in_comment, is_between_blocks, EVENTGROUP_END, EVENTGROUP_TAG, EVENT_END, EVENT_TAG};
+use {INIT_END, LHEF_LAST_LINE, LHEF_TAG};
+
+const INIT_TAG: &str = "init";
+const WGT_TAG: &str = "wgt";
+
+/// How severe a `Finding` is
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Copy, Clone)]
+pub enum Severity {
+    /// The file violates the specification
+    Error,
+    /// The file is valid, but likely not as intended
+    Warning,
+}
+
+/// The kind of requirement checked by a `Finding`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Rule {
+    /// The `<LesHouchesEvents>` tag declares a supported version, and
+    /// only tags of that version are used
+    Version,
+    /// The required tags are present, closed, and in the right order
+    Structure,
+    /// The lines of the init block and the events have the right
+    /// number and type of entries
+    Format,
+    /// Tags have their required attributes
+    Attribute,
+    /// Weights used in events are declared in the `<initrwgt>` tag
+    Weights,
+    /// Information given in different places agrees, e.g. the process
+    /// IDs of the events and the init block
+    Consistency,
+}
+
+/// A single result of a conformance check
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Finding {
+    /// How severe the finding is
+    pub severity: Severity,
+    /// The violated requirement
+    pub rule: Rule,
+    /// Line of the input, counting from 1, if the finding refers to a
+    /// specific line
+    pub line: Option<usize>,
+    /// Description of the finding
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        match self.line {
+            Some(line) => write!(f, "line {}: {}: {}", line, severity, self.message),
+            None => write!(f, "{}: {}", severity, self.message),
+        }
+    }
+}
+
+/// Result of checking a file with `check_conformance`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct ConformanceReport {
+    /// The version declared in the `<LesHouchesEvents>` tag
+    pub version: Option<String>,
+    /// The number of events, counting each event in an `<eventgroup>`
+    pub events: usize,
+    /// All findings, in the order of the input
+    pub findings: Vec<Finding>,
+}
+
+impl ConformanceReport {
+    /// Whether the file conforms to the specification, i.e. there are
+    /// no errors
+    pub fn is_conformant(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    /// Get the findings with severity `Error`
+    pub fn errors(&self) -> impl Iterator<Item = &Finding> {
+        self.findings.iter().filter(|finding| finding.severity == Severity::Error)
+    }
+
+    /// Get the findings with severity `Warning`
+    pub fn warnings(&self) -> impl Iterator<Item = &Finding> {
+        self.findings.iter().filter(|finding| finding.severity == Severity::Warning)
+    }
+}
+
+/// Check an event file against the LHEF 1.0, 2.0, and 3.0
+/// specifications
+///
+/// In contrast to a `Reader`, which stops at the first problem and
+/// accepts some deviations from the standard, this collects all
+/// problems found in the file. The checks cover the required tags and
+/// their order, the entries of the init block and the events, required
+/// attributes, tags that are not part of the declared version, and the
+/// consistency of declared and used weights. Only I/O errors are
+/// returned as errors.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// let file = std::fs::File::open("events.lhe").unwrap();
+/// let report = lhef::check_conformance(std::io::BufReader::new(file)).unwrap();
+/// for finding in &report.findings {
+///     eprintln!("{}", finding);
+/// }
+/// assert!(report.is_conformant());
+/// ```
+pub fn check_conformance<Stream: BufRead>(
+    stream: Stream,
+) -> Result<ConformanceReport, Box<dyn error::Error>> {
+    let mut checker = Checker {
+        lines: Lines { stream, line: 0, unread: None },
+        version: 1,
+        header: String::new(),
+        processes: HashSet::new(),
+        weight_ids: None,
+        neve: None,
+        report: ConformanceReport::default(),
+    };
+    checker.check()?;
+    Ok(checker.report)
+}
+
+/// Input lines with their line numbers
+struct Lines<Stream> {
+    stream: Stream,
+    line: usize,
+    unread: Option<String>,
+}
+
+impl<Stream: BufRead> Lines<Stream> {
+    fn next(&mut self) -> Result<Option<String>, Box<dyn error::Error>> {
+        if let Some(line) = self.unread.take() {
+            self.line += 1;
+            return Ok(Some(line));
+        }
+        let mut bytes = Vec::new();
+        if self.stream.read_until(b'\n', &mut bytes)? == 0 {
+            return Ok(None);
+        }
+        self.line += 1;
+        Ok(Some(String::from_utf8_lossy(&bytes).into_owned()))
+    }
+
+    /// Put back the last line to be read again
+    fn unread(&mut self, line: String) {
+        self.line -= 1;
+        self.unread = Some(line);
+    }
+}
+
+struct Checker<Stream> {
+    lines: Lines<Stream>,
+    /// major version number
+    version: u32,
+    header: String,
+    /// process IDs declared in the init block
+    processes: HashSet<i32>,
+    /// IDs of the weights declared in the header
+    weight_ids: Option<Vec<String>>,
+    /// number of events declared in `<xsecinfo>`
+    neve: Option<usize>,
+    report: ConformanceReport,
+}
+
+impl<Stream: BufRead> Checker<Stream> {
+    fn add(&mut self, severity: Severity, rule: Rule, line: Option<usize>, message: String) {
+        self.report.findings.push(Finding { severity, rule, line, message });
+    }
+
+    fn error(&mut self, rule: Rule, message: String) {
+        let line = Some(self.lines.line);
+        self.add(Severity::Error, rule, line, message)
+    }
+
+    fn warning(&mut self, rule: Rule, message: String) {
+        let line = Some(self.lines.line);
+        self.add(Severity::Warning, rule, line, message)
+    }
+
+    fn check(&mut self) -> Result<(), Box<dyn error::Error>> {
+        if !self.check_root()? {
+            return Ok(());
+        }
+        if self.check_header()? {
+            self.check_weight_declarations();
+            self.check_init()?;
+        }
+        self.check_events()
+    }
+
+    /// Get the next line that is not blank or part of a comment
+    fn next_content(&mut self) -> Result<Option<String>, Box<dyn error::Error>> {
+        let mut in_comment = false;
+        while let Some(line) = self.lines.next()? {
+            if is_between_blocks(&line, in_comment) {
+                in_comment = ends_in_comment(&line, in_comment);
+                continue;
+            }
+            return Ok(Some(line));
+        }
+        Ok(None)
+    }
+
+    /// Check the opening `<LesHouchesEvents>` tag
+    fn check_root(&mut self) -> Result<bool, Box<dyn error::Error>> {
+        let mut line = self.next_content()?;
+        if line.as_ref().is_some_and(|line| line.trim_start().starts_with("<?xml")) {
+            line = self.next_content()?;
+        }
+        let line = match line {
+            Some(line) => line,
+            None => {
+                self.error(Rule::Structure, "Input is empty".to_owned());
+                return Ok(false);
+            }
+        };
+        let attributes = match tags::opening_tag(&line, LHEF_TAG) {
+            Some(attributes) => attributes,
+            None => {
+                let message = format!("Expected a <{}> tag, found '{}'", LHEF_TAG, line.trim());
+                self.error(Rule::Structure, message);
+                return Ok(false);
+            }
+        };
+        match attributes.iter().find(|&&(key, _)| key == "version") {
+            Some(&(_, version)) => {
+                self.report.version = Some(version.to_owned());
+                match ::version(version) {
+                    Ok(version) => self.version = version[..1].parse()?,
+                    Err(_) => {
+                        let message = format!("Unsupported version '{}'", version);
+                        self.error(Rule::Version, message)
+                    }
+                }
+            }
+            None => {
+                let message = format!("The <{}> tag has no version attribute", LHEF_TAG);
+                self.error(Rule::Version, message)
+            }
+        }
+        Ok(true)
+    }
+
+    /// Check the optional header up to the opening `<init>` tag
+    ///
+    /// Returns whether the init block was found.
+    fn check_header(&mut self) -> Result<bool, Box<dyn error::Error>> {
+        let mut headers = 0;
+        while let Some(line) = self.next_content()? {
+            if tags::opening_tag(&line, INIT_TAG).is_some() {
+                return Ok(true);
+            }
+            if is_event_start(&line) {
+                self.error(Rule::Structure, "Event before the <init> block".to_owned());
+                self.lines.unread(line);
+                return Ok(false);
+            }
+            if tags::tag_name(&line) != Some(HEADER_TAG) {
+                let message = format!("Unexpected line '{}' before the <init> block", line.trim());
+                self.error(Rule::Structure, message);
+                continue;
+            }
+            headers += 1;
+            if headers == 2 {
+                self.warning(Rule::Structure, "More than one <header> tag".to_owned());
+            }
+            self.header.push_str(&line);
+            if line.trim_end().ends_with("</header>") {
+                continue;
+            }
+            loop {
+                match self.lines.next()? {
+                    Some(line) => {
+                        self.header.push_str(&line);
+                        if line.trim() == "</header>" {
+                            break;
+                        }
+                    }
+                    None => {
+                        self.error(Rule::Structure, "Unterminated <header> tag".to_owned());
+                        return Ok(false);
+                    }
+                }
+            }
+        }
+        self.error(Rule::Structure, "Missing <init> block".to_owned());
+        Ok(false)
+    }
+
+    fn check_weight_declarations(&mut self) {
+        match initrwgt::parse_initrwgt(&self.header) {
+            Ok(Some(initrwgt)) => {
+                let ids: Vec<_> = initrwgt.weights().iter().map(|w| w.id.clone()).collect();
+                self.weight_ids = Some(ids);
+            }
+            Ok(None) => {}
+            Err(err) => {
+                let message = format!("Invalid <{}> tag: {}", initrwgt::INITRWGT_TAG, err);
+                self.add(Severity::Error, Rule::Attribute, None, message)
+            }
+        }
+    }
+
+    /// Check the init block after the opening tag
+    fn check_init(&mut self) -> Result<(), Box<dyn error::Error>> {
+        let line = match self.lines.next()? {
+            Some(line) => line,
+            None => {
+                self.error(Rule::Structure, "Unterminated <init> block".to_owned());
+                return Ok(());
+            }
+        };
+        let mut fields = Fields::new(&line);
+        let mut nprup = 0;
+        let ok = self.entries::<i32>(&mut fields, &["IDBMUP(1)", "IDBMUP(2)"])
+            && self.entries::<f64>(&mut fields, &["EBMUP(1)", "EBMUP(2)"])
+            && self.entries::<i32>(&mut fields, &["PDFGUP(1)", "PDFGUP(2)"])
+            && self.entries::<i32>(&mut fields, &["PDFSUP(1)", "PDFSUP(2)"])
+            && self.entry(&mut fields, "IDWTUP", |idwtup: i32| {
+                idwtup != 0 && idwtup.abs() <= 4
+            })
+            && self.entry(&mut fields, "NPRUP", |n: i32| {
+                nprup = n;
+                n >= 0
+            });
+        if ok {
+            self.check_extra_entries(&mut fields);
+        }
+        for _ in 0..nprup {
+            let line = match self.lines.next()? {
+                Some(line) => line,
+                None => break,
+            };
+            if line.trim() == INIT_END {
+                self.error(Rule::Format, "Missing process lines in <init> block".to_owned());
+                self.lines.unread(line);
+                break;
+            }
+            let mut fields = Fields::new(&line);
+            let mut lprup = None;
+            let ok = self.entries::<f64>(&mut fields, &["XSECUP", "XERRUP", "XMAXUP"])
+                && self.entry(&mut fields, "LPRUP", |id: i32| {
+                    lprup = Some(id);
+                    true
+                });
+            if ok {
+                self.check_extra_entries(&mut fields);
+            }
+            if let Some(id) = lprup {
+                if !self.processes.insert(id) {
+                    self.warning(Rule::Consistency, format!("Duplicate process ID {}", id));
+                }
+            }
+        }
+        let start = self.lines.line + 1;
+        let mut info = String::new();
+        loop {
+            match self.lines.next()? {
+                Some(ref line) if line.trim() == INIT_END => break,
+                Some(line) => info.push_str(&line),
+                None => {
+                    self.error(Rule::Structure, "Unterminated <init> block".to_owned());
+                    break;
+                }
+            }
+        }
+        for (range, tag) in tags::tags(&info) {
+            let line = start + info[..range.start].matches('\n').count();
+            self.check_init_tag(&tag, line);
+        }
+        Ok(())
+    }
+
+    fn check_init_tag(&mut self, tag: &Tag, line: usize) {
+        let required: &[&str] = match tag.name() {
+            XSECINFO_TAG => &["neve", "totxsec"],
+            PROCINFO_TAG | MERGEINFO_TAG => &["iproc"],
+            GENERATOR_TAG | CUTSINFO_TAG => &[],
+            _ => return,
+        };
+        if self.version < 2 {
+            let message = format!("<{}> requires version 2.0 or later", tag.name());
+            self.add(Severity::Warning, Rule::Version, Some(line), message);
+        }
+        for attribute in required {
+            if tag.attribute(attribute).is_none() {
+                let message = format!("<{}> has no '{}' attribute", tag.name(), attribute);
+                self.add(Severity::Error, Rule::Attribute, Some(line), message);
+            }
+        }
+        if tag.name() == XSECINFO_TAG {
+            self.neve = tag.attribute("neve").and_then(|neve| neve.parse().ok());
+        }
+    }
+
+    /// Check the events after the init block
+    fn check_events(&mut self) -> Result<(), Box<dyn error::Error>> {
+        let mut closed = false;
+        while let Some(line) = self.next_content()? {
+            if closed {
+                let message = format!("Content after the closing </{}> tag", LHEF_TAG);
+                self.warning(Rule::Structure, message);
+                break;
+            }
+            let trimmed = line.trim();
+            if trimmed == LHEF_LAST_LINE {
+                closed = true;
+            } else if tags::opening_tag(&line, EVENT_TAG).is_some() {
+                self.check_event()?;
+            } else if tags::opening_tag(&line, EVENTGROUP_TAG).is_some() {
+                if self.version < 2 {
+                    let message = format!("<{}> requires version 2.0 or later", EVENTGROUP_TAG);
+                    self.warning(Rule::Version, message);
+                }
+                self.check_eventgroup()?;
+            } else {
+                let message = format!("Unexpected line '{}' between events", trimmed);
+                self.error(Rule::Structure, message);
+            }
+        }
+        if !closed {
+            let message = format!("Missing closing </{}> tag", LHEF_TAG);
+            self.add(Severity::Error, Rule::Structure, None, message);
+        }
+        if let Some(neve) = self.neve {
+            if neve != self.report.events {
+                let message = format!(
+                    "<{}> declares {} events, but the file contains {}",
+                    XSECINFO_TAG, neve, self.report.events
+                );
+                self.add(Severity::Warning, Rule::Consistency, None, message);
+            }
+        }
+        Ok(())
+    }
+
+    fn check_eventgroup(&mut self) -> Result<(), Box<dyn error::Error>> {
+        while let Some(line) = self.next_content()? {
+            if line.trim() == EVENTGROUP_END {
+                return Ok(());
+            }
+            if tags::opening_tag(&line, EVENT_TAG).is_some() {
+                self.check_event()?;
+                continue;
+            }
+            let message = format!("Missing closing </{}> tag", EVENTGROUP_TAG);
+            self.error(Rule::Structure, message);
+            self.lines.unread(line);
+            return Ok(());
+        }
+        Ok(())
+    }
+
+    /// Check an event after the opening tag
+    fn check_event(&mut self) -> Result<(), Box<dyn error::Error>> {
+        self.report.events += 1;
+        let line = match self.lines.next()? {
+            Some(line) => line,
+            None => return Ok(()),
+        };
+        if is_event_end(&line) {
+            self.error(Rule::Format, "Empty event".to_owned());
+            self.finish_event(line);
+            return Ok(());
+        }
+        let mut fields = Fields::new(&line);
+        let mut nup = 0;
+        let mut idrup = None;
+        let ok = self.entry(&mut fields, "NUP", |n: i32| {
+            nup = n;
+            n >= 0
+        }) && self.entry(&mut fields, "IDRUP", |id: i32| {
+            idrup = Some(id);
+            true
+        }) && self.entries::<f64>(&mut fields, &["XWGTUP", "SCALUP", "AQEDUP", "AQCDUP"]);
+        if ok {
+            self.check_extra_entries(&mut fields);
+        }
+        if let Some(id) = idrup {
+            if !self.processes.is_empty() && !self.processes.contains(&id) {
+                let message = format!("Process ID {} is not declared in the <init> block", id);
+                self.warning(Rule::Consistency, message);
+            }
+        }
+        for _ in 0..nup {
+            let line = match self.lines.next()? {
+                Some(line) => line,
+                None => return Ok(()),
+            };
+            if is_event_end(&line) {
+                self.error(Rule::Format, "Missing particle lines".to_owned());
+                self.finish_event(line);
+                return Ok(());
+            }
+            let mut fields = Fields::new(&line);
+            let ok = self.entries::<i32>(&mut fields, &["IDUP", "ISTUP"])
+                && self.entries::<i32>(&mut fields, &["MOTHUP(1)", "MOTHUP(2)"])
+                && self.entries::<i32>(&mut fields, &["ICOLUP(1)", "ICOLUP(2)"])
+                && self.entries::<f64>(&mut fields, &["PUP(1)", "PUP(2)", "PUP(3)", "PUP(4)"])
+                && self.entries::<f64>(&mut fields, &["PUP(5)", "VTIMUP", "SPINUP"]);
+            if ok {
+                self.check_extra_entries(&mut fields);
+            }
+        }
+        let start = self.lines.line + 1;
+        let mut info = String::new();
+        while let Some(line) = self.lines.next()? {
+            if is_event_end(&line) {
+                self.finish_event(line);
+                break;
+            }
+            info.push_str(&line);
+        }
+        for (range, tag) in tags::tags(&info) {
+            let line = start + info[..range.start].matches('\n').count();
+            self.check_event_tag(&tag, line);
+        }
+        Ok(())
+    }
+
+    /// Handle the line ending the event, which should be the closing tag
+    fn finish_event(&mut self, line: String) {
+        if line.trim() != EVENT_END {
+            let message = format!("Missing closing </{}> tag", EVENT_TAG);
+            self.error(Rule::Structure, message);
+            self.lines.unread(line);
+        }
+    }
+
+    fn check_event_tag(&mut self, tag: &Tag, line: usize) {
+        let name = tag.name();
+        if self.version < 3 && (name == WEIGHTS_TAG || name == SCALES_TAG) {
+            let message = format!("<{}> requires version 3.0", name);
+            self.add(Severity::Warning, Rule::Version, Some(line), message);
+        }
+        let mut findings = Vec::new();
+        match (name, self.weight_ids.as_ref()) {
+            (WEIGHTS_TAG, None) | (RWGT_TAG, None) => {
+                let message = format!("<{}> is used without an <initrwgt> declaration", name);
+                findings.push((Severity::Error, Rule::Weights, message));
+            }
+            (WEIGHTS_TAG, Some(ids)) => {
+                let found = tag.content().split_whitespace().count();
+                if found != ids.len() {
+                    let message = format!(
+                        "<{}> has {} values, but {} weights are declared",
+                        name,
+                        found,
+                        ids.len()
+                    );
+                    findings.push((Severity::Error, Rule::Weights, message));
+                }
+            }
+            (RWGT_TAG, Some(ids)) => {
+                let mut used = HashSet::new();
+                for (_, wgt) in tags::tags(tag.content()) {
+                    if wgt.name() != WGT_TAG {
+                        continue;
+                    }
+                    match wgt.attribute("id") {
+                        Some(id) if ids.iter().any(|declared| declared == id) => {
+                            used.insert(id);
+                        }
+                        Some(id) => {
+                            let message = format!("Weight '{}' is not declared", id);
+                            findings.push((Severity::Error, Rule::Weights, message));
+                        }
+                        None => {
+                            let message = format!("<{}> has no 'id' attribute", WGT_TAG);
+                            findings.push((Severity::Error, Rule::Attribute, message));
+                        }
+                    }
+                }
+                let missing = ids.iter().filter(|id| !used.contains(id.as_str())).count();
+                if missing > 0 {
+                    let message = format!("<{}> lacks {} declared weights", name, missing);
+                    findings.push((Severity::Warning, Rule::Weights, message));
+                }
+            }
+            _ => {}
+        }
+        for (severity, rule, message) in findings {
+            self.add(severity, rule, Some(line), message);
+        }
+    }
+
+    /// Check the next entries of a line, which should be of type `T`
+    ///
+    /// Returns whether all entries were found.
+    fn entries<T: FromStr>(&mut self, fields: &mut Fields, names: &[&str]) -> bool {
+        names.iter().all(|name| self.entry(fields, name, |_: T| true))
+    }
+
+    /// Check the next entry of a line, which should be of type `T` and
+    /// pass the check `valid`
+    ///
+    /// Returns whether the entry was found.
+    fn entry<T: FromStr, F>(&mut self, fields: &mut Fields, name: &str, valid: F) -> bool
+    where
+        F: FnOnce(T) -> bool,
+    {
+        let field = match fields.next() {
+            Some(field) => field,
+            None => {
+                self.error(Rule::Format, format!("Missing entry {}", name));
+                return false;
+            }
+        };
+        match field.parse().map(valid) {
+            Ok(true) => {}
+            _ => {
+                let message = format!("Invalid value '{}' for entry {}", field, name);
+                self.error(Rule::Format, message)
+            }
+        }
+        true
+    }
+
+    fn check_extra_entries(&mut self, fields: &mut Fields) {
+        if let Some(field) = fields.next() {
+            let message = format!("Unexpected additional entry '{}'", field);
+            self.error(Rule::Format, message);
+        }
+    }
+}
+
+fn is_event_start(line: &str) -> bool {
+    tags::opening_tag(line, EVENT_TAG).is_some()
+        || tags::opening_tag(line, EVENTGROUP_TAG).is_some()
+}
+
+/// Check if a line ends an event, either regularly or because the
+/// closing tag is missing
+fn is_event_end(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed == EVENT_END
+        || trimmed == EVENTGROUP_END
+        || trimmed == LHEF_LAST_LINE
+        || is_event_start(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_util::SyntheticEvents;
+
+    const SAMPLE: &str = r#"<LesHouchesEvents version="3.0">
+<header>
+<initrwgt>
+<weight id="a">first</weight>
+<weight id="b">second</weight>
+</initrwgt>
+</header>
+<init>
+2212 2212 6500 6500 0 0 0 0 3 1
+1 0 1 1
+<xsecinfo neve="2" totxsec="1"/>
+</init>
+<event>
+1 1 1 10 0 0.1
+21 1 0 0 0 0 0 0 0 0 0 0 9
+<weights>1 2</weights>
+</event>
+<event>
+1 1 1 10 0 0.1
+21 1 0 0 0 0 0 0 0 0 0 0 9
+<rwgt>
+<wgt id="a">1</wgt>
+<wgt id="b">2</wgt>
+</rwgt>
+</event>
+</LesHouchesEvents>
+"#;
+
+    fn check(text: &str) -> ConformanceReport {
+        check_conformance(text.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn conformant() {
+        let report = check(SAMPLE);
+        assert_eq!(report.findings, []);
+        assert_eq!(report.version, Some("3.0".to_owned()));
+        assert_eq!(report.events, 2);
+        let synthetic = SyntheticEvents::new().num_events(20).num_weights(3).to_bytes();
+        let report = check_conformance(synthetic.as_slice()).unwrap();
+        assert!(report.is_conformant(), "{:?}", report.findings);
+    }
+
+    #[test]
+    fn violations() {
+        let text = SAMPLE
+            .replace("version=\"3.0\"", "version=\"1.0\"")
+            .replace("neve=\"2\"", "")
+            .replace("1 1 1 10 0 0.1\n21", "1 2 1 10 0 x\n21")
+            .replacen("<weights>1 2</weights>\n</event>", "<weights>1</weights>", 1)
+            .replace("<wgt id=\"b\">", "<wgt id=\"c\">")
+            .replace("</LesHouchesEvents>\n", "");
+        let report = check(&text);
+        let findings: Vec<_> = report
+            .findings
+            .iter()
+            .map(|finding| (finding.severity, finding.rule, finding.line))
+            .collect();
+        use self::Rule::*;
+        use self::Severity::*;
+        assert_eq!(
+            findings,
+            [
+                (Warning, Version, Some(11)),
+                (Error, Attribute, Some(11)),
+                (Error, Format, Some(14)),
+                (Warning, Consistency, Some(14)),
+                (Error, Structure, Some(17)),
+                (Warning, Version, Some(16)),
+                (Error, Weights, Some(16)),
+                (Error, Format, Some(18)),
+                (Warning, Consistency, Some(18)),
+                (Error, Weights, Some(20)),
+                (Warning, Weights, Some(20)),
+                (Error, Structure, None),
+            ]
+        );
+        assert!(!report.is_conformant());
+        assert_eq!(report.warnings().count(), 5);
+        assert_eq!(
+            report.findings[2].to_string(),
+            "line 14: error: Invalid value 'x' for entry AQCDUP"
+        );
+    }
+
+    #[test]
+    fn structure() {
+        let report = check("<LesHouchesEvents>\n<event>\n");
+        let messages: Vec<_> = report.findings.iter().map(|f| f.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "The <LesHouchesEvents> tag has no version attribute",
+                "Event before the <init> block",
+                "Missing closing </LesHouchesEvents> tag",
+            ]
+        );
+        let report = check("<event>\n");
+        assert_eq!(report.findings.len(), 1);
+        assert_eq!(report.findings[0].rule, Rule::Structure);
+    }
+}
@@ -0,0 +1,345 @@
+use std::error;
+use std::io::{BufRead, Write};
+use std::ops::Range;
+
+use initrwgt::{InitRwgt, WeightEntry, WeightInfo};
+use tags;
+use weights::{Weight, WeightFormat, Weights};
+use {Attributes, Block, Reader, Writer, HEPEUP};
+
+/// ID of the weight declared for the nominal event weight when
+/// upgrading to version 3.0
+pub const NOMINAL_WEIGHT_ID: &str = "nominal";
+
+/// Rewrite an event file as LHEF version 3.0
+///
+/// The header and the attributes of the `<LesHouchesEvents>` tag are
+/// kept. Unless the header already declares weights, an `<initrwgt>`
+/// declaring a single weight with ID `NOMINAL_WEIGHT_ID` is added to
+/// the `<header>` block, which is created if necessary. Each event
+/// without additional weights then gets a `<rwgt>` tag with its nominal
+/// weight.
+///
+/// Version 3.0 only allows tags and lines starting with `#` as optional
+/// information. Any other free-form text in the init block and the
+/// events is therefore turned into `#` lines.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// let file = std::fs::File::open("old.lhe").unwrap();
+/// let reader = lhef::Reader::new(std::io::BufReader::new(file)).unwrap();
+/// let output = std::fs::File::create("new.lhe").unwrap();
+/// lhef::upgrade_to_v3(reader, std::io::BufWriter::new(output)).unwrap();
+/// ```
+pub fn upgrade_to_v3<R: BufRead, W: Write>(
+    mut reader: Reader<R>,
+    stream: W,
+) -> Result<(), Box<dyn error::Error>> {
+    let add_nominal = reader.initrwgt().is_none();
+    let mut writer = Writer::with_root_attributes(stream, "3.0", reader.root_attributes())?;
+    if add_nominal {
+        let initrwgt = InitRwgt {
+            entries: vec![WeightEntry::Weight(WeightInfo {
+                id: NOMINAL_WEIGHT_ID.to_owned(),
+                attr: Attributes::new(),
+                description: "nominal event weight".to_owned(),
+            })],
+        };
+        writer.header(&insert_into_header(reader.header(), &initrwgt.to_string()))?;
+    } else {
+        writer.header(reader.header())?;
+    }
+    let mut heprup = reader.heprup().clone();
+    heprup.info = comment_free_text(&heprup.info);
+    writer.heprup(&heprup)?;
+
+    let upgrade = |mut event: HEPEUP| {
+        event.info = comment_free_text(&event.info);
+        if add_nominal && event.weights.is_none() {
+            event.weights = Some(Weights {
+                weights: vec![Weight {
+                    id: Some(NOMINAL_WEIGHT_ID.to_owned()),
+                    value: event.XWGTUP,
+                }],
+                format: WeightFormat::Rwgt,
+            });
+        }
+        event
+    };
+    while let Some(block) = reader.block()? {
+        let block = match block {
+            Block::Event(event) => Block::Event(upgrade(event)),
+            Block::EventGroup(events) => {
+                Block::EventGroup(events.into_iter().map(upgrade).collect())
+            }
+        };
+        writer.block(&block)?;
+    }
+    writer.finish()
+}
+
+/// How to treat version 3.0 tags when converting to version 1.0
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum TagHandling {
+    /// Remove the tags
+    Strip,
+    /// Keep the tags inside an XML comment
+    Comment,
+}
+
+/// Rewrite an event file as LHEF version 1.0
+///
+/// All tags in the optional information of the init block and the
+/// events, including the ones parsed into dedicated `HEPEUP` fields,
+/// are removed or moved into an XML comment, depending on `tags`.
+/// The same applies to attributes of the `<event>` tags. Events in an
+/// `<eventgroup>` are written as individual events. The header and the
+/// attributes of the `<LesHouchesEvents>` tag are kept unchanged.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use lhef::TagHandling;
+///
+/// let file = std::fs::File::open("new.lhe").unwrap();
+/// let reader = lhef::Reader::new(std::io::BufReader::new(file)).unwrap();
+/// let output = std::fs::File::create("old.lhe").unwrap();
+/// let output = std::io::BufWriter::new(output);
+/// lhef::downgrade_to_v1(reader, output, TagHandling::Comment).unwrap();
+/// ```
+pub fn downgrade_to_v1<R: BufRead, W: Write>(
+    mut reader: Reader<R>,
+    stream: W,
+    tags: TagHandling,
+) -> Result<(), Box<dyn error::Error>> {
+    let mut writer = Writer::with_root_attributes(stream, "1.0", reader.root_attributes())?;
+    writer.header(reader.header())?;
+    let mut heprup = reader.heprup().clone();
+    heprup.info = strip_tags(&heprup.info, Vec::new(), tags);
+    writer.heprup(&heprup)?;
+
+    let downgrade = |mut event: HEPEUP| {
+        let mut removed = Vec::new();
+        if !event.attr.is_empty() {
+            removed.push(format!("<{}{}>", ::EVENT_TAG, event.attr));
+            event.attr = Attributes::new();
+        }
+        removed.extend(event.scales.take().map(|tag| tag.to_string()));
+        removed.extend(event.clustering.take().map(|tag| tag.to_string()));
+        removed.extend(event.mgrwt.take().map(|tag| tag.to_string()));
+        removed.extend(event.weights.take().map(|tag| tag.to_string()));
+        event.info = strip_tags(&event.info, removed, tags);
+        event.extensions = Default::default();
+        event
+    };
+    while let Some(block) = reader.block()? {
+        match block {
+            Block::Event(event) => writer.event(&downgrade(event))?,
+            Block::EventGroup(events) => {
+                for event in events {
+                    writer.event(&downgrade(event))?;
+                }
+            }
+        }
+    }
+    writer.finish()
+}
+
+/// Add `text` at the end of the `<header>` block in `header`
+///
+/// If there is no `<header>` block, a new one is appended.
+fn insert_into_header(header: &str, text: &str) -> String {
+    let block = tags::tags(header).find(|(_, tag)| tag.name() == tags::HEADER_TAG);
+    match block {
+        Some((range, _)) => {
+            let end = range.end - ::HEADER_END.len();
+            let separator = if header[..end].ends_with('\n') { "" } else { "\n" };
+            format!("{}{}{}\n{}", &header[..end], separator, text, &header[end..])
+        },
+        None => format!("{}{}\n{}\n{}\n", header, ::HEADER_START, text, ::HEADER_END),
+    }
+}
+
+/// Remove all tags from `info`
+///
+/// With `TagHandling::Comment`, the removed tags and the additional
+/// `removed` text are appended inside an XML comment. Since comments
+/// must not contain `--`, any occurrence is replaced by `- -`.
+fn strip_tags(info: &str, mut removed: Vec<String>, tags: TagHandling) -> String {
+    let ranges: Vec<_> = tags::tags(info).map(|(range, _)| range).collect();
+    let mut result = tags::remove_ranges(info, &ranges);
+    removed.extend(ranges.into_iter().map(|range| info[range].to_owned()));
+    if tags == TagHandling::Strip || removed.is_empty() {
+        return result;
+    }
+    if !result.is_empty() && !result.ends_with('\n') {
+        result.push('\n');
+    }
+    result.push_str(::COMMENT_START);
+    result.push('\n');
+    for text in removed {
+        result.push_str(&text.replace("--", "- -"));
+        result.push('\n');
+    }
+    result.push_str(::COMMENT_END);
+    result.push('\n');
+    result
+}
+
+/// Turn lines of free-form text outside tags and comments into `#` lines
+fn comment_free_text(info: &str) -> String {
+    let mut protected: Vec<Range<usize>> = tags::tags(info).map(|(range, _)| range).collect();
+    let mut pos = 0;
+    while let Some(start) = info[pos..].find(::COMMENT_START) {
+        let start = pos + start;
+        let end = info[start..]
+            .find(::COMMENT_END)
+            .map(|end| start + end + ::COMMENT_END.len())
+            .unwrap_or_else(|| info.len());
+        protected.push(start..end);
+        pos = end;
+    }
+    let mut result = String::with_capacity(info.len());
+    let mut line_start = 0;
+    for line in info.split_inclusive('\n') {
+        let line_end = line_start + line.len();
+        let is_protected = protected
+            .iter()
+            .any(|range| range.start < line_end && line_start < range.end);
+        let text = line.trim();
+        if !is_protected && !text.is_empty() && !text.starts_with('#') {
+            result.push_str("# ");
+        }
+        result.push_str(line);
+        line_start = line_end;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V1: &str = r#"<LesHouchesEvents version="1.0">
+<!--
+File generated with an old generator
+-->
+<init>
+2212 2212 6500 6500 0 0 0 0 3 1
+1 0 1 1
+free run information
+</init>
+<event>
+1 1 0.5 10 0 0.1
+21 1 0 0 0 0 0 0 0 0 0 0 9
+free event information
+<scales muf="10"/>
+</event>
+</LesHouchesEvents>
+"#;
+
+    #[test]
+    fn upgrade() {
+        let reader = Reader::new(V1.as_bytes()).unwrap();
+        let mut output = Vec::new();
+        upgrade_to_v3(reader, &mut output).unwrap();
+
+        let mut upgraded = Reader::new(output.as_slice()).unwrap();
+        assert_eq!(upgraded.version(), "3.0");
+        assert!(upgraded.header().contains("File generated with an old generator"));
+        let weights = upgraded.initrwgt().unwrap().weights();
+        assert_eq!(weights[0].id, NOMINAL_WEIGHT_ID);
+        assert_eq!(upgraded.heprup().info, "# free run information\n");
+        let event = upgraded.event().unwrap().unwrap();
+        assert_eq!(event.weight(NOMINAL_WEIGHT_ID), Some(0.5));
+        assert_eq!(event.info, "# free event information\n");
+        assert_eq!(event.scales.unwrap().muf, Some(10.));
+        assert_eq!(upgraded.event().unwrap(), None);
+    }
+
+    #[test]
+    fn upgrade_with_header() {
+        let input = V1
+            .replace("version=\"1.0\"", "version=\"1.0\" generator=\"old\"")
+            .replace("<init>", "<header>\n<run>1</run>\n</header>\n<init>");
+        let reader = Reader::new(input.as_bytes()).unwrap();
+        let mut output = Vec::new();
+        upgrade_to_v3(reader, &mut output).unwrap();
+
+        let upgraded = Reader::new(output.as_slice()).unwrap();
+        assert_eq!(upgraded.root_attributes().get("generator"), Some("old"));
+        let header = upgraded.header();
+        assert_eq!(header.matches("<header>").count(), 1);
+        assert!(header.contains("<run>1</run>\n<initrwgt>"));
+        assert!(header.starts_with("<!--\nFile generated with an old generator\n-->\n"));
+        assert_eq!(upgraded.initrwgt().unwrap().weights()[0].id, NOMINAL_WEIGHT_ID);
+        assert_eq!(insert_into_header("<header></header>", "text"), "<header>\ntext\n</header>");
+    }
+
+    const V3: &str = r#"<LesHouchesEvents version="3.0" generator="new">
+<init>
+2212 2212 6500 6500 0 0 0 0 3 1
+1 0 1 1
+<generator name="test"/>
+</init>
+<eventgroup>
+<event npLO="1">
+1 1 0.5 10 0 0.1
+21 1 0 0 0 0 0 0 0 0 0 0 9
+free event information
+<scales muf="10"/>
+<unknown>
+</unknown>
+</event>
+</eventgroup>
+</LesHouchesEvents>
+"#;
+
+    fn downgrade(tags: TagHandling) -> Vec<u8> {
+        let reader = Reader::new(V3.as_bytes()).unwrap();
+        let mut output = Vec::new();
+        downgrade_to_v1(reader, &mut output, tags).unwrap();
+        output
+    }
+
+    #[test]
+    fn downgrade_strip() {
+        let output = downgrade(TagHandling::Strip);
+        let mut downgraded = Reader::new(output.as_slice()).unwrap();
+        assert_eq!(downgraded.version(), "1.0");
+        assert_eq!(downgraded.root_attributes().get("generator"), Some("new"));
+        assert!(downgraded.heprup().info.is_empty());
+        match downgraded.block().unwrap() {
+            Some(Block::Event(event)) => {
+                assert_eq!(event.info, "free event information\n");
+                assert!(event.attr.is_empty());
+                assert_eq!(event.scales, None);
+            },
+            block => panic!("expected event, got {:?}", block),
+        }
+        assert_eq!(downgraded.block().unwrap(), None);
+    }
+
+    #[test]
+    fn downgrade_comment() {
+        let output = downgrade(TagHandling::Comment);
+        let mut downgraded = Reader::new(output.as_slice()).unwrap();
+        assert_eq!(downgraded.heprup().info, "<!--\n<generator name=\"test\"/>\n-->\n");
+        let event = downgraded.event().unwrap().unwrap();
+        assert_eq!(event.scales, None);
+        let lines: Vec<_> = event.info.lines().collect();
+        assert_eq!(lines[..3], ["free event information", "<!--", "<event npLO=\"1\">"]);
+        assert!(lines[3].starts_with("<scales muf=\""));
+        assert_eq!(lines[4..], ["<unknown>", "</unknown>", "-->"]);
+    }
+
+    #[test]
+    fn free_text() {
+        let info = "text\n# comment\n<!--\nnot text\n-->\n<tag>\nnot text\n</tag>\n\n";
+        assert_eq!(
+            comment_free_text(info),
+            "# text\n# comment\n<!--\nnot text\n-->\n<tag>\nnot text\n</tag>\n\n"
+        );
+    }
+}
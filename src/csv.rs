@@ -0,0 +1,210 @@
+use std::error;
+use std::fmt;
+use std::io::{self, Write};
+
+use {EventSink, HEPEUP, HEPRUP};
+
+/// A column of the CSV output of a `CsvWriter`
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum CsvColumn {
+    /// Event number, see `CsvWriter`
+    Event,
+    /// Event weight `XWGTUP`
+    Weight,
+    /// Process ID `IDRUP`
+    Process,
+    /// Particle ID `IDUP`
+    Pdg,
+    /// Status code `ISTUP`
+    Status,
+    /// Momentum in x direction
+    Px,
+    /// Momentum in y direction
+    Py,
+    /// Momentum in z direction
+    Pz,
+    /// Energy
+    E,
+    /// Mass
+    M,
+    /// Position of the first mother, counting from 1, or 0
+    Mother1,
+    /// Position of the second mother, counting from 1, or 0
+    Mother2,
+}
+
+impl CsvColumn {
+    /// All columns in their default order
+    pub const ALL: [CsvColumn; 12] = [
+        CsvColumn::Event,
+        CsvColumn::Weight,
+        CsvColumn::Process,
+        CsvColumn::Pdg,
+        CsvColumn::Status,
+        CsvColumn::Px,
+        CsvColumn::Py,
+        CsvColumn::Pz,
+        CsvColumn::E,
+        CsvColumn::M,
+        CsvColumn::Mother1,
+        CsvColumn::Mother2,
+    ];
+}
+
+/// Formats the column name as used in the header row
+impl fmt::Display for CsvColumn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::CsvColumn::*;
+        let name = match *self {
+            Event => "event",
+            Weight => "weight",
+            Process => "process",
+            Pdg => "pdg",
+            Status => "status",
+            Px => "px",
+            Py => "py",
+            Pz => "pz",
+            E => "e",
+            M => "m",
+            Mother1 => "mother1",
+            Mother2 => "mother2",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Writer for particles as comma-separated values
+///
+/// Each particle is written as one row, preceded by a header row with
+/// the column names. The event number is taken from the event, see
+/// `HEPEUP::event_number`, or otherwise counts the written events
+/// starting from 0.
+///
+/// `CsvWriter` is an `EventSink`, so it can be used alongside an LHEF
+/// `Writer` in a `Tee`.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use lhef::CsvColumn;
+///
+/// let file = std::fs::File::open("events.lhe").unwrap();
+/// let mut reader = lhef::Reader::new(std::io::BufReader::new(file)).unwrap();
+/// let output = std::fs::File::create("particles.csv").unwrap();
+/// let columns = [CsvColumn::Event, CsvColumn::Pdg, CsvColumn::Px, CsvColumn::Py];
+/// let mut csv = lhef::CsvWriter::with_columns(output, &columns).unwrap();
+/// while let Some(event) = reader.event().unwrap() {
+///     csv.event(&event).unwrap();
+/// }
+/// ```
+pub struct CsvWriter<Stream: Write> {
+    stream: Stream,
+    columns: Vec<CsvColumn>,
+    events: u64,
+}
+
+impl<Stream: Write> CsvWriter<Stream> {
+    /// Create a writer with all columns
+    pub fn new(stream: Stream) -> io::Result<CsvWriter<Stream>> {
+        Self::with_columns(stream, &CsvColumn::ALL)
+    }
+
+    /// Create a writer with the given columns in the given order
+    pub fn with_columns(
+        mut stream: Stream,
+        columns: &[CsvColumn],
+    ) -> io::Result<CsvWriter<Stream>> {
+        let names: Vec<_> = columns.iter().map(|column| column.to_string()).collect();
+        writeln!(stream, "{}", names.join(","))?;
+        Ok(CsvWriter {
+            stream,
+            columns: columns.to_vec(),
+            events: 0,
+        })
+    }
+
+    /// Write all particles of an event
+    pub fn event(&mut self, event: &HEPEUP) -> io::Result<()> {
+        let number = event.event_number().unwrap_or(self.events);
+        self.events += 1;
+        for i in 0..event.NUP as usize {
+            for (n, column) in self.columns.iter().enumerate() {
+                if n > 0 {
+                    self.stream.write_all(b",")?;
+                }
+                use self::CsvColumn::*;
+                match *column {
+                    Event => write!(self.stream, "{}", number),
+                    Weight => write!(self.stream, "{:e}", event.XWGTUP),
+                    Process => write!(self.stream, "{}", event.IDRUP),
+                    Pdg => write!(self.stream, "{}", event.IDUP[i]),
+                    Status => write!(self.stream, "{}", event.ISTUP[i]),
+                    Px => write!(self.stream, "{:e}", event.PUP[i][0]),
+                    Py => write!(self.stream, "{:e}", event.PUP[i][1]),
+                    Pz => write!(self.stream, "{:e}", event.PUP[i][2]),
+                    E => write!(self.stream, "{:e}", event.PUP[i][3]),
+                    M => write!(self.stream, "{:e}", event.PUP[i][4]),
+                    Mother1 => write!(self.stream, "{}", event.MOTHUP[i][0]),
+                    Mother2 => write!(self.stream, "{}", event.MOTHUP[i][1]),
+                }?;
+            }
+            self.stream.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    /// Flush the output
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+
+    /// Get the underlying stream
+    pub fn into_inner(self) -> Stream {
+        self.stream
+    }
+}
+
+impl<Stream: Write> EventSink for CsvWriter<Stream> {
+    fn heprup(&mut self, _heprup: &HEPRUP) -> Result<(), Box<dyn error::Error>> {
+        Ok(())
+    }
+
+    fn event(&mut self, event: &HEPEUP) -> Result<(), Box<dyn error::Error>> {
+        Ok(CsvWriter::event(self, event)?)
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn error::Error>> {
+        Ok(self.flush()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_util::MockEvent;
+
+    #[test]
+    fn csv() {
+        let mut event = MockEvent::drell_yan().with_weight(2.5).with_process_id(3).build();
+        let mut csv = CsvWriter::new(Vec::new()).unwrap();
+        csv.event(&event).unwrap();
+        event.set_event_number(7);
+        csv.event(&event).unwrap();
+        let output = String::from_utf8(csv.into_inner()).unwrap();
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines.len(), 1 + 2 * event.NUP as usize);
+        assert_eq!(lines[0], "event,weight,process,pdg,status,px,py,pz,e,m,mother1,mother2");
+        let first: Vec<_> = lines[1].split(',').collect();
+        assert_eq!(first[..5], ["0", "2.5e0", "3", &event.IDUP[0].to_string(), "-1"]);
+        assert_eq!(first[7].parse::<f64>().unwrap(), event.PUP[0][2]);
+        assert!(lines[lines.len() - 1].starts_with("7,"));
+
+        let columns = [CsvColumn::Pdg, CsvColumn::Mother1];
+        let mut csv = CsvWriter::with_columns(Vec::new(), &columns).unwrap();
+        csv.event(&event).unwrap();
+        let output = String::from_utf8(csv.into_inner()).unwrap();
+        let last = output.lines().last().unwrap();
+        let n = event.NUP as usize - 1;
+        assert_eq!(last, format!("{},{}", event.IDUP[n], event.MOTHUP[n][0]));
+    }
+}
@@ -0,0 +1,187 @@
+use std::error;
+use std::fmt;
+
+use tags::{self, Tag};
+use {parse, ParseError};
+
+pub(crate) const CUTSINFO_TAG: &str = "cutsinfo";
+
+/// Generator-level cuts from the LHEF 3.0 `<cutsinfo>` tag
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct CutsInfo {
+    /// Named groups of particles from `<ptype>` tags
+    pub ptypes: Vec<ParticleType>,
+    /// Cuts from `<cut>` tags
+    pub cuts: Vec<Cut>,
+}
+
+/// A named group of particles, e.g. all partons making up a jet
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub struct ParticleType {
+    /// Name of the group
+    pub name: String,
+    /// PDG IDs of the particles in the group
+    pub ids: Vec<i32>,
+}
+
+/// A cut on a kinematic variable
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub struct Cut {
+    /// Type of the cut, e.g. `PT`, `ETA`, or `m`
+    pub ctype: String,
+    /// First particle, either a PDG ID or the name of a particle type
+    pub p1: Option<String>,
+    /// Second particle for cuts on pairs of particles
+    pub p2: Option<String>,
+    /// Lower bound
+    pub min: Option<f64>,
+    /// Upper bound
+    pub max: Option<f64>,
+}
+
+/// Bounds beyond this magnitude are treated as absent
+const UNBOUNDED: f64 = 1e30;
+
+impl CutsInfo {
+    fn from_tag(tag: &Tag) -> Result<CutsInfo, Box<dyn error::Error>> {
+        let mut info = CutsInfo::default();
+        for (_, entry) in tags::tags(tag.content()) {
+            match entry.name() {
+                "ptype" => {
+                    let name = parse::<String>("ptype name", entry.attribute("name"))?;
+                    let ids = entry
+                        .content()
+                        .split_whitespace()
+                        .map(|id| parse::<i32>("ptype id", Some(id)))
+                        .collect::<Result<_, _>>()?;
+                    info.ptypes.push(ParticleType { name, ids });
+                }
+                "cut" => {
+                    let ctype = parse::<String>("cut type", entry.attribute("type"))?;
+                    let mut bounds = entry.content().split_whitespace();
+                    let bound = |value: Option<&str>| -> Result<_, Box<dyn error::Error>> {
+                        match value {
+                            Some(value) => {
+                                let value = parse::<f64>("cut", Some(value))?;
+                                Ok(Some(value).filter(|v| v.abs() < UNBOUNDED))
+                            }
+                            None => Ok(None),
+                        }
+                    };
+                    let min = bound(bounds.next())?;
+                    let max = bound(bounds.next())?;
+                    if let Some(extra) = bounds.next() {
+                        return Err(Box::new(ParseError::ConversionError(extra.to_owned())));
+                    }
+                    info.cuts.push(Cut {
+                        ctype,
+                        p1: entry.attribute("p1").map(|p| p.to_owned()),
+                        p2: entry.attribute("p2").map(|p| p.to_owned()),
+                        min,
+                        max,
+                    });
+                }
+                _ => {}
+            }
+        }
+        Ok(info)
+    }
+
+    /// Resolve a particle specification as used in the `p1` and `p2`
+    /// attributes of a cut
+    ///
+    /// This is either the name of a particle type or a single PDG ID.
+    pub fn particle_ids(&self, particle: &str) -> Option<Vec<i32>> {
+        match self.ptypes.iter().find(|ptype| ptype.name == particle) {
+            Some(ptype) => Some(ptype.ids.clone()),
+            None => particle.trim().parse().ok().map(|id| vec![id]),
+        }
+    }
+
+    /// Get all cuts of the given type, ignoring case
+    pub fn cuts_of_type<'a>(&'a self, ctype: &'a str) -> impl Iterator<Item = &'a Cut> + 'a {
+        self.cuts
+            .iter()
+            .filter(move |cut| cut.ctype.eq_ignore_ascii_case(ctype))
+    }
+}
+
+/// Parse the first `<cutsinfo>` tag in the optional run information
+pub(crate) fn parse_cutsinfo(info: &str) -> Result<Option<CutsInfo>, Box<dyn error::Error>> {
+    tags::tags(info)
+        .find(|(_, tag)| tag.name() == CUTSINFO_TAG)
+        .map(|(_, tag)| CutsInfo::from_tag(&tag))
+        .transpose()
+}
+
+/// Formats the information as a `<cutsinfo>` tag
+impl fmt::Display for CutsInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "<{}>", CUTSINFO_TAG)?;
+        for ptype in &self.ptypes {
+            write!(f, "<ptype name=\"{}\">", ptype.name)?;
+            for (i, id) in ptype.ids.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " ")?;
+                }
+                write!(f, "{}", id)?;
+            }
+            writeln!(f, "</ptype>")?;
+        }
+        for cut in &self.cuts {
+            write!(f, "<cut type=\"{}\"", cut.ctype)?;
+            if let Some(ref p1) = cut.p1 {
+                write!(f, " p1=\"{}\"", p1)?;
+            }
+            if let Some(ref p2) = cut.p2 {
+                write!(f, " p2=\"{}\"", p2)?;
+            }
+            writeln!(
+                f,
+                ">{:e} {:e}</cut>",
+                cut.min.unwrap_or(-UNBOUNDED),
+                cut.max.unwrap_or(UNBOUNDED)
+            )?;
+        }
+        write!(f, "</{}>", CUTSINFO_TAG)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_format() {
+        let info = "<cutsinfo>\n\
+                    <ptype name=\"jet\">1 2 3 4 5 -1 -2 -3 -4 -5 21</ptype>\n\
+                    <cut type=\"ETA\" p1=\"jet\"> -5.0 5.0 </cut>\n\
+                    <cut type=\"PT\" p1=\"jet\"> 20.0 </cut>\n\
+                    <cut type=\"m\" p1=\"11\" p2=\"-11\"> -1e30 100 </cut>\n\
+                    </cutsinfo>";
+        let cuts = parse_cutsinfo(info).unwrap().unwrap();
+        assert_eq!(cuts.ptypes[0].ids.len(), 11);
+        assert_eq!(cuts.cuts.len(), 3);
+        assert_eq!(cuts.cuts[0].min, Some(-5.));
+        assert_eq!(cuts.cuts[0].max, Some(5.));
+        let pt: Vec<_> = cuts.cuts_of_type("pt").collect();
+        assert_eq!(pt.len(), 1);
+        assert_eq!(pt[0].min, Some(20.));
+        assert_eq!(pt[0].max, None);
+        assert_eq!(cuts.cuts[2].min, None);
+        assert_eq!(cuts.cuts[2].p2, Some("-11".to_owned()));
+        assert_eq!(cuts.particle_ids("jet").unwrap().len(), 11);
+        assert_eq!(cuts.particle_ids("-11"), Some(vec![-11]));
+        assert_eq!(cuts.particle_ids("lepton"), None);
+        assert_eq!(parse_cutsinfo(&cuts.to_string()).unwrap(), Some(cuts));
+    }
+
+    #[test]
+    fn missing() {
+        assert_eq!(parse_cutsinfo("<xsecinfo/>").unwrap(), None);
+        assert!(parse_cutsinfo("<cutsinfo><cut>1</cut></cutsinfo>").is_err());
+    }
+}
@@ -0,0 +1,381 @@
+use std::error;
+use std::fmt::{self, Debug};
+use std::io::BufRead;
+
+use approx::ApproxEq;
+use {Reader, Tolerance, HEPEUP, HEPRUP};
+
+/// Settings for comparing two event files with `diff`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct DiffOptions {
+    /// Tolerance for floating-point numbers
+    pub tolerance: Tolerance,
+    /// Whether to compare the headers
+    ///
+    /// Headers often contain dates or paths that differ between
+    /// otherwise identical runs.
+    pub compare_header: bool,
+}
+
+impl Default for DiffOptions {
+    /// Compare headers and allow relative differences of 10⁻⁹
+    fn default() -> DiffOptions {
+        DiffOptions {
+            tolerance: Tolerance::new(0., 1e-9),
+            compare_header: true,
+        }
+    }
+}
+
+/// A differing field
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub struct FieldDifference {
+    /// Name of the field, e.g. `PUP[2][3]`, with indices counting from 0
+    pub field: String,
+    /// Value in the first file
+    pub a: String,
+    /// Value in the second file
+    pub b: String,
+    /// Difference `b - a` for numbers
+    pub delta: Option<f64>,
+}
+
+impl fmt::Display for FieldDifference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {} != {}", self.field, self.a, self.b)?;
+        if let Some(delta) = self.delta {
+            write!(f, " (delta {:e})", delta)?;
+        }
+        Ok(())
+    }
+}
+
+/// A difference between two event files
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub enum Difference {
+    /// Different LHEF versions
+    Version {
+        /// Version of the first file
+        a: String,
+        /// Version of the second file
+        b: String,
+    },
+    /// Different headers, ignoring whitespace
+    Header,
+    /// Different run information
+    Init(Vec<FieldDifference>),
+    /// The first differing event
+    Event {
+        /// Position of the event, counting from 0
+        index: u64,
+        /// The differing fields
+        fields: Vec<FieldDifference>,
+    },
+    /// Different numbers of events
+    EventCount {
+        /// Number of events in the first file
+        a: u64,
+        /// Number of events in the second file
+        b: u64,
+    },
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::Difference::*;
+        match *self {
+            Version { ref a, ref b } => write!(f, "Versions differ: {} != {}", a, b),
+            Header => write!(f, "Headers differ"),
+            Init(ref fields) => {
+                write!(f, "Init blocks differ:")?;
+                for field in fields {
+                    write!(f, "\n  {}", field)?;
+                }
+                Ok(())
+            }
+            Event { index, ref fields } => {
+                write!(f, "Event {} differs:", index)?;
+                for field in fields {
+                    write!(f, "\n  {}", field)?;
+                }
+                Ok(())
+            }
+            EventCount { a, b } => write!(f, "Numbers of events differ: {} != {}", a, b),
+        }
+    }
+}
+
+/// Compare two event files
+///
+/// The version, header, and run information are compared first. The
+/// events are then compared one by one, stopping at the first
+/// differing event. If all common events agree but one file has more
+/// events, the remaining events are counted. Events in an
+/// `<eventgroup>` are compared individually. An empty result means that
+/// the files agree.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # let (mut a, mut b): (lhef::Reader<std::io::Empty>, lhef::Reader<std::io::Empty>) = unimplemented!();
+/// let options = lhef::DiffOptions {
+///     compare_header: false,
+///     ..Default::default()
+/// };
+/// for difference in lhef::diff(&mut a, &mut b, &options).unwrap() {
+///     println!("{}", difference);
+/// }
+/// ```
+pub fn diff<A: BufRead, B: BufRead>(
+    a: &mut Reader<A>,
+    b: &mut Reader<B>,
+    options: &DiffOptions,
+) -> Result<Vec<Difference>, Box<dyn error::Error>> {
+    let mut differences = Vec::new();
+    if a.version() != b.version() {
+        differences.push(Difference::Version {
+            a: a.version().to_owned(),
+            b: b.version().to_owned(),
+        });
+    }
+    if options.compare_header && !a.header().split_whitespace().eq(b.header().split_whitespace()) {
+        differences.push(Difference::Header);
+    }
+    let init = diff_heprup(a.heprup(), b.heprup(), &options.tolerance);
+    if !init.is_empty() {
+        differences.push(Difference::Init(init));
+    }
+    let mut index = 0;
+    loop {
+        match (a.event()?, b.event()?) {
+            (Some(event_a), Some(event_b)) => {
+                let fields = diff_hepeup(&event_a, &event_b, &options.tolerance);
+                if !fields.is_empty() {
+                    differences.push(Difference::Event { index, fields });
+                    break;
+                }
+            }
+            (None, None) => break,
+            (Some(_), None) => {
+                let remaining = count_events(a)?;
+                differences.push(Difference::EventCount {
+                    a: index + 1 + remaining,
+                    b: index,
+                });
+                break;
+            }
+            (None, Some(_)) => {
+                let remaining = count_events(b)?;
+                differences.push(Difference::EventCount {
+                    a: index,
+                    b: index + 1 + remaining,
+                });
+                break;
+            }
+        }
+        index += 1;
+    }
+    Ok(differences)
+}
+
+fn count_events<S: BufRead>(reader: &mut Reader<S>) -> Result<u64, Box<dyn error::Error>> {
+    let mut count = 0;
+    while reader.event()?.is_some() {
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Collects the differing fields of two structs
+struct FieldDiffs<'a> {
+    tolerance: &'a Tolerance,
+    differences: Vec<FieldDifference>,
+}
+
+impl<'a> FieldDiffs<'a> {
+    fn push<T: Debug>(&mut self, field: String, a: &T, b: &T, delta: Option<f64>) {
+        self.differences.push(FieldDifference {
+            field,
+            a: format!("{:?}", a),
+            b: format!("{:?}", b),
+            delta,
+        })
+    }
+
+    fn float(&mut self, field: String, a: f64, b: f64) {
+        if !self.tolerance.close(a, b) {
+            self.differences.push(FieldDifference {
+                field,
+                a: format!("{:e}", a),
+                b: format!("{:e}", b),
+                delta: Some(b - a),
+            })
+        }
+    }
+
+    fn exact<T: PartialEq + Debug>(&mut self, field: String, a: &T, b: &T) {
+        if a != b {
+            self.push(field, a, b, None)
+        }
+    }
+
+    fn approx<T: ApproxEq + Debug>(&mut self, field: String, a: &T, b: &T) {
+        if !a.approx_eq(b, self.tolerance) {
+            self.push(field, a, b, None)
+        }
+    }
+
+    fn floats(&mut self, field: &str, a: &[f64], b: &[f64]) {
+        if a.len() != b.len() {
+            return self.push(field.to_owned(), &a, &b, None);
+        }
+        for (i, (&a, &b)) in a.iter().zip(b).enumerate() {
+            self.float(format!("{}[{}]", field, i), a, b)
+        }
+    }
+
+    fn exacts<T: PartialEq + Debug>(&mut self, field: &str, a: &[T], b: &[T]) {
+        if a.len() != b.len() {
+            return self.push(field.to_owned(), &a, &b, None);
+        }
+        for (i, (a, b)) in a.iter().zip(b).enumerate() {
+            self.exact(format!("{}[{}]", field, i), a, b)
+        }
+    }
+}
+
+fn diff_heprup(a: &HEPRUP, b: &HEPRUP, tolerance: &Tolerance) -> Vec<FieldDifference> {
+    let mut diffs = FieldDiffs {
+        tolerance,
+        differences: Vec::new(),
+    };
+    diffs.exacts("IDBMUP", &a.IDBMUP, &b.IDBMUP);
+    diffs.floats("EBMUP", &a.EBMUP, &b.EBMUP);
+    diffs.exacts("PDFGUP", &a.PDFGUP, &b.PDFGUP);
+    diffs.exacts("PDFSUP", &a.PDFSUP, &b.PDFSUP);
+    diffs.exact("IDWTUP".to_owned(), &a.IDWTUP, &b.IDWTUP);
+    diffs.exact("NPRUP".to_owned(), &a.NPRUP, &b.NPRUP);
+    diffs.floats("XSECUP", &a.XSECUP, &b.XSECUP);
+    diffs.floats("XERRUP", &a.XERRUP, &b.XERRUP);
+    diffs.floats("XMAXUP", &a.XMAXUP, &b.XMAXUP);
+    diffs.exacts("LPRUP", &a.LPRUP, &b.LPRUP);
+    diffs.approx("info".to_owned(), &a.info, &b.info);
+    diffs.differences
+}
+
+fn diff_hepeup(a: &HEPEUP, b: &HEPEUP, tolerance: &Tolerance) -> Vec<FieldDifference> {
+    let mut diffs = FieldDiffs {
+        tolerance,
+        differences: Vec::new(),
+    };
+    diffs.exact("NUP".to_owned(), &a.NUP, &b.NUP);
+    diffs.exact("IDRUP".to_owned(), &a.IDRUP, &b.IDRUP);
+    diffs.float("XWGTUP".to_owned(), a.XWGTUP, b.XWGTUP);
+    diffs.float("SCALUP".to_owned(), a.SCALUP, b.SCALUP);
+    diffs.float("AQEDUP".to_owned(), a.AQEDUP, b.AQEDUP);
+    diffs.float("AQCDUP".to_owned(), a.AQCDUP, b.AQCDUP);
+    diffs.exacts("IDUP", &a.IDUP, &b.IDUP);
+    diffs.exacts("ISTUP", &a.ISTUP, &b.ISTUP);
+    diffs.exacts("MOTHUP", &a.MOTHUP, &b.MOTHUP);
+    diffs.exacts("ICOLUP", &a.ICOLUP, &b.ICOLUP);
+    if a.PUP.len() == b.PUP.len() {
+        for (i, (pa, pb)) in a.PUP.iter().zip(&b.PUP).enumerate() {
+            diffs.floats(&format!("PUP[{}]", i), pa, pb);
+        }
+    } else {
+        diffs.push("PUP".to_owned(), &a.PUP, &b.PUP, None);
+    }
+    diffs.floats("VTIMUP", &a.VTIMUP, &b.VTIMUP);
+    diffs.floats("SPINUP", &a.SPINUP, &b.SPINUP);
+    diffs.approx("info".to_owned(), &a.info, &b.info);
+    diffs.exact("attr".to_owned(), &a.attr, &b.attr);
+    diffs.approx("scales".to_owned(), &a.scales, &b.scales);
+    diffs.approx("clustering".to_owned(), &a.clustering, &b.clustering);
+    diffs.approx("mgrwt".to_owned(), &a.mgrwt, &b.mgrwt);
+    diffs.approx("weights".to_owned(), &a.weights, &b.weights);
+    diffs.approx("comment_lines".to_owned(), &a.comment_lines, &b.comment_lines);
+    diffs.differences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EVENTS: &str = r#"<LesHouchesEvents version="1.0">
+<header>
+generated today
+</header>
+<init>
+2212 2212 6500 6500 0 0 0 0 3 1
+1 0 1 1
+</init>
+<event>
+2 1 2 10 0 0.1
+21 -1 0 0 0 0 0 0 1e2 1e2 0 0 9
+21 1 1 1 0 0 0 0 1e2 1e2 0 0 9
+</event>
+<event>
+2 1 2 10 0 0.1
+21 -1 0 0 0 0 0 0 1e2 1e2 0 0 9
+21 1 1 1 0 0 0 0 1e2 1e2 0 0 9
+</event>
+</LesHouchesEvents>
+"#;
+
+    fn compare(a: &str, b: &str, options: &DiffOptions) -> Vec<Difference> {
+        let mut a = Reader::new(a.as_bytes()).unwrap();
+        let mut b = Reader::new(b.as_bytes()).unwrap();
+        diff(&mut a, &mut b, options).unwrap()
+    }
+
+    #[test]
+    fn identical() {
+        let reformatted = EVENTS.replace("1e2 1e2", "100.0  100.00000000001");
+        assert_eq!(compare(EVENTS, &reformatted, &DiffOptions::default()), []);
+    }
+
+    #[test]
+    fn differences() {
+        let other = EVENTS
+            .replacen("generated today", "generated yesterday", 1)
+            .replacen("1 0 1 1", "1.5 0 1 1", 1)
+            .replacen("21 1 1 1 0 0 0 0 1e2", "22 1 1 1 0 0 0 0 2e2", 1);
+        let differences = compare(EVENTS, &other, &DiffOptions::default());
+        assert_eq!(differences.len(), 3);
+        assert_eq!(differences[0], Difference::Header);
+        match differences[1] {
+            Difference::Init(ref fields) => {
+                assert_eq!(fields.len(), 1);
+                assert_eq!(fields[0].field, "XSECUP[0]");
+                assert_eq!(fields[0].delta, Some(0.5));
+            }
+            ref other => panic!("unexpected difference {:?}", other),
+        }
+        match differences[2] {
+            Difference::Event { index, ref fields } => {
+                assert_eq!(index, 0);
+                let names: Vec<_> = fields.iter().map(|f| f.field.as_str()).collect();
+                assert_eq!(names, ["IDUP[1]", "PUP[1][2]"]);
+                assert_eq!(fields[1].to_string(), "PUP[1][2]: 1e2 != 2e2 (delta 1e2)");
+            }
+            ref other => panic!("unexpected difference {:?}", other),
+        }
+        let options = DiffOptions {
+            compare_header: false,
+            ..Default::default()
+        };
+        assert_eq!(compare(EVENTS, &other, &options).len(), 2);
+    }
+
+    #[test]
+    fn event_count() {
+        let end = EVENTS.find("<event>").unwrap();
+        let fewer = format!("{}</LesHouchesEvents>\n", &EVENTS[..end]);
+        let differences = compare(EVENTS, &fewer, &DiffOptions::default());
+        assert_eq!(differences, [Difference::EventCount { a: 2, b: 0 }]);
+        assert_eq!(differences[0].to_string(), "Numbers of events differ: 2 != 0");
+    }
+}
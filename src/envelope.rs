@@ -0,0 +1,462 @@
+use std::collections::HashMap;
+
+use systematics::SystematicWeights;
+use HEPEUP;
+
+/// How the uncertainty of a PDF set is computed from its members
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum PdfErrorType {
+    /// Asymmetric Hessian eigenvector pairs, as in CT or MSHT sets
+    Hessian,
+    /// Symmetric Hessian eigenvectors, as in PDF4LHC sets
+    SymmetricHessian,
+    /// Monte Carlo replicas, as in NNPDF sets
+    Replicas,
+}
+
+/// A central value with an uncertainty band
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct Envelope {
+    /// Central value
+    pub central: f64,
+    /// Lower edge of the band
+    pub min: f64,
+    /// Upper edge of the band
+    pub max: f64,
+}
+
+/// Systematic variations of the weight of a single event
+///
+/// The weights are `None` if a variation is missing from the event.
+struct VariationValues {
+    central: f64,
+    scale: Vec<Option<f64>>,
+    pdf: Vec<Option<f64>>,
+}
+
+impl SystematicWeights {
+    /// Compute the envelope of the 7-point scale variation of an event
+    ///
+    /// The envelope is taken over the central weight and all scale
+    /// variations by factors 1/2, 1, and 2 with a ratio between the
+    /// renormalisation and factorisation scale of at most 2. The
+    /// central weight is the nominal weight if there is one and
+    /// `XWGTUP` otherwise. Returns `None` if any of these variations is
+    /// missing from the event.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # let (reader, event): (lhef::Reader<std::io::Empty>, lhef::HEPEUP) = unimplemented!();
+    /// use lhef::{SystematicWeights, WeightConvention};
+    ///
+    /// let initrwgt = reader.initrwgt().unwrap();
+    /// let systematics = SystematicWeights::new(initrwgt, WeightConvention::MadGraph);
+    /// if let Some(scale) = systematics.scale_envelope(&event) {
+    ///     println!("{} in [{}, {}]", scale.central, scale.min, scale.max);
+    /// }
+    /// ```
+    pub fn scale_envelope(&self, event: &HEPEUP) -> Option<Envelope> {
+        let values = self.values(event);
+        self.scale_envelope_of(values.central, &values.scale)
+    }
+
+    /// Compute the PDF uncertainty of an event
+    ///
+    /// The PDF set consists of the first PDF variation, which is taken
+    /// as the central member, followed by all PDF variations with
+    /// consecutive LHAPDF IDs. Other PDF variations, for example
+    /// alternative sets, are ignored. Returns `None` if there are no
+    /// error members or any member of the set is missing from the
+    /// event.
+    pub fn pdf_uncertainty(&self, event: &HEPEUP, error_type: PdfErrorType) -> Option<Envelope> {
+        let values = self.values(event);
+        self.pdf_uncertainty_of(&values.pdf, error_type)
+    }
+
+    fn values(&self, event: &HEPEUP) -> VariationValues {
+        let get = |id: &str| event.weights.as_ref().and_then(|weights| weights.get(id));
+        let central = self.nominal.as_ref().and_then(|id| get(id)).unwrap_or(event.XWGTUP);
+        VariationValues {
+            central,
+            scale: self.scale.iter().map(|variation| get(&variation.id)).collect(),
+            pdf: self.pdf.iter().map(|variation| get(&variation.id)).collect(),
+        }
+    }
+
+    fn scale_envelope_of(&self, central: f64, values: &[Option<f64>]) -> Option<Envelope> {
+        let mut envelope = Envelope {
+            central,
+            min: central,
+            max: central,
+        };
+        let seven_point = |factor: f64| factor == 0.5 || factor == 1. || factor == 2.;
+        for (variation, value) in self.scale.iter().zip(values) {
+            let ratio = variation.mur / variation.muf;
+            let included = seven_point(variation.mur)
+                && seven_point(variation.muf)
+                && (0.5..=2.).contains(&ratio);
+            if !included {
+                continue;
+            }
+            let value = (*value)?;
+            envelope.min = envelope.min.min(value);
+            envelope.max = envelope.max.max(value);
+        }
+        Some(envelope)
+    }
+
+    fn pdf_uncertainty_of(
+        &self,
+        values: &[Option<f64>],
+        error_type: PdfErrorType,
+    ) -> Option<Envelope> {
+        let first = self.pdf.first()?.lhapdf_id;
+        let members = self
+            .pdf
+            .iter()
+            .zip(values)
+            .enumerate()
+            .take_while(|&(k, (variation, _))| variation.lhapdf_id == first + k as i32)
+            .map(|(_, (_, value))| *value)
+            .collect::<Option<Vec<_>>>()?;
+        let (&central, errors) = members.split_first()?;
+        if errors.is_empty() {
+            return None;
+        }
+        let (down, up) = match error_type {
+            PdfErrorType::Hessian => {
+                let (mut down, mut up) = (0., 0.);
+                for pair in errors.chunks(2) {
+                    let plus = pair[0] - central;
+                    let minus = pair.get(1).map_or(0., |value| value - central);
+                    up += plus.max(minus).max(0.).powi(2);
+                    down += (-plus).max(-minus).max(0.).powi(2);
+                }
+                (down.sqrt(), up.sqrt())
+            }
+            PdfErrorType::SymmetricHessian => {
+                let sum: f64 = errors.iter().map(|value| (value - central).powi(2)).sum();
+                (sum.sqrt(), sum.sqrt())
+            }
+            PdfErrorType::Replicas => {
+                let n = errors.len() as f64;
+                let mean = errors.iter().sum::<f64>() / n;
+                let variance = errors.iter().map(|value| (value - mean).powi(2)).sum::<f64>()
+                    / (n - 1.).max(1.);
+                (variance.sqrt(), variance.sqrt())
+            }
+        };
+        Some(Envelope {
+            central,
+            min: central - down,
+            max: central + up,
+        })
+    }
+}
+
+/// Sums of systematic variations over a sample
+///
+/// Uncertainties of the total cross section are computed from the
+/// summed weights of each variation, which correctly accounts for
+/// correlations between events.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # let (systematics, events): (lhef::SystematicWeights, Vec<lhef::HEPEUP>) = unimplemented!();
+/// use lhef::{PdfErrorType, SystematicSums};
+///
+/// let mut sums = SystematicSums::new(&systematics);
+/// for event in &events {
+///     sums.add(event);
+/// }
+/// let pdf = sums.pdf_uncertainty(PdfErrorType::Replicas);
+/// ```
+#[derive(PartialEq, Debug, Clone)]
+pub struct SystematicSums<'a> {
+    systematics: &'a SystematicWeights,
+    central: f64,
+    scale: Vec<Option<f64>>,
+    pdf: Vec<Option<f64>>,
+}
+
+impl<'a> SystematicSums<'a> {
+    /// Start summing the variations described by `systematics`
+    pub fn new(systematics: &'a SystematicWeights) -> SystematicSums<'a> {
+        SystematicSums {
+            systematics,
+            central: 0.,
+            scale: vec![Some(0.); systematics.scale.len()],
+            pdf: vec![Some(0.); systematics.pdf.len()],
+        }
+    }
+
+    /// Add the weights of an event
+    ///
+    /// If a variation is missing from any event, uncertainties that
+    /// depend on it are no longer available.
+    pub fn add(&mut self, event: &HEPEUP) {
+        let values = self.systematics.values(event);
+        self.central += values.central;
+        for (sum, value) in self.scale.iter_mut().zip(values.scale) {
+            *sum = sum.and_then(|sum| Some(sum + value?));
+        }
+        for (sum, value) in self.pdf.iter_mut().zip(values.pdf) {
+            *sum = sum.and_then(|sum| Some(sum + value?));
+        }
+    }
+
+    /// Sum of the central weights
+    pub fn central(&self) -> f64 {
+        self.central
+    }
+
+    /// Envelope of the summed 7-point scale variations
+    ///
+    /// See `SystematicWeights::scale_envelope`.
+    pub fn scale_envelope(&self) -> Option<Envelope> {
+        self.systematics.scale_envelope_of(self.central, &self.scale)
+    }
+
+    /// PDF uncertainty of the summed weights
+    ///
+    /// See `SystematicWeights::pdf_uncertainty`.
+    pub fn pdf_uncertainty(&self, error_type: PdfErrorType) -> Option<Envelope> {
+        self.systematics.pdf_uncertainty_of(&self.pdf, error_type)
+    }
+}
+
+/// Sum, minimum, and maximum of a weight over a sample, see
+/// `WeightTotals`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct WeightTotal {
+    /// Number of events with this weight
+    pub events: u64,
+    /// Sum of the weight
+    pub sum: f64,
+    /// Smallest value of the weight
+    pub min: f64,
+    /// Largest value of the weight
+    pub max: f64,
+}
+
+impl WeightTotal {
+    fn new() -> WeightTotal {
+        WeightTotal {
+            events: 0,
+            sum: 0.,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    fn add(&mut self, value: f64) {
+        self.events += 1;
+        self.sum += value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    fn merge(&mut self, other: &WeightTotal) {
+        self.events += other.events;
+        self.sum += other.sum;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+}
+
+/// Totals of every named weight over a sample
+///
+/// In contrast to `SystematicSums`, this does not interpret the
+/// weights, so a single pass yields the total cross section for each
+/// variation in the file, whatever it describes. Weights without an ID
+/// are ignored. The totals are kept in the order in which the weight
+/// IDs first appear.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// let file = std::fs::File::open("events.lhe").unwrap();
+/// let mut reader = lhef::Reader::new(std::io::BufReader::new(file)).unwrap();
+/// let mut totals = lhef::WeightTotals::new();
+/// while let Some(event) = reader.event().unwrap() {
+///     totals.add(&event);
+/// }
+/// for (id, total) in totals.iter() {
+///     println!("{} {} [{}, {}]", id, total.sum, total.min, total.max);
+/// }
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct WeightTotals {
+    events: u64,
+    totals: Vec<(String, WeightTotal)>,
+    positions: HashMap<String, usize>,
+}
+
+impl WeightTotals {
+    /// Start with an empty sample
+    pub fn new() -> WeightTotals {
+        WeightTotals::default()
+    }
+
+    /// Add the weights of an event
+    pub fn add(&mut self, event: &HEPEUP) {
+        self.events += 1;
+        let weights = match event.weights {
+            Some(ref weights) => weights,
+            None => return,
+        };
+        for weight in &weights.weights {
+            if let Some(ref id) = weight.id {
+                self.total_mut(id).add(weight.value);
+            }
+        }
+    }
+
+    /// Add the totals of another sample, e.g. one processed in parallel
+    pub fn merge(&mut self, other: &WeightTotals) {
+        self.events += other.events;
+        for (id, total) in &other.totals {
+            self.total_mut(id).merge(total);
+        }
+    }
+
+    /// Number of added events
+    pub fn events(&self) -> u64 {
+        self.events
+    }
+
+    /// Get the total of the weight with the given ID
+    pub fn get(&self, id: &str) -> Option<&WeightTotal> {
+        self.positions.get(id).map(|&i| &self.totals[i].1)
+    }
+
+    /// Iterate over the weight IDs and their totals
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &WeightTotal)> {
+        self.totals.iter().map(|(id, total)| (id.as_str(), total))
+    }
+
+    fn total_mut(&mut self, id: &str) -> &mut WeightTotal {
+        let i = match self.positions.get(id) {
+            Some(&i) => i,
+            None => {
+                self.positions.insert(id.to_owned(), self.totals.len());
+                self.totals.push((id.to_owned(), WeightTotal::new()));
+                self.totals.len() - 1
+            }
+        };
+        &mut self.totals[i].1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use initrwgt::parse_initrwgt;
+    use test_util::MockEvent;
+    use WeightConvention;
+
+    const INITRWGT: &str = r#"<initrwgt>
+<weightgroup name="scale">
+<weight id="1">mur=1 muf=1</weight>
+<weight id="2">mur=2 muf=1</weight>
+<weight id="3">mur=0.5 muf=0.5</weight>
+<weight id="4">mur=2 muf=0.5</weight>
+</weightgroup>
+<weightgroup name="pdf">
+<weight id="10">pdf=1000</weight>
+<weight id="11">pdf=1001</weight>
+<weight id="12">pdf=1002</weight>
+<weight id="13">pdf=1003</weight>
+<weight id="14">pdf=1004</weight>
+<weight id="20">pdf=2000</weight>
+</weightgroup>
+</initrwgt>"#;
+
+    fn event(weights: &[(&str, f64)]) -> HEPEUP {
+        let mut event = MockEvent::dijet().with_weight(10.);
+        for &(id, weight) in weights {
+            event = event.with_extra_weight(id, weight);
+        }
+        event.build()
+    }
+
+    fn read() -> (SystematicWeights, Vec<HEPEUP>) {
+        let initrwgt = parse_initrwgt(INITRWGT).unwrap().unwrap();
+        let systematics = SystematicWeights::new(&initrwgt, WeightConvention::MadGraph);
+        let events = vec![
+            event(&[
+                ("1", 10.), ("2", 8.), ("3", 13.), ("4", 20.),
+                ("10", 10.), ("11", 11.), ("12", 9.5), ("13", 9.), ("14", 8.), ("20", 30.),
+            ]),
+            event(&[
+                ("1", 10.), ("2", 12.), ("3", 9.), ("4", 0.),
+                ("10", 10.), ("11", 11.), ("12", 9.), ("13", 10.), ("14", 10.),
+            ]),
+        ];
+        (systematics, events)
+    }
+
+    #[test]
+    fn per_event() {
+        let (systematics, events) = read();
+        let scale = systematics.scale_envelope(&events[0]).unwrap();
+        assert_eq!(scale, Envelope { central: 10., min: 8., max: 13. });
+
+        let hessian = systematics.pdf_uncertainty(&events[0], PdfErrorType::Hessian).unwrap();
+        assert_eq!(hessian.central, 10.);
+        assert_eq!(hessian.max, 11.);
+        assert!((10. - hessian.min - 0.5f64.hypot(2.)).abs() < 1e-12);
+
+        let symmetric = systematics.pdf_uncertainty(&events[0], PdfErrorType::SymmetricHessian);
+        let expected = (1f64 + 0.25 + 1. + 4.).sqrt();
+        assert!((symmetric.unwrap().max - 10. - expected).abs() < 1e-12);
+
+        let replicas = systematics.pdf_uncertainty(&events[0], PdfErrorType::Replicas).unwrap();
+        assert!((replicas.max - 11.25).abs() < 1e-12);
+        assert!((replicas.min - 8.75).abs() < 1e-12);
+    }
+
+    #[test]
+    fn sample() {
+        let (systematics, events) = read();
+        let mut sums = SystematicSums::new(&systematics);
+        for event in &events {
+            sums.add(event);
+        }
+        assert_eq!(sums.central(), 20.);
+        let scale = sums.scale_envelope().unwrap();
+        assert_eq!(scale, Envelope { central: 20., min: 20., max: 22. });
+        let hessian = sums.pdf_uncertainty(PdfErrorType::Hessian).unwrap();
+        assert_eq!(hessian.central, 20.);
+        assert!((hessian.max - 22.).abs() < 1e-12);
+        assert!((hessian.min - 17.5).abs() < 1e-12);
+
+        let none = SystematicWeights::default();
+        assert_eq!(none.pdf_uncertainty(&events[0], PdfErrorType::Hessian), None);
+        let scale = none.scale_envelope(&events[0]).unwrap();
+        assert_eq!(scale, Envelope { central: 10., min: 10., max: 10. });
+    }
+
+    #[test]
+    fn totals() {
+        let (_, events) = read();
+        let mut totals = WeightTotals::new();
+        totals.add(&events[0]);
+        let mut rest = WeightTotals::new();
+        rest.add(&events[1]);
+        totals.merge(&rest);
+        assert_eq!(totals.events(), 2);
+        let ids: Vec<_> = totals.iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, ["1", "2", "3", "4", "10", "11", "12", "13", "14", "20"]);
+        let total = totals.get("4").unwrap();
+        assert_eq!(*total, WeightTotal { events: 2, sum: 20., min: 0., max: 20. });
+        assert_eq!(totals.get("20").unwrap().events, 1);
+        assert_eq!(totals.get("5"), None);
+    }
+}
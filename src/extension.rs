@@ -0,0 +1,249 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::sync::Arc;
+
+use tags::{self, Tag};
+
+/// A value produced by a custom tag parser
+///
+/// This trait is implemented automatically for all types that are
+/// `Debug`, `PartialEq`, `Send`, and `Sync`.
+pub trait Extension: Any + fmt::Debug + Send + Sync {
+    /// Access the value as `Any`, e.g. for downcasting
+    fn as_any(&self) -> &dyn Any;
+
+    /// Compare with another extension value
+    fn eq_dyn(&self, other: &dyn Extension) -> bool;
+}
+
+impl<T: Any + fmt::Debug + PartialEq + Send + Sync> Extension for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn eq_dyn(&self, other: &dyn Extension) -> bool {
+        other.as_any().downcast_ref::<T>() == Some(self)
+    }
+}
+
+/// Parsed values of custom tags
+///
+/// Each entry is labelled by the name of the tag it was parsed
+/// from. Tags that appear several times lead to several entries.
+#[derive(Clone, Debug, Default)]
+pub struct Extensions {
+    entries: Vec<(String, Arc<dyn Extension>)>,
+}
+
+impl Extensions {
+    /// Create an empty set of extensions
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the first value parsed from the tag with the given name
+    ///
+    /// Returns `None` if there is no such value or if it is not of type `T`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # let event: lhef::HEPEUP = unimplemented!();
+    /// #[derive(Debug, PartialEq)]
+    /// struct Clustering(String);
+    ///
+    /// if let Some(clustering) = event.extensions.get::<Clustering>("clustering") {
+    ///     println!("{:?}", clustering);
+    /// }
+    /// ```
+    pub fn get<T: Extension>(&self, tag: &str) -> Option<&T> {
+        self.entries
+            .iter()
+            .filter(|(name, _)| name == tag)
+            .find_map(|(_, value)| value.as_any().downcast_ref::<T>())
+    }
+
+    /// Get all values of type `T` parsed from the tag with the given name
+    pub fn get_all<'a, T: Extension>(
+        &'a self,
+        tag: &'a str,
+    ) -> impl Iterator<Item = &'a T> + 'a {
+        self.entries
+            .iter()
+            .filter(move |(name, _)| name == tag)
+            .filter_map(|(_, value)| value.as_any().downcast_ref::<T>())
+    }
+
+    /// Add a value
+    pub fn insert<T: Extension>(&mut self, tag: &str, value: T) {
+        self.entries.push((tag.to_owned(), Arc::new(value)));
+    }
+
+    /// Check whether there is any value for the given tag
+    pub fn contains(&self, tag: &str) -> bool {
+        self.entries.iter().any(|(name, _)| name == tag)
+    }
+
+    /// Iterate over the names of the tags and their parsed values
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &dyn Extension)> {
+        self.entries
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_ref()))
+    }
+
+    /// Number of values
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check whether there are no values
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl PartialEq for Extensions {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries.len() == other.entries.len()
+            && self.entries.iter().zip(other.entries.iter()).all(
+                |((name, value), (other_name, other_value))| {
+                    name == other_name && value.eq_dyn(other_value.as_ref())
+                },
+            )
+    }
+}
+
+type Parser = Arc<
+    dyn Fn(&Tag) -> Result<Arc<dyn Extension>, Box<dyn error::Error>>
+        + Send
+        + Sync,
+>;
+
+/// Registry of custom parsers for tags in the optional information
+///
+/// Whenever the `Reader` encounters a registered tag in the
+/// optional information of the init block or an event, it calls the
+/// corresponding parser and stores the result in the `extensions` of
+/// the `HEPRUP` or `HEPEUP` object. The original text is kept in the
+/// `info` field. Custom parsers take precedence over the built-in
+/// parsing of tags like `<scales>`.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use lhef::{ExtensionRegistry, Reader};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Mups(f64);
+///
+/// let mut registry = ExtensionRegistry::new();
+/// registry.register("scales", |tag| {
+///     let mups = tag.attribute("mups").ok_or("missing mups")?;
+///     Ok(Mups(mups.parse()?))
+/// });
+///
+/// let file = std::fs::File::open("events.lhe").unwrap();
+/// let file = std::io::BufReader::new(file);
+/// let mut reader = Reader::with_extensions(file, registry).unwrap();
+/// let event = reader.event().unwrap().unwrap();
+/// let mups = event.extensions.get::<Mups>("scales");
+/// ```
+#[derive(Clone, Default)]
+pub struct ExtensionRegistry {
+    parsers: HashMap<String, Parser>,
+}
+
+impl ExtensionRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a parser for the tag with the given name
+    ///
+    /// Any parser previously registered for the same tag is replaced.
+    pub fn register<T, F>(&mut self, tag: &str, parser: F)
+    where
+        T: Extension,
+        F: Fn(&Tag) -> Result<T, Box<dyn error::Error>> + Send + Sync + 'static,
+    {
+        let parser: Parser = Arc::new(move |tag| {
+            parser(tag).map(|value| Arc::new(value) as Arc<dyn Extension>)
+        });
+        self.parsers.insert(tag.to_owned(), parser);
+    }
+
+    /// Check whether a parser is registered for the given tag
+    pub fn is_registered(&self, tag: &str) -> bool {
+        self.parsers.contains_key(tag)
+    }
+
+    /// Parse all registered top-level tags in the given text
+    pub(crate) fn parse(
+        &self,
+        text: &str,
+    ) -> Result<Extensions, Box<dyn error::Error>> {
+        let mut extensions = Extensions::new();
+        if self.parsers.is_empty() {
+            return Ok(extensions);
+        }
+        for (_, tag) in tags::tags(text) {
+            if let Some(parser) = self.parsers.get(tag.name()) {
+                let value = parser(&tag)?;
+                extensions.entries.push((tag.name().to_owned(), value));
+            }
+        }
+        Ok(extensions)
+    }
+}
+
+impl fmt::Debug for ExtensionRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.parsers.keys()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Count(usize);
+
+    #[test]
+    fn parse_registered() {
+        let mut registry = ExtensionRegistry::new();
+        registry.register("a", |tag| Ok(Count(tag.content().len())));
+        let ext = registry.parse("<a>xyz</a><b>ignored</b><a/>").unwrap();
+        assert_eq!(ext.len(), 2);
+        assert_eq!(ext.get::<Count>("a"), Some(&Count(3)));
+        let all: Vec<_> = ext.get_all::<Count>("a").collect();
+        assert_eq!(all, [&Count(3), &Count(0)]);
+        assert!(!ext.contains("b"));
+        assert_eq!(ext.get::<String>("a"), None);
+    }
+
+    #[test]
+    fn compare() {
+        let mut a = Extensions::new();
+        a.insert("x", 1u32);
+        let mut b = Extensions::new();
+        b.insert("x", 1u32);
+        assert_eq!(a, b);
+        b.insert("x", 2u32);
+        assert_ne!(a, b);
+        let mut c = Extensions::new();
+        c.insert("x", 1u64);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn parser_error() {
+        let mut registry = ExtensionRegistry::new();
+        registry.register("a", |tag| tag.content().parse::<u32>().map_err(Into::into));
+        assert!(registry.parse("<a>12</a>").is_ok());
+        assert!(registry.parse("<a>x</a>").is_err());
+    }
+}
@@ -0,0 +1,71 @@
+use std::error;
+use std::str::FromStr;
+
+use memchr::memchr2;
+
+use ParseError;
+
+/// Iterator over the whitespace-separated fields of a line
+///
+/// This is a faster replacement for `str::split_whitespace` for the
+/// numeric lines of the init and event blocks. Fields are separated by
+/// spaces or tabs, which are found with `memchr`, and only ASCII
+/// whitespace is recognised. Since the separators are ASCII, the
+/// fields can be sliced from the line without validating them again.
+pub(crate) struct Fields<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Fields<'a> {
+    pub(crate) fn new(line: &'a str) -> Fields<'a> {
+        Fields { rest: line }
+    }
+
+    /// Parse the next field
+    ///
+    /// `name` is only called to describe a missing field in the error.
+    pub(crate) fn parse<T, F>(&mut self, name: F) -> Result<T, Box<dyn error::Error>>
+    where
+        T: FromStr,
+        F: FnOnce() -> String,
+    {
+        match self.next() {
+            Some(field) => match field.parse() {
+                Ok(value) => Ok(value),
+                Err(_) => Err(Box::new(ParseError::ConversionError(field.to_owned()))),
+            },
+            None => Err(Box::new(ParseError::MissingEntry(name()))),
+        }
+    }
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let bytes = self.rest.as_bytes();
+        let start = bytes.iter().position(|c| !c.is_ascii_whitespace())?;
+        let len = memchr2(b' ', b'\t', &bytes[start..]).unwrap_or(bytes.len() - start);
+        let field = &self.rest[start..start + len];
+        self.rest = &self.rest[start + len..];
+        // the last field can be followed by a line break
+        Some(field.trim_end_matches(|c: char| c.is_ascii_whitespace()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields() {
+        let line = "  1\t-2  3.5e1 x\r\n";
+        assert_eq!(Fields::new(line).collect::<Vec<_>>(), ["1", "-2", "3.5e1", "x"]);
+        assert_eq!(Fields::new(" \r\n").next(), None);
+        let mut fields = Fields::new("1 a");
+        assert_eq!(fields.parse::<i32, _>(|| "first".to_owned()).unwrap(), 1);
+        assert!(fields.parse::<f64, _>(|| "second".to_owned()).is_err());
+        let err = fields.parse::<f64, _>(|| "third".to_owned()).unwrap_err();
+        assert!(err.to_string().contains("third"));
+    }
+}
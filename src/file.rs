@@ -0,0 +1,80 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use flate2::bufread::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use BackgroundReader;
+
+/// The first bytes of a gzip stream
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Open a file for reading, decompressing it if it is gzipped
+///
+/// Compression is detected from the content, not the file name. With
+/// `background`, decompression runs on a separate thread.
+pub(crate) fn open(path: &Path, background: bool) -> io::Result<Box<dyn BufRead + Send>> {
+    let mut file = BufReader::new(File::open(path)?);
+    if file.fill_buf()?.starts_with(&GZIP_MAGIC) {
+        let decoder = MultiGzDecoder::new(file);
+        if background {
+            Ok(Box::new(BackgroundReader::new(decoder)))
+        } else {
+            Ok(Box::new(BufReader::new(decoder)))
+        }
+    } else {
+        Ok(Box::new(file))
+    }
+}
+
+/// Create a file for writing, compressing it with gzip if the name
+/// ends in `.gz`
+pub(crate) fn create(path: &Path) -> io::Result<Box<dyn Write + Send>> {
+    compress(path, File::create(path)?)
+}
+
+/// Like `create`, but adding the number of bytes that reach the file
+/// to `written`
+///
+/// Because of buffering and compression, the count lags behind the
+/// data passed to the returned stream.
+pub(crate) fn create_counted(
+    path: &Path,
+    written: Arc<AtomicU64>,
+) -> io::Result<Box<dyn Write + Send>> {
+    let file = File::create(path)?;
+    compress(path, CountingWriter { stream: file, written })
+}
+
+fn compress<W>(path: &Path, stream: W) -> io::Result<Box<dyn Write + Send>>
+where
+    W: Write + Send + 'static,
+{
+    let file = BufWriter::new(stream);
+    if path.extension().is_some_and(|extension| extension == "gz") {
+        Ok(Box::new(GzEncoder::new(file, Compression::default())))
+    } else {
+        Ok(Box::new(file))
+    }
+}
+
+struct CountingWriter<W> {
+    stream: W,
+    written: Arc<AtomicU64>,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self.stream.write(buf)?;
+        self.written.fetch_add(len as u64, Ordering::Relaxed);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
@@ -0,0 +1,121 @@
+use std::error;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use HEPEUP;
+
+/// What to do with weights and momenta that are NaN or infinite, see
+/// `ReaderBuilder::non_finite`
+///
+/// Such values propagate silently through most analyses, so they
+/// usually indicate a problem with the generator. This applies to the
+/// event weight `XWGTUP`, the additional weights, and the momenta
+/// `PUP`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Copy, Clone, Default)]
+pub enum NonFinitePolicy {
+    /// Reject the event with a `NonFiniteValue` error
+    Error,
+    /// Keep the value, recording a warning with the `tracing` feature
+    #[default]
+    Warn,
+    /// Replace the value by the given one
+    Replace(f64),
+}
+
+/// Error for a weight or momentum that is NaN or infinite
+///
+/// This is returned when reading an event with
+/// `NonFinitePolicy::Error`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct NonFiniteValue {
+    /// Name of the entry, e.g. `PUP(2, 4)`
+    pub field: String,
+    /// The value found in the input
+    pub value: f64,
+}
+
+impl fmt::Display for NonFiniteValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Entry {} has the non-finite value {}", self.field, self.value)
+    }
+}
+
+impl error::Error for NonFiniteValue {}
+
+/// Apply `policy` to the non-finite weights and momenta of an event
+///
+/// Values that are kept or replaced are added to `count`.
+pub(crate) fn check_event(
+    event: &mut HEPEUP,
+    policy: NonFinitePolicy,
+    count: &AtomicU64,
+) -> Result<(), Box<dyn error::Error>> {
+    let check = |value: &mut f64, field: &dyn Fn() -> String| {
+        if value.is_finite() {
+            return Ok(());
+        }
+        match policy {
+            NonFinitePolicy::Error => {
+                return Err(NonFiniteValue {
+                    field: field(),
+                    value: *value,
+                });
+            }
+            NonFinitePolicy::Warn => {
+                event!(WARN, field = field().as_str(), "non-finite value {}", value);
+            }
+            NonFinitePolicy::Replace(replacement) => *value = replacement,
+        }
+        count.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    };
+    check(&mut event.XWGTUP, &|| "XWGTUP".to_owned())?;
+    for (i, p) in event.PUP.iter_mut().enumerate() {
+        for (j, value) in p.iter_mut().enumerate() {
+            check(value, &|| format!("PUP({}, {})", i + 1, j + 1))?;
+        }
+    }
+    if let Some(ref mut weights) = event.weights {
+        for (i, weight) in weights.weights.iter_mut().enumerate() {
+            let id = &weight.id;
+            check(&mut weight.value, &|| match *id {
+                Some(ref id) => format!("weight {}", id),
+                None => format!("weight {}", i + 1),
+            })?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_util::MockEvent;
+
+    #[test]
+    fn policies() {
+        let mut event = MockEvent::drell_yan().with_extra_weight("up", 1.).build();
+        event.XWGTUP = f64::NAN;
+        event.PUP[2][3] = f64::INFINITY;
+        let count = AtomicU64::new(0);
+
+        let err = check_event(&mut event.clone(), NonFinitePolicy::Error, &count).unwrap_err();
+        let err = err.downcast::<NonFiniteValue>().unwrap();
+        assert_eq!(err.field, "XWGTUP");
+
+        let mut kept = event.clone();
+        check_event(&mut kept, NonFinitePolicy::Warn, &count).unwrap();
+        assert!(kept.XWGTUP.is_nan());
+        assert_eq!(count.load(Ordering::Relaxed), 2);
+
+        event.XWGTUP = 1.;
+        event.weights.as_mut().unwrap().set("up", f64::NEG_INFINITY);
+        let err = check_event(&mut event.clone(), NonFinitePolicy::Error, &count).unwrap_err();
+        assert_eq!(err.to_string(), "Entry PUP(3, 4) has the non-finite value inf");
+        check_event(&mut event, NonFinitePolicy::Replace(0.), &count).unwrap();
+        assert_eq!(event.PUP[2][3], 0.);
+        assert_eq!(event.weights.unwrap().get("up"), Some(0.));
+        assert_eq!(count.load(Ordering::Relaxed), 4);
+    }
+}
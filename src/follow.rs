@@ -0,0 +1,61 @@
+use std::io::{self, BufRead};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use cancel::{self, CancellationToken};
+use limits;
+
+/// Settings for reading a stream that is still being written
+///
+/// See `Reader::follow`.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct Follow {
+    /// Time to wait before checking for new data at the end of the stream
+    pub poll_interval: Duration,
+    /// Maximum time to wait without receiving new data, or `None` to
+    /// wait indefinitely
+    pub timeout: Option<Duration>,
+}
+
+impl Default for Follow {
+    fn default() -> Follow {
+        Follow {
+            poll_interval: Duration::from_millis(100),
+            timeout: None,
+        }
+    }
+}
+
+impl Follow {
+    /// Append bytes from `stream` to `line` until the line is complete
+    ///
+    /// Lines longer than `max_len` are rejected. On timeout or
+    /// cancellation, `line` holds the incomplete line read so far.
+    pub(crate) fn read_line<Stream: BufRead>(
+        &self,
+        stream: &mut Stream,
+        line: &mut Vec<u8>,
+        max_len: usize,
+        cancellation: Option<&CancellationToken>,
+    ) -> io::Result<()> {
+        let mut last_data = Instant::now();
+        loop {
+            if limits::read_line(stream, line, max_len)? > 0 {
+                last_data = Instant::now();
+            }
+            if line.ends_with(b"\n") {
+                return Ok(());
+            }
+            if let Some(timeout) = self.timeout {
+                if last_data.elapsed() >= timeout {
+                    return Err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        "Timed out waiting for more events",
+                    ));
+                }
+            }
+            cancel::check(cancellation)?;
+            thread::sleep(self.poll_interval);
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::fmt;
+
+use tags::{self, Tag};
+
+pub(crate) const GENERATOR_TAG: &str = "generator";
+
+/// Information about a program used to generate the events
+///
+/// This corresponds to a LHEF 3.0 `<generator>` tag in the init block.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct GeneratorInfo {
+    /// Name of the generator
+    pub name: Option<String>,
+    /// Version of the generator
+    pub version: Option<String>,
+    /// Free-form content of the tag
+    pub text: String,
+}
+
+impl GeneratorInfo {
+    fn from_tag(tag: &Tag) -> GeneratorInfo {
+        GeneratorInfo {
+            name: tag.attribute("name").map(|name| name.to_owned()),
+            version: tag.attribute("version").map(|version| version.to_owned()),
+            text: tag.content().to_owned(),
+        }
+    }
+}
+
+/// Parse all `<generator>` tags in the optional run information
+pub(crate) fn parse_generators(info: &str) -> Vec<GeneratorInfo> {
+    tags::tags(info)
+        .filter(|(_, tag)| tag.name() == GENERATOR_TAG)
+        .map(|(_, tag)| GeneratorInfo::from_tag(&tag))
+        .collect()
+}
+
+/// Formats the information as a `<generator>` tag
+impl fmt::Display for GeneratorInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<{}", GENERATOR_TAG)?;
+        if let Some(ref name) = self.name {
+            write!(f, " name=\"{}\"", name)?;
+        }
+        if let Some(ref version) = self.version {
+            write!(f, " version=\"{}\"", version)?;
+        }
+        if self.text.is_empty() {
+            write!(f, "/>")
+        } else {
+            write!(f, ">{}</{}>", self.text, GENERATOR_TAG)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_format() {
+        let info = "<generator name=\"Reversed HEJ\" version=\"0.0.1\"/>\n\
+                    <generator name='Sherpa'>with comments</generator>\n";
+        let generators = parse_generators(info);
+        assert_eq!(
+            generators,
+            [
+                GeneratorInfo {
+                    name: Some("Reversed HEJ".to_owned()),
+                    version: Some("0.0.1".to_owned()),
+                    text: String::new(),
+                },
+                GeneratorInfo {
+                    name: Some("Sherpa".to_owned()),
+                    version: None,
+                    text: "with comments".to_owned(),
+                },
+            ]
+        );
+        let formatted: String = generators.iter().map(|g| format!("{}\n", g)).collect();
+        assert_eq!(parse_generators(&formatted), generators);
+    }
+
+    #[test]
+    fn read_file() {
+        let reader = ::Reader::open("test_data/HEJFOG.lhe.gz").unwrap();
+        assert_eq!(reader.generators().len(), 1);
+        assert_eq!(reader.generators()[0].name, Some("Reversed HEJ".to_owned()));
+        assert_eq!(reader.generators()[0].version, Some("0.0.1".to_owned()));
+    }
+}
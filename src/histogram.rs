@@ -0,0 +1,243 @@
+use HEPEUP;
+
+/// Contents of a histogram bin
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Copy, Clone, Default)]
+pub struct Bin {
+    /// Number of fills
+    pub entries: u64,
+    /// Sum of weights
+    pub sum: f64,
+    /// Sum of squared weights
+    pub sum_squares: f64,
+}
+
+impl Bin {
+    /// Statistical uncertainty of the sum of weights
+    pub fn error(&self) -> f64 {
+        self.sum_squares.sqrt()
+    }
+
+    fn fill(&mut self, weight: f64) {
+        self.entries += 1;
+        self.sum += weight;
+        self.sum_squares += weight * weight;
+    }
+
+    fn merge(&mut self, other: &Bin) {
+        self.entries += other.entries;
+        self.sum += other.sum;
+        self.sum_squares += other.sum_squares;
+    }
+}
+
+/// A one-dimensional histogram with equally sized bins
+///
+/// Values outside the range of the bins are collected in underflow and
+/// overflow bins.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// let file = std::fs::File::open("events.lhe").unwrap();
+/// let file = std::io::BufReader::new(file);
+/// let mut reader = lhef::Reader::new(file).unwrap();
+///
+/// // transverse momentum of the first outgoing particle
+/// let mut pt = lhef::Histogram::new(20, 0., 200.);
+/// while let Some(event) = reader.event().unwrap() {
+///     pt.fill_event(&event, |event| Some(event.pt(2)));
+/// }
+/// for (i, bin) in pt.bins().iter().enumerate() {
+///     let (low, high) = pt.bin_range(i);
+///     println!("{} {} {} {}", low, high, bin.sum, bin.error());
+/// }
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub struct Histogram {
+    min: f64,
+    max: f64,
+    bins: Vec<Bin>,
+    underflow: Bin,
+    overflow: Bin,
+}
+
+impl Histogram {
+    /// Create an empty histogram with `nbins` bins between `min` and
+    /// `max`
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no bins or if `min` is not less than `max`.
+    pub fn new(nbins: usize, min: f64, max: f64) -> Histogram {
+        assert!(nbins > 0, "histogram needs at least one bin");
+        assert!(min < max, "histogram range {}..{} is empty", min, max);
+        Histogram {
+            min,
+            max,
+            bins: vec![Bin::default(); nbins],
+            underflow: Bin::default(),
+            overflow: Bin::default(),
+        }
+    }
+
+    /// Add a value with the given weight
+    ///
+    /// NaN values are ignored.
+    pub fn fill(&mut self, value: f64, weight: f64) {
+        if value.is_nan() {
+            return;
+        }
+        if value < self.min {
+            self.underflow.fill(weight);
+        } else if value >= self.max {
+            self.overflow.fill(weight);
+        } else {
+            let i = self.bin_index(value).unwrap();
+            self.bins[i].fill(weight);
+        }
+    }
+
+    /// Add an observable of an event with the event weight `XWGTUP`
+    ///
+    /// Nothing is added if `observable` returns `None`, for example
+    /// because the event fails a selection.
+    pub fn fill_event<F>(&mut self, event: &HEPEUP, observable: F)
+    where
+        F: FnOnce(&HEPEUP) -> Option<f64>,
+    {
+        if let Some(value) = observable(event) {
+            self.fill(value, event.XWGTUP)
+        }
+    }
+
+    /// Add an observable for all events
+    ///
+    /// See `fill_event`.
+    pub fn fill_events<'a, I, F>(&mut self, events: I, mut observable: F)
+    where
+        I: IntoIterator<Item = &'a HEPEUP>,
+        F: FnMut(&HEPEUP) -> Option<f64>,
+    {
+        for event in events {
+            self.fill_event(event, &mut observable)
+        }
+    }
+
+    /// Add the contents of a histogram with the same binning
+    ///
+    /// # Panics
+    ///
+    /// Panics if the binning differs.
+    pub fn merge(&mut self, other: &Histogram) {
+        assert!(
+            self.min == other.min && self.max == other.max && self.bins.len() == other.bins.len(),
+            "cannot merge histograms with different binning"
+        );
+        for (bin, other) in self.bins.iter_mut().zip(&other.bins) {
+            bin.merge(other);
+        }
+        self.underflow.merge(&other.underflow);
+        self.overflow.merge(&other.overflow);
+    }
+
+    /// The bins between the lower and upper edge, without underflow and
+    /// overflow
+    pub fn bins(&self) -> &[Bin] {
+        &self.bins
+    }
+
+    /// Values below the lower edge
+    pub fn underflow(&self) -> &Bin {
+        &self.underflow
+    }
+
+    /// Values at or above the upper edge
+    pub fn overflow(&self) -> &Bin {
+        &self.overflow
+    }
+
+    /// Index of the bin containing `value`, or `None` if it is outside
+    /// the range
+    pub fn bin_index(&self, value: f64) -> Option<usize> {
+        if !(self.min..self.max).contains(&value) {
+            return None;
+        }
+        let i = ((value - self.min) / self.bin_width()) as usize;
+        // guard against rounding at the upper edge
+        Some(i.min(self.bins.len() - 1))
+    }
+
+    /// Lower and upper edge of bin `i`
+    pub fn bin_range(&self, i: usize) -> (f64, f64) {
+        let width = self.bin_width();
+        (self.min + i as f64 * width, self.min + (i + 1) as f64 * width)
+    }
+
+    /// Width of each bin
+    pub fn bin_width(&self) -> f64 {
+        (self.max - self.min) / self.bins.len() as f64
+    }
+
+    /// Sum of weights in all bins, excluding underflow and overflow
+    pub fn integral(&self) -> f64 {
+        self.bins.iter().map(|bin| bin.sum).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Reader;
+
+    const EVENTS: &str = r#"<LesHouchesEvents version="1.0">
+<init>
+2212 2212 6500 6500 0 0 0 0 3 1
+1 0 1 1
+</init>
+<event>
+1 1 2 10 0 0.1
+1 1 0 0 0 0 3e1 4e1 0 5e1 0 0 9
+</event>
+<event>
+1 1 -1 10 0 0.1
+1 1 0 0 0 0 3e1 0 0 3e1 0 0 9
+</event>
+<event>
+1 1 4 10 0 0.1
+1 1 0 0 0 0 1e3 0 0 1e3 0 0 9
+</event>
+</LesHouchesEvents>
+"#;
+
+    #[test]
+    fn histogram() {
+        let mut reader = Reader::new(EVENTS.as_bytes()).unwrap();
+        let mut events = Vec::new();
+        while let Some(event) = reader.event().unwrap() {
+            events.push(event);
+        }
+        let mut pt = Histogram::new(4, 0., 100.);
+        pt.fill_events(&events, |event| Some(event.pt(0)));
+        assert_eq!(pt.bins()[1], Bin { entries: 1, sum: -1., sum_squares: 1. });
+        assert_eq!(pt.bins()[2].sum, 2.);
+        assert_eq!(pt.overflow().sum, 4.);
+        assert_eq!(pt.integral(), 1.);
+        assert_eq!(pt.bin_range(2), (50., 75.));
+        pt.fill(-1., 0.5);
+        pt.fill(f64::NAN, 1.);
+        assert_eq!(pt.underflow().error(), 0.5);
+        assert_eq!(pt.bin_index(100.), None);
+        assert_eq!(pt.bin_index(99.999), Some(3));
+
+        let mut twice = pt.clone();
+        twice.merge(&pt);
+        assert_eq!(twice.bins()[1].entries, 2);
+        assert_eq!(twice.integral(), 2.);
+
+        let mut selected = Histogram::new(1, 0., 1.);
+        selected.fill_events(&events, |event| if event.XWGTUP > 0. { Some(0.5) } else { None });
+        assert_eq!(selected.bins()[0].entries, 2);
+    }
+}
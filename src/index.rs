@@ -0,0 +1,389 @@
+use std::error;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+use {tags, EVENT_TAG, INIT_END, LHEF_TAG};
+
+/// Magic bytes at the start of an index file
+const MAGIC: &[u8; 8] = b"LHEFIDX\0";
+/// Version of the index file format
+const FORMAT_VERSION: u32 = 1;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Byte offsets of the events in an event file
+///
+/// An index allows reading arbitrary events of a large file without
+/// parsing all preceding ones, see `Reader::with_index`. It is built
+/// once with `build` and can be stored in a sidecar file next to the
+/// events with `write` and read back with `load`. Events inside an
+/// `<eventgroup>` are indexed individually.
+///
+/// To detect outdated indices, the size and a checksum of the indexed
+/// input are stored as well. The size is checked by
+/// `Reader::with_index`, while the more expensive comparison of the
+/// checksum has to be requested with `verify`.
+///
+/// # Format
+///
+/// All integers are stored in little-endian byte order.
+///
+/// - 8 bytes magic `LHEFIDX\0`
+/// - `u32` format version, currently 1
+/// - `u64` size of the input in bytes
+/// - `u64` 64-bit FNV-1a hash of the input
+/// - `u64` number of events
+/// - for each event, the distance to the offset of the previous event
+///   (or the start of the input) as an unsigned LEB128 variable-length
+///   integer
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use std::fs::File;
+/// use std::io::BufReader;
+///
+/// let index = lhef::Index::build(File::open("events.lhe").unwrap()).unwrap();
+/// index.write(File::create("events.lhe.idx").unwrap()).unwrap();
+///
+/// // later
+/// let index = lhef::Index::load(File::open("events.lhe.idx").unwrap()).unwrap();
+/// let file = BufReader::new(File::open("events.lhe").unwrap());
+/// let mut reader = lhef::Reader::new(file).unwrap().with_index(index).unwrap();
+/// let event = reader.event_at(1_000_000).unwrap();
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Index {
+    offsets: Vec<u64>,
+    source_len: u64,
+    checksum: u64,
+}
+
+impl Index {
+    /// Index the events in `stream`
+    ///
+    /// The stream is read to the end. It may contain a complete event
+    /// file or only events, like the input of `Reader::from_fragments`.
+    pub fn build<Stream: Read>(stream: Stream) -> io::Result<Index> {
+        let mut stream = BufReader::new(stream);
+        let mut offsets = Vec::new();
+        let mut checksum = Checksum::new();
+        let mut pos = 0;
+        let mut in_events = true;
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            let len = stream.read_until(b'\n', &mut line)?;
+            if len == 0 {
+                break;
+            }
+            checksum.update(&line);
+            let text = String::from_utf8_lossy(&line);
+            let text = text.trim();
+            if pos == 0 && tags::opening_tag(text, LHEF_TAG).is_some() {
+                in_events = false;
+            } else if !in_events {
+                in_events = text == INIT_END;
+            } else if tags::opening_tag(text, EVENT_TAG).is_some() {
+                offsets.push(pos);
+            }
+            pos += len as u64;
+        }
+        Ok(Index {
+            offsets,
+            source_len: pos,
+            checksum: checksum.finish(),
+        })
+    }
+
+    /// Read an index in the format written by `write`
+    pub fn load<Stream: Read>(stream: Stream) -> Result<Index, Box<dyn error::Error>> {
+        let mut stream = BufReader::new(stream);
+        let mut magic = [0; 8];
+        stream.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(Box::new(IndexError::BadMagic));
+        }
+        let mut version = [0; 4];
+        stream.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        if version != FORMAT_VERSION {
+            return Err(Box::new(IndexError::UnsupportedVersion(version)));
+        }
+        let source_len = read_u64(&mut stream)?;
+        let checksum = read_u64(&mut stream)?;
+        let len = read_u64(&mut stream)?;
+        // the length is untrusted, so only a small buffer is reserved
+        let mut offsets = Vec::with_capacity(len.min(source_len).min(4096) as usize);
+        let mut offset = 0u64;
+        for _ in 0..len {
+            offset = offset
+                .checked_add(read_varint(&mut stream)?)
+                .filter(|&offset| offset < source_len)
+                .ok_or(IndexError::OffsetOutOfRange)?;
+            offsets.push(offset);
+        }
+        Ok(Index { offsets, source_len, checksum })
+    }
+
+    /// Write the index
+    pub fn write<Stream: Write>(&self, mut stream: Stream) -> io::Result<()> {
+        stream.write_all(MAGIC)?;
+        stream.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        stream.write_all(&self.source_len.to_le_bytes())?;
+        stream.write_all(&self.checksum.to_le_bytes())?;
+        stream.write_all(&(self.offsets.len() as u64).to_le_bytes())?;
+        let mut buf = Vec::with_capacity(2 * self.offsets.len());
+        let mut previous = 0;
+        for &offset in &self.offsets {
+            write_varint(&mut buf, offset - previous);
+            previous = offset;
+        }
+        stream.write_all(&buf)
+    }
+
+    /// Check whether the index belongs to the input in `stream`
+    ///
+    /// This reads the complete input and compares its size and checksum.
+    pub fn verify<Stream: Read>(&self, mut stream: Stream) -> io::Result<bool> {
+        let mut checksum = Checksum::new();
+        let len = checksum.update_from(&mut stream)?;
+        Ok(len == self.source_len && checksum.finish() == self.checksum)
+    }
+
+    /// Byte offset of the `<event>` line of the event with the given
+    /// number, counting from 0
+    pub fn offset(&self, event: usize) -> Option<u64> {
+        self.offsets.get(event).copied()
+    }
+
+    /// Number of indexed events
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// Whether there are no indexed events
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    /// Size of the indexed input in bytes
+    pub fn source_len(&self) -> u64 {
+        self.source_len
+    }
+
+    /// 64-bit FNV-1a hash of the indexed input
+    pub fn checksum(&self) -> u64 {
+        self.checksum
+    }
+}
+
+/// Incremental 64-bit FNV-1a hash
+pub(crate) struct Checksum(u64);
+
+impl Checksum {
+    fn new() -> Checksum {
+        Checksum(FNV_OFFSET)
+    }
+
+    /// The hash of everything read from `stream`
+    pub(crate) fn of<Stream: Read>(mut stream: Stream) -> io::Result<u64> {
+        let mut checksum = Checksum::new();
+        checksum.update_from(&mut stream)?;
+        Ok(checksum.finish())
+    }
+
+    /// Hash everything read from `stream`, returning the number of bytes
+    fn update_from<Stream: Read>(&mut self, stream: &mut Stream) -> io::Result<u64> {
+        let mut len = 0;
+        let mut buf = vec![0; 64 * 1024];
+        loop {
+            let n = match stream.read(&mut buf) {
+                Ok(0) => return Ok(len),
+                Ok(n) => n,
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            self.update(&buf[..n]);
+            len += n as u64;
+        }
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+fn read_u64<Stream: Read>(stream: &mut Stream) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    stream.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_varint<Stream: Read>(stream: &mut Stream) -> Result<u64, Box<dyn error::Error>> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0];
+        stream.read_exact(&mut byte)?;
+        value |= u64::from(byte[0] & 0x7f) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(Box::new(IndexError::OffsetOutOfRange))
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+/// Error for an invalid or unusable `Index`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IndexError {
+    /// The file does not start with the magic bytes of an index file
+    BadMagic,
+    /// The file uses an unsupported format version
+    UnsupportedVersion(u32),
+    /// An offset is malformed or lies outside the indexed input
+    OffsetOutOfRange,
+    /// The index was built for an input of a different size
+    SizeMismatch {
+        /// Size of the indexed input in bytes
+        index: u64,
+        /// Size of the actual input in bytes
+        input: u64,
+    },
+    /// Random access was requested from a reader without an index
+    NoIndex,
+}
+
+impl fmt::Display for IndexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::IndexError::*;
+        match *self {
+            BadMagic => write!(f, "Not an event index file"),
+            UnsupportedVersion(version) => {
+                write!(f, "Unsupported event index format version {}", version)
+            }
+            OffsetOutOfRange => write!(f, "Event index contains an invalid offset"),
+            SizeMismatch { index, input } => write!(
+                f,
+                "Event index is for an input of {} bytes, but the input has {} bytes",
+                index, input
+            ),
+            NoIndex => write!(f, "Random access to events requires an index"),
+        }
+    }
+}
+
+impl error::Error for IndexError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use test_util::SyntheticEvents;
+    use Reader;
+
+    const EVENTS: &str = r#"<LesHouchesEvents version="3.0">
+<header>
+<event>not an event</event>
+</header>
+<init>
+2212 2212 6500 6500 0 0 0 0 3 1
+1 0 1 1
+</init>
+<event>
+1 1 1 10 0 0.1
+21 -1 0 0 0 0 0 0 1e2 1e2 0 0 9
+</event>
+<eventgroup>
+<event>
+1 1 2 10 0 0.1
+21 -1 0 0 0 0 0 0 1e2 1e2 0 0 9
+</event>
+<event>
+1 1 3 10 0 0.1
+21 -1 0 0 0 0 0 0 1e2 1e2 0 0 9
+</event>
+</eventgroup>
+</LesHouchesEvents>
+"#;
+
+    #[test]
+    fn roundtrip() {
+        let index = Index::build(EVENTS.as_bytes()).unwrap();
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.source_len(), EVENTS.len() as u64);
+        assert_eq!(index.offset(0), EVENTS.find("<event>\n1").map(|pos| pos as u64));
+        assert!(index.verify(EVENTS.as_bytes()).unwrap());
+        assert!(!index.verify(&EVENTS.as_bytes()[1..]).unwrap());
+
+        let mut file = Vec::new();
+        index.write(&mut file).unwrap();
+        // the distance to the first event needs two bytes, the others one
+        assert_eq!(file.len(), 8 + 4 + 3 * 8 + 2 + 1 + 1);
+        assert_eq!(Index::load(file.as_slice()).unwrap(), index);
+        assert!(Index::load(&file[..file.len() - 1]).is_err());
+        file[0] = b'X';
+        let err = Index::load(file.as_slice()).unwrap_err();
+        assert_eq!(err.downcast_ref(), Some(&IndexError::BadMagic));
+    }
+
+    #[test]
+    fn malformed() {
+        let mut file = Vec::new();
+        file.extend_from_slice(MAGIC);
+        file.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        for _ in 0..3 {
+            file.extend_from_slice(&u64::MAX.to_le_bytes());
+        }
+        assert_eq!(file.len(), 36);
+        assert!(Index::load(file.as_slice()).is_err());
+        file.extend_from_slice(&[0xff; 10]);
+        let err = Index::load(file.as_slice()).unwrap_err();
+        assert_eq!(err.downcast_ref(), Some(&IndexError::OffsetOutOfRange));
+    }
+
+    #[test]
+    fn random_access() {
+        let sample = SyntheticEvents::new().num_events(50).to_bytes();
+        let index = Index::build(sample.as_slice()).unwrap();
+        assert_eq!(index.len(), 50);
+        let mut events = Vec::new();
+        let mut reader = Reader::new(sample.as_slice()).unwrap();
+        while let Some(event) = reader.event().unwrap() {
+            events.push(event);
+        }
+
+        let reader = Reader::new(Cursor::new(&sample)).unwrap();
+        let mut reader = reader.with_index(index.clone()).unwrap();
+        assert_eq!(reader.event().unwrap().as_ref(), Some(&events[0]));
+        for &i in &[42, 7, 0, 49] {
+            assert_eq!(reader.event_at(i).unwrap().as_ref(), Some(&events[i]));
+        }
+        assert_eq!(reader.event_at(50).unwrap(), None);
+        // the position of the reader is unchanged
+        assert_eq!(reader.event().unwrap().as_ref(), Some(&events[1]));
+
+        let mut reader = Reader::new(Cursor::new(EVENTS)).unwrap();
+        assert!(reader.event_at(0).is_err());
+        let mut reader = reader.with_index(Index::build(EVENTS.as_bytes()).unwrap()).unwrap();
+        assert_eq!(reader.event_at(2).unwrap().unwrap().XWGTUP, 3.);
+
+        let reader = Reader::new(Cursor::new(EVENTS)).unwrap();
+        assert!(reader.with_index(index).is_err());
+    }
+}
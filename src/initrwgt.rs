@@ -0,0 +1,345 @@
+use std::error;
+use std::fmt;
+
+use tags::{self, Tag};
+use {parse, Attributes};
+
+pub(crate) const INITRWGT_TAG: &str = "initrwgt";
+const WEIGHTGROUP_TAG: &str = "weightgroup";
+const WEIGHT_TAG: &str = "weight";
+
+/// Declaration of additional event weights from the `<initrwgt>` tag
+///
+/// The weights can be organised in (possibly nested) `<weightgroup>`
+/// tags. The order of the declarations is preserved, since it determines
+/// the order of the weights in each event.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct InitRwgt {
+    /// Weights and weight groups in the order of declaration
+    pub entries: Vec<WeightEntry>,
+}
+
+/// An entry of a weight declaration: either a single weight or a group
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub enum WeightEntry {
+    /// A `<weight>` tag
+    Weight(WeightInfo),
+    /// A `<weightgroup>` tag
+    Group(WeightGroup),
+}
+
+/// A group of weights from a `<weightgroup>` tag
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct WeightGroup {
+    /// Name of the group
+    ///
+    /// Older MadGraph versions use a `type` attribute instead, which is
+    /// also accepted.
+    pub name: Option<String>,
+    /// How the weights in this group should be combined
+    pub combine: Option<Combine>,
+    /// Any other attributes
+    pub attr: Attributes,
+    /// Weights and nested groups in the order of declaration
+    pub entries: Vec<WeightEntry>,
+}
+
+/// Declaration of a single weight from a `<weight>` tag
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct WeightInfo {
+    /// Weight ID, as used in the events
+    pub id: String,
+    /// Any other attributes, e.g. `MUR` and `MUF` scale factors
+    pub attr: Attributes,
+    /// Free-form description of the weight
+    pub description: String,
+}
+
+/// Strategy for combining the weights in a group into an uncertainty
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Combine {
+    /// The weights should not be combined
+    None,
+    /// Take the envelope, i.e. the minimum and maximum
+    Envelope,
+    /// Standard deviation of the weights
+    Gaussian,
+    /// Hessian PDF error sets
+    Hessian,
+    /// Monte Carlo PDF replicas
+    Replicas,
+    /// Any other strategy
+    Other(String),
+}
+
+impl From<&str> for Combine {
+    fn from(combine: &str) -> Self {
+        match combine.trim().to_lowercase().as_str() {
+            "none" => Combine::None,
+            "envelope" => Combine::Envelope,
+            "gaussian" => Combine::Gaussian,
+            "hessian" => Combine::Hessian,
+            "replicas" => Combine::Replicas,
+            _ => Combine::Other(combine.to_owned()),
+        }
+    }
+}
+
+impl fmt::Display for Combine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Combine::None => write!(f, "none"),
+            Combine::Envelope => write!(f, "envelope"),
+            Combine::Gaussian => write!(f, "gaussian"),
+            Combine::Hessian => write!(f, "hessian"),
+            Combine::Replicas => write!(f, "replicas"),
+            Combine::Other(combine) => write!(f, "{}", combine),
+        }
+    }
+}
+
+fn parse_entries(text: &str) -> Result<Vec<WeightEntry>, Box<dyn error::Error>> {
+    let mut entries = Vec::new();
+    for (_, tag) in tags::tags(text) {
+        match tag.name() {
+            WEIGHT_TAG => entries.push(WeightEntry::Weight(WeightInfo::from_tag(&tag)?)),
+            WEIGHTGROUP_TAG => entries.push(WeightEntry::Group(WeightGroup::from_tag(&tag)?)),
+            _ => {}
+        }
+    }
+    Ok(entries)
+}
+
+fn collect_weights<'a>(entries: &'a [WeightEntry], weights: &mut Vec<&'a WeightInfo>) {
+    for entry in entries {
+        match entry {
+            WeightEntry::Weight(weight) => weights.push(weight),
+            WeightEntry::Group(group) => collect_weights(&group.entries, weights),
+        }
+    }
+}
+
+fn find_group<'a>(entries: &'a [WeightEntry], name: &str) -> Option<&'a WeightGroup> {
+    for entry in entries {
+        if let WeightEntry::Group(group) = entry {
+            if group.name.as_deref() == Some(name) {
+                return Some(group);
+            }
+            if let Some(group) = find_group(&group.entries, name) {
+                return Some(group);
+            }
+        }
+    }
+    None
+}
+
+fn collect_groups<'a>(entries: &'a [WeightEntry], groups: &mut Vec<&'a WeightGroup>) {
+    for entry in entries {
+        if let WeightEntry::Group(group) = entry {
+            groups.push(group);
+            collect_groups(&group.entries, groups);
+        }
+    }
+}
+
+impl InitRwgt {
+    fn from_tag(tag: &Tag) -> Result<InitRwgt, Box<dyn error::Error>> {
+        Ok(InitRwgt {
+            entries: parse_entries(tag.content())?,
+        })
+    }
+
+    /// All declared weights, including those in groups, in the order
+    /// of declaration
+    pub fn weights(&self) -> Vec<&WeightInfo> {
+        let mut weights = Vec::new();
+        collect_weights(&self.entries, &mut weights);
+        weights
+    }
+
+    /// All weight groups, including nested ones
+    pub fn groups(&self) -> Vec<&WeightGroup> {
+        let mut groups = Vec::new();
+        collect_groups(&self.entries, &mut groups);
+        groups
+    }
+
+    /// Find the (possibly nested) weight group with the given name
+    pub fn group(&self, name: &str) -> Option<&WeightGroup> {
+        find_group(&self.entries, name)
+    }
+
+    /// All weights in the group with the given name,
+    /// including those in nested groups
+    pub fn weights_in_group(&self, name: &str) -> Option<Vec<&WeightInfo>> {
+        self.group(name).map(|group| group.weights())
+    }
+
+    /// Get the declaration of the weight with the given ID
+    pub fn weight(&self, id: &str) -> Option<&WeightInfo> {
+        self.weights().into_iter().find(|weight| weight.id == id)
+    }
+
+    /// Find the innermost group containing the weight with the given ID
+    pub fn group_of(&self, id: &str) -> Option<&WeightGroup> {
+        self.groups().into_iter().rev().find(|group| {
+            group.entries.iter().any(|entry| match entry {
+                WeightEntry::Weight(weight) => weight.id == id,
+                WeightEntry::Group(_) => false,
+            })
+        })
+    }
+}
+
+impl WeightGroup {
+    fn from_tag(tag: &Tag) -> Result<WeightGroup, Box<dyn error::Error>> {
+        let mut group = WeightGroup::default();
+        for &(key, value) in tag.attributes() {
+            match key {
+                "name" => group.name = Some(value.to_owned()),
+                "type" if tag.attribute("name").is_none() => group.name = Some(value.to_owned()),
+                "combine" => group.combine = Some(Combine::from(value)),
+                _ => group.attr.set(key, value),
+            }
+        }
+        group.entries = parse_entries(tag.content())?;
+        Ok(group)
+    }
+
+    /// All weights in this group, including those in nested groups,
+    /// in the order of declaration
+    pub fn weights(&self) -> Vec<&WeightInfo> {
+        let mut weights = Vec::new();
+        collect_weights(&self.entries, &mut weights);
+        weights
+    }
+}
+
+impl WeightInfo {
+    fn from_tag(tag: &Tag) -> Result<WeightInfo, Box<dyn error::Error>> {
+        let mut attr = Attributes::new();
+        for &(key, value) in tag.attributes() {
+            if key != "id" {
+                attr.set(key, value);
+            }
+        }
+        Ok(WeightInfo {
+            id: parse::<String>("weight id", tag.attribute("id"))?.trim().to_owned(),
+            attr,
+            description: tag.content().to_owned(),
+        })
+    }
+}
+
+/// Parse the `<initrwgt>` tag in the header
+pub(crate) fn parse_initrwgt(header: &str) -> Result<Option<InitRwgt>, Box<dyn error::Error>> {
+    tags::header_tag(header, &[INITRWGT_TAG])
+        .map(|tag| InitRwgt::from_tag(&tag))
+        .transpose()
+}
+
+fn write_entries(f: &mut fmt::Formatter, entries: &[WeightEntry]) -> fmt::Result {
+    for entry in entries {
+        match entry {
+            WeightEntry::Weight(weight) => writeln!(f, "{}", weight)?,
+            WeightEntry::Group(group) => writeln!(f, "{}", group)?,
+        }
+    }
+    Ok(())
+}
+
+/// Formats the declaration as an `<initrwgt>` tag
+impl fmt::Display for InitRwgt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "<{}>", INITRWGT_TAG)?;
+        write_entries(f, &self.entries)?;
+        write!(f, "</{}>", INITRWGT_TAG)
+    }
+}
+
+/// Formats the group as a `<weightgroup>` tag
+impl fmt::Display for WeightGroup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<{}", WEIGHTGROUP_TAG)?;
+        if let Some(ref name) = self.name {
+            write!(f, " name=\"{}\"", name)?;
+        }
+        if let Some(ref combine) = self.combine {
+            write!(f, " combine=\"{}\"", combine)?;
+        }
+        writeln!(f, "{}>", self.attr)?;
+        write_entries(f, &self.entries)?;
+        write!(f, "</{}>", WEIGHTGROUP_TAG)
+    }
+}
+
+/// Formats the declaration as a `<weight>` tag
+impl fmt::Display for WeightInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "<{} id=\"{}\"{}>{}</{}>",
+            WEIGHT_TAG, self.id, self.attr, self.description, WEIGHT_TAG
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "<header>\n\
+                          <MGVersion>2.6.0</MGVersion>\n\
+                          <initrwgt>\n\
+                          <weight id=\"0\">nominal</weight>\n\
+                          <weightgroup name=\"scale_variation\" combine=\"envelope\">\n\
+                          <weight id=\"1\" MUR=\"2.0\" MUF=\"2.0\"> mur=2 muf=2 </weight>\n\
+                          <weight id=\"2\" MUR=\"0.5\" MUF=\"0.5\"> mur=0.5 muf=0.5 </weight>\n\
+                          </weightgroup>\n\
+                          <weightgroup type=\"PDF_variation\" combine=\"hessian\" pdf=\"13000\">\n\
+                          <weight id=\"3\">member 1</weight>\n\
+                          <weightgroup name=\"alphas\" combine=\"Envelope\">\n\
+                          <weight id=\"4\">alphas down</weight>\n\
+                          </weightgroup>\n\
+                          </weightgroup>\n\
+                          </initrwgt>\n\
+                          </header>\n";
+
+    #[test]
+    fn hierarchy() {
+        let initrwgt = parse_initrwgt(HEADER).unwrap().unwrap();
+        let ids: Vec<_> = initrwgt.weights().iter().map(|w| w.id.as_str()).collect();
+        assert_eq!(ids, ["0", "1", "2", "3", "4"]);
+        assert_eq!(initrwgt.groups().len(), 3);
+
+        let scale = initrwgt.group("scale_variation").unwrap();
+        assert_eq!(scale.combine, Some(Combine::Envelope));
+        assert_eq!(scale.weights()[0].attr.parse::<f64>("MUR"), Some(2.));
+
+        let pdf = initrwgt.weights_in_group("PDF_variation").unwrap();
+        assert_eq!(pdf.len(), 2);
+        let pdf_group = initrwgt.group("PDF_variation").unwrap();
+        assert_eq!(pdf_group.combine, Some(Combine::Hessian));
+        assert_eq!(pdf_group.attr.get("pdf"), Some("13000"));
+
+        assert_eq!(initrwgt.group("alphas").unwrap().combine, Some(Combine::Envelope));
+        assert_eq!(initrwgt.group_of("4").unwrap().name, Some("alphas".to_owned()));
+        assert_eq!(initrwgt.group_of("0"), None);
+        assert_eq!(initrwgt.weight("2").unwrap().description, " mur=0.5 muf=0.5 ");
+        assert!(initrwgt.group("missing").is_none());
+
+        assert_eq!(parse_initrwgt(&initrwgt.to_string()).unwrap(), Some(initrwgt));
+    }
+
+    #[test]
+    fn missing() {
+        assert_eq!(parse_initrwgt("<header>\n</header>\n").unwrap(), None);
+        assert_eq!(parse_initrwgt("<!--\ncomment\n-->\n").unwrap(), None);
+        assert!(parse_initrwgt("<initrwgt><weight>1</weight></initrwgt>").is_err());
+    }
+}
@@ -0,0 +1,201 @@
+use kinematics::{self, delta_phi};
+use {ParticleSelection, HEPEUP};
+
+/// A jet of particles
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub struct Jet {
+    /// Momentum `[px, py, pz, E, m]` of the jet, where the mass is
+    /// computed from the energy and momentum
+    pub momentum: [f64; 5],
+    /// Positions of the constituents, counting from 0
+    pub constituents: Vec<usize>,
+}
+
+impl Jet {
+    /// Transverse momentum in GeV
+    pub fn pt(&self) -> f64 {
+        kinematics::pt(&self.momentum)
+    }
+
+    /// Pseudorapidity
+    pub fn eta(&self) -> f64 {
+        kinematics::eta(&self.momentum)
+    }
+
+    /// Rapidity
+    pub fn rapidity(&self) -> f64 {
+        kinematics::rapidity(&self.momentum)
+    }
+
+    /// Azimuthal angle in the range (-π, π]
+    pub fn phi(&self) -> f64 {
+        kinematics::phi(&self.momentum)
+    }
+
+    /// Invariant mass in GeV
+    pub fn mass(&self) -> f64 {
+        self.momentum[4]
+    }
+}
+
+/// An algorithm for clustering particles into jets
+///
+/// Implement this trait to use other algorithms with `HEPEUP::jets`,
+/// for example by wrapping an external jet clustering library.
+pub trait JetAlgorithm {
+    /// Cluster momenta `[px, py, pz, E, m]` into jets
+    ///
+    /// The constituents of the returned jets are positions in
+    /// `momenta`.
+    fn cluster(&self, momenta: &[[f64; 5]]) -> Vec<Jet>;
+}
+
+/// The anti-kt algorithm with E-scheme recombination
+///
+/// Distances are computed from rapidity and azimuthal angle. This is
+/// a straightforward implementation meant for the small multiplicities
+/// of parton-level events. Particles without transverse momentum are
+/// ignored.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct AntiKt {
+    /// Jet radius
+    pub r: f64,
+    /// Minimum transverse momentum of a jet in GeV
+    pub min_pt: f64,
+}
+
+impl JetAlgorithm for AntiKt {
+    fn cluster(&self, momenta: &[[f64; 5]]) -> Vec<Jet> {
+        let mut pseudojets: Vec<Jet> = momenta
+            .iter()
+            .enumerate()
+            .filter(|&(_, p)| kinematics::pt(p) > 0.)
+            .map(|(i, p)| Jet {
+                momentum: *p,
+                constituents: vec![i],
+            })
+            .collect();
+        let mut jets = Vec::new();
+        while !pseudojets.is_empty() {
+            // find the smallest distance, either between two
+            // pseudojets or between a pseudojet and the beam
+            let mut closest = (f64::INFINITY, 0, None);
+            for (i, a) in pseudojets.iter().enumerate() {
+                let d_beam = a.pt().powi(-2);
+                if d_beam < closest.0 {
+                    closest = (d_beam, i, None);
+                }
+                for (j, b) in pseudojets.iter().enumerate().skip(i + 1) {
+                    let dy = a.rapidity() - b.rapidity();
+                    let dphi = delta_phi(a.phi(), b.phi());
+                    let dr2 = (dy * dy + dphi * dphi) / (self.r * self.r);
+                    let d = d_beam.min(b.pt().powi(-2)) * dr2;
+                    if d < closest.0 {
+                        closest = (d, i, Some(j));
+                    }
+                }
+            }
+            match closest {
+                (_, i, Some(j)) => {
+                    let b = pseudojets.swap_remove(j);
+                    let a = &mut pseudojets[i];
+                    for k in 0..4 {
+                        a.momentum[k] += b.momentum[k];
+                    }
+                    a.momentum[4] = kinematics::mass(&a.momentum);
+                    a.constituents.extend(b.constituents);
+                }
+                (_, i, None) => jets.push(pseudojets.swap_remove(i)),
+            }
+        }
+        jets.retain(|jet| jet.pt() >= self.min_pt);
+        for jet in jets.iter_mut() {
+            jet.momentum[4] = kinematics::mass(&jet.momentum);
+            jet.constituents.sort_unstable();
+        }
+        jets.sort_by(|a, b| b.pt().partial_cmp(&a.pt()).unwrap_or(::std::cmp::Ordering::Equal));
+        jets
+    }
+}
+
+/// Jet clustering
+impl HEPEUP {
+    /// Cluster the final-state coloured particles into jets
+    ///
+    /// The constituents of the jets are positions in the event,
+    /// counting from 0 as in `PUP`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # let event: lhef::HEPEUP = unimplemented!();
+    /// let jets = event.jets(&lhef::AntiKt { r: 0.4, min_pt: 20. });
+    /// println!("Found {} jets", jets.len());
+    /// ```
+    pub fn jets<A: JetAlgorithm>(&self, algorithm: &A) -> Vec<Jet> {
+        let coloured: Vec<_> = ParticleSelection::final_state()
+            .indices(self)
+            .into_iter()
+            .filter(|&i| self.ICOLUP[i] != [0, 0])
+            .collect();
+        self.cluster(algorithm, &coloured)
+    }
+
+    /// Cluster the selected particles into jets
+    pub fn jets_with<A: JetAlgorithm>(&self, algorithm: &A, selection: &ParticleSelection) -> Vec<Jet> {
+        self.cluster(algorithm, &selection.indices(self))
+    }
+
+    fn cluster<A: JetAlgorithm>(&self, algorithm: &A, particles: &[usize]) -> Vec<Jet> {
+        let momenta: Vec<_> = particles.iter().map(|&i| self.PUP[i]).collect();
+        let mut jets = algorithm.cluster(&momenta);
+        for jet in jets.iter_mut() {
+            for constituent in jet.constituents.iter_mut() {
+                *constituent = particles[*constituent];
+            }
+        }
+        jets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Reader;
+
+    const EVENT: &str = r#"<LesHouchesEvents version="1.0">
+<init>
+2212 2212 6500 6500 0 0 0 0 3 1
+1 0 1 1
+</init>
+<event>
+6 1 1 10 0 0.1
+21 -1 0 0 0 0 0 0 1e2 1e2 0 0 9
+21 -1 0 0 0 0 0 0 -1e2 1e2 0 0 9
+21 1 1 2 501 502 5e1 0 0 5e1 0 0 9
+1 1 1 2 502 0 1e1 1e0 0 1.00498756e1 0 0 9
+-1 1 1 2 0 501 -4e1 0 0 4e1 0 0 9
+11 1 1 2 0 0 0 5e0 0 5e0 0 0 9
+</event>
+</LesHouchesEvents>
+"#;
+
+    #[test]
+    fn anti_kt() {
+        let mut reader = Reader::new(EVENT.as_bytes()).unwrap();
+        let event = reader.event().unwrap().unwrap();
+        let jets = event.jets(&AntiKt { r: 0.4, min_pt: 20. });
+        assert_eq!(jets.len(), 2);
+        assert_eq!(jets[0].constituents, [2, 3]);
+        assert!((jets[0].pt() - 60f64.hypot(1.)).abs() < 1e-9);
+        assert_eq!(jets[1].constituents, [4]);
+        assert_eq!(jets[1].pt(), 40.);
+        assert_eq!(jets[1].mass(), 0.);
+        let all = ParticleSelection::final_state();
+        let jets = event.jets_with(&AntiKt { r: 0.4, min_pt: 0. }, &all);
+        assert_eq!(jets.len(), 3);
+        assert_eq!(jets[2].constituents, [5]);
+    }
+}
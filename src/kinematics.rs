@@ -0,0 +1,333 @@
+use std::f64::consts::PI;
+
+use {ParticleSelection, HEPEUP};
+
+/// Particle IDs treated as invisible by `HEPEUP::met`: neutrinos and
+/// the lightest neutralino
+///
+/// Antiparticles are included implicitly.
+pub const INVISIBLE_IDS: &[i32] = &[12, 14, 16, 1000022];
+
+/// Missing transverse momentum
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct Met {
+    /// Magnitude in GeV
+    pub pt: f64,
+    /// Azimuthal angle in the range (-π, π]
+    pub phi: f64,
+}
+
+/// Transverse momentum of a momentum `[px, py, pz, E, m]`
+pub(crate) fn pt(p: &[f64; 5]) -> f64 {
+    p[0].hypot(p[1])
+}
+
+/// Pseudorapidity of a momentum `[px, py, pz, E, m]`
+pub(crate) fn eta(p: &[f64; 5]) -> f64 {
+    (p[2] / pt(p)).asinh()
+}
+
+/// Azimuthal angle in (-π, π] of a momentum `[px, py, pz, E, m]`
+pub(crate) fn phi(p: &[f64; 5]) -> f64 {
+    p[1].atan2(p[0])
+}
+
+/// Rapidity of a momentum `[px, py, pz, E, m]`
+pub(crate) fn rapidity(p: &[f64; 5]) -> f64 {
+    0.5 * ((p[3] + p[2]) / (p[3] - p[2])).ln()
+}
+
+/// Invariant mass computed from energy and momentum, negative for
+/// space-like momenta
+pub(crate) fn mass(p: &[f64; 5]) -> f64 {
+    let m2 = p[3] * p[3] - p[0] * p[0] - p[1] * p[1] - p[2] * p[2];
+    m2.signum() * m2.abs().sqrt()
+}
+
+/// Difference between two azimuthal angles, wrapped into [-π, π]
+pub(crate) fn delta_phi(phi1: f64, phi2: f64) -> f64 {
+    let mut dphi = (phi1 - phi2) % (2. * PI);
+    if dphi > PI {
+        dphi -= 2. * PI;
+    } else if dphi < -PI {
+        dphi += 2. * PI;
+    }
+    dphi
+}
+
+/// Kinematic properties of single particles and pairs of particles
+///
+/// Particles are indexed from 0 as in `PUP`, unlike the indices in
+/// `MOTHUP`, which count from 1. Out-of-range indices lead to a
+/// panic.
+impl HEPEUP {
+    /// Transverse momentum of particle `i` in GeV
+    pub fn pt(&self, i: usize) -> f64 {
+        pt(&self.PUP[i])
+    }
+
+    /// Pseudorapidity of particle `i`
+    ///
+    /// This is infinite for particles along the beam axis.
+    pub fn eta(&self, i: usize) -> f64 {
+        eta(&self.PUP[i])
+    }
+
+    /// Rapidity of particle `i`
+    pub fn rapidity(&self, i: usize) -> f64 {
+        rapidity(&self.PUP[i])
+    }
+
+    /// Azimuthal angle of particle `i` in the range (-π, π]
+    pub fn phi(&self, i: usize) -> f64 {
+        phi(&self.PUP[i])
+    }
+
+    /// Invariant mass of particle `i` in GeV
+    ///
+    /// In contrast to the mass stored in `PUP`, this is computed from
+    /// the energy and momentum. It is negative for space-like momenta.
+    pub fn mass(&self, i: usize) -> f64 {
+        mass(&self.PUP[i])
+    }
+
+    /// Difference in azimuthal angle between particles `i` and `j`,
+    /// in the range [-π, π]
+    pub fn delta_phi(&self, i: usize, j: usize) -> f64 {
+        delta_phi(self.phi(i), self.phi(j))
+    }
+
+    /// Difference in pseudorapidity between particles `i` and `j`
+    pub fn delta_eta(&self, i: usize, j: usize) -> f64 {
+        self.eta(i) - self.eta(j)
+    }
+
+    /// Angular separation ΔR = √(Δη² + Δφ²) between particles `i` and
+    /// `j`, using the pseudorapidity
+    pub fn delta_r(&self, i: usize, j: usize) -> f64 {
+        self.delta_eta(i, j).hypot(self.delta_phi(i, j))
+    }
+
+    /// Missing transverse momentum from the invisible final-state
+    /// particles listed in `INVISIBLE_IDS`
+    pub fn met(&self) -> Met {
+        self.met_with(INVISIBLE_IDS)
+    }
+
+    /// Missing transverse momentum from final-state particles with the
+    /// given IDs
+    ///
+    /// The IDs of antiparticles are included implicitly.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # let event: lhef::HEPEUP = unimplemented!();
+    /// let mut invisible = lhef::INVISIBLE_IDS.to_vec();
+    /// invisible.push(1000039); // gravitino
+    /// println!("MET: {} GeV", event.met_with(&invisible).pt);
+    /// ```
+    pub fn met_with(&self, invisible: &[i32]) -> Met {
+        let (mut px, mut py) = (0., 0.);
+        for ((&status, id), p) in self.ISTUP.iter().zip(&self.IDUP).zip(&self.PUP) {
+            if status == 1 && invisible.contains(&id.abs()) {
+                px += p[0];
+                py += p[1];
+            }
+        }
+        Met { pt: f64::hypot(px, py), phi: f64::atan2(py, px) }
+    }
+
+    /// Scalar sum of the transverse momenta of final-state partons
+    ///
+    /// See `ParticleSelection::partons` for the selected particles.
+    pub fn ht(&self) -> f64 {
+        self.ht_with(&ParticleSelection::partons())
+    }
+
+    /// Scalar sum of the transverse momenta of the selected particles
+    pub fn ht_with(&self, selection: &ParticleSelection) -> f64 {
+        selection.indices(self).into_iter().map(|i| self.pt(i)).sum()
+    }
+
+    /// Negative vector sum of the transverse momenta of the selected
+    /// particles, also known as missing HT
+    pub fn mht_with(&self, selection: &ParticleSelection) -> Met {
+        let (mut px, mut py) = (0., 0.);
+        for i in selection.indices(self) {
+            px -= self.PUP[i][0];
+            py -= self.PUP[i][1];
+        }
+        Met { pt: f64::hypot(px, py), phi: f64::atan2(py, px) }
+    }
+
+    /// Invariant mass of the combined momenta of the given particles
+    ///
+    /// The result is negative for space-like total momenta.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # let event: lhef::HEPEUP = unimplemented!();
+    /// let leptons = lhef::ParticleSelection::final_state().with_ids(&[11, 13]);
+    /// for (i, j) in event.opposite_sign_pairs(&leptons) {
+    ///     println!("Dilepton mass: {} GeV", event.invariant_mass(&[i, j]));
+    /// }
+    /// ```
+    pub fn invariant_mass(&self, particles: &[usize]) -> f64 {
+        let mut total = [0.; 5];
+        for &i in particles {
+            for (t, p) in total.iter_mut().zip(&self.PUP[i][..4]) {
+                *t += p;
+            }
+        }
+        mass(&total)
+    }
+
+    /// All pairs of selected particles
+    ///
+    /// In each pair, the first index is smaller than the second one.
+    pub fn pairs(&self, selection: &ParticleSelection) -> Vec<(usize, usize)> {
+        let selected = selection.indices(self);
+        let mut pairs = Vec::new();
+        for (n, &i) in selected.iter().enumerate() {
+            for &j in &selected[n + 1..] {
+                pairs.push((i, j));
+            }
+        }
+        pairs
+    }
+
+    /// All pairs of a selected particle with its antiparticle
+    ///
+    /// Selecting charged leptons gives the opposite-sign same-flavour
+    /// lepton pairs.
+    pub fn opposite_sign_pairs(&self, selection: &ParticleSelection) -> Vec<(usize, usize)> {
+        self.pairs(selection)
+            .into_iter()
+            .filter(|&(i, j)| self.IDUP[i] == -self.IDUP[j])
+            .collect()
+    }
+
+    /// Scalar sum of the energies of the selected particles
+    pub fn sum_energy_with(&self, selection: &ParticleSelection) -> f64 {
+        selection.indices(self).into_iter().map(|i| self.PUP[i][3]).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {Met, ParticleSelection, Reader};
+
+    const EVENT: &str = r#"<LesHouchesEvents version="1.0">
+<init>
+2212 2212 6500 6500 0 0 0 0 3 1
+1 0 1 1
+</init>
+<event>
+3 1 1 10 0 0.1
+21 -1 0 0 0 0 0 0 5e2 5e2 0 0 9
+13 1 1 1 0 0 3e1 -4e1 0 5e1 0 0 9
+6 1 1 1 0 0 0 6e1 8e1 2e2 1.73e2 0 9
+</event>
+<event>
+4 1 1 10 0 0.1
+21 -1 0 0 0 0 0 0 5e2 5e2 0 0 9
+-12 1 1 1 0 0 3e1 -4e1 0 5e1 0 0 9
+14 1 1 1 0 0 1e1 0 0 1e1 0 0 9
+1000039 1 1 1 0 0 0 4e1 0 4e1 0 0 9
+</event>
+<event>
+5 1 1 10 0 0.1
+11 1 0 0 0 0 0 0 3e1 3e1 0 0 9
+-11 1 0 0 0 0 0 0 -4e1 4e1 0 0 9
+13 1 0 0 0 0 1e1 0 0 1e1 0 0 9
+-13 1 0 0 0 0 -1e1 0 0 1e1 0 0 9
+11 -1 0 0 0 0 0 0 1e1 1e1 0 0 9
+</event>
+</LesHouchesEvents>
+"#;
+
+    #[test]
+    fn particle_kinematics() {
+        let mut reader = Reader::new(EVENT.as_bytes()).unwrap();
+        let event = reader.event().unwrap().unwrap();
+        assert_eq!(event.pt(0), 0.);
+        assert_eq!(event.eta(0), f64::INFINITY);
+        assert_eq!(event.mass(0), 0.);
+        assert_eq!(event.pt(1), 50.);
+        assert_eq!(event.eta(1), 0.);
+        assert_eq!(event.rapidity(1), 0.);
+        assert_eq!(event.phi(1), (-4f64).atan2(3.));
+        assert_eq!(event.pt(2), 60.);
+        assert_eq!(event.phi(2), std::f64::consts::FRAC_PI_2);
+        assert!((event.eta(2) - (80f64 / 60.).asinh()).abs() < 1e-12);
+        assert!((event.rapidity(2) - 0.5 * (280f64 / 120.).ln()).abs() < 1e-12);
+        assert!((event.mass(2) - 30_000f64.sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn wrap_delta_phi() {
+        use super::delta_phi;
+        use std::f64::consts::PI;
+        assert!((delta_phi(3., -3.) - (6. - 2. * PI)).abs() < 1e-12);
+        assert!((delta_phi(-3., 3.) - (2. * PI - 6.)).abs() < 1e-12);
+        assert_eq!(delta_phi(1., 0.5), 0.5);
+        assert!(delta_phi(7. * PI, 0.).abs() <= PI);
+    }
+
+    #[test]
+    fn pair_separation() {
+        let mut reader = Reader::new(EVENT.as_bytes()).unwrap();
+        let event = reader.event().unwrap().unwrap();
+        let dphi = std::f64::consts::FRAC_PI_2 - (-4f64).atan2(3.);
+        assert!((event.delta_phi(2, 1) - dphi).abs() < 1e-12);
+        assert_eq!(event.delta_phi(2, 1), -event.delta_phi(1, 2));
+        assert_eq!(event.delta_eta(2, 1), event.eta(2));
+        let dr = event.eta(2).hypot(dphi);
+        assert!((event.delta_r(1, 2) - dr).abs() < 1e-12);
+    }
+
+    #[test]
+    fn missing_momentum() {
+        let mut reader = Reader::new(EVENT.as_bytes()).unwrap();
+        let event = reader.event().unwrap().unwrap();
+        assert_eq!(event.met().pt, 0.);
+        let event = reader.event().unwrap().unwrap();
+        let phi = -std::f64::consts::FRAC_PI_4;
+        assert_eq!(event.met(), Met { pt: 40f64.hypot(-40.), phi });
+        let met = event.met_with(&[12, 14, 1000039]);
+        assert_eq!(met.pt, 40.);
+        assert_eq!(met.phi, 0.);
+    }
+
+    #[test]
+    fn sums() {
+        let mut reader = Reader::new(EVENT.as_bytes()).unwrap();
+        let event = reader.event().unwrap().unwrap();
+        assert_eq!(event.ht(), 0.);
+        let final_state = ParticleSelection::final_state();
+        assert_eq!(event.ht_with(&final_state), 110.);
+        assert_eq!(event.sum_energy_with(&final_state), 250.);
+        let mht = event.mht_with(&final_state);
+        assert!((mht.pt - 30f64.hypot(20.)).abs() < 1e-12);
+        assert_eq!(mht.phi, (-20f64).atan2(-30.));
+    }
+
+    #[test]
+    fn pair_masses() {
+        let mut reader = Reader::new(EVENT.as_bytes()).unwrap();
+        reader.event().unwrap();
+        reader.event().unwrap();
+        let event = reader.event().unwrap().unwrap();
+        let leptons = ParticleSelection::final_state().with_ids(&[11, 13]);
+        assert_eq!(event.pairs(&leptons).len(), 6);
+        let pairs = event.opposite_sign_pairs(&leptons);
+        assert_eq!(pairs, [(0, 1), (2, 3)]);
+        assert!((event.invariant_mass(&[0, 1]) - 4800f64.sqrt()).abs() < 1e-12);
+        assert_eq!(event.invariant_mass(&[2, 3]), 20.);
+        assert_eq!(event.invariant_mass(&[0]), 0.);
+        assert_eq!(event.invariant_mass(&[]), 0.);
+    }
+}
@@ -0,0 +1,153 @@
+use std::error;
+use std::fmt;
+use std::io::BufRead;
+
+use ParseError;
+
+/// Common PDF sets: LHAPDF ID of the central member, name, and number
+/// of members
+const BUILTIN_SETS: &[(i32, &str, i32)] = &[
+    (10042, "cteq6l1", 1),
+    (10550, "cteq66", 45),
+    (10800, "CT10", 53),
+    (11000, "CT10nlo", 53),
+    (13000, "CT14nnlo", 57),
+    (13100, "CT14nlo", 57),
+    (13200, "CT14lo", 1),
+    (14000, "CT18NNLO", 59),
+    (14400, "CT18NLO", 59),
+    (21000, "MSTW2008lo68cl", 41),
+    (21100, "MSTW2008nlo68cl", 41),
+    (21200, "MSTW2008nnlo68cl", 41),
+    (25000, "MMHT2014lo68cl", 51),
+    (25100, "MMHT2014nlo68cl", 51),
+    (25300, "MMHT2014nnlo68cl", 51),
+    (90400, "PDF4LHC15_nlo_30_pdfas", 33),
+    (91200, "PDF4LHC15_nnlo_30_pdfas", 33),
+    (93300, "PDF4LHC21_40_pdfas", 43),
+    (247000, "NNPDF23_lo_as_0130_qed", 101),
+    (260000, "NNPDF30_nlo_as_0118", 101),
+    (261000, "NNPDF30_nnlo_as_0118", 101),
+    (262000, "NNPDF30_lo_as_0130", 101),
+    (303400, "NNPDF31_nlo_as_0118", 101),
+    (303600, "NNPDF31_nnlo_as_0118", 101),
+    (306000, "NNPDF31_nnlo_hessian_pdfas", 103),
+    (315200, "NNPDF31_lo_as_0130", 101),
+    (325100, "NNPDF31_nnlo_as_0118_luxqed", 101),
+    (331100, "NNPDF40_nnlo_as_01180", 101),
+];
+
+/// Index of PDF sets by their LHAPDF IDs
+///
+/// This resolves the PDF set IDs in `HEPRUP::PDFSUP` and in weight
+/// declarations to set names and member numbers. A table of commonly
+/// used sets is built in. The complete list of sets can be read from
+/// the `pdfsets.index` file distributed with LHAPDF.
+///
+/// # Example
+///
+/// ```rust
+/// let sets = lhef::PdfSets::builtin();
+/// let pdf = sets.lookup(303600).unwrap();
+/// assert_eq!(pdf.set, "NNPDF31_nnlo_as_0118");
+/// assert_eq!(pdf.member, 0);
+/// assert_eq!(pdf.to_string(), "NNPDF31_nnlo_as_0118/0");
+/// ```
+#[derive(PartialEq, Debug, Clone)]
+pub struct PdfSets {
+    /// ID of the central member, name, and number of members if
+    /// known, ordered by ID
+    sets: Vec<(i32, String, Option<i32>)>,
+}
+
+/// A member of a PDF set
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct PdfMember<'a> {
+    /// Name of the PDF set
+    pub set: &'a str,
+    /// Member number within the set, 0 for the central member
+    pub member: i32,
+}
+
+impl<'a> fmt::Display for PdfMember<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.set, self.member)
+    }
+}
+
+impl PdfSets {
+    /// Get the built-in table of common PDF sets
+    pub fn builtin() -> PdfSets {
+        let sets = BUILTIN_SETS
+            .iter()
+            .map(|&(id, name, members)| (id, name.to_owned(), Some(members)))
+            .collect();
+        PdfSets { sets }
+    }
+
+    /// Read an LHAPDF `pdfsets.index` file
+    ///
+    /// Each line of the file consists of the ID of the central member,
+    /// the name of the set, and a version number. Since the number of
+    /// members is not listed, each ID is attributed to the set with the
+    /// closest smaller or equal central ID.
+    pub fn from_index<Stream: BufRead>(index: Stream) -> Result<PdfSets, Box<dyn error::Error>> {
+        let mut sets = Vec::new();
+        for line in index.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut entries = line.split_whitespace();
+            let id = ::parse::<i32>("PDF set ID", entries.next())?;
+            let name = entries
+                .next()
+                .ok_or_else(|| ParseError::MissingEntry("PDF set name".to_owned()))?;
+            sets.push((id, name.to_owned(), None));
+        }
+        sets.sort_by_key(|set| set.0);
+        Ok(PdfSets { sets })
+    }
+
+    /// Find the set and member corresponding to an LHAPDF ID
+    pub fn lookup(&self, lhapdf_id: i32) -> Option<PdfMember<'_>> {
+        let pos = self.sets.partition_point(|set| set.0 <= lhapdf_id);
+        let (id, ref name, members) = *self.sets[..pos].last()?;
+        let member = lhapdf_id - id;
+        if members.is_some_and(|members| member >= members) {
+            return None;
+        }
+        Some(PdfMember { set: name, member })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin() {
+        let sets = PdfSets::builtin();
+        assert_eq!(
+            sets.lookup(260042),
+            Some(PdfMember { set: "NNPDF30_nlo_as_0118", member: 42 })
+        );
+        assert_eq!(sets.lookup(260101), None);
+        assert_eq!(sets.lookup(10041), None);
+        assert_eq!(sets.lookup(10042).unwrap().to_string(), "cteq6l1/0");
+    }
+
+    #[test]
+    fn index() {
+        let index = "10042 cteq6l1 1\n\n303400 NNPDF31_nlo_as_0118 1\n303600 NNPDF31_nnlo_as_0118 1\n";
+        let sets = PdfSets::from_index(index.as_bytes()).unwrap();
+        assert_eq!(
+            sets.lookup(303512),
+            Some(PdfMember { set: "NNPDF31_nlo_as_0118", member: 112 })
+        );
+        assert_eq!(sets.lookup(303601).unwrap().set, "NNPDF31_nnlo_as_0118");
+        assert!(PdfSets::from_index("x cteq6l1 1\n".as_bytes()).is_err());
+        assert!(PdfSets::from_index("10042\n".as_bytes()).is_err());
+    }
+}
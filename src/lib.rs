@@ -1,27 +1,235 @@
 #[cfg(feature = "serde")]
 #[macro_use]
 extern crate serde;
-use std::io::BufRead;
+extern crate flate2;
+extern crate memchr;
+#[cfg(feature = "indicatif")]
+extern crate indicatif;
+#[cfg(feature = "tracing")]
+extern crate tracing;
+#[cfg(feature = "particle_id")]
+extern crate particle_id;
+#[cfg(feature = "testing")]
+extern crate proptest;
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::path::Path;
 use std::fmt;
 use std::error;
+use std::mem;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
-const LHEF_TAG_OPEN: &'static str = "<LesHouchesEvents version=";
-const COMMENT_START: &'static str = "<!--";
-const COMMENT_END: &'static str = "-->";
-const HEADER_START: &'static str = "<header>";
-const HEADER_END: &'static str = "</header>";
-const INIT_START: &'static str = "<init>";
-const INIT_END: &'static str = "</init>";
-const EVENT_START: &'static str = "<event>";
-const EVENT_END: &'static str = "</event>";
-const LHEF_LAST_LINE: &'static str = "</LesHouchesEvents>";
+#[macro_use]
+mod trace;
+
+mod approx;
+mod attributes;
+mod background;
+mod banner;
+mod builder;
+mod cancel;
+mod checkpoint;
+mod clustering;
+mod combine;
+mod comments;
+mod conformance;
+mod convert;
+mod csv;
+mod cutsinfo;
+mod diff;
+mod envelope;
+mod extension;
+mod fields;
+mod file;
+mod finite;
+mod follow;
+mod generator;
+mod histogram;
+mod index;
+mod initrwgt;
+mod jets;
+mod kinematics;
+mod lhapdf;
+mod limits;
+mod lossy;
+mod mgrwt;
+mod overlay;
+mod partition;
+mod patch;
+mod pdg;
+mod proccard;
+mod procinfo;
+mod progress;
+mod provenance;
+#[cfg(feature = "indicatif")]
+mod progress_bar;
+mod random;
+mod raw;
+mod record;
+mod recompute;
+mod rescale;
+mod resample;
+mod resonance;
+mod retention;
+mod reweight;
+mod runcard;
+mod scales;
+mod selection;
+mod sequence;
+mod shapes;
+mod slha;
+mod slice;
+mod split;
+pub mod stats;
+mod systematics;
+mod tags;
+mod tail;
+mod tee;
+pub mod test_util;
+#[cfg(feature = "testing")]
+mod testing;
+mod transform;
+mod validate;
+mod visitor;
+mod weights;
+mod writer;
+mod writer_builder;
+mod xsecinfo;
+
+pub use approx::Tolerance;
+pub use attributes::Attributes;
+pub use background::BackgroundReader;
+pub use banner::GeneratorMetadata;
+pub use builder::ReaderBuilder;
+pub use cancel::{CancellationToken, Cancelled};
+pub use checkpoint::Checkpoint;
+pub use clustering::{Clustering, ClusteringStep};
+pub use combine::Combiner;
+pub use comments::{AmcAtNloLine, CommentLines, PdfLine};
+pub use conformance::{check_conformance, ConformanceReport, Finding, Rule, Severity};
+pub use convert::{downgrade_to_v1, upgrade_to_v3, TagHandling, NOMINAL_WEIGHT_ID};
+pub use csv::{CsvColumn, CsvWriter};
+pub use cutsinfo::{Cut, CutsInfo, ParticleType};
+pub use diff::{diff, DiffOptions, Difference, FieldDifference};
+pub use envelope::{Envelope, PdfErrorType, SystematicSums, WeightTotal, WeightTotals};
+pub use extension::{Extension, ExtensionRegistry, Extensions};
+pub use finite::{NonFinitePolicy, NonFiniteValue};
+pub use follow::Follow;
+pub use generator::GeneratorInfo;
+pub use histogram::{Bin, Histogram};
+pub use index::{Index, IndexError};
+pub use initrwgt::{Combine, InitRwgt, WeightEntry, WeightGroup, WeightInfo};
+pub use jets::{AntiKt, Jet, JetAlgorithm};
+pub use kinematics::{Met, INVISIBLE_IDS};
+pub use lhapdf::{PdfMember, PdfSets};
+pub use limits::{LimitExceeded, Limits};
+pub use lossy::{Diagnostic, LossyEvents};
+pub use mgrwt::{MgRwt, PdfRwt, PdfRwtEntry, RScale};
+pub use overlay::WeightCombination;
+pub use patch::patch_cross_sections;
+pub use pdg::particle_name;
+pub use proccard::ProcCard;
+pub use procinfo::{MergeInfo, ProcInfo};
+pub use progress::Progress;
+pub use provenance::{Provenance, ProvenanceInput};
+#[cfg(feature = "indicatif")]
+pub use progress_bar::ProgressBarEvents;
+pub use raw::RawEvent;
+pub use record::{EventRecord, Particle};
+pub use rescale::ScaleRescaling;
+pub use resample::Resampler;
+pub use resonance::Resonance;
+pub use retention::InfoRetention;
+pub use runcard::{RunCard, RunCardEntry};
+pub use scales::{ParticleScale, Scale, Scales};
+pub use selection::ParticleSelection;
+pub use sequence::{ReorderBufferFull, SequencedWriter};
+pub use shapes::EventShapes;
+pub use slha::{Decay, DecayChannel, Slha, SlhaBlock, SlhaEntry};
+pub use slice::SliceReader;
+pub use split::SplitWriter;
+pub use systematics::{
+    PdfVariation, ScaleVariation, SystematicWeights, Variation, WeightConvention,
+};
+pub use tags::Tag;
+pub use tee::{EventSink, Tee};
+pub use transform::LorentzTransform;
+pub use validate::{Issue, PdgIdCheck, Validator};
+pub use visitor::{parse_with, LheVisitor};
+pub use weights::{Weight, WeightFormat, Weights};
+pub use writer::Writer;
+pub use writer_builder::WriterBuilder;
+pub use xsecinfo::XSecInfo;
+
+const LHEF_TAG: &str = "LesHouchesEvents";
+const COMMENT_START: &str = "<!--";
+const COMMENT_END: &str = "-->";
+const HEADER_START: &str = "<header>";
+const HEADER_END: &str = "</header>";
+const INIT_START: &str = "<init>";
+const INIT_END: &str = "</init>";
+const EVENT_TAG: &str = "event";
+const EVENT_START: &str = "<event>";
+const EVENT_END: &str = "</event>";
+const EVENTGROUP_TAG: &str = "eventgroup";
+const EVENTGROUP_START: &str = "<eventgroup";
+const EVENTGROUP_END: &str = "</eventgroup>";
+const LHEF_LAST_LINE: &str = "</LesHouchesEvents>";
+const EVENT_NUMBER_ATTRIBUTE: &str = "event_number";
 
 /// Reader for the LHEF format
 pub struct Reader<Stream> {
     stream: Stream,
     version: &'static str,
+    /// version attribute as found in the input
+    declared_version: Option<String>,
+    /// attributes of the root tag other than the version
+    root_attributes: Attributes,
     header: String, // TODO: or some xml struct?
+    initrwgt: Option<InitRwgt>,
+    slha: Option<Slha>,
+    run_card: Option<RunCard>,
+    proc_card: Option<ProcCard>,
+    generator_metadata: Vec<GeneratorMetadata>,
     heprup: HEPRUP,
+    generators: Vec<GeneratorInfo>,
+    xsecinfo: Vec<XSecInfo>,
+    cutsinfo: Option<CutsInfo>,
+    procinfo: Vec<ProcInfo>,
+    mergeinfo: Vec<MergeInfo>,
+    context: Arc<EventContext>,
+    pending: VecDeque<HEPEUP>,
+    /// raw lines to be read again before continuing with the stream
+    unread: Vec<u8>,
+    /// raw lines read since the start of the current block, which can
+    /// be put back with `unread_line`
+    consumed: Vec<u8>,
+    /// incomplete last line when following a growing stream
+    partial: Vec<u8>,
+    follow: Option<Follow>,
+    finished: bool,
+    /// comments and blank lines before the last block
+    skipped: String,
+    /// text of the last block if the event information is borrowed
+    retained: String,
+    /// whether `retained` is an event group
+    retained_group: bool,
+    trailing: Option<String>,
+    /// whether the stream only contains events
+    fragments: bool,
+    /// whether block tags are matched case-insensitively
+    lenient_tags: bool,
+    continuations: VecDeque<Stream>,
+    /// number of bytes consumed from the streams
+    bytes_read: u64,
+    nevents: u64,
+    total_bytes: Option<u64>,
+    cancellation: Option<CancellationToken>,
+    weight_hook: Option<reweight::WeightHook>,
+    index: Option<Index>,
 }
 
 impl<Stream: BufRead> Reader<Stream> {
@@ -34,16 +242,129 @@ impl<Stream: BufRead> Reader<Stream> {
     /// let file = std::io::BufReader::new(file);
     /// let reader = lhef::Reader::new(file).unwrap();
     /// ```
-    pub fn new(mut stream: Stream) -> Result<Reader<Stream>, Box<error::Error>> {
-        let version = parse_version(&mut stream)?;
-        let header = parse_header(&mut stream)?;
-        let heprup = parse_init(&mut stream)?;
-        Ok(Reader{stream, version, header, heprup})
+    pub fn new(stream: Stream) -> Result<Reader<Stream>, Box<dyn error::Error>> {
+        ReaderBuilder::new().build(stream)
+    }
+
+    /// Create a new LHEF reader using custom parsers for extension tags
+    ///
+    /// See `ExtensionRegistry` for an example.
+    pub fn with_extensions(
+        stream: Stream,
+        extensions: ExtensionRegistry
+    ) -> Result<Reader<Stream>, Box<dyn error::Error>> {
+        ReaderBuilder::new().extensions(extensions).build(stream)
+    }
+
+    /// Create a reader for a stream that only contains events
+    ///
+    /// The stream consists of `<event>` and `<eventgroup>` blocks
+    /// without the surrounding `<LesHouchesEvents>` tags, header, and
+    /// init block, as is common when sending individual events over a
+    /// network. The run information is taken from `heprup` instead.
+    /// The end of the stream marks the end of the events, and the
+    /// version is assumed to be 3.0.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # let heprup: lhef::HEPRUP = unimplemented!();
+    /// let fragment = "<event>\n0 1 1.0 0 0 0\n</event>\n";
+    /// let mut reader = lhef::Reader::from_fragments(fragment.as_bytes(), heprup).unwrap();
+    /// let event = reader.event().unwrap();
+    /// ```
+    pub fn from_fragments(
+        stream: Stream,
+        heprup: HEPRUP
+    ) -> Result<Reader<Stream>, Box<dyn error::Error>> {
+        ReaderBuilder::new().build_fragments(stream, heprup)
+    }
+
+    fn from_parts(
+        stream: Stream,
+        version: &'static str,
+        header: String,
+        heprup: HEPRUP,
+        extensions: ExtensionRegistry,
+        limits: Limits,
+        info: InfoRetention
+    ) -> Result<Reader<Stream>, Box<dyn error::Error>> {
+        let initrwgt = optional("weight information", initrwgt::parse_initrwgt(&header));
+        let slha = optional("SLHA parameters", slha::parse_slha(&header));
+        let run_card = runcard::parse_run_card(&header);
+        let proc_card = proccard::parse_proc_card(&header);
+        let generator_metadata = banner::parse_generator_metadata(&header);
+        let generators = generator::parse_generators(&heprup.info);
+        let xsecinfo = xsecinfo::parse_xsecinfo(&heprup.info);
+        let xsecinfo = optional("cross section information", xsecinfo);
+        let cutsinfo = optional("cut information", cutsinfo::parse_cutsinfo(&heprup.info));
+        let procinfo = optional("process information", procinfo::parse_procinfo(&heprup.info));
+        let mergeinfo = optional("merging information", procinfo::parse_mergeinfo(&heprup.info));
+        let weight_ids = initrwgt.as_ref()
+            .map(|initrwgt| initrwgt.weights().iter().map(|w| w.id.clone()).collect())
+            .unwrap_or_default();
+        let context = Arc::new(EventContext{
+            extensions, weight_ids, limits, info,
+            non_finite: NonFinitePolicy::default(),
+            non_finite_count: AtomicU64::new(0),
+        });
+        Ok(Reader{
+            stream, version, header, initrwgt, slha, run_card, proc_card,
+            declared_version: Some(version.to_owned()),
+            root_attributes: Attributes::new(),
+            generator_metadata, heprup,
+            generators, xsecinfo, cutsinfo, procinfo, mergeinfo,
+            context,
+            pending: VecDeque::new(),
+            unread: Vec::new(),
+            consumed: Vec::new(),
+            partial: Vec::new(),
+            follow: None,
+            finished: false,
+            skipped: String::new(),
+            retained: String::new(),
+            retained_group: false,
+            trailing: None,
+            fragments: false,
+            lenient_tags: false,
+            continuations: VecDeque::new(),
+            bytes_read: 0,
+            nevents: 0,
+            total_bytes: None,
+            cancellation: None,
+            weight_hook: None,
+            index: None,
+        })
     }
 
     /// Get the LHEF version
     pub fn version(&self) -> &str {
-        &self.version
+        self.version
+    }
+
+    /// Get the version attribute of the `<LesHouchesEvents>` tag as
+    /// found in the input, or `None` if it is missing
+    ///
+    /// This only differs from `version` if the reader was created with
+    /// `ReaderBuilder::default_version` and the declared version was
+    /// missing or not supported.
+    pub fn declared_version(&self) -> Option<&str> {
+        self.declared_version.as_deref()
+    }
+
+    /// Get the comments and blank lines between the previous block and
+    /// the last block returned by `block` or `event`
+    ///
+    /// Such lines are skipped when reading events. The text is returned
+    /// verbatim, including line breaks.
+    pub fn skipped_text(&self) -> &str {
+        &self.skipped
+    }
+
+    /// Get the attributes of the `<LesHouchesEvents>` tag other than
+    /// the version, for example `xmlns`
+    pub fn root_attributes(&self) -> &Attributes {
+        &self.root_attributes
     }
 
     /// Get the LHEF header
@@ -51,70 +372,1272 @@ impl<Stream: BufRead> Reader<Stream> {
         &self.header
     }
 
-    /// Get the LHEF run information
-    pub fn heprup(&self) -> &HEPRUP {
-        &self.heprup
-    }
+    /// Get the weight declarations from the `<initrwgt>` tag in the header
+    pub fn initrwgt(&self) -> Option<&InitRwgt> {
+        self.initrwgt.as_ref()
+    }
+
+    /// Get the model parameters from the `<slha>` tag in the header
+    pub fn slha(&self) -> Option<&Slha> {
+        self.slha.as_ref()
+    }
+
+    /// Get the MadGraph run card from the `<MGRunCard>` tag in the header
+    pub fn run_card(&self) -> Option<&RunCard> {
+        self.run_card.as_ref()
+    }
+
+    /// Get the processes from the MadGraph process card in the header
+    pub fn proc_card(&self) -> Option<&ProcCard> {
+        self.proc_card.as_ref()
+    }
+
+    /// Get the generators recognised from their banners in the header
+    ///
+    /// MadGraph, POWHEG-BOX, and Pythia banners are recognised.
+    pub fn generator_metadata(&self) -> &[GeneratorMetadata] {
+        &self.generator_metadata
+    }
+
+    /// Get the records of the processing steps from the `<provenance>`
+    /// tags in the header, oldest first
+    pub fn provenance(&self) -> Vec<Provenance> {
+        provenance::parse_provenance(&self.header)
+    }
+
+    /// Get the LHEF run information
+    pub fn heprup(&self) -> &HEPRUP {
+        &self.heprup
+    }
+
+    /// Get the information from the `<generator>` tags in the init block
+    pub fn generators(&self) -> &[GeneratorInfo] {
+        &self.generators
+    }
+
+    /// Get the information from the `<xsecinfo>` tag in the init block
+    ///
+    /// If there are several such tags, the one without a `weightname`
+    /// attribute is returned.
+    pub fn xsecinfo(&self) -> Option<&XSecInfo> {
+        self.xsecinfo.iter()
+            .find(|info| info.weightname.is_none())
+            .or_else(|| self.xsecinfo.first())
+    }
+
+    /// Get the information from all `<xsecinfo>` tags in the init block
+    pub fn xsecinfos(&self) -> &[XSecInfo] {
+        &self.xsecinfo
+    }
+
+    /// Get the generator-level cuts from the `<cutsinfo>` tag in the
+    /// init block
+    pub fn cutsinfo(&self) -> Option<&CutsInfo> {
+        self.cutsinfo.as_ref()
+    }
+
+    /// Get the process information from the `<procinfo>` tags in the
+    /// init block
+    pub fn procinfo(&self) -> &[ProcInfo] {
+        &self.procinfo
+    }
+
+    /// Get the merging information from the `<mergeinfo>` tags in the
+    /// init block
+    pub fn mergeinfo(&self) -> &[MergeInfo] {
+        &self.mergeinfo
+    }
+
+    /// Get the next event
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// let file = std::fs::File::open("events.lhe").unwrap();
+    /// let file = std::io::BufReader::new(file);
+    /// let mut reader = lhef::Reader::new(file).unwrap();
+    ///
+    /// let event = reader.event().unwrap();
+    /// match event {
+    ///    Some(event) => println!("Found an event."),
+    ///    None => println!("Reached end of event file."),
+    /// }
+    /// ```
+    pub fn event(&mut self) -> Result<Option<HEPEUP>, Box<dyn error::Error>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(Some(event))
+            }
+            match self.block()? {
+                Some(Block::Event(event)) => return Ok(Some(event)),
+                Some(Block::EventGroup(events)) => self.pending = events.into(),
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Get the next event or group of events
+    ///
+    /// Unlike `event`, this keeps the events in an `<eventgroup>`
+    /// together. If some events of a group have already been retrieved
+    /// with `event`, the remaining ones are returned as a group.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use lhef::Block;
+    ///
+    /// let file = std::fs::File::open("events.lhe").unwrap();
+    /// let file = std::io::BufReader::new(file);
+    /// let mut reader = lhef::Reader::new(file).unwrap();
+    ///
+    /// while let Some(block) = reader.block().unwrap() {
+    ///     match block {
+    ///         Block::Event(event) => println!("Found an event."),
+    ///         Block::EventGroup(events) => {
+    ///             println!("Found a group of {} events.", events.len())
+    ///         },
+    ///     }
+    /// }
+    /// ```
+    pub fn block(&mut self) -> Result<Option<Block>, Box<dyn error::Error>> {
+        span!(TRACE, "lhef_block", nevents = self.nevents);
+        if !self.pending.is_empty() {
+            let events = self.pending.drain(..).collect();
+            return Ok(Some(Block::EventGroup(events)))
+        }
+        let text = self.block_text()?;
+        self.parse_block(text)
+    }
+
+    /// Parse the text of a block read with `block_text`
+    fn parse_block(
+        &mut self,
+        text: Option<BlockText>,
+    ) -> Result<Option<Block>, Box<dyn error::Error>> {
+        match text {
+            Some(BlockText::Event(attr, text)) => {
+                let mut event = parse_event(&text, attr, &self.context)?;
+                self.apply_weight_hook(std::slice::from_mut(&mut event))?;
+                self.nevents += 1;
+                self.retain(text, false);
+                Ok(Some(Block::Event(event)))
+            },
+            Some(BlockText::EventGroup(text)) => {
+                let mut events = parse_eventgroup(&text, &self.context)?;
+                self.apply_weight_hook(&mut events)?;
+                self.nevents += events.len() as u64;
+                self.retain(text, true);
+                Ok(Some(Block::EventGroup(events)))
+            },
+            Some(BlockText::Other(line)) => Err(Box::new(ParseError::BadEventStart(line))),
+            None => Ok(None)
+        }
+    }
+
+    /// Get the next event without parsing it
+    ///
+    /// Only the text of the event is read, so filters that reject most
+    /// events, for example based on the weight or the process ID, can
+    /// skip the cost of parsing them. Accepted events are parsed with
+    /// `RawEvent::parse`. The weight hook of the reader is not applied
+    /// to them.
+    ///
+    /// Events in an `<eventgroup>` are parsed together when the group
+    /// is reached, like with `event`, and returned one by one.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// let mut reader = lhef::Reader::open("events.lhe").unwrap();
+    /// while let Some(raw) = reader.raw_event().unwrap() {
+    ///     if raw.process_id().unwrap() == 1 {
+    ///         let event = raw.parse().unwrap();
+    ///         println!("Found an event with {} particles.", event.NUP);
+    ///     }
+    /// }
+    /// ```
+    pub fn raw_event(&mut self) -> Result<Option<RawEvent>, Box<dyn error::Error>> {
+        span!(TRACE, "lhef_raw_event", nevents = self.nevents);
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(Some(RawEvent::parsed(event)))
+            }
+            match self.block_text()? {
+                Some(BlockText::Event(attr, text)) => {
+                    self.nevents += 1;
+                    return Ok(Some(RawEvent::new(text, attr, self.context.clone())))
+                },
+                Some(BlockText::EventGroup(text)) => {
+                    let mut events = parse_eventgroup(&text, &self.context)?;
+                    self.apply_weight_hook(&mut events)?;
+                    self.nevents += events.len() as u64;
+                    self.retain(text, true);
+                    self.pending = events.into();
+                },
+                Some(BlockText::Other(line)) => {
+                    return Err(Box::new(ParseError::BadEventStart(line)))
+                },
+                None => return Ok(None)
+            }
+        }
+    }
+
+    /// Pass the remaining contents of the event file to a visitor
+    ///
+    /// The header and the run information are visited first, followed
+    /// by the events in the order in which they appear. Unlike with
+    /// `block`, unknown tags between the events are passed to
+    /// `LheVisitor::on_unknown_tag` instead of causing an error.
+    /// Visiting stops at the first error returned by the visitor.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use lhef::{LheVisitor, HEPEUP};
+    ///
+    /// struct Count(usize);
+    ///
+    /// impl LheVisitor for Count {
+    ///     fn on_event(&mut self, _event: &HEPEUP) -> Result<(), Box<dyn std::error::Error>> {
+    ///         self.0 += 1;
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let mut reader = lhef::Reader::open("events.lhe").unwrap();
+    /// let mut count = Count(0);
+    /// reader.visit(&mut count).unwrap();
+    /// println!("Found {} events.", count.0);
+    /// ```
+    pub fn visit<V>(&mut self, visitor: &mut V) -> Result<(), Box<dyn error::Error>>
+    where
+        V: LheVisitor + ?Sized,
+    {
+        span!(TRACE, "lhef_visit", nevents = self.nevents);
+        visitor.on_header(&self.header)?;
+        visitor.on_init(&self.heprup)?;
+        if !self.pending.is_empty() {
+            let events: Vec<_> = self.pending.drain(..).collect();
+            visitor.on_event_group(&events)?;
+        }
+        loop {
+            let text = match self.block_text()? {
+                Some(BlockText::Other(line)) => {
+                    let tag = self.read_other_block(line)?;
+                    visitor.on_unknown_tag(&tag)?;
+                    continue
+                },
+                text => text,
+            };
+            match self.parse_block(text)? {
+                Some(Block::Event(event)) => visitor.on_event(&event)?,
+                Some(Block::EventGroup(events)) => visitor.on_event_group(&events)?,
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /// Read the text of the next block
+    fn block_text(&mut self) -> Result<Option<BlockText>, Box<dyn error::Error>> {
+        self.check_cancelled()?;
+        if self.finished && !self.next_stream() {
+            return Ok(None)
+        }
+        self.consumed.clear();
+        let mut skipped = String::new();
+        let mut in_comment = false;
+        let line = loop {
+            let line = match self.read_line() {
+                Ok(line) => line,
+                Err(err) => {
+                    if cancel::is_resumable(&err) {
+                        self.unread_line(&skipped);
+                    }
+                    return Err(cancel::from_io_error(err))
+                },
+            };
+            if !line.is_empty() && is_between_blocks(&line, in_comment) {
+                in_comment = ends_in_comment(&line, in_comment);
+                skipped.push_str(&line);
+                limits::check("comment", skipped.len(), self.context.limits.max_block_bytes)?;
+                continue
+            }
+            let at_end = line.is_empty() || line.trim() == LHEF_LAST_LINE;
+            if !at_end || !self.next_stream() {
+                break line
+            }
+            event!(DEBUG, "continuing with next stream");
+        };
+        self.skipped = skipped;
+        if line.is_empty() && self.fragments {
+            self.finished = true;
+            return Ok(None)
+        }
+        if let Some(attr) = tags::opening_tag(&line, EVENT_TAG) {
+            let attr = Attributes::from(attr);
+            let text = self.read_block_text(&line, EVENT_END, "event", starts_block)?;
+            return Ok(Some(BlockText::Event(attr, text)))
+        }
+        match line.trim() {
+            LHEF_LAST_LINE => {
+                self.finished = true;
+                Ok(None)
+            },
+            l if is_eventgroup_start(l) => {
+                let text = self.read_block_text(
+                    &line, EVENTGROUP_END, "eventgroup", starts_eventgroup
+                )?;
+                Ok(Some(BlockText::EventGroup(text)))
+            },
+            _ => Ok(Some(BlockText::Other(line)))
+        }
+    }
+
+    /// Get the number of weights and momenta that were NaN or infinite
+    ///
+    /// This counts the values that were kept or replaced according to
+    /// the `NonFinitePolicy` of the reader, including those in events
+    /// parsed later with `RawEvent::parse`.
+    pub fn non_finite_values(&self) -> u64 {
+        self.context.non_finite_count.load(Ordering::Relaxed)
+    }
+
+    /// Set the policy for weights and momenta that are NaN or infinite
+    ///
+    /// This has to be done before any event is read.
+    fn set_non_finite_policy(&mut self, policy: NonFinitePolicy) {
+        let context = Arc::get_mut(&mut self.context).expect("event context is shared");
+        context.non_finite = policy;
+    }
+
+    /// Keep the text of a block if the event information is borrowed
+    fn retain(&mut self, text: String, group: bool) {
+        if self.context.info == InfoRetention::Borrow {
+            self.retained = text;
+            self.retained_group = group;
+        }
+    }
+
+    /// Get the optional information of the events in the most recent
+    /// block
+    ///
+    /// This is only available if the reader was built with
+    /// `InfoRetention::Borrow` and is empty otherwise. The information
+    /// is borrowed from the text of the block kept by the reader, unless
+    /// tags with a dedicated representation in `HEPEUP` have to be
+    /// removed from it. Blocks are read by `event`, `block`,
+    /// `raw_event`, and `visit`; for `raw_event`, only event groups
+    /// are kept, since `RawEvent::text` contains the whole event.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use lhef::InfoRetention;
+    ///
+    /// let mut reader = lhef::ReaderBuilder::new()
+    ///     .info_retention(InfoRetention::Borrow)
+    ///     .open("events.lhe")
+    ///     .unwrap();
+    /// while let Some(_block) = reader.block().unwrap() {
+    ///     for info in reader.block_info().unwrap() {
+    ///         print!("{}", info);
+    ///     }
+    /// }
+    /// ```
+    pub fn block_info(&self) -> Result<Vec<Cow<'_, str>>, Box<dyn error::Error>> {
+        if self.retained.is_empty() {
+            return Ok(Vec::new())
+        }
+        retention::block_info(&self.retained, self.retained_group, &self.context)
+    }
+
+    fn apply_weight_hook(&mut self, events: &mut [HEPEUP]) -> Result<(), Box<dyn error::Error>> {
+        if let Some(ref mut hook) = self.weight_hook {
+            for event in events {
+                reweight::apply(hook, event)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the progress in reading the events
+    ///
+    /// The fraction of the input that has been read can only be
+    /// estimated if the total size is known. For seekable streams, it
+    /// can be determined with `detect_total_bytes`, otherwise it can be
+    /// set with `set_total_bytes`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// let file = std::fs::File::open("events.lhe").unwrap();
+    /// let file = std::io::BufReader::new(file);
+    /// let mut reader = lhef::Reader::new(file).unwrap();
+    /// reader.detect_total_bytes().unwrap();
+    /// while let Some(event) = reader.event().unwrap() {
+    ///     let progress = reader.progress();
+    ///     if progress.events % 10000 == 0 {
+    ///         let percent = 100. * progress.fraction().unwrap();
+    ///         eprintln!("Read {} events ({:.1}%)", progress.events, percent);
+    ///     }
+    /// }
+    /// ```
+    pub fn progress(&self) -> Progress {
+        Progress {
+            bytes: self.position(),
+            events: self.nevents,
+            total_bytes: self.total_bytes,
+        }
+    }
+
+    /// Get the current state, from which reading can be resumed later
+    ///
+    /// See `Checkpoint` and `resume`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// let file = std::fs::File::open("events.lhe").unwrap();
+    /// let file = std::io::BufReader::new(file);
+    /// let mut reader = lhef::Reader::new(file).unwrap();
+    /// while let Some(event) = reader.event().unwrap() {
+    ///     if reader.progress().events % 100000 == 0 {
+    ///         let checkpoint = reader.checkpoint();
+    ///         // store the checkpoint together with the output so far
+    ///     }
+    /// }
+    /// ```
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            offset: self.position(),
+            events: self.nevents,
+            version: self.version.to_owned(),
+            declared_version: self.declared_version.clone(),
+            root_attributes: self.root_attributes.clone(),
+            header: self.header.clone(),
+            heprup: self.heprup.clone(),
+            pending: self.pending.iter().cloned().collect(),
+            fragments: self.fragments,
+            lenient_tags: self.lenient_tags,
+        }
+    }
+
+    /// Number of bytes consumed up to the first line that has not been
+    /// read completely
+    fn position(&self) -> u64 {
+        self.bytes_read - (self.unread.len() + self.partial.len()) as u64
+    }
+
+    /// Iterate over the remaining events while showing a progress bar
+    ///
+    /// This requires the `indicatif` feature. The bar shows the number
+    /// of bytes and events read, as well as the estimated remaining time
+    /// if the total size of the input is known (see `progress`).
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # #[cfg(feature = "indicatif")]
+    /// # fn main() {
+    /// let file = std::fs::File::open("events.lhe").unwrap();
+    /// let file = std::io::BufReader::new(file);
+    /// let mut reader = lhef::Reader::new(file).unwrap();
+    /// reader.set_total_bytes(Some(1_000_000));
+    /// for event in reader.events_with_progress_bar() {
+    ///     let event = event.unwrap();
+    /// }
+    /// # }
+    /// # #[cfg(not(feature = "indicatif"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg(feature = "indicatif")]
+    pub fn events_with_progress_bar(&mut self) -> ProgressBarEvents<'_, Stream> {
+        let bar = progress_bar::progress_bar(self);
+        ProgressBarEvents::new(self, bar)
+    }
+
+    /// Iterate over the remaining events while updating the given
+    /// progress bar
+    ///
+    /// Like `events_with_progress_bar`, but using a custom bar, for
+    /// example one that is part of an `indicatif::MultiProgress`.
+    #[cfg(feature = "indicatif")]
+    pub fn events_with_custom_progress_bar(
+        &mut self,
+        bar: indicatif::ProgressBar
+    ) -> ProgressBarEvents<'_, Stream> {
+        ProgressBarEvents::new(self, bar)
+    }
+
+    /// Set the total size of the input in bytes
+    ///
+    /// This is used to estimate the fraction of the input that has been
+    /// read, see `progress`. For compressed input, this should be the
+    /// uncompressed size.
+    pub fn set_total_bytes(&mut self, total_bytes: Option<u64>) {
+        self.total_bytes = total_bytes
+    }
+
+    /// Allow cancelling reading with the given token
+    ///
+    /// See `CancellationToken`. While waiting for data in follow mode,
+    /// the token is checked after each poll interval. Reading can be
+    /// resumed after setting a different token or `None`.
+    pub fn set_cancellation(&mut self, token: Option<CancellationToken>) {
+        self.cancellation = token
+    }
+
+    fn check_cancelled(&self) -> Result<(), Box<dyn error::Error>> {
+        cancel::check(self.cancellation.as_ref()).map_err(cancel::from_io_error)
+    }
+
+    /// Append a stream with further events
+    ///
+    /// The continuation only contains `<event>` and `<eventgroup>`
+    /// blocks, like the streams read by `from_fragments`, and optionally
+    /// a closing `</LesHouchesEvents>` tag. Its events are returned once
+    /// those of the current stream are exhausted, which happens at its
+    /// closing tag or at the end of the stream. This allows reading event
+    /// files that are split into a primary file with the header and init
+    /// block and any number of continuation files.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    ///
+    /// let file = BufReader::new(File::open("events.lhe").unwrap());
+    /// let mut reader = lhef::Reader::new(file).unwrap();
+    /// for continuation in &["events_1.lhe", "events_2.lhe"] {
+    ///     reader.attach(BufReader::new(File::open(continuation).unwrap()));
+    /// }
+    /// while let Some(event) = reader.event().unwrap() {
+    ///     // events from all files
+    /// }
+    /// ```
+    pub fn attach(&mut self, continuation: Stream) {
+        self.continuations.push_back(continuation)
+    }
+
+    /// Wait for more data at the end of the stream
+    ///
+    /// With `Some(follow)`, the reader keeps polling a stream that is
+    /// still being written, like `tail -f`, instead of treating its
+    /// current end as the end of the events. If `follow.timeout` passes
+    /// without new data, `event` and `block` fail with an error of kind
+    /// `std::io::ErrorKind::TimedOut`. Reading can then be resumed by
+    /// calling them again. Pass `None` to stop following.
+    ///
+    /// The header and init block are read when creating the reader and
+    /// must already be complete.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    ///
+    /// let file = std::fs::File::open("events.lhe").unwrap();
+    /// let file = std::io::BufReader::new(file);
+    /// let mut reader = lhef::Reader::new(file).unwrap();
+    /// reader.follow(Some(lhef::Follow {
+    ///     poll_interval: Duration::from_millis(500),
+    ///     timeout: Some(Duration::from_secs(600)),
+    /// }));
+    /// while let Some(event) = reader.event().unwrap() {
+    ///     // process events as they are written
+    /// }
+    /// ```
+    pub fn follow(&mut self, follow: Option<Follow>) {
+        self.follow = follow
+    }
+
+    /// Switch to the next continuation stream, if any
+    fn next_stream(&mut self) -> bool {
+        match self.continuations.pop_front() {
+            Some(stream) => {
+                self.stream = stream;
+                self.unread.clear();
+                self.consumed.clear();
+                self.partial.clear();
+                self.finished = false;
+                self.trailing = None;
+                self.fragments = true;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Get the content following the closing `</LesHouchesEvents>` tag
+    ///
+    /// Some tools append comments or further banners to the end of the
+    /// file. Returns `None` if the closing tag has not been reached yet.
+    /// Otherwise, the remainder of the stream is read on the first call.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// let file = std::fs::File::open("events.lhe").unwrap();
+    /// let file = std::io::BufReader::new(file);
+    /// let mut reader = lhef::Reader::new(file).unwrap();
+    ///
+    /// while let Some(_event) = reader.event().unwrap() { }
+    /// if let Some(trailing) = reader.trailing().unwrap() {
+    ///     println!("Found content after the last event: {}", trailing);
+    /// }
+    /// ```
+    pub fn trailing(&mut self) -> Result<Option<&str>, Box<dyn error::Error>> {
+        if !self.finished {
+            return Ok(None)
+        }
+        if self.trailing.is_none() {
+            let mut trailing = mem::take(&mut self.unread);
+            trailing.append(&mut self.partial);
+            self.bytes_read += self.stream.read_to_end(&mut trailing)? as u64;
+            self.trailing = Some(limits::decode(trailing));
+        }
+        Ok(self.trailing.as_deref())
+    }
+
+    /// Iterate over the remaining events, skipping malformed ones
+    ///
+    /// When an event or event group cannot be read, the iterator skips
+    /// ahead to the next `<event>` or `<eventgroup>` and records a
+    /// `Diagnostic`. This allows extracting the intact events from a
+    /// partially corrupted file.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// let file = std::fs::File::open("events.lhe").unwrap();
+    /// let file = std::io::BufReader::new(file);
+    /// let mut reader = lhef::Reader::new(file).unwrap();
+    ///
+    /// let mut events = reader.events_lossy();
+    /// let nevents = events.by_ref().count();
+    /// for diagnostic in events.diagnostics() {
+    ///     eprintln!("Skipped a bad event: {}", diagnostic.message);
+    /// }
+    /// ```
+    pub fn events_lossy(&mut self) -> LossyEvents<'_, Stream> {
+        LossyEvents::new(self)
+    }
+
+    /// Get the next line, either from the stream or a line put back
+    /// after reading too far
+    ///
+    /// Returns an empty string at the end of the stream.
+    fn read_line(&mut self) -> io::Result<String> {
+        let mut line = String::new();
+        self.read_line_into(&mut line)?;
+        Ok(line)
+    }
+
+    /// Append the next line to `text`, see `read_line`
+    ///
+    /// The raw bytes of the line are collected in `consumed`, which is
+    /// reused for all lines, so reading a block into a single string
+    /// does not allocate for each line. Returns the length of the
+    /// appended line, which is 0 at the end of the stream.
+    fn read_line_into(&mut self, text: &mut String) -> io::Result<usize> {
+        let start = text.len();
+        let raw_start = self.consumed.len();
+        if !self.unread.is_empty() {
+            let len = self.unread.iter().position(|&c| c == b'\n');
+            let len = len.map_or(self.unread.len(), |pos| pos + 1);
+            self.consumed.extend(self.unread.drain(..len));
+        } else {
+            self.read_stream_line()?;
+            self.consumed.append(&mut self.partial);
+        }
+        limits::decode_into(&self.consumed[raw_start..], text);
+        if self.lenient_tags {
+            let names = [EVENT_TAG, EVENTGROUP_TAG, LHEF_TAG];
+            if let Some(line) = tags::normalize_tag(&text[start..], &names) {
+                text.truncate(start);
+                text.push_str(&line);
+            }
+        }
+        Ok(text.len() - start)
+    }
+
+    /// Read the next line from the stream into `partial`
+    fn read_stream_line(&mut self) -> io::Result<()> {
+        let before = self.partial.len();
+        let max_len = self.context.limits.max_line_length;
+        let result = match self.follow {
+            Some(ref follow) => {
+                let cancellation = self.cancellation.as_ref();
+                follow.read_line(&mut self.stream, &mut self.partial, max_len, cancellation)
+            },
+            None => limits::read_line(&mut self.stream, &mut self.partial, max_len).map(|_| ()),
+        };
+        self.bytes_read += (self.partial.len() - before) as u64;
+        if let Err(err) = result {
+            if cancel::is_resumable(&err) {
+                event!(DEBUG, "stopped waiting for more data: {}", err);
+            } else {
+                self.partial.clear();
+            }
+            return Err(err)
+        }
+        Ok(())
+    }
+
+    /// Put back the last lines read, which are given by `lines`, to be
+    /// read again
+    ///
+    /// The raw lines are put back instead of the decoded ones, so that
+    /// the byte counts stay exact for Latin-1 input and lenient tags.
+    fn unread_line(&mut self, lines: &str) {
+        let mut start = self.consumed.len();
+        for _ in lines.split_inclusive('\n') {
+            start = self.consumed[..start.saturating_sub(1)]
+                .iter()
+                .rposition(|&c| c == b'\n')
+                .map_or(0, |pos| pos + 1);
+        }
+        let mut unread = self.consumed.split_off(start);
+        unread.append(&mut self.unread);
+        self.unread = unread;
+    }
+
+    /// Read the text of a block starting with the line `start` up to
+    /// the closing tag `end`
+    ///
+    /// If a line for which `is_next_block` is true is encountered first
+    /// outside a comment, the closing tag is missing. That line is then
+    /// kept for the next block, so that only the unterminated block is
+    /// lost. If following the stream times out or is cancelled, the
+    /// whole block is kept to be read again later.
+    fn read_block_text(
+        &mut self,
+        start: &str,
+        end: &str,
+        block: &'static str,
+        is_next_block: fn(&str) -> bool,
+    ) -> Result<String, Box<dyn error::Error>> {
+        let mut text = String::new();
+        let mut in_comment = false;
+        loop {
+            let line_start = text.len();
+            match self.read_line_into(&mut text) {
+                Ok(0) => return Err(Box::new(ParseError::EndOfFile(block))),
+                Ok(_) => {},
+                Err(err) => {
+                    if cancel::is_resumable(&err) {
+                        self.unread_line(&text);
+                        self.unread_line(start);
+                    }
+                    return Err(cancel::from_io_error(err))
+                },
+            };
+            let line = &text[line_start..];
+            if line.trim() == end {
+                text.truncate(line_start);
+                return Ok(text)
+            }
+            if !in_comment && is_next_block(line) {
+                event!(WARN, block, "missing closing tag before {}", line.trim());
+                let line = text.split_off(line_start);
+                self.unread_line(&line);
+                return Err(Box::new(ParseError::EndOfFile(block)));
+            }
+            in_comment = ends_in_comment(line, in_comment);
+            limits::check(block, text.len(), self.context.limits.max_block_bytes)?;
+        }
+    }
+
+    /// Read the complete element starting with the line `start`
+    ///
+    /// This is used for tags other than events and event groups.
+    fn read_other_block(&mut self, start: String) -> Result<String, Box<dyn error::Error>> {
+        let name = match tags::tag_name(&start) {
+            Some(name) => name.to_owned(),
+            None => return Err(Box::new(ParseError::BadEventStart(start))),
+        };
+        let end = format!("</{}>", name);
+        let trimmed = start.trim();
+        if trimmed.ends_with("/>") || trimmed.ends_with(&end) {
+            return Ok(start)
+        }
+        let text = self.read_block_text(&start, &end, "tag", starts_block)?;
+        Ok(start + &text + &end + "\n")
+    }
+
+    /// Skip lines up to the start of the next block
+    ///
+    /// Returns the number of skipped lines, or `None` if the end of the
+    /// stream was reached without finding another block.
+    fn skip_to_next_block(&mut self) -> io::Result<Option<usize>> {
+        self.consumed.clear();
+        let mut skipped = 0;
+        loop {
+            let line = match self.read_line() {
+                Ok(line) => line,
+                Err(ref err) if err.kind() == io::ErrorKind::InvalidData => {
+                    skipped += 1;
+                    continue
+                },
+                Err(err) => return Err(err),
+            };
+            if line.is_empty() {
+                return Ok(None)
+            }
+            if starts_block(&line) {
+                self.unread_line(&line);
+                return Ok(Some(skipped))
+            }
+            skipped += 1;
+        }
+    }
+}
+
+impl Reader<Box<dyn BufRead + Send>> {
+    /// Open the LHEF file at `path`
+    ///
+    /// The file is buffered, and decompressed if it is gzipped.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// let mut reader = lhef::Reader::open("events.lhe.gz").unwrap();
+    /// while let Some(event) = reader.event().unwrap() {
+    ///     println!("Found an event.");
+    /// }
+    /// ```
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn error::Error>> {
+        ReaderBuilder::new().open(path)
+    }
+}
+
+impl<Stream: Read> Reader<BufReader<Stream>> {
+    /// Create a new LHEF reader for an unbuffered stream
+    ///
+    /// The stream is wrapped in a `BufReader` with the default
+    /// capacity. Use `ReaderBuilder::buffer_capacity` and
+    /// `ReaderBuilder::build_from_read` to choose a different capacity.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// let file = std::fs::File::open("events.lhe").unwrap();
+    /// let reader = lhef::Reader::from_read(file).unwrap();
+    /// ```
+    pub fn from_read(stream: Stream) -> Result<Self, Box<dyn error::Error>> {
+        ReaderBuilder::new().build_from_read(stream)
+    }
+}
+
+impl<Stream: BufRead + Seek> Reader<Stream> {
+    /// Continue reading from a checkpoint
+    ///
+    /// `stream` has to be the same input from which the checkpoint was
+    /// taken. The header and init block are not parsed again, but taken
+    /// from the checkpoint. To use custom settings, see
+    /// `ReaderBuilder::resume`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # let checkpoint: lhef::Checkpoint = unimplemented!();
+    /// let file = std::fs::File::open("events.lhe").unwrap();
+    /// let file = std::io::BufReader::new(file);
+    /// let mut reader = lhef::Reader::resume(file, checkpoint).unwrap();
+    /// while let Some(event) = reader.event().unwrap() {
+    ///     // continue with the events after the checkpoint
+    /// }
+    /// ```
+    pub fn resume(
+        stream: Stream,
+        checkpoint: Checkpoint
+    ) -> Result<Reader<Stream>, Box<dyn error::Error>> {
+        ReaderBuilder::new().resume(stream, checkpoint)
+    }
+
+    /// Get the last `n` events of a seekable stream
+    ///
+    /// Only the end of the stream is scanned and parsed, which makes it
+    /// cheap to check how a long run ended. Events inside an
+    /// `<eventgroup>` are counted individually. If there are fewer than
+    /// `n` events, all events are returned. The position of the reader
+    /// is left unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// let file = std::fs::File::open("events.lhe").unwrap();
+    /// let file = std::io::BufReader::new(file);
+    /// let mut reader = lhef::Reader::new(file).unwrap();
+    /// let last = reader.last_events(10).unwrap();
+    /// println!("Weight of the last event: {}", last[last.len() - 1].XWGTUP);
+    /// ```
+    pub fn last_events(&mut self, n: usize) -> Result<Vec<HEPEUP>, Box<dyn error::Error>> {
+        let pos = self.stream.stream_position()?;
+        let text = tail::read_last_events(&mut self.stream, n);
+        self.stream.seek(SeekFrom::Start(pos))?;
+        let mut events = parse_events_in(&text?, &self.context)?;
+        let skip = events.len().saturating_sub(n);
+        events.drain(..skip);
+        self.apply_weight_hook(&mut events)?;
+        Ok(events)
+    }
+
+    /// Use an index for random access to the events
+    ///
+    /// See `Index` and `event_at`. Fails if the index was built for an
+    /// input of a different size.
+    pub fn with_index(mut self, index: Index) -> Result<Reader<Stream>, Box<dyn error::Error>> {
+        let pos = self.stream.stream_position()?;
+        let end = self.stream.seek(SeekFrom::End(0))?;
+        self.stream.seek(SeekFrom::Start(pos))?;
+        if end != index.source_len() {
+            return Err(Box::new(index::IndexError::SizeMismatch {
+                index: index.source_len(),
+                input: end,
+            }))
+        }
+        self.index = Some(index);
+        Ok(self)
+    }
+
+    /// Get the index used for random access, if any
+    pub fn index(&self) -> Option<&Index> {
+        self.index.as_ref()
+    }
+
+    /// Get the event with the given number, counting from 0
+    ///
+    /// This requires an index, see `with_index`. Events inside an
+    /// `<eventgroup>` are counted individually. Returns `None` if there
+    /// are not enough events. The position of the reader is left
+    /// unchanged.
+    pub fn event_at(&mut self, n: usize) -> Result<Option<HEPEUP>, Box<dyn error::Error>> {
+        let offset = match self.index {
+            Some(ref index) => match index.offset(n) {
+                Some(offset) => offset,
+                None => return Ok(None),
+            },
+            None => return Err(Box::new(index::IndexError::NoIndex)),
+        };
+        let pos = self.stream.stream_position()?;
+        self.stream.seek(SeekFrom::Start(offset))?;
+        let text = self.read_event_text();
+        self.stream.seek(SeekFrom::Start(pos))?;
+        let mut events = parse_events_in(&text?, &self.context)?;
+        events.truncate(1);
+        self.apply_weight_hook(&mut events)?;
+        Ok(events.pop())
+    }
+
+    /// Read the text of the event starting at the current position of
+    /// the stream, including the opening and closing tags
+    fn read_event_text(&mut self) -> Result<String, Box<dyn error::Error>> {
+        let mut text = String::new();
+        let limits = &self.context.limits;
+        loop {
+            let len = limits::read_text_line(
+                &mut self.stream, &mut text, "event",
+                limits.max_block_bytes, limits.max_line_length
+            )?;
+            if len == 0 {
+                return Err(Box::new(ParseError::EndOfFile("event")))
+            }
+            if text.trim_end().ends_with(EVENT_END) {
+                return Ok(text)
+            }
+        }
+    }
+
+    /// Determine the total size of the input from the stream
+    ///
+    /// This is used to estimate the fraction of the input that has been
+    /// read, see `progress`. The position of the reader is left
+    /// unchanged.
+    pub fn detect_total_bytes(&mut self) -> io::Result<u64> {
+        let pos = self.stream.stream_position()?;
+        let end = self.stream.seek(SeekFrom::End(0))?;
+        self.stream.seek(SeekFrom::Start(pos))?;
+        self.total_bytes = Some(end);
+        Ok(end)
+    }
+
+    /// Split the remaining events into `k` parts of similar size
+    ///
+    /// Returns the byte ranges of the parts, which start at the
+    /// beginning of an `<event>` or `<eventgroup>`. Each range can be
+    /// read separately with `into_partition`, for example by the jobs of
+    /// a job array. Event groups are usually kept together, but groups
+    /// larger than about a megabyte may be split. Some ranges may be
+    /// empty if there are fewer than `k` events. The position of the
+    /// reader is left unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # let job_id = 0;
+    /// let file = std::fs::File::open("events.lhe").unwrap();
+    /// let file = std::io::BufReader::new(file);
+    /// let mut reader = lhef::Reader::new(file).unwrap();
+    /// let ranges = reader.partitions(100).unwrap();
+    /// let mut reader = reader.into_partition(ranges[job_id].clone()).unwrap();
+    /// while let Some(event) = reader.event().unwrap() {
+    ///     // process this job's share of the events
+    /// }
+    /// ```
+    pub fn partitions(&mut self, k: usize) -> Result<Vec<Range<u64>>, Box<dyn error::Error>> {
+        let pos = self.stream.stream_position()?;
+        let result = self.find_partitions(pos, k);
+        self.stream.seek(SeekFrom::Start(pos))?;
+        result
+    }
+
+    fn find_partitions(&mut self, pos: u64, k: usize) -> Result<Vec<Range<u64>>, Box<dyn error::Error>> {
+        let start = pos - (self.unread.len() + self.partial.len()) as u64;
+        let end = self.stream.seek(SeekFrom::End(0))?;
+        let mut boundaries = vec![start];
+        for i in 1..k as u64 {
+            let target = start + (end - start) * i / k as u64;
+            let previous = *boundaries.last().unwrap();
+            let boundary = partition::block_boundary(&mut self.stream, target, end)?;
+            boundaries.push(boundary.max(previous));
+        }
+        boundaries.push(end);
+        Ok(boundaries.windows(2).map(|b| b[0]..b[1]).collect())
+    }
+
+    /// Create a reader for the events in the given byte range
+    ///
+    /// The range should be one of those computed by `partitions`. The
+    /// new reader shares the header and run information of this one.
+    /// Any attached continuations are dropped.
+    pub fn into_partition(
+        mut self,
+        range: Range<u64>
+    ) -> Result<Reader<io::Take<Stream>>, Box<dyn error::Error>> {
+        self.stream.seek(SeekFrom::Start(range.start))?;
+        let len = range.end.saturating_sub(range.start);
+        let Reader{
+            stream, version, declared_version, root_attributes, header,
+            initrwgt, slha, run_card, proc_card,
+            generator_metadata, heprup,
+            generators, xsecinfo, cutsinfo, procinfo, mergeinfo,
+            lenient_tags, context, follow, cancellation, weight_hook, ..
+        } = self;
+        Ok(Reader{
+            stream: stream.take(len),
+            version, declared_version, root_attributes, header,
+            initrwgt, slha, run_card, proc_card,
+            generator_metadata, heprup,
+            generators, xsecinfo, cutsinfo, procinfo, mergeinfo,
+            context,
+            pending: VecDeque::new(),
+            unread: Vec::new(),
+            consumed: Vec::new(),
+            partial: Vec::new(),
+            follow,
+            finished: false,
+            skipped: String::new(),
+            retained: String::new(),
+            retained_group: false,
+            trailing: None,
+            fragments: true,
+            lenient_tags,
+            continuations: VecDeque::new(),
+            bytes_read: 0,
+            nevents: 0,
+            total_bytes: Some(len),
+            cancellation,
+            weight_hook,
+            index: None,
+        })
+    }
+}
+
+/// Parse all events in `text`, ignoring `<eventgroup>` boundaries and
+/// anything after the closing `</LesHouchesEvents>` tag
+fn parse_events_in(
+    text: &str,
+    context: &EventContext
+) -> Result<Vec<HEPEUP>, Box<dyn error::Error>> {
+    let mut events = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let line = split_line(&mut rest);
+        let attr = match tags::opening_tag(line, EVENT_TAG) {
+            Some(attr) => Attributes::from(attr),
+            None => match line.trim() {
+                LHEF_LAST_LINE => break,
+                EVENTGROUP_END => continue,
+                l if is_eventgroup_start(l) => continue,
+                _ => return Err(Box::new(ParseError::BadEventStart(line.to_owned()))),
+            },
+        };
+        let (event_text, after) = split_event_text(rest)?;
+        rest = after;
+        events.push(parse_event(event_text, attr, context)?);
+    }
+    Ok(events)
+}
+
+/// Split `text` into the event text up to the closing `</event>` tag and
+/// the text after it
+fn split_event_text(text: &str) -> Result<(&str, &str), Box<dyn error::Error>> {
+    let mut rest = text;
+    let mut len = 0;
+    loop {
+        if rest.is_empty() {
+            return Err(Box::new(ParseError::EndOfFile("event")));
+        }
+        let line = split_line(&mut rest);
+        if line.trim() == EVENT_END {
+            return Ok((&text[..len], rest))
+        }
+        len += line.len();
+    }
+}
+
+/// Check if a comment is still open at the end of the line
+fn ends_in_comment(mut line: &str, mut in_comment: bool) -> bool {
+    loop {
+        let delimiter = if in_comment { COMMENT_END } else { COMMENT_START };
+        match line.find(delimiter) {
+            Some(pos) => {
+                line = &line[pos + delimiter.len()..];
+                in_comment = !in_comment;
+            },
+            None => return in_comment,
+        }
+    }
+}
+
+/// Check if a line is blank or part of a comment, which can appear
+/// between blocks
+fn is_between_blocks(line: &str, in_comment: bool) -> bool {
+    let line = line.trim();
+    in_comment || line.is_empty() || line.starts_with(COMMENT_START)
+}
+
+fn is_eventgroup_start(line: &str) -> bool {
+    let line = line.trim();
+    line.starts_with(EVENTGROUP_START) && line.ends_with('>')
+}
+
+/// Check if a line starts a new block or ends the event file
+fn starts_block(line: &str) -> bool {
+    tags::opening_tag(line, EVENT_TAG).is_some() || starts_eventgroup(line)
+}
+
+/// Check if a line starts a new event group or ends the event file
+fn starts_eventgroup(line: &str) -> bool {
+    is_eventgroup_start(line) || line.trim() == LHEF_LAST_LINE
+}
+
+/// The text of a block without the enclosing tags
+enum BlockText {
+    Event(Attributes, String),
+    EventGroup(String),
+    /// The first line of a block that is neither an event nor a group
+    Other(String),
+}
+
+/// Information from the header and init block needed to parse events
+struct EventContext {
+    extensions: ExtensionRegistry,
+    /// IDs of the declared weights, in order
+    weight_ids: Vec<String>,
+    limits: Limits,
+    info: InfoRetention,
+    non_finite: NonFinitePolicy,
+    /// number of non-finite values that were kept or replaced
+    non_finite_count: AtomicU64,
+}
+
+/// An event or a group of correlated events
+///
+/// Generators at next-to-leading order can group a real-emission
+/// event with its counter-events, which should not be treated as
+/// statistically independent.
+#[allow(clippy::large_enum_variant)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq,Debug,Clone)]
+pub enum Block {
+    /// A single event
+    Event(HEPEUP),
+    /// The events inside an `<eventgroup>`
+    EventGroup(Vec<HEPEUP>),
+}
+
+/// Get optional metadata, or the default if it is malformed
+///
+/// The metadata in the header and the init block is not needed to read
+/// the events, so errors are only logged.
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+fn optional<T: Default>(what: &'static str, result: Result<T, Box<dyn error::Error>>) -> T {
+    result.unwrap_or_else(|err| {
+        event!(WARN, "ignoring malformed {}: {}", what, err);
+        T::default()
+    })
+}
 
-    /// Get the next event
-    ///
-    /// # Example
-    ///
-    /// ```rust,no_run
-    /// let file = std::fs::File::open("events.lhe").unwrap();
-    /// let file = std::io::BufReader::new(file);
-    /// let mut reader = lhef::Reader::new(file).unwrap();
-    ///
-    /// let event = reader.event().unwrap();
-    /// match event {
-    ///    Some(event) => println!("Found an event."),
-    ///    None => println!("Reached end of event file."),
-    /// }
-    /// ```
-    pub fn event(&mut self) -> Result<Option<HEPEUP>, Box<error::Error>> {
-        let mut line = String::new();
-        self.stream.read_line(&mut line)?;
-        match line.trim() {
-            EVENT_START => Ok(Some(parse_event(&mut self.stream)?)),
-            LHEF_LAST_LINE => Ok(None),
-            _ => Err(Box::new(ParseError::BadEventStart(line)))
-        }
+/// The static string for a supported LHEF version
+pub(crate) fn version(version: &str) -> Result<&'static str, Box<dyn error::Error>> {
+    match version {
+        "1.0" => Ok("1.0"),
+        "2.0" => Ok("2.0"),
+        "3.0" => Ok("3.0"),
+        _ => Err(Box::new(ParseError::UnsupportedVersion(version.to_owned()))),
     }
 }
 
-fn parse_version<Stream: BufRead>(stream: &mut Stream) -> Result<&'static str, Box<error::Error>> {
+/// Parse the opening `<LesHouchesEvents>` tag
+///
+/// Returns the version, the declared version, and the remaining
+/// attributes. A missing or unsupported version is replaced by
+/// `default`, if given.
+fn parse_version<Stream: BufRead>(
+    stream: &mut Stream,
+    limits: &Limits,
+    default: Option<&'static str>
+) -> Result<(&'static str, Option<String>, Attributes), Box<dyn error::Error>> {
     use ParseError::*;
     let mut first_line = String::new();
-    stream.read_line(&mut first_line)?;
-    let line_cp = first_line.clone();
-    let mut line_entries = first_line.trim().split('"');
-    if line_entries.next() != Some(LHEF_TAG_OPEN) {
-        return Err(Box::new(ParseError::BadFirstLine(line_cp)))
+    let max_len = limits.max_line_length;
+    limits::read_text_line(stream, &mut first_line, "first line", max_len, max_len)?;
+    let mut attributes: Attributes = match tags::opening_tag(&first_line, LHEF_TAG) {
+        Some(attributes) => attributes.into(),
+        None => return Err(Box::new(BadFirstLine(first_line)))
     };
-    let version = match line_entries.next() {
-        Some("1.0") => {"1.0"},
-        Some("2.0") => {"2.0"},
-        Some("3.0") => {"3.0"},
-        Some(version) => {
-            return Err(Box::new(UnsupportedVersion(version.to_string())))
-        }
-        None => {
-            return Err(Box::new(MissingVersion))
-        }
+    let declared = attributes.remove("version");
+    let version = match declared {
+        Some(ref version) => ::version(version.trim()),
+        None => Err(Box::new(MissingVersion).into())
     };
-    if line_entries.next() != Some(">") {
-        return Err(Box::new(ParseError::BadFirstLine(line_cp)))
+    let version = match (version, default) {
+        (Ok(version), _) => version,
+        (Err(_), Some(default)) => {
+            event!(WARN, ?declared, default, "unsupported version, assuming the default");
+            default
+        },
+        (Err(err), None) => return Err(err)
     };
-    Ok(version)
+    Ok((version, declared, attributes))
 }
 
-fn parse_header<Stream: BufRead>(mut stream: &mut Stream) -> Result<String, Box<error::Error>> {
+fn parse_header<Stream: BufRead>(
+    mut stream: &mut Stream,
+    limits: &Limits
+) -> Result<String, Box<dyn error::Error>> {
     let mut header = String::new();
     loop {
-        stream.read_line(&mut header)?;
+        if read_header_line(stream, &mut header, limits)? == 0 {
+            return Err(Box::new(ParseError::EndOfFile("header")));
+        }
         match header.lines().last().unwrap().trim() {
-            COMMENT_START => parse_comment_header(&mut stream, &mut header)?,
-            HEADER_START => parse_structured_header(&mut stream, &mut header)?,
+            COMMENT_START => parse_comment_header(&mut stream, &mut header, limits)?,
+            HEADER_START => parse_structured_header(&mut stream, &mut header, limits)?,
             INIT_START => {
                 pop_line(&mut header);
                 return Ok(header)
@@ -128,16 +1651,27 @@ fn parse_header<Stream: BufRead>(mut stream: &mut Stream) -> Result<String, Box<
 
 fn pop_line(s: &mut String) {
     s.pop();
-    while !s.is_empty() && s.chars().last().unwrap() != '\n' {
+    while !s.is_empty() && !s.ends_with('\n') {
         s.pop();
     }
 }
 
+/// Append the next line to the header, checking the limits
+fn read_header_line<Stream: BufRead>(
+    stream: &mut Stream,
+    header: &mut String,
+    limits: &Limits
+) -> Result<usize, Box<dyn error::Error>> {
+    limits::read_text_line(
+        stream, header, "header", limits.max_header_bytes, limits.max_line_length
+    )
+}
+
 fn parse_comment_header<Stream: BufRead>(
-    stream: &mut Stream, header: &mut String
-) -> Result<(), Box<error::Error>> {
+    stream: &mut Stream, header: &mut String, limits: &Limits
+) -> Result<(), Box<dyn error::Error>> {
     loop {
-        if stream.read_line(header)? == 0 {
+        if read_header_line(stream, header, limits)? == 0 {
             return Err(Box::new(ParseError::EndOfFile("header")));
         }
         if header.lines().last().unwrap().trim() == COMMENT_END {
@@ -148,10 +1682,10 @@ fn parse_comment_header<Stream: BufRead>(
 
 //TODO: parse as xml
 fn parse_structured_header<Stream: BufRead>(
-    stream: &mut Stream, header: &mut String
-) -> Result<(), Box<error::Error>> {
+    stream: &mut Stream, header: &mut String, limits: &Limits
+) -> Result<(), Box<dyn error::Error>> {
     loop {
-        if stream.read_line(header)? == 0 {
+        if read_header_line(stream, header, limits)? == 0 {
             return Err(Box::new(ParseError::EndOfFile("header")));
         }
         if header.lines().last().unwrap().trim() == HEADER_END {
@@ -160,7 +1694,7 @@ fn parse_structured_header<Stream: BufRead>(
     }
 }
 
-fn parse<T>(name: &str, text: Option<&str>) -> Result<T, Box<error::Error>>
+fn parse<T>(name: &str, text: Option<&str>) -> Result<T, Box<dyn error::Error>>
 where T: std::str::FromStr {
     use ParseError::*;
     let text: &str = text.ok_or(Box::new(MissingEntry(String::from(name))))?;
@@ -172,11 +1706,14 @@ where T: std::str::FromStr {
 
 #[allow(non_snake_case)]
 fn parse_init<Stream: BufRead>(
-    stream: &mut Stream
-) -> Result<HEPRUP, Box<error::Error>> {
+    stream: &mut Stream,
+    extensions: &ExtensionRegistry,
+    limits: &Limits
+) -> Result<HEPRUP, Box<dyn error::Error>> {
     // we have already consumed to opening <init> when reading the header
+    let max_len = limits.max_line_length;
     let mut line = String::new();
-    stream.read_line(&mut line)?;
+    limits::read_text_line(stream, &mut line, "init", max_len, max_len)?;
     let mut entries = line.split_whitespace();
     let IDBMUP = [
         parse::<i32>("IDBMUP(1)", entries.next())?,
@@ -196,13 +1733,14 @@ fn parse_init<Stream: BufRead>(
     ];
     let IDWTUP = parse::<i32>("IDWTUP", entries.next())?;
     let NPRUP = parse::<i32>("NPRUP", entries.next())?;
-    let mut XSECUP = Vec::with_capacity(NPRUP as usize);
-    let mut XERRUP = Vec::with_capacity(NPRUP as usize);
-    let mut XMAXUP = Vec::with_capacity(NPRUP as usize);
-    let mut LPRUP = Vec::with_capacity(NPRUP as usize);
+    let nprocesses = limits::count("number of processes", NPRUP, limits.max_processes)?;
+    let mut XSECUP = Vec::with_capacity(nprocesses);
+    let mut XERRUP = Vec::with_capacity(nprocesses);
+    let mut XMAXUP = Vec::with_capacity(nprocesses);
+    let mut LPRUP = Vec::with_capacity(nprocesses);
     for i in 0..NPRUP {
         let mut line = String::new();
-        stream.read_line(&mut line)?;
+        limits::read_text_line(stream, &mut line, "init", max_len, max_len)?;
         let mut entries = line.split_whitespace();
         XSECUP.push(parse::<f64>(&format!("XSECUP({})", i+1), entries.next())?);
         XERRUP.push(parse::<f64>(&format!("XERRUP({})", i+1), entries.next())?);
@@ -210,8 +1748,9 @@ fn parse_init<Stream: BufRead>(
         LPRUP.push(parse::<i32> (&format!("LPRUP({})", i+1), entries.next())?);
     }
     let mut info = String::new();
+    let max_info_len = limits.max_header_bytes;
     loop {
-        if stream.read_line(&mut info)? == 0 {
+        if limits::read_text_line(stream, &mut info, "init", max_info_len, max_len)? == 0 {
             return Err(Box::new(ParseError::EndOfFile("init")));
         }
         if info.lines().last().unwrap() == INIT_END {
@@ -219,73 +1758,205 @@ fn parse_init<Stream: BufRead>(
             break;
         }
     }
+    let extensions = extensions.parse(&info)?;
     Ok(HEPRUP{
         IDBMUP, EBMUP, PDFGUP, PDFSUP, IDWTUP, NPRUP,
         XSECUP, XERRUP, XMAXUP, LPRUP,
-        info
+        info, extensions
     })
 }
 
 #[allow(non_snake_case)]
-fn parse_event<Stream: BufRead>(
-    stream: &mut Stream
-) -> Result<HEPEUP, Box<error::Error>> {
-    // we have already consumed to opening <event>
-    let mut line = String::new();
-    stream.read_line(&mut line)?;
-    let mut entries = line.split_whitespace();
-    let NUP = parse::<i32>("NUP", entries.next())?;
-    let IDRUP = parse::<i32>("IDRUP", entries.next())?;
-    let XWGTUP = parse::<f64>("XWGTUP", entries.next())?;
-    let SCALUP = parse::<f64>("SCALUP", entries.next())?;
-    let AQEDUP = parse::<f64>("AQEDUP", entries.next())?;
-    let AQCDUP = parse::<f64>("AQCDUP", entries.next())?;
-    let mut IDUP = Vec::with_capacity(NUP as usize);
-    let mut ISTUP = Vec::with_capacity(NUP as usize);
-    let mut MOTHUP = Vec::with_capacity(NUP as usize);
-    let mut ICOLUP = Vec::with_capacity(NUP as usize);
-    let mut PUP = Vec::with_capacity(NUP as usize);
-    let mut VTIMUP = Vec::with_capacity(NUP as usize);
-    let mut SPINUP = Vec::with_capacity(NUP as usize);
+fn parse_event(
+    text: &str,
+    attr: Attributes,
+    context: &EventContext
+) -> Result<HEPEUP, Box<dyn error::Error>> {
+    // text is everything between the opening <event> and closing </event>
+    let mut rest = text;
+    let mut entries = fields::Fields::new(split_line(&mut rest));
+    let NUP: i32 = entries.parse(|| "NUP".to_owned())?;
+    let IDRUP: i32 = entries.parse(|| "IDRUP".to_owned())?;
+    let XWGTUP: f64 = entries.parse(|| "XWGTUP".to_owned())?;
+    let SCALUP: f64 = entries.parse(|| "SCALUP".to_owned())?;
+    let AQEDUP: f64 = entries.parse(|| "AQEDUP".to_owned())?;
+    let AQCDUP: f64 = entries.parse(|| "AQCDUP".to_owned())?;
+    let max_particles = context.limits.max_particles;
+    let nparticles = limits::count("number of particles", NUP, max_particles)?;
+    let mut IDUP = Vec::with_capacity(nparticles);
+    let mut ISTUP = Vec::with_capacity(nparticles);
+    let mut MOTHUP = Vec::with_capacity(nparticles);
+    let mut ICOLUP = Vec::with_capacity(nparticles);
+    let mut PUP = Vec::with_capacity(nparticles);
+    let mut VTIMUP = Vec::with_capacity(nparticles);
+    let mut SPINUP = Vec::with_capacity(nparticles);
     for i in 0..NUP {
-        let mut line = String::new();
-        stream.read_line(&mut line)?;
-        let mut entries = line.split_whitespace();
-        IDUP.push(parse::<i32>(&format!("IDUP({})", i+1), entries.next())?);
-        ISTUP.push(parse::<i32>(&format!("ISTUP({})", i+1), entries.next())?);
+        // the names of missing entries are only formatted on error
+        let mut entries = fields::Fields::new(split_line(&mut rest));
+        IDUP.push(entries.parse(|| format!("IDUP({})", i+1))?);
+        ISTUP.push(entries.parse(|| format!("ISTUP({})", i+1))?);
         MOTHUP.push([
-            parse::<i32>(&format!("MOTHUP({}, 1)", i+1), entries.next())?,
-            parse::<i32>(&format!("MOTHUP({}, 2)", i+1), entries.next())?,
+            entries.parse(|| format!("MOTHUP({}, 1)", i+1))?,
+            entries.parse(|| format!("MOTHUP({}, 2)", i+1))?,
         ]);
         ICOLUP.push([
-            parse::<i32>(&format!("ICOLUP({}, 1)", i+1), entries.next())?,
-            parse::<i32>(&format!("ICOLUP({}, 2)", i+1), entries.next())?,
+            entries.parse(|| format!("ICOLUP({}, 1)", i+1))?,
+            entries.parse(|| format!("ICOLUP({}, 2)", i+1))?,
         ]);
         PUP.push([
-            parse::<f64>(&format!("PUP({}, 1)", i+1), entries.next())?,
-            parse::<f64>(&format!("PUP({}, 2)", i+1), entries.next())?,
-            parse::<f64>(&format!("PUP({}, 3)", i+1), entries.next())?,
-            parse::<f64>(&format!("PUP({}, 4)", i+1), entries.next())?,
-            parse::<f64>(&format!("PUP({}, 5)", i+1), entries.next())?,
+            entries.parse(|| format!("PUP({}, 1)", i+1))?,
+            entries.parse(|| format!("PUP({}, 2)", i+1))?,
+            entries.parse(|| format!("PUP({}, 3)", i+1))?,
+            entries.parse(|| format!("PUP({}, 4)", i+1))?,
+            entries.parse(|| format!("PUP({}, 5)", i+1))?,
         ]);
-        VTIMUP.push(parse::<f64>(&format!("VTIMUP({})", i+1), entries.next())?);
-        SPINUP.push(parse::<f64>(&format!("SPINUP({})", i+1), entries.next())?);
+        VTIMUP.push(entries.parse(|| format!("VTIMUP({})", i+1))?);
+        SPINUP.push(entries.parse(|| format!("SPINUP({})", i+1))?);
     }
-    let mut info = String::new();
-    loop {
-        if stream.read_line(&mut info)? == 0 {
-            return Err(Box::new(ParseError::EndOfFile("event")));
+    let mut event = HEPEUP{
+        NUP, IDRUP, XWGTUP, SCALUP, AQEDUP, AQCDUP,
+        IDUP, ISTUP, MOTHUP, ICOLUP, PUP, VTIMUP, SPINUP,
+        info: String::new(), attr,
+        scales: None,
+        clustering: None,
+        mgrwt: None,
+        weights: None,
+        comment_lines: None,
+        extensions: Extensions::new(),
+    };
+    parse_event_tags(&mut event, rest, context)?;
+    finite::check_event(&mut event, context.non_finite, &context.non_finite_count)?;
+    Ok(event)
+}
+
+/// Parse the tags in the optional event information `info`
+///
+/// Tags with a dedicated representation in `HEPEUP` are removed from
+/// the information, unless a custom parser is registered for them. Only
+/// the first tag of each kind is parsed, so that for example a second
+/// `<rwgt>` tag does not replace the weights of the first. The remaining
+/// information, including such repeated tags, is stored in the event if
+/// it is retained.
+fn parse_event_tags(
+    event: &mut HEPEUP,
+    info: &str,
+    context: &EventContext
+) -> Result<(), Box<dyn error::Error>> {
+    let extensions = &context.extensions;
+    let mut parsed = Vec::new();
+    let mut all = Vec::new();
+    for (range, tag) in tags::tags(info) {
+        all.push(range.clone());
+        if extensions.is_registered(tag.name()) {
+            continue;
         }
-        if info.lines().last().unwrap().trim() == EVENT_END {
-            pop_line(&mut info);
-            break;
+        match tag.name() {
+            scales::SCALES_TAG if event.scales.is_none() => {
+                event.scales = Some(Scales::from_tag(&tag)?)
+            },
+            clustering::CLUSTERING_TAG if event.clustering.is_none() => {
+                event.clustering = Some(Clustering::from_tag(&tag)?)
+            },
+            mgrwt::MGRWT_TAG if event.mgrwt.is_none() => {
+                event.mgrwt = Some(MgRwt::from_tag(&tag)?)
+            },
+            weights::RWGT_TAG if event.weights.is_none() => {
+                event.weights = Some(Weights::from_rwgt(&tag)?)
+            },
+            weights::WEIGHTS_TAG if event.weights.is_none() => {
+                let weights = Weights::from_compact(&tag, &context.weight_ids)?;
+                event.weights = Some(weights)
+            },
+            _ => continue,
+        };
+        parsed.push(range);
+    }
+    let (comment_lines, lines) = CommentLines::parse(info, &all);
+    event.comment_lines = comment_lines;
+    merge_ranges(&mut parsed, lines);
+    let info = if parsed.is_empty() {
+        Cow::Borrowed(info)
+    } else {
+        Cow::Owned(tags::remove_ranges(info, &parsed))
+    };
+    event.extensions = extensions.parse(&info)?;
+    if context.info == InfoRetention::Own {
+        event.info = info.into_owned();
+    }
+    Ok(())
+}
+
+/// Remove the tags parsed by `parse_event_tags` from the optional
+/// event information
+fn strip_event_tags<'a>(info: &'a str, context: &EventContext) -> Cow<'a, str> {
+    let mut parsed = Vec::new();
+    let mut all = Vec::new();
+    let mut seen = Vec::new();
+    for (range, tag) in tags::tags(info) {
+        all.push(range.clone());
+        let name = tag.name();
+        // both weight formats are stored in `HEPEUP::weights`
+        let kind = if name == weights::WEIGHTS_TAG { weights::RWGT_TAG } else { name };
+        if !context.extensions.is_registered(name) && is_event_tag(name) && !seen.contains(&kind) {
+            seen.push(kind);
+            parsed.push(range);
         }
     }
-    Ok(HEPEUP{
-        NUP, IDRUP, XWGTUP, SCALUP, AQEDUP, AQCDUP,
-        IDUP, ISTUP, MOTHUP, ICOLUP, PUP, VTIMUP, SPINUP,
-        info
-    })
+    merge_ranges(&mut parsed, CommentLines::parse(info, &all).1);
+    if parsed.is_empty() {
+        Cow::Borrowed(info)
+    } else {
+        Cow::Owned(tags::remove_ranges(info, &parsed))
+    }
+}
+
+/// Add the sorted `ranges` to the sorted, disjoint `parsed`
+fn merge_ranges(parsed: &mut Vec<Range<usize>>, ranges: Vec<Range<usize>>) {
+    if !ranges.is_empty() {
+        parsed.extend(ranges);
+        parsed.sort_by_key(|range| range.start);
+    }
+}
+
+/// Check if a tag in the optional event information has a dedicated
+/// representation in `HEPEUP`
+fn is_event_tag(name: &str) -> bool {
+    [
+        scales::SCALES_TAG,
+        clustering::CLUSTERING_TAG,
+        mgrwt::MGRWT_TAG,
+        weights::RWGT_TAG,
+        weights::WEIGHTS_TAG,
+    ].contains(&name)
+}
+
+fn parse_eventgroup(
+    text: &str,
+    context: &EventContext
+) -> Result<Vec<HEPEUP>, Box<dyn error::Error>> {
+    // text is everything between the opening <eventgroup> and closing
+    // </eventgroup>
+    let mut events = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let line = split_line(&mut rest);
+        let attr = match tags::opening_tag(line, EVENT_TAG) {
+            Some(attr) => Attributes::from(attr),
+            None => return Err(Box::new(ParseError::BadEventStart(line.to_owned()))),
+        };
+        let (event_text, after) = split_event_text(rest)?;
+        rest = after;
+        events.push(parse_event(event_text, attr, context)?);
+    }
+    Ok(events)
+}
+
+/// Split off the first line of `text`, including the line break
+fn split_line<'a>(text: &mut &'a str) -> &'a str {
+    let end = text.find('\n').map(|pos| pos + 1).unwrap_or(text.len());
+    let (line, rest) = text.split_at(end);
+    *text = rest;
+    line
 }
 
 /// Generator run information
@@ -317,6 +1988,69 @@ pub struct HEPRUP {
     pub LPRUP: Vec<i32>,
     /// Optional run information
     pub info: String,
+    /// Values parsed from registered extension tags in `info`
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub extensions: Extensions,
+}
+
+impl HEPRUP {
+    /// Energies of the two beams in GeV
+    pub fn beam_energies(&self) -> [f64; 2] {
+        self.EBMUP
+    }
+
+    /// Names of the two beam particles, if known
+    ///
+    /// See `particle_name` for the particles with known names.
+    pub fn beam_particles(&self) -> [Option<&'static str>; 2] {
+        [pdg::particle_name(self.IDBMUP[0]), pdg::particle_name(self.IDBMUP[1])]
+    }
+
+    /// Centre-of-mass energy of the collision in GeV
+    ///
+    /// The beams are assumed to collide head-on. The masses of
+    /// electrons, muons, pions, and nucleons are taken into account,
+    /// other beam particles are treated as massless.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// let file = std::fs::File::open("events.lhe").unwrap();
+    /// let file = std::io::BufReader::new(file);
+    /// let reader = lhef::Reader::new(file).unwrap();
+    /// println!("sqrt(s) = {} GeV", reader.heprup().sqrt_s());
+    /// ```
+    pub fn sqrt_s(&self) -> f64 {
+        let mut s = 0.;
+        let mut energy = [0.; 2];
+        let mut momentum = [0.; 2];
+        for i in 0..2 {
+            let mass = pdg::beam_mass(self.IDBMUP[i]).unwrap_or(0.);
+            energy[i] = self.EBMUP[i];
+            momentum[i] = (energy[i] * energy[i] - mass * mass).max(0.).sqrt();
+            s += mass * mass;
+        }
+        s += 2. * (energy[0] * energy[1] + momentum[0] * momentum[1]);
+        s.sqrt()
+    }
+
+    /// Resolve the PDF set IDs `PDFSUP` of both beams
+    ///
+    /// This assumes that the IDs are LHAPDF IDs, as is common in
+    /// modern event files.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # let heprup: lhef::HEPRUP = unimplemented!();
+    /// let sets = lhef::PdfSets::builtin();
+    /// if let Some(pdf) = heprup.pdf_sets(&sets)[0] {
+    ///     println!("PDF of the first beam: {}", pdf);
+    /// }
+    /// ```
+    pub fn pdf_sets<'a>(&self, sets: &'a PdfSets) -> [Option<PdfMember<'a>>; 2] {
+        [sets.lookup(self.PDFSUP[0]), sets.lookup(self.PDFSUP[1])]
+    }
 }
 
 /// Event information
@@ -354,6 +2088,62 @@ pub struct HEPEUP{
     pub SPINUP: Vec<f64>,
     /// Optional event information
     pub info: String,
+    /// Attributes of the `<event>` tag
+    pub attr: Attributes,
+    /// Scale information from the `<scales>` tag
+    pub scales: Option<Scales>,
+    /// Clustering history from the `<clustering>` tag
+    pub clustering: Option<Clustering>,
+    /// MadGraph reweighting information from the `<mgrwt>` tag
+    pub mgrwt: Option<MgRwt>,
+    /// Additional weights from the `<rwgt>` or `<weights>` tag
+    pub weights: Option<Weights>,
+    /// Generator-specific comment lines like `#pdf`
+    pub comment_lines: Option<CommentLines>,
+    /// Values parsed from registered extension tags in `info`
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub extensions: Extensions,
+}
+
+impl HEPEUP {
+    /// Number of additional partons at leading order
+    ///
+    /// This is taken from the `npLO` attribute of the `<event>` tag,
+    /// which is used to label jet multiplicities in merged samples.
+    pub fn np_lo(&self) -> Option<i32> {
+        self.attr.parse("npLO")
+    }
+
+    /// Number of additional partons at next-to-leading order
+    ///
+    /// This is taken from the `npNLO` attribute of the `<event>` tag,
+    /// which is used to label jet multiplicities in merged samples.
+    pub fn np_nlo(&self) -> Option<i32> {
+        self.attr.parse("npNLO")
+    }
+
+    /// Value of the additional weight with the given ID
+    ///
+    /// This works for both the `<rwgt>` and the compact `<weights>`
+    /// encoding.
+    pub fn weight(&self, id: &str) -> Option<f64> {
+        self.weights.as_ref().and_then(|weights| weights.get(id))
+    }
+
+    /// Event number
+    ///
+    /// This is taken from the `event_number` attribute of the `<event>`
+    /// tag, which is written by `Writer`s created with
+    /// `WriterBuilder::number_events` and helps to identify the event
+    /// in the output of later simulation steps.
+    pub fn event_number(&self) -> Option<u64> {
+        self.attr.parse(EVENT_NUMBER_ATTRIBUTE)
+    }
+
+    /// Set the event number, see `event_number`
+    pub fn set_event_number(&mut self, number: u64) {
+        self.attr.set(EVENT_NUMBER_ATTRIBUTE, &number.to_string());
+    }
 }
 
 #[derive(Debug)]
@@ -375,8 +2165,8 @@ impl fmt::Display for ParseError {
             BadFirstLine(ref line) => {
                 write!(
                     f,
-                    "First line '{}' in input does start with '{}'",
-                    line, LHEF_TAG_OPEN
+                    "First line '{}' in input is not a '<{}>' tag with a version",
+                    line.trim(), LHEF_TAG
                 )
             },
             BadHeaderStart(ref line) => {
@@ -414,7 +2204,7 @@ impl fmt::Display for ParseError {
             },
             EndOfFile(ref block) => {
                 write!(f, "Encountered '{}' block without closing tag", block)
-            }
+            },
         }
     }
 }
@@ -425,7 +2215,7 @@ impl error::Error for ParseError {
         ""
     }
 
-    fn cause(&self) -> Option<&error::Error> {
+    fn cause(&self) -> Option<&dyn error::Error> {
         // Generic error, underlying cause isn't tracked.
         None
     }
@@ -436,8 +2226,11 @@ mod tests {
     extern crate flate2;
     use super::*;
 
+    use std::cell::RefCell;
     use std::fs::File;
-    use std::io::BufReader;
+    use std::io::{BufReader, Read};
+    use std::rc::Rc;
+    use std::time::Duration;
     use tests::flate2::bufread::GzDecoder;
 
     #[test]
@@ -460,4 +2253,655 @@ mod tests {
         while let Ok(Some(_)) = lhef.event() { nevents += 1 };
         assert_eq!(nevents, 10);
     }
+
+    #[test]
+    fn read_scales() {
+        let file = File::open("test_data/HEJFOG.lhe.gz").expect("file not found");
+        let reader = BufReader::new(GzDecoder::new(BufReader::new(file)));
+        let mut lhef = Reader::new(reader).unwrap();
+        let event = lhef.event().unwrap().unwrap();
+        assert_eq!(event.scales.unwrap().mups, Some(-1.));
+        assert!(!event.info.contains("<scales"));
+    }
+
+    #[test]
+    fn read_comment_lines() {
+        let sample = EVENTGROUP.replacen(
+            "</event>",
+            "#pdf 21 21 0.1 0.2 91.2 2.5 2.1\n# free text\n</event>",
+            1
+        );
+        let mut lhef = Reader::new(sample.as_bytes()).unwrap();
+        let event = lhef.event().unwrap().unwrap();
+        assert!(event.weights.is_some());
+        assert_eq!(event.comment_lines.as_ref().unwrap().pdf.as_ref().unwrap().x, [0.1, 0.2]);
+        assert_eq!(event.info, "# free text\n");
+
+        let mut output = Vec::new();
+        {
+            let mut writer = Writer::new(&mut output, "3.0").unwrap();
+            writer.heprup(lhef.heprup()).unwrap();
+            writer.event(&event).unwrap();
+        }
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("# free text\n#pdf 21 21 1e-1 2e-1 9.12e1 2.5e0 2.1e0\n"));
+    }
+
+    #[test]
+    fn read_non_finite() {
+        let sample = EVENTGROUP.replace("1 1 2 10 0 0.1", "1 1 NaN 10 0 0.1");
+        let mut lhef = Reader::new(sample.as_bytes()).unwrap();
+        let events: Vec<_> = (0..3).map(|_| lhef.event().unwrap().unwrap()).collect();
+        assert!(events[2].XWGTUP.is_nan());
+        assert_eq!(lhef.non_finite_values(), 1);
+
+        let mut lhef = ReaderBuilder::new()
+            .non_finite(NonFinitePolicy::Error)
+            .build(sample.as_bytes())
+            .unwrap();
+        lhef.block().unwrap();
+        assert!(lhef.block().unwrap_err().is::<NonFiniteValue>());
+    }
+
+    #[test]
+    fn read_extensions() {
+        #[derive(Debug, PartialEq)]
+        struct Mups(f64);
+
+        let mut registry = ExtensionRegistry::new();
+        registry.register("scales", |tag| {
+            let mups = tag.attribute("mups").ok_or("missing mups")?;
+            Ok(Mups(mups.parse()?))
+        });
+        let file = File::open("test_data/HEJFOG.lhe.gz").expect("file not found");
+        let reader = BufReader::new(GzDecoder::new(BufReader::new(file)));
+        let mut lhef = Reader::with_extensions(reader, registry).unwrap();
+        assert!(lhef.heprup().extensions.is_empty());
+        let event = lhef.event().unwrap().unwrap();
+        assert_eq!(event.scales, None);
+        assert_eq!(event.extensions.get::<Mups>("scales"), Some(&Mups(-1.)));
+        assert!(event.info.contains("<scales"));
+    }
+
+    const EVENTGROUP: &str = r#"<LesHouchesEvents version="3.0">
+<header>
+<initrwgt>
+<weightgroup name="scale_variation" combine="envelope">
+<weight id="mur2">muR = 2</weight>
+<weight id="mur0.5">muR = 0.5</weight>
+</weightgroup>
+</initrwgt>
+</header>
+<init>
+2212 2212 6500 6500 0 0 0 0 3 1
+1 0 1 1
+<xsecinfo neve="3" totxsec="1.2" negweights="yes"/>
+<procinfo iproc="1" loops="1" qcdorder="2">real emission</procinfo>
+<mergeinfo iproc="1" mergingscale="20" maxmult="yes"/>
+</init>
+<eventgroup nreal="1" ncounter="1">
+<event npLO="1" npNLO='-1'>
+1 1 1 10 0 0.1
+21 1 0 0 0 0 0 0 0 0 0 0 9
+<weights>1.5 0.5</weights>
+</event>
+<event>
+1 1 -1 10 0 0.1
+21 1 0 0 0 0 0 0 0 0 0 0 9
+</event>
+</eventgroup>
+<event>
+1 1 2 10 0 0.1
+21 1 0 0 0 0 0 0 0 0 0 0 9
+<rwgt>
+<wgt id="mur2">3</wgt>
+<wgt id="mur0.5">1</wgt>
+</rwgt>
+</event>
+</LesHouchesEvents>
+"#;
+
+    #[test]
+    fn read_eventgroup() {
+        let mut lhef = Reader::new(EVENTGROUP.as_bytes()).unwrap();
+        let xsecinfo = lhef.xsecinfo().unwrap();
+        assert_eq!(xsecinfo.neve, 3);
+        assert_eq!(xsecinfo.totxsec, 1.2);
+        assert!(xsecinfo.negweights);
+        assert_eq!(lhef.procinfo()[0].description, "real emission");
+        assert_eq!(lhef.mergeinfo()[0].mergingscale, Some(20.));
+        let scale = lhef.initrwgt().unwrap().weights_in_group("scale_variation").unwrap();
+        assert_eq!(scale.len(), 2);
+        assert_eq!(scale[1].id, "mur0.5");
+        match lhef.block().unwrap() {
+            Some(Block::EventGroup(events)) => {
+                let weights: Vec<_> = events.iter().map(|e| e.XWGTUP).collect();
+                assert_eq!(weights, [1., -1.]);
+                assert_eq!(events[0].np_lo(), Some(1));
+                assert_eq!(events[0].np_nlo(), Some(-1));
+                assert_eq!(events[1].np_lo(), None);
+                assert_eq!(events[0].weight("mur0.5"), Some(0.5));
+                assert!(events[0].info.is_empty());
+                assert_eq!(events[1].weights, None);
+            },
+            block => panic!("expected event group, got {:?}", block)
+        }
+        match lhef.block().unwrap() {
+            Some(Block::Event(event)) => {
+                assert_eq!(event.XWGTUP, 2.);
+                assert_eq!(event.weight("mur2"), Some(3.));
+                assert_eq!(event.weights.unwrap().values(), [3., 1.]);
+            },
+            block => panic!("expected event, got {:?}", block)
+        }
+        assert_eq!(lhef.block().unwrap(), None);
+
+        let mut lhef = Reader::new(EVENTGROUP.as_bytes()).unwrap();
+        let mut nevents = 0;
+        while lhef.event().unwrap().is_some() { nevents += 1 };
+        assert_eq!(nevents, 3);
+    }
+
+    #[test]
+    fn read_fragments() {
+        let heprup = Reader::new(EVENTGROUP.as_bytes()).unwrap().heprup().clone();
+        let start = EVENTGROUP.find("<eventgroup").unwrap();
+        let end = EVENTGROUP.find(LHEF_LAST_LINE).unwrap();
+        let fragments = &EVENTGROUP[start..end];
+        let mut lhef = Reader::from_fragments(fragments.as_bytes(), heprup).unwrap();
+        assert_eq!(lhef.xsecinfo().unwrap().neve, 3);
+        assert_eq!(lhef.header(), "");
+        let mut nevents = 0;
+        while lhef.event().unwrap().is_some() { nevents += 1 };
+        assert_eq!(nevents, 3);
+        assert_eq!(lhef.event().unwrap(), None);
+
+        let heprup = lhef.heprup().clone();
+        let mut lhef = Reader::from_fragments("<event>\n".as_bytes(), heprup).unwrap();
+        assert!(lhef.event().is_err());
+    }
+
+    #[test]
+    fn read_continuations() {
+        let primary = &EVENTGROUP[..EVENTGROUP.find(LHEF_LAST_LINE).unwrap()];
+        let continuation = &EVENTGROUP[EVENTGROUP.find("<eventgroup").unwrap()..];
+        let unterminated = &continuation[..continuation.find(LHEF_LAST_LINE).unwrap()];
+        let mut lhef = Reader::new(primary.as_bytes()).unwrap();
+        lhef.attach(continuation.as_bytes());
+        lhef.attach(unterminated.as_bytes());
+        let mut nevents = 0;
+        while lhef.event().unwrap().is_some() { nevents += 1 };
+        assert_eq!(nevents, 9);
+
+        let mut lhef = Reader::new(EVENTGROUP.as_bytes()).unwrap();
+        while lhef.event().unwrap().is_some() { };
+        lhef.attach(continuation.as_bytes());
+        let mut nevents = 0;
+        while lhef.event().unwrap().is_some() { nevents += 1 };
+        assert_eq!(nevents, 3);
+    }
+
+    /// A stream that is written in chunks, with an end of file after each
+    struct Growing {
+        chunks: Rc<RefCell<VecDeque<&'static str>>>,
+        at_end: bool,
+    }
+
+    impl io::Read for Growing {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.at_end = !self.at_end;
+            if !self.at_end {
+                return Ok(0)
+            }
+            match self.chunks.borrow_mut().pop_front() {
+                Some(chunk) => {
+                    buf[..chunk.len()].copy_from_slice(chunk.as_bytes());
+                    Ok(chunk.len())
+                },
+                None => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn read_follow() {
+        let split = EVENTGROUP.find("1 1 -1").unwrap() + 2;
+        let end = EVENTGROUP.find("<event>\n1 1 2").unwrap();
+        let chunks = Rc::new(RefCell::new(VecDeque::from(vec![
+            &EVENTGROUP[..split], &EVENTGROUP[split..end]
+        ])));
+        let stream = Growing{chunks: chunks.clone(), at_end: false};
+        let mut lhef = Reader::new(BufReader::new(stream)).unwrap();
+        lhef.follow(Some(Follow {
+            poll_interval: Duration::from_millis(1),
+            timeout: Some(Duration::from_millis(20)),
+        }));
+        assert_eq!(lhef.event().unwrap().unwrap().XWGTUP, 1.);
+        assert_eq!(lhef.event().unwrap().unwrap().XWGTUP, -1.);
+        let err = lhef.event().unwrap_err();
+        let err = err.downcast_ref::<io::Error>().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        let rest = &EVENTGROUP[end..];
+        let split = rest.find("<wgt").unwrap() + 3;
+        chunks.borrow_mut().push_back(&rest[..split]);
+        assert!(lhef.event().is_err());
+        assert_eq!(lhef.progress().bytes, end as u64);
+        assert_eq!(lhef.checkpoint().offset, end as u64);
+        chunks.borrow_mut().push_back(&rest[split..]);
+        lhef.follow(Some(Follow::default()));
+        assert_eq!(lhef.event().unwrap().unwrap().weight("mur2"), Some(3.));
+        assert_eq!(lhef.event().unwrap(), None);
+    }
+
+    #[test]
+    fn read_last_events() {
+        let mut lhef = Reader::new(io::Cursor::new(EVENTGROUP)).unwrap();
+        let last = lhef.last_events(2).unwrap();
+        let weights: Vec<_> = last.iter().map(|e| e.XWGTUP).collect();
+        assert_eq!(weights, [-1., 2.]);
+        assert_eq!(last[1].weight("mur0.5"), Some(1.));
+        assert_eq!(lhef.last_events(10).unwrap().len(), 3);
+        assert_eq!(lhef.event().unwrap().unwrap().XWGTUP, 1.);
+    }
+
+    #[test]
+    fn read_root_tag() {
+        let body = "<init>\n2212 2212 6500 6500 0 0 0 0 3 1\n1 0 1 1\n</init>\n\
+                    </LesHouchesEvents>\n";
+        let read = |root: &str| Reader::new(io::Cursor::new(format!("{}\n{}", root, body)));
+        let lhef = read("<LesHouchesEvents version='3.0'>").unwrap();
+        assert_eq!(lhef.version(), "3.0");
+        assert!(lhef.root_attributes().is_empty());
+        let lhef = read("  <LesHouchesEvents\tversion = \" 1.0 \"  xmlns=\"urn:x\" >").unwrap();
+        assert_eq!(lhef.version(), "1.0");
+        let lhef = read("<LesHouchesEvents xmlns:x=\"a\" version=\"2.0\" b='c'>").unwrap();
+        assert_eq!(lhef.version(), "2.0");
+        let attributes: Vec<_> = lhef.root_attributes().iter().collect();
+        assert_eq!(attributes, [("xmlns:x", "a"), ("b", "c")]);
+        for bad in &[
+            "<LesHouchesEvents>",
+            "<LesHouchesEvents version=\"4.0\">",
+            "<LesHouchesEventsX version=\"1.0\">",
+            "<LesHouchesEvents version=\"1.0\"",
+        ] {
+            assert!(read(bad).is_err());
+        }
+    }
+
+    #[test]
+    fn read_default_version() {
+        let body = "<init>\n2212 2212 6500 6500 0 0 0 0 3 1\n1 0 1 1\n</init>\n\
+                    </LesHouchesEvents>\n";
+        let read = |root: &str, default: &str| {
+            let input = io::Cursor::new(format!("{}\n{}", root, body));
+            ReaderBuilder::new().default_version(default).build(input)
+        };
+        let lhef = read("<LesHouchesEvents>", "1.0").unwrap();
+        assert_eq!(lhef.version(), "1.0");
+        assert_eq!(lhef.declared_version(), None);
+        let lhef = read("<LesHouchesEvents version=\"0.9\">", "2.0").unwrap();
+        assert_eq!(lhef.version(), "2.0");
+        assert_eq!(lhef.declared_version(), Some("0.9"));
+        let lhef = read("<LesHouchesEvents version=\"3.0\">", "1.0").unwrap();
+        assert_eq!(lhef.version(), "3.0");
+        assert_eq!(lhef.declared_version(), Some("3.0"));
+        assert!(read("<LesHouchesEvents>", "0.9").is_err());
+        assert!(Reader::new(io::Cursor::new(format!("<LesHouchesEvents>\n{}", body))).is_err());
+    }
+
+    #[test]
+    fn read_lenient_tags() {
+        let text = EVENTGROUP
+            .replace("<eventgroup nreal", "<EventGroup  nreal")
+            .replace("</eventgroup>", "</eventGroup>")
+            .replace("<event>\n1 1 2", "< Event >\n1 1 2")
+            .replace("</rwgt>\n</event>", "</rwgt>\n< /EVENT>")
+            .replace("</LesHouchesEvents>", "</lesHouchesEvents >");
+        assert!(Reader::new(text.as_bytes()).unwrap().block().is_err());
+        let mut lhef = ReaderBuilder::new().lenient_tags(true).build(text.as_bytes()).unwrap();
+        let mut expected = Reader::new(EVENTGROUP.as_bytes()).unwrap();
+        while let Some(block) = expected.block().unwrap() {
+            assert_eq!(lhef.block().unwrap(), Some(block));
+        }
+        assert_eq!(lhef.block().unwrap(), None);
+    }
+
+    #[test]
+    fn read_comments_between_events() {
+        let comment = "\n<!-- reweighted\n  by a tool -->\n  \n";
+        let text = EVENTGROUP
+            .replace("</eventgroup>\n", &format!("</eventgroup>\n{}", comment))
+            .replace("</LesHouchesEvents>", "<!-- end -->\n</LesHouchesEvents>");
+        let mut lhef = Reader::new(text.as_bytes()).unwrap();
+        let mut slice = SliceReader::new(text.as_bytes()).unwrap();
+        let mut expected = Reader::new(EVENTGROUP.as_bytes()).unwrap();
+        let mut skipped = Vec::new();
+        while let Some(block) = expected.block().unwrap() {
+            assert_eq!(lhef.block().unwrap(), Some(block.clone()));
+            assert_eq!(slice.block().unwrap(), Some(block));
+            assert_eq!(slice.skipped_text(), lhef.skipped_text());
+            skipped.push(lhef.skipped_text().to_owned());
+        }
+        assert_eq!(skipped, ["", comment]);
+        assert_eq!(lhef.block().unwrap(), None);
+        assert_eq!(lhef.skipped_text(), "<!-- end -->\n");
+        assert_eq!(slice.block().unwrap(), None);
+    }
+
+    #[test]
+    fn read_unbuffered() {
+        let mut expected = Reader::new(EVENTGROUP.as_bytes()).unwrap();
+        let mut lhef = Reader::from_read(EVENTGROUP.as_bytes()).unwrap();
+        let mut small = ReaderBuilder::new()
+            .buffer_capacity(7)
+            .build_from_read(EVENTGROUP.as_bytes())
+            .unwrap();
+        assert_eq!(small.header(), expected.header());
+        while let Some(block) = expected.block().unwrap() {
+            assert_eq!(lhef.block().unwrap(), Some(block.clone()));
+            assert_eq!(small.block().unwrap(), Some(block));
+        }
+        assert_eq!(small.block().unwrap(), None);
+    }
+
+    #[test]
+    fn read_raw_eventgroup() {
+        let mut lhef = Reader::new(EVENTGROUP.as_bytes()).unwrap();
+        let mut expected = Reader::new(EVENTGROUP.as_bytes()).unwrap();
+        let mut parsed = Vec::new();
+        while let Some(raw) = lhef.raw_event().unwrap() {
+            parsed.push(raw.text().is_none());
+            assert_eq!(raw.weight().unwrap(), expected.event().unwrap().unwrap().XWGTUP);
+        }
+        assert_eq!(parsed, [true, true, false]);
+        assert_eq!(expected.event().unwrap(), None);
+    }
+
+    #[test]
+    fn read_latin1() {
+        let events = b"<LesHouchesEvents version=\"3.0\">
+<header>
+<author>Jos\xe9</author>
+</header>
+<init>
+2212 2212 6500 6500 0 0 0 0 3 1
+1 0 1 1
+# \xa9 2024
+</init>
+<event>
+1 1 2 10 0 0.1
+21 -1 0 0 0 0 0 0 1e2 1e2 0 0 9
+# r\xe9sum\xe9
+</event>
+</LesHouchesEvents>
+";
+        let mut lhef = Reader::new(&events[..]).unwrap();
+        assert!(lhef.header().contains("<author>José</author>"));
+        assert_eq!(lhef.heprup().info, "# © 2024\n");
+        let event = lhef.event().unwrap().unwrap();
+        assert_eq!(event.XWGTUP, 2.);
+        assert_eq!(event.info, "# résumé\n");
+    }
+
+    #[test]
+    fn read_partitions() {
+        fn weights<Stream: BufRead>(reader: &mut Reader<Stream>) -> Vec<Vec<f64>> {
+            let mut weights = Vec::new();
+            while let Some(block) = reader.block().unwrap() {
+                match block {
+                    Block::Event(event) => weights.push(vec![event.XWGTUP]),
+                    Block::EventGroup(events) => {
+                        weights.push(events.iter().map(|e| e.XWGTUP).collect())
+                    },
+                }
+            }
+            weights
+        }
+        let all = weights(&mut Reader::new(io::Cursor::new(EVENTGROUP)).unwrap());
+        for k in 1..20 {
+            let mut lhef = Reader::new(io::Cursor::new(EVENTGROUP)).unwrap();
+            let ranges = lhef.partitions(k).unwrap();
+            assert_eq!(ranges.len(), k);
+            let mut partitioned = Vec::new();
+            for range in ranges {
+                let lhef = Reader::new(io::Cursor::new(EVENTGROUP)).unwrap();
+                let mut partition = lhef.into_partition(range).unwrap();
+                assert_eq!(partition.xsecinfo().unwrap().neve, 3);
+                partitioned.extend(weights(&mut partition));
+            }
+            assert_eq!(partitioned, all);
+        }
+    }
+
+    #[test]
+    fn read_slice() {
+        let mut data = Vec::new();
+        let file = File::open("test_data/2j.lhe.gz").expect("file not found");
+        GzDecoder::new(BufReader::new(file)).read_to_end(&mut data).unwrap();
+        let mut lhef = Reader::new(data.as_slice()).unwrap();
+        lhef.set_total_bytes(Some(data.len() as u64));
+        let mut slice = SliceReader::new(&data).unwrap();
+        assert_eq!(slice.heprup(), lhef.heprup());
+        assert_eq!(slice.run_card().unwrap().nevents(), Some(1000));
+        while let Some(event) = lhef.event().unwrap() {
+            assert_eq!(slice.event().unwrap(), Some(event));
+        }
+        assert_eq!(slice.event().unwrap(), None);
+        assert_eq!(slice.event().unwrap(), None);
+        assert_eq!(slice.progress(), lhef.progress());
+
+        let mut lhef = Reader::new(EVENTGROUP.as_bytes()).unwrap();
+        let mut slice = SliceReader::new(EVENTGROUP.as_bytes()).unwrap();
+        while let Some(block) = lhef.block().unwrap() {
+            assert_eq!(slice.block().unwrap(), Some(block));
+        }
+        assert_eq!(slice.block().unwrap(), None);
+
+        let truncated = &EVENTGROUP[..EVENTGROUP.find("</eventgroup>").unwrap()];
+        let mut slice = SliceReader::new(truncated.as_bytes()).unwrap();
+        assert!(slice.block().is_err());
+    }
+
+    #[test]
+    fn read_progress() {
+        let mut lhef = Reader::new(io::Cursor::new(EVENTGROUP)).unwrap();
+        let init_end = (EVENTGROUP.find("</init>").unwrap() + "</init>\n".len()) as u64;
+        assert_eq!(lhef.progress().bytes, init_end);
+        assert_eq!(lhef.progress().fraction(), None);
+        assert_eq!(lhef.detect_total_bytes().unwrap(), EVENTGROUP.len() as u64);
+        lhef.event().unwrap();
+        let progress = lhef.progress();
+        assert_eq!(progress.events, 2);
+        let group_end = EVENTGROUP.find("</eventgroup>").unwrap() + "</eventgroup>\n".len();
+        assert_eq!(progress.bytes, group_end as u64);
+        lhef.event().unwrap();
+        assert_eq!(lhef.progress().events, 2);
+        while lhef.event().unwrap().is_some() { };
+        assert_eq!(lhef.progress().events, 3);
+        assert_eq!(lhef.progress().fraction(), Some(1.));
+    }
+
+    #[cfg(feature = "indicatif")]
+    #[test]
+    fn read_with_progress_bar() {
+        let mut lhef = Reader::new(io::Cursor::new(EVENTGROUP)).unwrap();
+        lhef.detect_total_bytes().unwrap();
+        let bar = indicatif::ProgressBar::hidden();
+        let mut events = lhef.events_with_custom_progress_bar(bar);
+        assert_eq!(events.by_ref().count(), 3);
+        assert!(events.bar().is_finished());
+        assert_eq!(events.bar().position(), EVENTGROUP.len() as u64);
+        assert_eq!(events.bar().message(), "3 events");
+        assert!(events.next().is_none());
+    }
+
+    #[test]
+    fn read_cancelled() {
+        let token = CancellationToken::new();
+        let mut lhef = Reader::new(EVENTGROUP.as_bytes()).unwrap();
+        lhef.set_cancellation(Some(token.clone()));
+        assert!(lhef.event().unwrap().is_some());
+        token.cancel();
+        assert!(lhef.event().unwrap().is_some());
+        assert!(lhef.event().unwrap_err().is::<Cancelled>());
+        assert!(lhef.events_lossy().next().is_none());
+        lhef.set_cancellation(None);
+        assert_eq!(lhef.event().unwrap().unwrap().XWGTUP, 2.);
+
+        let mid_event = EVENTGROUP.find("<event>\n1 1 2").unwrap() + 10;
+        let chunks = Rc::new(RefCell::new(VecDeque::from(vec![&EVENTGROUP[..mid_event]])));
+        let stream = Growing{chunks: chunks.clone(), at_end: false};
+        let mut lhef = Reader::new(BufReader::new(stream)).unwrap();
+        lhef.follow(Some(Follow {
+            poll_interval: Duration::from_millis(1),
+            timeout: None,
+        }));
+        let token = CancellationToken::new();
+        lhef.set_cancellation(Some(token.clone()));
+        assert!(matches!(lhef.block().unwrap(), Some(Block::EventGroup(_))));
+        let canceller = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(10));
+            token.cancel()
+        });
+        assert!(lhef.event().unwrap_err().is::<Cancelled>());
+        canceller.join().unwrap();
+        lhef.set_cancellation(None);
+        chunks.borrow_mut().push_back(&EVENTGROUP[mid_event..]);
+        assert_eq!(lhef.event().unwrap().unwrap().XWGTUP, 2.);
+    }
+
+    #[test]
+    fn read_slice_cancelled() {
+        let token = CancellationToken::new();
+        let mut slice = SliceReader::new(EVENTGROUP.as_bytes()).unwrap();
+        slice.set_cancellation(Some(token.clone()));
+        assert!(slice.event().unwrap().is_some());
+        token.cancel();
+        assert!(slice.event().unwrap().is_some());
+        assert!(slice.event().unwrap_err().is::<Cancelled>());
+        slice.set_cancellation(None);
+        assert_eq!(slice.event().unwrap().unwrap().XWGTUP, 2.);
+        assert!(slice.event().unwrap().is_none());
+    }
+
+    #[test]
+    fn read_trailing() {
+        let text = format!("{}<!-- appended -->\n", EVENTGROUP);
+        let mut lhef = Reader::new(text.as_bytes()).unwrap();
+        assert_eq!(lhef.trailing().unwrap(), None);
+        while lhef.event().unwrap().is_some() { };
+        assert_eq!(lhef.event().unwrap(), None);
+        assert_eq!(lhef.block().unwrap(), None);
+        assert_eq!(lhef.trailing().unwrap(), Some("<!-- appended -->\n"));
+        assert_eq!(lhef.trailing().unwrap(), Some("<!-- appended -->\n"));
+
+        let mut lhef = Reader::new(EVENTGROUP.as_bytes()).unwrap();
+        while lhef.event().unwrap().is_some() { };
+        assert_eq!(lhef.trailing().unwrap(), Some(""));
+    }
+
+    #[test]
+    fn beams() {
+        let lhef = Reader::new(EVENTGROUP.as_bytes()).unwrap();
+        let heprup = lhef.heprup();
+        assert_eq!(heprup.beam_energies(), [6500., 6500.]);
+        assert_eq!(heprup.beam_particles(), [Some("p"), Some("p")]);
+        assert!((heprup.sqrt_s() - 13000.).abs() < 1e-6);
+        let hera = HEPRUP {
+            IDBMUP: [-11, 2212],
+            EBMUP: [27.5, 920.],
+            ..heprup.clone()
+        };
+        assert_eq!(hera.beam_particles(), [Some("e+"), Some("p")]);
+        assert!((hera.sqrt_s() - 318.12).abs() < 0.01);
+    }
+
+    #[test]
+    fn read_limits() {
+        let build = |limits: Limits| {
+            ReaderBuilder::new().limits(limits).build(EVENTGROUP.as_bytes())
+        };
+        let header = build(Limits{max_header_bytes: 100, ..Default::default()});
+        assert!(header.is_err());
+        let line = build(Limits{max_line_length: 40, ..Default::default()});
+        assert!(line.is_err());
+        let processes = build(Limits{max_processes: 0, ..Default::default()});
+        assert!(processes.is_err());
+        let mut lhef = build(Limits{max_block_bytes: 100, ..Default::default()}).unwrap();
+        assert!(lhef.block().is_err());
+        assert_eq!(lhef.events_lossy().count(), 1);
+        let mut lhef = build(Limits{max_particles: 0, ..Default::default()}).unwrap();
+        let err = lhef.event().unwrap_err();
+        assert_eq!(err.to_string(), "Exceeded limit of 0 for number of particles");
+        let err = err.downcast_ref::<LimitExceeded>().unwrap();
+        assert_eq!(*err, LimitExceeded { what: "number of particles", max: 0 });
+        let line = build(Limits{max_line_length: 40, ..Default::default()});
+        let err = line.err().unwrap();
+        assert_eq!(err.downcast_ref::<LimitExceeded>().unwrap().what, "line length");
+
+        let huge = EVENTGROUP.replace("1 1 2 10 0 0.1", "2000000000 1 2 10 0 0.1");
+        let mut lhef = Reader::new(huge.as_bytes()).unwrap();
+        let nevents = lhef.events_lossy().count();
+        assert_eq!(nevents, 2);
+        let negative = EVENTGROUP.replace("1 1 2 10 0 0.1", "-1 1 2 10 0 0.1");
+        let mut lhef = Reader::new(negative.as_bytes()).unwrap();
+        assert_eq!(lhef.events_lossy().count(), 2);
+    }
+
+    /// Read a file with one event and the given header and optional run
+    /// information
+    fn read_metadata(header: &str, info: &str) -> Reader<io::Cursor<Vec<u8>>> {
+        let sample = test_util::SyntheticEvents::new().num_events(1);
+        let mut heprup = sample.heprup();
+        heprup.info = info.to_owned();
+        let mut buf = Vec::new();
+        {
+            let mut writer = Writer::new(&mut buf, "3.0").unwrap();
+            writer.header(header).unwrap();
+            writer.heprup(&heprup).unwrap();
+            for event in sample.events() {
+                writer.event(&event).unwrap();
+            }
+            writer.finish().unwrap();
+        }
+        let mut reader = Reader::new(io::Cursor::new(buf)).unwrap();
+        assert!(reader.event().unwrap().is_some());
+        reader
+    }
+
+    #[test]
+    fn read_malformed_metadata() {
+        let header = "<header>\n<initrwgt><weight>1</weight></initrwgt>\n</header>";
+        assert_eq!(read_metadata(header, "").initrwgt(), None);
+        let info = "<xsecinfo neve=\"1\" totxsec=\"1\" negweights=\"maybe\"/>\n";
+        assert_eq!(read_metadata("", info).xsecinfo(), None);
+        let info = "<cutsinfo><cut>1</cut></cutsinfo>\n";
+        assert_eq!(read_metadata("", info).cutsinfo(), None);
+        let reader = read_metadata("", "<procinfo loops=\"1\"/>\n<mergeinfo/>\n");
+        assert!(reader.procinfo().is_empty());
+        assert!(reader.mergeinfo().is_empty());
+    }
+
+    #[test]
+    fn read_repeated_tags() {
+        let heprup = test_util::SyntheticEvents::new().heprup();
+        let fragment = "<event>\n1 1 0.5 10 0 0.1\n21 1 0 0 0 0 0 0 0 0 0 0 9\n\
+                        <rwgt>\n<wgt id=\"a\">1</wgt>\n</rwgt>\n\
+                        <rwgt>\n<wgt id=\"b\">2</wgt>\n</rwgt>\n\
+                        <scales muf=\"1\"/>\n<scales muf=\"2\"/>\n</event>\n";
+        let mut lhef = Reader::from_fragments(fragment.as_bytes(), heprup.clone()).unwrap();
+        let event = lhef.event().unwrap().unwrap();
+        assert_eq!(event.weight("a"), Some(1.));
+        assert_eq!(event.weight("b"), None);
+        assert_eq!(event.scales.unwrap().muf, Some(1.));
+        let info = "<rwgt>\n<wgt id=\"b\">2</wgt>\n</rwgt>\n<scales muf=\"2\"/>\n";
+        assert_eq!(event.info, info);
+
+        let mut lhef = ReaderBuilder::new()
+            .info_retention(InfoRetention::Borrow)
+            .build_fragments(fragment.as_bytes(), heprup)
+            .unwrap();
+        lhef.event().unwrap();
+        assert_eq!(lhef.block_info().unwrap(), [info]);
+    }
 }
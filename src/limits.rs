@@ -0,0 +1,207 @@
+use std::error;
+use std::fmt;
+use std::io::{self, BufRead, Read};
+use std::str;
+
+use ParseError;
+
+/// Upper bounds on the input accepted by a `Reader`
+///
+/// The number of particles and processes declared in a file are used
+/// to reserve memory before the corresponding lines are read, and the
+/// header and each event are kept in memory as a whole. These limits
+/// prevent crafted or corrupted input from exhausting the memory.
+/// Exceeding any of them results in an error.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// let file = std::fs::File::open("events.lhe").unwrap();
+/// let file = std::io::BufReader::new(file);
+/// let limits = lhef::Limits {
+///     max_header_bytes: 1 << 20,
+///     ..Default::default()
+/// };
+/// let reader = lhef::ReaderBuilder::new().limits(limits).build(file).unwrap();
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct Limits {
+    /// Maximum number of particles `NUP` in an event
+    pub max_particles: usize,
+    /// Maximum number of processes `NPRUP` in the init block
+    pub max_processes: usize,
+    /// Maximum size in bytes of the header, and of the optional
+    /// information in the init block
+    pub max_header_bytes: usize,
+    /// Maximum size in bytes of an `<event>` or `<eventgroup>` block
+    pub max_block_bytes: usize,
+    /// Maximum length in bytes of a line, excluding the line break
+    pub max_line_length: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits {
+            max_particles: 100_000,
+            max_processes: 100_000,
+            max_header_bytes: 64 << 20,
+            max_block_bytes: 64 << 20,
+            max_line_length: 16 << 20,
+        }
+    }
+}
+
+impl Limits {
+    /// No limits at all
+    pub fn unlimited() -> Limits {
+        Limits {
+            max_particles: usize::MAX,
+            max_processes: usize::MAX,
+            max_header_bytes: usize::MAX,
+            max_block_bytes: usize::MAX,
+            max_line_length: usize::MAX,
+        }
+    }
+}
+
+/// Error returned when the input exceeds one of the `Limits`
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct LimitExceeded {
+    /// Description of the limited quantity, e.g. `line length`
+    pub what: &'static str,
+    /// The limit that was exceeded
+    pub max: usize,
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Exceeded limit of {} for {}", self.max, self.what)
+    }
+}
+
+impl error::Error for LimitExceeded {}
+
+/// Check that `size` does not exceed `max`
+pub(crate) fn check(
+    what: &'static str,
+    size: usize,
+    max: usize
+) -> Result<(), Box<dyn error::Error>> {
+    if size > max {
+        return Err(Box::new(LimitExceeded { what, max }));
+    }
+    Ok(())
+}
+
+/// Convert a declared number of entries, checking that it is neither
+/// negative nor larger than `max`
+pub(crate) fn count(
+    name: &'static str,
+    value: i32,
+    max: usize
+) -> Result<usize, Box<dyn error::Error>> {
+    if value < 0 {
+        return Err(Box::new(ParseError::ConversionError(value.to_string())));
+    }
+    check(name, value as usize, max)?;
+    Ok(value as usize)
+}
+
+/// Append bytes from `stream` to `line` up to and including the next
+/// line break
+///
+/// At most `max_len` bytes plus the line break are read in total,
+/// including what `line` already holds. If there is no line break
+/// within that length, an `InvalidData` error is returned.
+pub(crate) fn read_line<Stream: BufRead>(
+    stream: &mut Stream,
+    line: &mut Vec<u8>,
+    max_len: usize
+) -> io::Result<usize> {
+    let limit = (max_len as u64)
+        .saturating_add(1)
+        .saturating_sub(line.len() as u64);
+    let len = stream.take(limit).read_until(b'\n', line)?;
+    if line.len() > max_len && !line.ends_with(b"\n") {
+        let err = LimitExceeded { what: "line length", max: max_len };
+        return Err(io::Error::new(io::ErrorKind::InvalidData, err));
+    }
+    Ok(len)
+}
+
+/// Append the next line from `stream` to `text`, checking its length
+/// and the total length of `text`
+///
+/// Returns the number of bytes read.
+pub(crate) fn read_text_line<Stream: BufRead>(
+    stream: &mut Stream,
+    text: &mut String,
+    what: &'static str,
+    max_text_len: usize,
+    max_line_len: usize
+) -> Result<usize, Box<dyn error::Error>> {
+    let mut line = Vec::new();
+    let len = read_line(stream, &mut line, max_line_len)
+        .map_err(::cancel::from_io_error)?;
+    decode_into(&line, text);
+    check(what, text.len(), max_text_len)?;
+    Ok(len)
+}
+
+/// Decode a line as UTF-8, or as Latin-1 if it is not valid UTF-8
+///
+/// Banners and comments sometimes contain Latin-1 characters, for
+/// example in author names. Since any byte sequence is valid Latin-1,
+/// such lines can still be read, and the numeric data are unaffected.
+pub(crate) fn decode(line: Vec<u8>) -> String {
+    match String::from_utf8(line) {
+        Ok(line) => line,
+        Err(err) => err.into_bytes().into_iter().map(char::from).collect(),
+    }
+}
+
+/// Append a line to `text`, decoding it like `decode`
+pub(crate) fn decode_into(line: &[u8], text: &mut String) {
+    match str::from_utf8(line) {
+        Ok(line) => text.push_str(line),
+        Err(_) => text.extend(line.iter().map(|&c| char::from(c))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latin1() {
+        assert_eq!(decode("José".as_bytes().to_vec()), "José");
+        assert_eq!(decode(b"Jos\xe9".to_vec()), "José");
+        let mut text = "Jos\u{e9}\n".to_owned();
+        decode_into(b"Jos\xe9\n", &mut text);
+        assert_eq!(text, "José\nJosé\n");
+    }
+
+    #[test]
+    fn line_length() {
+        let mut stream = "abc\nabcd\nabcde".as_bytes();
+        let mut line = Vec::new();
+        assert_eq!(read_line(&mut stream, &mut line, 3).unwrap(), 4);
+        assert_eq!(line, b"abc\n");
+        line.clear();
+        assert!(read_line(&mut stream, &mut line, 3).is_err());
+        let mut stream = "abc".as_bytes();
+        line.clear();
+        assert_eq!(read_line(&mut stream, &mut line, 3).unwrap(), 3);
+        let mut stream = "c\n".as_bytes();
+        line = b"ab".to_vec();
+        assert_eq!(read_line(&mut stream, &mut line, 3).unwrap(), 2);
+    }
+
+    #[test]
+    fn declared_count() {
+        assert_eq!(count("number of particles", 3, 3).unwrap(), 3);
+        assert!(count("number of particles", 4, 3).is_err());
+        assert!(count("number of particles", -1, 3).is_err());
+    }
+}
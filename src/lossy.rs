@@ -0,0 +1,163 @@
+use std::io::{self, BufRead};
+
+use {Cancelled, Reader, HEPEUP};
+
+/// A problem encountered while reading events with `Reader::events_lossy`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub struct Diagnostic {
+    /// Number of events successfully read before the problem occurred
+    pub events_before: usize,
+    /// Description of the problem
+    pub message: String,
+    /// Number of lines skipped to get to the next event
+    pub skipped_lines: usize,
+}
+
+/// Iterator over events that skips malformed events
+///
+/// This is created by `Reader::events_lossy`. The problems encountered
+/// so far can be retrieved with `diagnostics`.
+pub struct LossyEvents<'a, Stream: 'a> {
+    reader: &'a mut Reader<Stream>,
+    diagnostics: Vec<Diagnostic>,
+    nevents: usize,
+    finished: bool,
+}
+
+impl<'a, Stream: BufRead> LossyEvents<'a, Stream> {
+    pub(crate) fn new(reader: &'a mut Reader<Stream>) -> LossyEvents<'a, Stream> {
+        LossyEvents {
+            reader,
+            diagnostics: Vec::new(),
+            nevents: 0,
+            finished: false,
+        }
+    }
+
+    /// Get the problems encountered so far
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Get the problems encountered so far, consuming the iterator
+    pub fn into_diagnostics(self) -> Vec<Diagnostic> {
+        self.diagnostics
+    }
+}
+
+impl<'a, Stream: BufRead> Iterator for LossyEvents<'a, Stream> {
+    type Item = HEPEUP;
+
+    fn next(&mut self) -> Option<HEPEUP> {
+        while !self.finished {
+            let err = match self.reader.event() {
+                Ok(Some(event)) => {
+                    self.nevents += 1;
+                    return Some(event);
+                }
+                Ok(None) => {
+                    self.finished = true;
+                    return None;
+                }
+                Err(err) => err,
+            };
+            if err.is::<Cancelled>() {
+                self.finished = true;
+                break;
+            }
+            let mut diagnostic = Diagnostic {
+                events_before: self.nevents,
+                message: err.to_string(),
+                skipped_lines: 0,
+            };
+            let kind = err.downcast_ref::<io::Error>().map(|err| err.kind());
+            if kind == Some(io::ErrorKind::TimedOut) {
+                // nothing to skip, the stream has not been written yet
+                self.diagnostics.push(diagnostic);
+                self.finished = true;
+                break;
+            }
+            match self.reader.skip_to_next_block() {
+                Ok(Some(skipped)) => diagnostic.skipped_lines = skipped,
+                Ok(None) => self.finished = true,
+                Err(err) => {
+                    diagnostic.message = format!("{}; {}", diagnostic.message, err);
+                    self.finished = true;
+                }
+            }
+            event!(
+                WARN,
+                events_before = diagnostic.events_before,
+                skipped_lines = diagnostic.skipped_lines,
+                "skipped bad event: {}",
+                diagnostic.message
+            );
+            self.diagnostics.push(diagnostic);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use Reader;
+
+    const CORRUPTED: &str = r#"<LesHouchesEvents version="1.0">
+<init>
+2212 2212 6500 6500 0 0 0 0 3 1
+1 0 1 1
+</init>
+<event>
+1 1 1 10 0 0.1
+21 1 0 0 0 0 0 0 0 0 0 0 9
+</event>
+<event>
+1 1 2 10 0 0.1
+21 1 0 0 0 0 0 garbage 0 0 0 0 9
+some trailing junk
+</event>
+<event>
+1 1 3 10 0 0.1
+21 1 0 0 0 0 0 0 0 0 0 0 9
+<event>
+1 1 4 10 0 0.1
+21 1 0 0 0 0 0 0 0 0 0 0 9
+</event>
+not an event
+more junk
+<eventgroup>
+<event>
+1 1 5 10 0 0.1
+</event>
+</eventgroup>
+<event>
+1 1 6 10 0 0.1
+21 1 0 0 0 0 0 0 0 0 0 0 9
+</event>
+</LesHouchesEvents>
+"#;
+
+    #[test]
+    fn skip_bad_events() {
+        let mut reader = Reader::new(CORRUPTED.as_bytes()).unwrap();
+        let mut events = reader.events_lossy();
+        let weights: Vec<_> = events.by_ref().map(|e| e.XWGTUP).collect();
+        assert_eq!(weights, [1., 4., 6.]);
+        let diagnostics = events.into_diagnostics();
+        let events_before: Vec<_> = diagnostics.iter().map(|d| d.events_before).collect();
+        assert_eq!(events_before, [1, 1, 2, 2]);
+        let skipped: Vec<_> = diagnostics.iter().map(|d| d.skipped_lines).collect();
+        assert_eq!(skipped, [0, 0, 1, 0]);
+    }
+
+    #[test]
+    fn truncated() {
+        let text = &CORRUPTED[..CORRUPTED.find("<event>\n1 1 4").unwrap()];
+        let mut reader = Reader::new(text.as_bytes()).unwrap();
+        let mut events = reader.events_lossy();
+        assert_eq!(events.by_ref().count(), 1);
+        assert_eq!(events.diagnostics().len(), 2);
+        assert_eq!(events.next(), None);
+    }
+}
@@ -0,0 +1,194 @@
+use std::error;
+use std::fmt;
+
+use tags::{self, Tag};
+use {parse, ParseError};
+
+pub(crate) const MGRWT_TAG: &str = "mgrwt";
+
+/// Reweighting information written by MadGraph in the `<mgrwt>` tag
+///
+/// This information allows recomputing the event weight for different
+/// scale and PDF choices.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct MgRwt {
+    /// Renormalisation scale information from `<rscale>`
+    pub rscale: Option<RScale>,
+    /// Scales of additional strong couplings from `<asrwt>`
+    pub asrwt: Vec<f64>,
+    /// PDF reweighting information for each beam from `<pdfrwt>`
+    pub pdfrwt: Vec<PdfRwt>,
+    /// Total reweighting factor from `<totfact>`
+    pub totfact: Option<f64>,
+}
+
+/// Renormalisation scale information
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub struct RScale {
+    /// Number of strong couplings evaluated at this scale
+    pub n_alphas: u32,
+    /// Renormalisation scale in GeV
+    pub scale: f64,
+}
+
+/// PDF reweighting information for one beam
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub struct PdfRwt {
+    /// Beam number, either 1 or 2
+    pub beam: u32,
+    /// PDF evaluations, in order
+    pub entries: Vec<PdfRwtEntry>,
+}
+
+/// A single PDF evaluation
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub struct PdfRwtEntry {
+    /// Parton ID
+    pub id: i32,
+    /// Momentum fraction
+    pub x: f64,
+    /// Factorisation scale in GeV
+    pub scale: f64,
+}
+
+impl MgRwt {
+    pub(crate) fn from_tag(tag: &Tag) -> Result<MgRwt, Box<dyn error::Error>> {
+        let mut mgrwt = MgRwt::default();
+        for (_, entry) in tags::tags(tag.content()) {
+            let mut values = entry.content().split_whitespace();
+            match entry.name() {
+                "rscale" => {
+                    mgrwt.rscale = Some(RScale {
+                        n_alphas: parse("rscale n", values.next())?,
+                        scale: parse("rscale scale", values.next())?,
+                    })
+                }
+                "asrwt" => {
+                    let n = parse::<usize>("asrwt n", values.next())?;
+                    mgrwt.asrwt = (0..n)
+                        .map(|i| parse(&format!("asrwt scale({})", i + 1), values.next()))
+                        .collect::<Result<_, _>>()?;
+                }
+                "pdfrwt" => {
+                    let beam = parse("pdfrwt beam", entry.attribute("beam"))?;
+                    let n = parse::<usize>("pdfrwt n", values.next())?;
+                    let values: Vec<_> = values.collect();
+                    if values.len() != 3 * n {
+                        return Err(Box::new(ParseError::MissingEntry(format!(
+                            "pdfrwt beam {}: expected {} values, found {}",
+                            beam,
+                            3 * n,
+                            values.len()
+                        ))));
+                    }
+                    let mut entries = Vec::with_capacity(n);
+                    for i in 0..n {
+                        entries.push(PdfRwtEntry {
+                            id: parse("pdfrwt id", Some(values[i]))?,
+                            x: parse("pdfrwt x", Some(values[n + i]))?,
+                            scale: parse("pdfrwt scale", Some(values[2 * n + i]))?,
+                        });
+                    }
+                    mgrwt.pdfrwt.push(PdfRwt { beam, entries });
+                }
+                "totfact" => mgrwt.totfact = Some(parse("totfact", values.next())?),
+                _ => {}
+            }
+        }
+        Ok(mgrwt)
+    }
+}
+
+/// Formats the reweighting information as a `<mgrwt>` tag
+impl fmt::Display for MgRwt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "<{}>", MGRWT_TAG)?;
+        if let Some(ref rscale) = self.rscale {
+            writeln!(f, "<rscale> {} {:e}</rscale>", rscale.n_alphas, rscale.scale)?;
+        }
+        if !self.asrwt.is_empty() {
+            write!(f, "<asrwt>{}", self.asrwt.len())?;
+            for scale in &self.asrwt {
+                write!(f, " {:e}", scale)?;
+            }
+            writeln!(f, "</asrwt>")?;
+        }
+        for pdfrwt in &self.pdfrwt {
+            write!(f, "<pdfrwt beam=\"{}\"> {}", pdfrwt.beam, pdfrwt.entries.len())?;
+            for entry in &pdfrwt.entries {
+                write!(f, " {}", entry.id)?;
+            }
+            for entry in &pdfrwt.entries {
+                write!(f, " {:e}", entry.x)?;
+            }
+            for entry in &pdfrwt.entries {
+                write!(f, " {:e}", entry.scale)?;
+            }
+            writeln!(f, "</pdfrwt>")?;
+        }
+        if let Some(totfact) = self.totfact {
+            writeln!(f, "<totfact> {:e}</totfact>", totfact)?;
+        }
+        write!(f, "</{}>", MGRWT_TAG)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_mgrwt(text: &str) -> Result<MgRwt, Box<dyn error::Error>> {
+        let (_, tag) = tags::tags(text).next().unwrap();
+        MgRwt::from_tag(&tag)
+    }
+
+    #[test]
+    fn parse_and_format() {
+        let text = "<mgrwt>\n\
+                    <rscale>  2 0.91188000E+02</rscale>\n\
+                    <asrwt>  1 0.50000000E+02</asrwt>\n\
+                    <pdfrwt beam=\"1\">  2       21 1 0.17719659E+00 0.2 0.91188000E+02 30</pdfrwt>\n\
+                    <pdfrwt beam=\"2\">  1        1 0.68270633E-03 0.91188000E+02</pdfrwt>\n\
+                    <totfact> 0.49322010E+04</totfact>\n\
+                    </mgrwt>";
+        let mgrwt = parse_mgrwt(text).unwrap();
+        assert_eq!(mgrwt.rscale, Some(RScale { n_alphas: 2, scale: 91.188 }));
+        assert_eq!(mgrwt.asrwt, [50.]);
+        assert_eq!(mgrwt.pdfrwt.len(), 2);
+        assert_eq!(
+            mgrwt.pdfrwt[0].entries[1],
+            PdfRwtEntry { id: 1, x: 0.2, scale: 30. }
+        );
+        assert_eq!(mgrwt.pdfrwt[1].beam, 2);
+        assert_eq!(mgrwt.totfact, Some(0.49322010E+04));
+        assert_eq!(parse_mgrwt(&mgrwt.to_string()).unwrap(), mgrwt);
+    }
+
+    #[test]
+    fn optional_parts() {
+        let text = "<mgrwt>\n<totfact> 5e-1</totfact>\n</mgrwt>";
+        let mgrwt = parse_mgrwt(text).unwrap();
+        assert!(mgrwt.asrwt.is_empty());
+        assert_eq!(mgrwt.to_string(), text);
+    }
+
+    #[test]
+    fn wrong_count() {
+        let text = "<mgrwt><pdfrwt beam=\"1\"> 2 21 0.1 91</pdfrwt></mgrwt>";
+        assert!(parse_mgrwt(text).is_err());
+    }
+
+    #[test]
+    fn read_file() {
+        let mut reader = ::Reader::open("test_data/2j.lhe.gz").unwrap();
+        let event = reader.event().unwrap().unwrap();
+        assert!(event.info.is_empty());
+        let mgrwt = event.mgrwt.unwrap();
+        assert_eq!(mgrwt.pdfrwt[0].entries[0].id, 21);
+        assert_eq!(mgrwt.totfact, Some(0.49322010E+04));
+    }
+}
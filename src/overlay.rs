@@ -0,0 +1,123 @@
+use HEPEUP;
+
+/// How the weights of two events are combined by `HEPEUP::overlay`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+pub enum WeightCombination {
+    /// Keep the weights of the first event, e.g. when overlaying
+    /// unweighted pileup
+    #[default]
+    First,
+    /// Multiply the weights, e.g. for independent partial processes
+    Product,
+    /// Add the weights
+    Sum,
+}
+
+impl WeightCombination {
+    fn combine(self, first: f64, second: f64) -> f64 {
+        match self {
+            WeightCombination::First => first,
+            WeightCombination::Product => first * second,
+            WeightCombination::Sum => first + second,
+        }
+    }
+}
+
+/// Merging of events
+impl HEPEUP {
+    /// Combine the particles of this event and `other` into one event
+    ///
+    /// The particles of `other` are appended, with their mother
+    /// positions shifted accordingly and their colour lines renumbered
+    /// so that they do not connect to the particles of this event. The
+    /// event weights `XWGTUP` are combined according to `weights`, and
+    /// so is each additional weight of this event with the weight of
+    /// `other` that has the same ID, or its `XWGTUP` if there is none.
+    /// Additional weights only present in `other` are dropped. All
+    /// other properties, like the process ID, scales, and optional
+    /// information, are taken from this event.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # let (signal, pileup): (lhef::HEPEUP, Vec<lhef::HEPEUP>) = unimplemented!();
+    /// use lhef::WeightCombination;
+    ///
+    /// let overlaid = pileup.iter().fold(signal, |event, pileup| {
+    ///     event.overlay(pileup, WeightCombination::First)
+    /// });
+    /// ```
+    pub fn overlay(&self, other: &HEPEUP, weights: WeightCombination) -> HEPEUP {
+        let mut event = self.clone();
+        let offset = self.IDUP.len() as i32;
+        let colour_offset = self.ICOLUP.iter().flatten().copied().max().unwrap_or(0).max(0);
+        let shift = |index: i32, offset: i32| if index > 0 { index + offset } else { index };
+        event.NUP += other.NUP;
+        event.IDUP.extend_from_slice(&other.IDUP);
+        event.ISTUP.extend_from_slice(&other.ISTUP);
+        event.MOTHUP.extend(
+            other.MOTHUP.iter().map(|m| [shift(m[0], offset), shift(m[1], offset)]),
+        );
+        event.ICOLUP.extend(
+            other
+                .ICOLUP
+                .iter()
+                .map(|c| [shift(c[0], colour_offset), shift(c[1], colour_offset)]),
+        );
+        event.PUP.extend_from_slice(&other.PUP);
+        event.VTIMUP.extend_from_slice(&other.VTIMUP);
+        event.SPINUP.extend_from_slice(&other.SPINUP);
+        event.XWGTUP = weights.combine(self.XWGTUP, other.XWGTUP);
+        if let Some(ref mut extra) = event.weights {
+            for weight in &mut extra.weights {
+                let second = weight
+                    .id
+                    .as_ref()
+                    .and_then(|id| other.weights.as_ref()?.get(id))
+                    .unwrap_or(other.XWGTUP);
+                weight.value = weights.combine(weight.value, second);
+            }
+        }
+        event
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_util::MockEvent;
+
+    #[test]
+    fn overlay() {
+        let signal = MockEvent::drell_yan()
+            .with_weight(2.)
+            .with_extra_weight("a", 4.)
+            .with_extra_weight("b", 6.)
+            .build();
+        let pileup = MockEvent::dijet().with_weight(3.).with_extra_weight("a", 5.).build();
+
+        let event = signal.overlay(&pileup, WeightCombination::Product);
+        assert_eq!(event.NUP, signal.NUP + pileup.NUP);
+        assert_eq!(event.IDUP.len(), event.NUP as usize);
+        assert_eq!(event.IDUP[5..], pileup.IDUP[..]);
+        assert_eq!(event.PUP[5..], pileup.PUP[..]);
+        assert_eq!(event.MOTHUP[7], [6, 7]);
+        assert_eq!(event.MOTHUP[5], [0, 0]);
+        assert_eq!(event.ICOLUP[5], [1002, 1003]);
+        assert_eq!(event.ICOLUP[8], [1005, 1003]);
+        assert_eq!(event.ICOLUP[..5], signal.ICOLUP[..]);
+        assert_eq!(event.XWGTUP, 6.);
+        let weights = event.weights.as_ref().unwrap();
+        assert_eq!(weights.get("a"), Some(20.));
+        assert_eq!(weights.get("b"), Some(18.));
+        assert_eq!(event.SCALUP, signal.SCALUP);
+
+        let event = signal.overlay(&pileup, WeightCombination::Sum);
+        assert_eq!(event.XWGTUP, 5.);
+        assert_eq!(event.weights.unwrap().get("b"), Some(9.));
+        let event = signal.overlay(&pileup, WeightCombination::First);
+        assert_eq!(event.XWGTUP, 2.);
+        assert_eq!(event.weights, signal.weights);
+    }
+}
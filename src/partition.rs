@@ -0,0 +1,100 @@
+use std::io::{self, BufRead, Seek, SeekFrom};
+
+use {is_eventgroup_start, tags, EVENTGROUP_END, EVENT_TAG, LHEF_LAST_LINE};
+
+/// Maximum number of bytes scanned to find out whether a position is
+/// inside an `<eventgroup>`
+const MAX_GROUP_SCAN: u64 = 1024 * 1024;
+
+/// Find the first block boundary at or after `target`
+///
+/// A block boundary is the start of an `<event>` or `<eventgroup>`
+/// line outside an event group. Returns `end` if there is no further
+/// block before `end`.
+///
+/// To decide whether a position is inside an event group, the stream is
+/// scanned forward for the next opening or closing `<eventgroup>` tag.
+/// This scan is limited to `MAX_GROUP_SCAN` bytes, so larger groups may
+/// be split.
+pub(crate) fn block_boundary<Stream: BufRead + Seek>(
+    stream: &mut Stream,
+    target: u64,
+    end: u64,
+) -> io::Result<u64> {
+    if target == 0 {
+        stream.seek(SeekFrom::Start(0))?;
+    } else {
+        // skip the rest of the line containing target - 1, so that we
+        // continue from the first line starting at or after target
+        stream.seek(SeekFrom::Start(target - 1))?;
+    }
+    let mut pos = stream.stream_position()?;
+    if target > 0 {
+        pos += read_line(stream, &mut Vec::new())?;
+    }
+    let mut candidate = None;
+    let mut line = Vec::new();
+    while pos < end {
+        let len = read_line(stream, &mut line)?;
+        if len == 0 {
+            break;
+        }
+        let text = String::from_utf8_lossy(&line);
+        let trimmed = text.trim();
+        match candidate {
+            None => {
+                if is_eventgroup_start(trimmed) || trimmed == LHEF_LAST_LINE {
+                    return Ok(pos);
+                }
+                if tags::opening_tag(trimmed, EVENT_TAG).is_some() {
+                    candidate = Some(pos);
+                }
+            }
+            Some(candidate) => {
+                if is_eventgroup_start(trimmed) || trimmed == LHEF_LAST_LINE {
+                    return Ok(candidate);
+                }
+                if trimmed == EVENTGROUP_END {
+                    // the candidate event was inside a group
+                    return Ok(pos + len);
+                }
+                if pos - candidate > MAX_GROUP_SCAN {
+                    return Ok(candidate);
+                }
+            }
+        }
+        pos += len;
+    }
+    Ok(candidate.unwrap_or(end).min(end))
+}
+
+fn read_line<Stream: BufRead>(stream: &mut Stream, line: &mut Vec<u8>) -> io::Result<u64> {
+    line.clear();
+    stream.read_until(b'\n', line).map(|len| len as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn boundaries() {
+        let text = "<init>\n</init>\n\
+                    <event>\n1\n</event>\n\
+                    <eventgroup>\n<event>\n2\n</event>\n<event>\n3\n</event>\n</eventgroup>\n\
+                    <event>\n4\n</event>\n\
+                    </LesHouchesEvents>\n";
+        let mut stream = Cursor::new(text);
+        let end = text.len() as u64;
+        let pos = |s: &str| text.find(s).unwrap() as u64;
+        let boundary = |stream: &mut Cursor<&str>, target| block_boundary(stream, target, end).unwrap();
+        assert_eq!(boundary(&mut stream, 0), pos("<event>\n1"));
+        assert_eq!(boundary(&mut stream, pos("<event>\n1")), pos("<event>\n1"));
+        assert_eq!(boundary(&mut stream, pos("<event>\n1") + 1), pos("<eventgroup>"));
+        assert_eq!(boundary(&mut stream, pos("<event>\n2")), pos("<event>\n4"));
+        assert_eq!(boundary(&mut stream, pos("3")), pos("<event>\n4"));
+        assert_eq!(boundary(&mut stream, pos("4")), pos("</LesHouchesEvents>"));
+        assert_eq!(block_boundary(&mut stream, pos("4"), pos("4") + 2).unwrap(), pos("4") + 2);
+    }
+}
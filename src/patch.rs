@@ -0,0 +1,293 @@
+use std::cmp;
+use std::error;
+use std::fmt;
+use std::str;
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+
+use limits;
+use progress::CountingReader;
+use tags;
+use xsecinfo::XSECINFO_TAG;
+use {parse_header, parse_version, Limits, INIT_END};
+
+/// Size of the chunks in which the events are moved if the init block
+/// grows
+const CHUNK_SIZE: u64 = 1024 * 1024;
+
+/// Update the cross sections in the init block of an existing file
+///
+/// The cross sections `XSECUP` and their errors `XERRUP` of all
+/// processes are replaced by the given values. If the init block
+/// contains an `<xsecinfo>` tag without a `weightname`, its `totxsec`
+/// and `xsecerr` attributes are set to the total cross section and its
+/// error as well. Everything else, including the formatting of the
+/// remaining entries, is preserved byte for byte.
+///
+/// Generators often only know the final cross section after all events
+/// have been written, which can then be filled in with this function.
+/// If the patched init block is shorter than the original one, it is
+/// padded with spaces. If it is longer, the events are moved, which
+/// requires rewriting the remainder of the file.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// let mut file = std::fs::OpenOptions::new()
+///     .read(true)
+///     .write(true)
+///     .open("events.lhe")
+///     .unwrap();
+/// lhef::patch_cross_sections(&mut file, &[1.25], &[0.01]).unwrap();
+/// ```
+pub fn patch_cross_sections<Stream: Read + Write + Seek>(
+    mut stream: Stream,
+    xsecup: &[f64],
+    xerrup: &[f64],
+) -> Result<(), Box<dyn error::Error>> {
+    if xsecup.len() != xerrup.len() {
+        return Err(Box::new(PatchError::ErrorCount(xsecup.len(), xerrup.len())));
+    }
+    stream.seek(SeekFrom::Start(0))?;
+    let (start, init) = read_init(&mut stream)?;
+    let patched = patch_init(&init, xsecup, xerrup)?;
+    let patched = match patched.len().cmp(&init.len()) {
+        cmp::Ordering::Greater => {
+            let growth = (patched.len() - init.len()) as u64;
+            shift_tail(&mut stream, start + init.len() as u64, growth)?;
+            patched
+        }
+        _ => pad(patched, init.len()),
+    };
+    stream.seek(SeekFrom::Start(start))?;
+    stream.write_all(patched.as_bytes())?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Read the init block after the opening tag, including the closing tag
+///
+/// Returns the byte offset of the block and its text.
+fn read_init<Stream: Read>(stream: Stream) -> Result<(u64, String), Box<dyn error::Error>> {
+    let limits = Limits::default();
+    let mut stream = CountingReader { stream: BufReader::new(stream), count: 0 };
+    parse_version(&mut stream, &limits, None)?;
+    parse_header(&mut stream, &limits)?;
+    let start = stream.count;
+    let mut init = String::new();
+    loop {
+        let mut line = Vec::new();
+        if limits::read_line(&mut stream, &mut line, limits.max_line_length)? == 0 {
+            return Err(Box::new(PatchError::MissingInit));
+        }
+        // the length of the text has to match the raw bytes, so there is
+        // no fallback decoding
+        init.push_str(str::from_utf8(&line)?);
+        limits::check("init", init.len(), limits.max_header_bytes)?;
+        if init.lines().last().map(str::trim) == Some(INIT_END) {
+            return Ok((start, init));
+        }
+    }
+}
+
+/// Replace the cross sections in the text of an init block
+fn patch_init(
+    init: &str,
+    xsecup: &[f64],
+    xerrup: &[f64],
+) -> Result<String, Box<dyn error::Error>> {
+    let mut lines = init.split_inclusive('\n');
+    let mut patched = String::with_capacity(init.len() + 32);
+    let run_line = lines.next().ok_or(PatchError::MissingInit)?;
+    patched.push_str(run_line);
+    let nprup = run_line.split_whitespace().nth(9).and_then(|n| n.parse::<usize>().ok());
+    if nprup != Some(xsecup.len()) {
+        return Err(Box::new(PatchError::ProcessCount(nprup, xsecup.len())));
+    }
+    for (xsec, xerr) in xsecup.iter().zip(xerrup) {
+        let line = lines.next().ok_or(PatchError::MissingInit)?;
+        let entries = [format!("{:e}", xsec), format!("{:e}", xerr)];
+        patched.push_str(&replace_entries(line, &entries));
+    }
+    let info: String = lines.collect();
+    let total = xsecup.iter().sum::<f64>();
+    let error = xerrup.iter().map(|e| e * e).sum::<f64>().sqrt();
+    patched.push_str(&patch_xsecinfo(&info, total, error));
+    Ok(patched)
+}
+
+/// Replace the leading whitespace-separated entries of a line
+fn replace_entries(line: &str, entries: &[String]) -> String {
+    let mut result = String::with_capacity(line.len() + 16);
+    let mut rest = line;
+    for entry in entries {
+        let start = rest.len() - rest.trim_start().len();
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        result.push_str(entry);
+        rest = &rest[end..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Update the total cross section and its error in the `<xsecinfo>`
+/// tag for the nominal weight
+fn patch_xsecinfo(info: &str, total: f64, error: f64) -> String {
+    let tag = tags::tags(info)
+        .map(|(_, tag)| tag)
+        .find(|tag| tag.name() == XSECINFO_TAG && tag.attribute("weightname").is_none());
+    let tag = match tag {
+        Some(tag) => tag,
+        None => return info.to_owned(),
+    };
+    let mut replacements: Vec<_> = [("totxsec", total), ("xsecerr", error)]
+        .iter()
+        .filter_map(|&(name, value)| {
+            let old = tag.attribute(name)?;
+            let start = old.as_ptr() as usize - info.as_ptr() as usize;
+            Some((start..start + old.len(), format!("{:e}", value)))
+        })
+        .collect();
+    replacements.sort_by_key(|(range, _)| range.start);
+    let mut patched = String::with_capacity(info.len() + 16);
+    let mut pos = 0;
+    for (range, value) in replacements {
+        patched.push_str(&info[pos..range.start]);
+        patched.push_str(&value);
+        pos = range.end;
+    }
+    patched.push_str(&info[pos..]);
+    patched
+}
+
+/// Pad the init block with spaces at the end of the line before the
+/// closing tag
+fn pad(mut init: String, len: usize) -> String {
+    let missing = len - init.len();
+    let closing = init.trim_end().rfind('\n').map_or(0, |pos| pos + 1);
+    let line_end = init[..closing].trim_end_matches(&['\n', '\r'][..]).len();
+    init.insert_str(line_end, &" ".repeat(missing));
+    init
+}
+
+/// Move everything from `start` to the end of the stream back by
+/// `shift` bytes
+fn shift_tail<Stream: Read + Write + Seek>(
+    stream: &mut Stream,
+    start: u64,
+    shift: u64,
+) -> Result<(), Box<dyn error::Error>> {
+    let mut pos = stream.seek(SeekFrom::End(0))?;
+    let mut buf = Vec::new();
+    while pos > start {
+        let len = cmp::min(CHUNK_SIZE, pos - start);
+        pos -= len;
+        stream.seek(SeekFrom::Start(pos))?;
+        buf.clear();
+        Read::by_ref(stream).take(len).read_to_end(&mut buf)?;
+        stream.seek(SeekFrom::Start(pos + shift))?;
+        stream.write_all(&buf)?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PatchError {
+    MissingInit,
+    ProcessCount(Option<usize>, usize),
+    ErrorCount(usize, usize),
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::PatchError::*;
+        match *self {
+            MissingInit => write!(f, "Could not find a complete init block"),
+            ProcessCount(Some(nprup), n) => write!(
+                f,
+                "Init block has {} processes, but {} cross sections were given",
+                nprup, n
+            ),
+            ProcessCount(None, _) => write!(f, "Init block lacks the number of processes"),
+            ErrorCount(nxsec, nerr) => write!(
+                f,
+                "Got {} cross sections, but {} errors",
+                nxsec, nerr
+            ),
+        }
+    }
+}
+
+impl error::Error for PatchError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use Reader;
+
+    const EVENTS: &str = r#"<LesHouchesEvents version="3.0">
+<header>
+<init>
+</init>
+</header>
+<init>
+2212  2212 6500 6500 0 0 0 0 3 2
+  123.456789  0.5 1 1
+  1 2 3 2
+<xsecinfo neve="2" totxsec="124.456789" xsecerr="0.5"/>
+<xsecinfo neve="2" totxsec="1" weightname="other"/>
+</init>
+<event>
+1 1 1 10 0 0.1
+21 -1 0 0 0 0 0 0 1e2 1e2 0 0 9
+</event>
+</LesHouchesEvents>
+"#;
+
+    fn check_patched(file: &[u8], xsecup: &[f64], xerrup: &[f64]) {
+        let text = String::from_utf8(file.to_vec()).unwrap();
+        let header_end = EVENTS.find("<init>\n2212").unwrap();
+        assert_eq!(&text[..header_end], &EVENTS[..header_end]);
+        let events = &EVENTS[EVENTS.find("<event>").unwrap()..];
+        assert!(text.ends_with(events));
+
+        let mut reader = Reader::new(file).unwrap();
+        assert_eq!(reader.heprup().XSECUP, xsecup);
+        assert_eq!(reader.heprup().XERRUP, xerrup);
+        assert_eq!(reader.heprup().XMAXUP, vec![1., 3.]);
+        let total: f64 = xsecup.iter().sum();
+        assert_eq!(reader.xsecinfo().unwrap().totxsec, total);
+        assert_eq!(reader.xsecinfos()[1].totxsec, 1.);
+        assert!(reader.event().unwrap().is_some());
+    }
+
+    #[test]
+    fn patch() {
+        let mut file = Cursor::new(EVENTS.as_bytes().to_vec());
+        patch_cross_sections(&mut file, &[1., 2.], &[0., 0.]).unwrap();
+        assert_eq!(file.get_ref().len(), EVENTS.len());
+        check_patched(file.get_ref(), &[1., 2.], &[0., 0.]);
+        assert!(String::from_utf8_lossy(file.get_ref()).contains("\n2212  2212 6500"));
+
+        let xsecup = [1.234567890123, 9.876543210987e-3];
+        let xerrup = [0.012345678901, 0.1];
+        let mut file = Cursor::new(EVENTS.as_bytes().to_vec());
+        patch_cross_sections(&mut file, &xsecup, &xerrup).unwrap();
+        assert!(file.get_ref().len() > EVENTS.len());
+        check_patched(file.get_ref(), &xsecup, &xerrup);
+
+        let mut file = Cursor::new(EVENTS.as_bytes().to_vec());
+        assert!(patch_cross_sections(&mut file, &[1.], &[0.]).is_err());
+        assert!(patch_cross_sections(&mut file, &[1., 2.], &[0.]).is_err());
+        assert_eq!(file.get_ref().as_slice(), EVENTS.as_bytes());
+    }
+
+    #[test]
+    fn shift() {
+        let mut file = Cursor::new(b"0123456789".to_vec());
+        shift_tail(&mut file, 4, 3).unwrap();
+        assert_eq!(file.get_ref().as_slice(), b"0123456456789");
+    }
+}
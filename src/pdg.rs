@@ -0,0 +1,171 @@
+//! Particle IDs according to the PDG numbering scheme
+//!
+//! See <https://pdg.lbl.gov/2023/reviews/rpp2023-rev-monte-carlo-numbering.pdf>.
+
+/// Names of common particles, antiparticles have negative IDs
+const NAMES: &[(i32, &str, &str)] = &[
+    (1, "d", "dbar"),
+    (2, "u", "ubar"),
+    (3, "s", "sbar"),
+    (4, "c", "cbar"),
+    (5, "b", "bbar"),
+    (6, "t", "tbar"),
+    (11, "e-", "e+"),
+    (12, "nu_e", "nu_ebar"),
+    (13, "mu-", "mu+"),
+    (14, "nu_mu", "nu_mubar"),
+    (15, "tau-", "tau+"),
+    (16, "nu_tau", "nu_taubar"),
+    (21, "g", ""),
+    (22, "gamma", ""),
+    (23, "Z0", ""),
+    (24, "W+", "W-"),
+    (25, "h0", ""),
+    (111, "pi0", ""),
+    (211, "pi+", "pi-"),
+    (2112, "n", "nbar"),
+    (2212, "p", "pbar"),
+];
+
+/// Masses in GeV of particles commonly used as beams
+const MASSES: &[(i32, f64)] = &[
+    (11, 0.51099895e-3),
+    (13, 0.1056583755),
+    (22, 0.),
+    (211, 0.13957039),
+    (2112, 0.93956542052),
+    (2212, 0.93827208816),
+];
+
+/// Get the name of a particle
+///
+/// Names are known for quarks, leptons, gauge bosons, the Higgs
+/// boson, pions, and nucleons. With the `particle_id` feature, the
+/// names of all particles known to the
+/// [particle_id](https://crates.io/crates/particle_id) crate are
+/// available in addition.
+///
+/// # Example
+///
+/// ```rust
+/// assert_eq!(lhef::particle_name(2212), Some("p"));
+/// assert_eq!(lhef::particle_name(-11), Some("e+"));
+/// ```
+pub fn particle_name(id: i32) -> Option<&'static str> {
+    let name = NAMES
+        .iter()
+        .find(|&&(known, _, _)| known == id.abs())
+        .map(|&(_, particle, anti)| if id > 0 { particle } else { anti })
+        .filter(|name| !name.is_empty());
+    #[cfg(feature = "particle_id")]
+    let name = name.or_else(|| ::particle_id::ParticleID::new(id).name());
+    name
+}
+
+/// Get the mass in GeV of a particle commonly used as a beam
+pub(crate) fn beam_mass(id: i32) -> Option<f64> {
+    MASSES
+        .iter()
+        .find(|&&(known, _)| known == id.abs())
+        .map(|&(_, mass)| mass)
+}
+
+/// Check whether `id` conforms to the PDG numbering scheme
+///
+/// This checks the structure of the number, not whether the particle
+/// actually exists. IDs reserved for generator-specific and new
+/// particles are accepted.
+pub(crate) fn is_valid(id: i32) -> bool {
+    let abs = id.unsigned_abs();
+    if abs >= 1_000_000_000 {
+        return is_valid_nucleus(abs);
+    }
+    if abs >= 10_000_000 {
+        return false;
+    }
+    let digit = |i: u32| (abs / 10u32.pow(i)) % 10;
+    let n = digit(6);
+    if abs % 10_000 < 100 {
+        // fundamental particle, possibly excited
+        let core = abs % 100;
+        let valid_n = match n {
+            0 => abs == core,
+            1..=5 => true,
+            9 => return true,
+            _ => false,
+        };
+        return valid_n && is_valid_fundamental(core, id < 0);
+    }
+    if n != 0 && n != 9 {
+        return false;
+    }
+    // hadron or diquark
+    let (q1, q2, q3, j) = (digit(3), digit(2), digit(1), digit(0));
+    if abs == 130 || abs == 310 {
+        // K_L and K_S are the exceptions with spin digit 0
+        return id > 0;
+    }
+    if j == 0 || q2 == 0 || q2 > 8 || q1 > 8 || q3 > 8 {
+        return false;
+    }
+    match (q1, q3) {
+        // meson, quarkonia are their own antiparticles
+        (0, _) => q3 > 0 && q2 >= q3 && j % 2 == 1 && (id > 0 || q2 != q3),
+        // diquark
+        (_, 0) => q1 >= q2 && j % 2 == 1,
+        // baryon
+        _ => q1 >= q2 && q1 >= q3 && j % 2 == 0,
+    }
+}
+
+fn is_valid_fundamental(id: u32, anti: bool) -> bool {
+    match id {
+        1..=8 | 11..=18 | 24 | 34 | 37 | 41 | 42 | 81..=99 => true,
+        // neutral bosons are their own antiparticles
+        9 | 21..=23 | 25 | 32 | 33 | 35 | 36 | 39 => !anti,
+        _ => false,
+    }
+}
+
+/// Check a nucleus code `10LZZZAAAI`
+fn is_valid_nucleus(abs: u32) -> bool {
+    let z = (abs / 10_000) % 1000;
+    let a = (abs / 10) % 1000;
+    abs / 100_000_000 == 10 && a > 0 && a >= z
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid() {
+        let valid = [
+            1, -5, 11, -12, 21, 22, 23, 24, -24, 25, 1000021, 2000011, 4000001,
+            9000005, 111, 211, -211, 130, 310, 321, 443, 2212, -2212, 2112, 3122,
+            5122, 2101, -3303, 9010221, 1000020040,
+        ];
+        for &id in valid.iter() {
+            assert!(is_valid(id), "{}", id);
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        let invalid = [
+            0, 10, 19, -21, -22, -111, -130, 100, 1234, 2210, 3313, 20,
+            12345678, 1000050020,
+        ];
+        for &id in invalid.iter() {
+            assert!(!is_valid(id), "{}", id);
+        }
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(particle_name(5), Some("b"));
+        assert_eq!(particle_name(-24), Some("W-"));
+        assert_eq!(particle_name(-21), None);
+        assert_eq!(beam_mass(-2212), Some(0.93827208816));
+    }
+}
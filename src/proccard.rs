@@ -0,0 +1,178 @@
+use tags::{self, Tag};
+
+pub(crate) const MG5_PROC_CARD_TAG: &str = "MG5ProcCard";
+pub(crate) const MG_PROC_CARD_TAG: &str = "MGProcCard";
+
+/// Generated processes from a MadGraph process card
+///
+/// MadGraph copies the commands used to generate the events into the
+/// `<MG5ProcCard>` tag of the header, and a compatibility version into
+/// `<MGProcCard>`. The former is preferred if both are present.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct ProcCard {
+    /// Name of the model, including any restriction, e.g. `sm-no_b_mass`
+    pub model: Option<String>,
+    /// Generated processes, e.g. `p p > j j`
+    pub processes: Vec<String>,
+    /// Multiparticle labels with their particles
+    pub multiparticles: Vec<(String, Vec<String>)>,
+}
+
+impl ProcCard {
+    /// Parse the commands of a MadGraph 5 process card
+    ///
+    /// The model is taken from the last `import model` command. The
+    /// processes are collected from `generate` and `add process`
+    /// commands, and multiparticles from `define` commands.
+    pub fn parse(text: &str) -> ProcCard {
+        let mut card = ProcCard::default();
+        for line in text.lines() {
+            let line = strip_comment(line);
+            let words: Vec<_> = line.split_whitespace().collect();
+            match words.as_slice() {
+                ["import", "model", model, ..] => card.model = Some((*model).to_owned()),
+                ["generate", process @ ..] | ["add", "process", process @ ..]
+                    if !process.is_empty() =>
+                {
+                    card.processes.push(process.join(" "))
+                }
+                ["define", label, rest @ ..] => {
+                    let particles = rest
+                        .iter()
+                        .filter(|p| **p != "=")
+                        .map(|p| (*p).to_owned())
+                        .collect();
+                    let label = label.trim_end_matches('=').to_owned();
+                    card.multiparticles.retain(|(l, _)| *l != label);
+                    card.multiparticles.push((label, particles));
+                }
+                _ => {}
+            }
+        }
+        card
+    }
+
+    /// Parse the compatibility process card written for MadEvent
+    ///
+    /// The processes and model are taken from the sections between the
+    /// `# Begin PROCESS` and `# End PROCESS` and the `# Begin MODEL` and
+    /// `# End MODEL` tags.
+    pub fn parse_legacy(text: &str) -> ProcCard {
+        let mut card = ProcCard::default();
+        let mut section = None;
+        for line in text.lines() {
+            let trimmed = line.trim();
+            if let Some(comment) = trimmed.strip_prefix('#') {
+                let words: Vec<_> = comment.split_whitespace().take(2).collect();
+                match words.as_slice() {
+                    ["Begin", name] => section = Some(name.to_uppercase()),
+                    ["End", _] => section = None,
+                    _ => {}
+                }
+                continue;
+            }
+            let content = strip_comment(line).trim();
+            if content.is_empty() {
+                continue;
+            }
+            match section.as_deref() {
+                Some("PROCESS") if content.contains('>') => {
+                    card.processes.push(content.to_owned())
+                }
+                Some("MODEL") => card.model = Some(content.to_owned()),
+                _ => {}
+            }
+        }
+        card
+    }
+
+    /// Get the particles of a multiparticle label
+    pub fn multiparticle(&self, label: &str) -> Option<&[String]> {
+        self.multiparticles
+            .iter()
+            .find(|(l, _)| l == label)
+            .map(|(_, particles)| particles.as_slice())
+    }
+
+    fn from_tag(tag: &Tag) -> ProcCard {
+        if tag.name() == MG5_PROC_CARD_TAG {
+            ProcCard::parse(tag.content())
+        } else {
+            ProcCard::parse_legacy(tag.content())
+        }
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    match line.find('#') {
+        Some(pos) => &line[..pos],
+        None => line,
+    }
+}
+
+/// Parse the MadGraph process card in the header
+pub(crate) fn parse_proc_card(header: &str) -> Option<ProcCard> {
+    tags::header_tag(header, &[MG5_PROC_CARD_TAG])
+        .or_else(|| tags::header_tag(header, &[MG_PROC_CARD_TAG]))
+        .map(|tag| ProcCard::from_tag(&tag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mg5() {
+        let text = "#*  MadGraph5_aMC@NLO *\n\
+                    set group_subprocesses Auto\n\
+                    import model sm\n\
+                    define p = g u c d s u~ c~ d~ s~\n\
+                    define l+ = e+ mu+\n\
+                    import model sm-no_b_mass\n\
+                    generate p p > t t~ @1 # top pairs\n\
+                    add process p p > t t~ j @2\n\
+                    output ttbar\n";
+        let card = ProcCard::parse(text);
+        assert_eq!(card.model, Some("sm-no_b_mass".to_owned()));
+        assert_eq!(card.processes, ["p p > t t~ @1", "p p > t t~ j @2"]);
+        assert_eq!(card.multiparticle("l+").unwrap(), ["e+", "mu+"]);
+        assert_eq!(card.multiparticle("p").unwrap().len(), 9);
+    }
+
+    #[test]
+    fn legacy() {
+        let text = "#*****************\n\
+                    # Begin PROCESS # This is TAG. Do not modify this line\n\
+                    p p > j j           #Process\n\
+                    end_coup           # End the couplings input\n\
+                    \n\
+                    done               # this tells MG there are no more procs\n\
+                    # End PROCESS  # This is TAG. Do not modify this line\n\
+                    # Begin MODEL  # This is TAG. Do not modify this line\n\
+                    sm-no_b_mass\n\
+                    # End   MODEL  # This is TAG. Do not modify this line\n";
+        let card = ProcCard::parse_legacy(text);
+        assert_eq!(card.model, Some("sm-no_b_mass".to_owned()));
+        assert_eq!(card.processes, ["p p > j j"]);
+    }
+
+    #[test]
+    fn header() {
+        let header = "<header>\n\
+                      <MGProcCard>\n# Begin PROCESS\ne+ e- > mu+ mu-\n# End PROCESS\n</MGProcCard>\n\
+                      <MG5ProcCard>\ngenerate e+ e- > mu+ mu-\n</MG5ProcCard>\n\
+                      </header>\n";
+        let card = parse_proc_card(header).unwrap();
+        assert_eq!(card.processes, ["e+ e- > mu+ mu-"]);
+        assert_eq!(card.model, None);
+    }
+
+    #[test]
+    fn read_file() {
+        let reader = ::Reader::open("test_data/2j.lhe.gz").unwrap();
+        let proc_card = reader.proc_card().unwrap();
+        assert_eq!(proc_card.model, Some("sm-no_b_mass".to_owned()));
+        assert_eq!(proc_card.processes, ["p p > j j"]);
+    }
+}
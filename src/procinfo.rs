@@ -0,0 +1,176 @@
+use std::error;
+use std::fmt;
+
+use parse;
+use tags::{self, Tag};
+use xsecinfo::parse_bool;
+
+pub(crate) const PROCINFO_TAG: &str = "procinfo";
+pub(crate) const MERGEINFO_TAG: &str = "mergeinfo";
+
+/// Process information from a LHEF 3.0 `<procinfo>` tag
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct ProcInfo {
+    /// Process ID, corresponding to an entry in `HEPRUP::LPRUP`
+    pub iproc: i32,
+    /// Number of loops
+    pub loops: Option<i32>,
+    /// Order in the strong coupling
+    pub qcdorder: Option<i32>,
+    /// Order in the electroweak coupling
+    pub eworder: Option<i32>,
+    /// Renormalisation scheme
+    pub rscheme: Option<String>,
+    /// Factorisation scheme
+    pub fscheme: Option<String>,
+    /// Scheme for the treatment of infrared divergences
+    pub scheme: Option<String>,
+    /// Free-form description of the process
+    pub description: String,
+}
+
+/// Merging information from a LHEF 3.0 `<mergeinfo>` tag
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct MergeInfo {
+    /// Process ID, corresponding to an entry in `HEPRUP::LPRUP`
+    pub iproc: i32,
+    /// Merging scale in GeV
+    pub mergingscale: Option<f64>,
+    /// Whether this process has the highest multiplicity in the merging
+    pub maxmult: bool,
+    /// Free-form description of the merging scheme
+    pub description: String,
+}
+
+impl ProcInfo {
+    fn from_tag(tag: &Tag) -> Result<ProcInfo, Box<dyn error::Error>> {
+        let attr = |name| tag.attribute(name).map(str::trim);
+        let optional_i32 = |name| attr(name).map(|v| parse::<i32>(name, Some(v))).transpose();
+        let optional_string = |name| attr(name).map(|v| v.to_owned());
+        Ok(ProcInfo {
+            iproc: parse("iproc", attr("iproc"))?,
+            loops: optional_i32("loops")?,
+            qcdorder: optional_i32("qcdorder")?,
+            eworder: optional_i32("eworder")?,
+            rscheme: optional_string("rscheme"),
+            fscheme: optional_string("fscheme"),
+            scheme: optional_string("scheme"),
+            description: tag.content().to_owned(),
+        })
+    }
+}
+
+impl MergeInfo {
+    fn from_tag(tag: &Tag) -> Result<MergeInfo, Box<dyn error::Error>> {
+        let attr = |name| tag.attribute(name).map(str::trim);
+        Ok(MergeInfo {
+            iproc: parse("iproc", attr("iproc"))?,
+            mergingscale: attr("mergingscale")
+                .map(|v| parse::<f64>("mergingscale", Some(v)))
+                .transpose()?,
+            maxmult: attr("maxmult").map(parse_bool).transpose()?.unwrap_or(false),
+            description: tag.content().to_owned(),
+        })
+    }
+}
+
+/// Parse all `<procinfo>` tags in the optional run information
+pub(crate) fn parse_procinfo(info: &str) -> Result<Vec<ProcInfo>, Box<dyn error::Error>> {
+    tags::tags(info)
+        .filter(|(_, tag)| tag.name() == PROCINFO_TAG)
+        .map(|(_, tag)| ProcInfo::from_tag(&tag))
+        .collect()
+}
+
+/// Parse all `<mergeinfo>` tags in the optional run information
+pub(crate) fn parse_mergeinfo(info: &str) -> Result<Vec<MergeInfo>, Box<dyn error::Error>> {
+    tags::tags(info)
+        .filter(|(_, tag)| tag.name() == MERGEINFO_TAG)
+        .map(|(_, tag)| MergeInfo::from_tag(&tag))
+        .collect()
+}
+
+/// Formats the information as a `<procinfo>` tag
+impl fmt::Display for ProcInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<{} iproc=\"{}\"", PROCINFO_TAG, self.iproc)?;
+        let orders = [
+            ("loops", self.loops),
+            ("qcdorder", self.qcdorder),
+            ("eworder", self.eworder),
+        ];
+        for &(name, value) in &orders {
+            if let Some(value) = value {
+                write!(f, " {}=\"{}\"", name, value)?;
+            }
+        }
+        let schemes = [
+            ("rscheme", &self.rscheme),
+            ("fscheme", &self.fscheme),
+            ("scheme", &self.scheme),
+        ];
+        for &(name, value) in &schemes {
+            if let Some(ref value) = *value {
+                write!(f, " {}=\"{}\"", name, value)?;
+            }
+        }
+        write!(f, ">{}</{}>", self.description, PROCINFO_TAG)
+    }
+}
+
+/// Formats the information as a `<mergeinfo>` tag
+impl fmt::Display for MergeInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<{} iproc=\"{}\"", MERGEINFO_TAG, self.iproc)?;
+        if let Some(scale) = self.mergingscale {
+            write!(f, " mergingscale=\"{:e}\"", scale)?;
+        }
+        if self.maxmult {
+            write!(f, " maxmult=\"yes\"")?;
+        }
+        write!(f, ">{}</{}>", self.description, MERGEINFO_TAG)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INFO: &str = "<procinfo iproc=\"1\" loops=\"1\" qcdorder=\"2\" eworder=\"0\" \
+                        rscheme=\"MSbar\" scheme=\"CS\">p p > j j</procinfo>\n\
+                        <procinfo iproc=\"2\"></procinfo>\n\
+                        <mergeinfo iproc=\"1\" mergingscale=\"30\" maxmult=\"no\"/>\n\
+                        <mergeinfo iproc=\"2\" mergingscale=\"30\" maxmult=\"yes\">CKKW-L</mergeinfo>";
+
+    #[test]
+    fn parse_and_format() {
+        let procinfo = parse_procinfo(INFO).unwrap();
+        assert_eq!(procinfo.len(), 2);
+        assert_eq!(procinfo[0].iproc, 1);
+        assert_eq!(procinfo[0].loops, Some(1));
+        assert_eq!(procinfo[0].qcdorder, Some(2));
+        assert_eq!(procinfo[0].rscheme, Some("MSbar".to_owned()));
+        assert_eq!(procinfo[0].fscheme, None);
+        assert_eq!(procinfo[0].description, "p p > j j");
+        let mergeinfo = parse_mergeinfo(INFO).unwrap();
+        assert_eq!(mergeinfo.len(), 2);
+        assert!(!mergeinfo[0].maxmult);
+        assert!(mergeinfo[1].maxmult);
+        assert_eq!(mergeinfo[1].mergingscale, Some(30.));
+        assert_eq!(mergeinfo[1].description, "CKKW-L");
+
+        let formatted: String = procinfo.iter().map(|p| p.to_string())
+            .chain(mergeinfo.iter().map(|m| m.to_string()))
+            .collect();
+        assert_eq!(parse_procinfo(&formatted).unwrap(), procinfo);
+        assert_eq!(parse_mergeinfo(&formatted).unwrap(), mergeinfo);
+    }
+
+    #[test]
+    fn missing_iproc() {
+        assert!(parse_procinfo("<procinfo loops=\"1\"/>").is_err());
+        assert!(parse_mergeinfo("<mergeinfo/>").is_err());
+    }
+}
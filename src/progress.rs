@@ -0,0 +1,78 @@
+use std::io::{self, BufRead, Read};
+
+/// Progress of a `Reader`
+///
+/// See `Reader::progress`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+pub struct Progress {
+    /// Number of bytes consumed so far, including header and init block
+    pub bytes: u64,
+    /// Number of events read so far
+    ///
+    /// All events in an `<eventgroup>` are counted as soon as the group
+    /// has been read.
+    pub events: u64,
+    /// Total number of bytes, if known
+    pub total_bytes: Option<u64>,
+}
+
+impl Progress {
+    /// Estimate the completed fraction from the number of bytes
+    ///
+    /// Returns `None` if the total number of bytes is unknown.
+    pub fn fraction(&self) -> Option<f64> {
+        match self.total_bytes {
+            Some(0) => Some(1.),
+            Some(total) => Some((self.bytes as f64 / total as f64).min(1.)),
+            None => None,
+        }
+    }
+}
+
+/// Adaptor counting the number of bytes consumed from a stream
+pub(crate) struct CountingReader<Stream> {
+    pub(crate) stream: Stream,
+    pub(crate) count: u64,
+}
+
+impl<Stream: BufRead> Read for CountingReader<Stream> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.stream.read(buf)?;
+        self.count += len as u64;
+        Ok(len)
+    }
+}
+
+impl<Stream: BufRead> BufRead for CountingReader<Stream> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.stream.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.count += amt as u64;
+        self.stream.consume(amt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count() {
+        let mut reader = CountingReader { stream: "line 1\nline 2\n".as_bytes(), count: 0 };
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(reader.count, 7);
+        reader.read_to_string(&mut line).unwrap();
+        assert_eq!(reader.count, 14);
+    }
+
+    #[test]
+    fn fraction() {
+        let progress = Progress { bytes: 10, events: 1, total_bytes: Some(40) };
+        assert_eq!(progress.fraction(), Some(0.25));
+        assert_eq!(Progress::default().fraction(), None);
+    }
+}
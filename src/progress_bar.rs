@@ -0,0 +1,82 @@
+use std::error;
+use std::io::BufRead;
+
+use indicatif::{ProgressBar, ProgressStyle};
+
+use {Reader, HEPEUP};
+
+const TEMPLATE: &str = "{wide_bar} {bytes}/{total_bytes} {msg} [{elapsed_precise}<{eta_precise}]";
+const SPINNER_TEMPLATE: &str = "{spinner} {bytes} {msg} [{elapsed_precise}]";
+
+/// Iterator over events that drives a progress bar
+///
+/// This is created by `Reader::events_with_progress_bar` and requires
+/// the `indicatif` feature. The position of the bar is the number of
+/// bytes read, and its message shows the number of events. The bar is
+/// finished after the last event and abandoned on error.
+pub struct ProgressBarEvents<'a, Stream: 'a> {
+    reader: &'a mut Reader<Stream>,
+    bar: ProgressBar,
+}
+
+impl<'a, Stream: BufRead> ProgressBarEvents<'a, Stream> {
+    pub(crate) fn new(reader: &'a mut Reader<Stream>, bar: ProgressBar) -> Self {
+        ProgressBarEvents { reader, bar }
+    }
+
+    /// Get the progress bar
+    pub fn bar(&self) -> &ProgressBar {
+        &self.bar
+    }
+
+    fn update(&self) {
+        let progress = self.reader.progress();
+        if let Some(total) = progress.total_bytes {
+            self.bar.set_length(total);
+        }
+        self.bar.set_position(progress.bytes);
+        self.bar.set_message(format!("{} events", progress.events));
+    }
+}
+
+impl<'a, Stream: BufRead> Iterator for ProgressBarEvents<'a, Stream> {
+    type Item = Result<HEPEUP, Box<dyn error::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.bar.is_finished() {
+            return None;
+        }
+        let event = self.reader.event();
+        self.update();
+        match event {
+            Ok(Some(event)) => Some(Ok(event)),
+            Ok(None) => {
+                self.bar.finish();
+                None
+            }
+            Err(err) => {
+                self.bar.abandon();
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// Create a progress bar suitable for the reader
+///
+/// This shows a bar if the total size is known, see
+/// `Reader::progress`, and a spinner otherwise.
+pub(crate) fn progress_bar<Stream>(reader: &Reader<Stream>) -> ProgressBar {
+    match reader.total_bytes {
+        Some(total) => {
+            let bar = ProgressBar::new(total);
+            bar.set_style(ProgressStyle::with_template(TEMPLATE).unwrap());
+            bar
+        }
+        None => {
+            let bar = ProgressBar::new_spinner();
+            bar.set_style(ProgressStyle::with_template(SPINNER_TEMPLATE).unwrap());
+            bar
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use index::Checksum;
+use tags::{self, Tag, HEADER_TAG};
+
+pub(crate) const PROVENANCE_TAG: &str = "provenance";
+const INPUT_TAG: &str = "input";
+const FILTER_TAG: &str = "filter";
+
+/// Record of a processing step that produced an event file
+///
+/// A provenance record is added to the header with
+/// `WriterBuilder::provenance` and written as a `<provenance>` tag
+/// inside a `<header>` block:
+///
+/// ```xml
+/// <provenance tool="skim" version="1.2" timestamp="2024-05-01T12:00:00Z">
+/// <input name="events.lhe" fnv1a="cbf29ce484222325"/>
+/// <filter>at least two jets</filter>
+/// </provenance>
+/// ```
+///
+/// When the banner of the input is passed on, the records of all
+/// previous steps are kept, so the complete processing chain can be
+/// retrieved with `Reader::provenance`. Input files are identified by
+/// their 64-bit FNV-1a hash, as used by `Index`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Provenance {
+    /// Name of the program
+    pub tool: String,
+    /// Version of the program
+    pub version: String,
+    /// Time of processing in the format `YYYY-MM-DDThh:mm:ssZ`
+    pub timestamp: String,
+    /// Input files
+    pub inputs: Vec<ProvenanceInput>,
+    /// Descriptions of the applied filters
+    pub filters: Vec<String>,
+}
+
+/// An input file in a `Provenance` record
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct ProvenanceInput {
+    /// File name
+    pub name: String,
+    /// 64-bit FNV-1a hash of the file content
+    pub fnv1a: Option<u64>,
+}
+
+impl Provenance {
+    /// Create a record for the given program, with the current time as
+    /// timestamp
+    pub fn new(tool: &str, version: &str) -> Provenance {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or_default();
+        Provenance {
+            tool: tool.to_owned(),
+            version: version.to_owned(),
+            timestamp: format_timestamp(now),
+            ..Default::default()
+        }
+    }
+
+    /// Add an input file, reading it to compute its hash
+    pub fn input_file<P: AsRef<Path>>(mut self, path: P) -> io::Result<Provenance> {
+        let path = path.as_ref();
+        let fnv1a = Checksum::of(File::open(path)?)?;
+        self.inputs.push(ProvenanceInput {
+            name: path.display().to_string(),
+            fnv1a: Some(fnv1a),
+        });
+        Ok(self)
+    }
+
+    /// Add an input with a known hash, or without a hash
+    pub fn input(mut self, name: &str, fnv1a: Option<u64>) -> Provenance {
+        self.inputs.push(ProvenanceInput {
+            name: name.to_owned(),
+            fnv1a,
+        });
+        self
+    }
+
+    /// Add the description of an applied filter
+    pub fn filter(mut self, description: &str) -> Provenance {
+        self.filters.push(description.to_owned());
+        self
+    }
+
+    fn from_tag(tag: &Tag) -> Provenance {
+        let attribute = |name| tag.attribute(name).unwrap_or_default().to_owned();
+        let mut provenance = Provenance {
+            tool: attribute("tool"),
+            version: attribute("version"),
+            timestamp: attribute("timestamp"),
+            ..Default::default()
+        };
+        for (_, tag) in tags::tags(tag.content()) {
+            match tag.name() {
+                INPUT_TAG => provenance.inputs.push(ProvenanceInput {
+                    name: tag.attribute("name").unwrap_or_default().to_owned(),
+                    fnv1a: tag
+                        .attribute("fnv1a")
+                        .and_then(|hash| u64::from_str_radix(hash.trim(), 16).ok()),
+                }),
+                FILTER_TAG => provenance.filters.push(tag.content().trim().to_owned()),
+                _ => {}
+            }
+        }
+        provenance
+    }
+}
+
+/// Parse all `<provenance>` tags in the header, in order of appearance
+pub(crate) fn parse_provenance(header: &str) -> Vec<Provenance> {
+    let mut result = Vec::new();
+    collect_provenance(header, &mut result);
+    result
+}
+
+fn collect_provenance(text: &str, result: &mut Vec<Provenance>) {
+    for (_, tag) in tags::tags(text) {
+        match tag.name() {
+            PROVENANCE_TAG => result.push(Provenance::from_tag(&tag)),
+            HEADER_TAG => collect_provenance(tag.content(), result),
+            _ => {}
+        }
+    }
+}
+
+/// Format seconds since the Unix epoch as a UTC timestamp
+fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let secs = secs % 86400;
+    // civil date from days since 1970-01-01, see
+    // http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+/// Formats the record as a `<provenance>` tag
+impl fmt::Display for Provenance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "<{} tool=\"{}\" version=\"{}\" timestamp=\"{}\">",
+            PROVENANCE_TAG, self.tool, self.version, self.timestamp
+        )?;
+        for input in &self.inputs {
+            write!(f, "<{} name=\"{}\"", INPUT_TAG, input.name)?;
+            if let Some(hash) = input.fnv1a {
+                write!(f, " fnv1a=\"{:016x}\"", hash)?;
+            }
+            writeln!(f, "/>")?;
+        }
+        for filter in &self.filters {
+            writeln!(f, "<{}>{}</{}>", FILTER_TAG, filter, FILTER_TAG)?;
+        }
+        write!(f, "</{}>", PROVENANCE_TAG)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_util::SyntheticEvents;
+    use {Reader, WriterBuilder};
+
+    #[test]
+    fn timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_timestamp(951_782_400 + 3723), "2000-02-29T01:02:03Z");
+        assert_eq!(format_timestamp(1_767_225_599), "2025-12-31T23:59:59Z");
+        assert_eq!(Provenance::new("x", "1").timestamp.len(), 20);
+    }
+
+    #[test]
+    fn chain() {
+        let sample = SyntheticEvents::new().num_events(3).to_bytes();
+        let first = Provenance::new("skim", "1.0")
+            .input("events.lhe", Some(Checksum::of(sample.as_slice()).unwrap()))
+            .filter("two jets with pT > 20 GeV");
+        let second = Provenance::new("reweight", "0.3").input("skim.lhe", None);
+
+        let mut stages = Vec::new();
+        let mut input = sample.clone();
+        for provenance in [&first, &second] {
+            let mut reader = Reader::new(input.as_slice()).unwrap();
+            let mut output = Vec::new();
+            {
+                let mut writer = WriterBuilder::new()
+                    .banner(reader.header())
+                    .provenance(provenance.clone())
+                    .build(&mut output)
+                    .unwrap();
+                writer.heprup(reader.heprup()).unwrap();
+                while let Some(block) = reader.block().unwrap() {
+                    writer.block(&block).unwrap();
+                }
+                writer.finish().unwrap();
+            }
+            stages.push(provenance.clone());
+            assert_eq!(Reader::new(output.as_slice()).unwrap().provenance(), stages);
+            input = output;
+        }
+    }
+}
@@ -0,0 +1,103 @@
+/// A small deterministic pseudo-random number generator
+///
+/// This is the SplitMix64 generator, which is fast and good enough for
+/// resampling events, but not for cryptographic purposes.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub(crate) struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniformly distributed number in [0, 1)
+    pub(crate) fn uniform(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Poisson-distributed number with the given mean
+    ///
+    /// Small means use Knuth's multiplication method, large ones the
+    /// transformed rejection method of Hörmann (1993). Returns 0 for
+    /// means that are not positive and finite.
+    pub(crate) fn poisson(&mut self, mean: f64) -> u64 {
+        if !(mean > 0. && mean.is_finite()) {
+            return 0;
+        }
+        if mean < 10. {
+            let limit = (-mean).exp();
+            let mut k = 0;
+            let mut product = self.uniform();
+            while product > limit {
+                k += 1;
+                product *= self.uniform();
+            }
+            return k;
+        }
+        let log_mean = mean.ln();
+        let b = 0.931 + 2.53 * mean.sqrt();
+        let a = -0.059 + 0.02483 * b;
+        let inv_alpha = 1.1239 + 1.1328 / (b - 3.4);
+        let v_r = 0.9277 - 3.6224 / (b - 2.);
+        loop {
+            let u = self.uniform() - 0.5;
+            let v = self.uniform();
+            let us = 0.5 - u.abs();
+            let k = ((2. * a / us + b) * u + mean + 0.43).floor();
+            if us >= 0.07 && v <= v_r {
+                return k as u64;
+            }
+            if k < 0. || (us < 0.013 && v > us) {
+                continue;
+            }
+            let lhs = (v * inv_alpha / (a / (us * us) + b)).ln();
+            let rhs = -mean + k * log_mean - ln_factorial(k);
+            if lhs <= rhs {
+                return k as u64;
+            }
+        }
+    }
+}
+
+/// Logarithm of k! via Stirling's series, exact for small k
+fn ln_factorial(k: f64) -> f64 {
+    if k < 10. {
+        return (2..=k as u64).map(|i| (i as f64).ln()).sum();
+    }
+    let k2 = k * k;
+    (k + 0.5) * k.ln() - k + 0.5 * (2. * ::std::f64::consts::PI).ln() + 1. / (12. * k)
+        - 1. / (360. * k * k2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn poisson() {
+        let mut rng = Rng::new(1);
+        for &mean in &[0.3, 4., 25., 1000.] {
+            let n = 20_000;
+            let draws: Vec<_> = (0..n).map(|_| rng.poisson(mean) as f64).collect();
+            let average = draws.iter().sum::<f64>() / n as f64;
+            let variance = draws.iter().map(|d| (d - average).powi(2)).sum::<f64>() / n as f64;
+            let tolerance = 5. * (mean / n as f64).sqrt();
+            assert!((average - mean).abs() < tolerance, "{} != {}", average, mean);
+            assert!((variance / mean - 1.).abs() < 0.05, "{} != {}", variance, mean);
+        }
+        assert_eq!(rng.poisson(0.), 0);
+        assert_eq!(rng.poisson(f64::NAN), 0);
+        assert_eq!(rng.poisson(f64::INFINITY), 0);
+        assert_eq!(Rng::new(7).next_u64(), Rng::new(7).next_u64());
+    }
+}
@@ -0,0 +1,133 @@
+use std::error;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use fields::Fields;
+use {parse_event, split_line, Attributes, EventContext, HEPEUP};
+
+/// An event that has not been parsed yet, see `Reader::raw_event`
+///
+/// The accessors only parse the first line of the event, which is
+/// much cheaper than parsing the whole event with `parse`.
+pub struct RawEvent {
+    content: Content,
+}
+
+enum Content {
+    Text {
+        text: String,
+        attr: Attributes,
+        context: Arc<EventContext>,
+    },
+    Parsed(Box<HEPEUP>),
+}
+
+impl RawEvent {
+    pub(crate) fn new(text: String, attr: Attributes, context: Arc<EventContext>) -> RawEvent {
+        RawEvent {
+            content: Content::Text {
+                text,
+                attr,
+                context,
+            },
+        }
+    }
+
+    pub(crate) fn parsed(event: HEPEUP) -> RawEvent {
+        RawEvent {
+            content: Content::Parsed(Box::new(event)),
+        }
+    }
+
+    /// The text between the `<event>` and `</event>` tags
+    ///
+    /// Returns `None` for events in an `<eventgroup>`, which are
+    /// already parsed.
+    pub fn text(&self) -> Option<&str> {
+        match self.content {
+            Content::Text { ref text, .. } => Some(text),
+            Content::Parsed(_) => None,
+        }
+    }
+
+    /// The attributes of the `<event>` tag
+    pub fn attributes(&self) -> &Attributes {
+        match self.content {
+            Content::Text { ref attr, .. } => attr,
+            Content::Parsed(ref event) => &event.attr,
+        }
+    }
+
+    /// The number of particles `NUP`
+    pub fn num_particles(&self) -> Result<i32, Box<dyn error::Error>> {
+        match self.content {
+            Content::Text { ref text, .. } => first_line_entry(text, 0, "NUP"),
+            Content::Parsed(ref event) => Ok(event.NUP),
+        }
+    }
+
+    /// The process ID `IDRUP`
+    pub fn process_id(&self) -> Result<i32, Box<dyn error::Error>> {
+        match self.content {
+            Content::Text { ref text, .. } => first_line_entry(text, 1, "IDRUP"),
+            Content::Parsed(ref event) => Ok(event.IDRUP),
+        }
+    }
+
+    /// The event weight `XWGTUP`
+    pub fn weight(&self) -> Result<f64, Box<dyn error::Error>> {
+        match self.content {
+            Content::Text { ref text, .. } => first_line_entry(text, 2, "XWGTUP"),
+            Content::Parsed(ref event) => Ok(event.XWGTUP),
+        }
+    }
+
+    /// Parse the complete event
+    pub fn parse(self) -> Result<HEPEUP, Box<dyn error::Error>> {
+        match self.content {
+            Content::Text {
+                text,
+                attr,
+                context,
+            } => parse_event(&text, attr, &context),
+            Content::Parsed(event) => Ok(*event),
+        }
+    }
+}
+
+/// Parse the entry at position `index` of the first line of an event
+fn first_line_entry<T: FromStr>(
+    mut text: &str,
+    index: usize,
+    name: &str,
+) -> Result<T, Box<dyn error::Error>> {
+    let mut entries = Fields::new(split_line(&mut text));
+    for _ in 0..index {
+        entries.next();
+    }
+    entries.parse(|| name.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use test_util::SyntheticEvents;
+    use Reader;
+
+    #[test]
+    fn raw_events() {
+        let sample = SyntheticEvents::new().num_events(10).to_bytes();
+        let mut reader = Reader::new(sample.as_slice()).unwrap();
+        let mut expected = Reader::new(sample.as_slice()).unwrap();
+        while let Some(raw) = reader.raw_event().unwrap() {
+            let event = expected.event().unwrap().unwrap();
+            assert!(raw.text().unwrap().starts_with(&format!("{} ", event.NUP)));
+            assert_eq!(raw.attributes(), &event.attr);
+            assert_eq!(raw.num_particles().unwrap(), event.NUP);
+            assert_eq!(raw.process_id().unwrap(), event.IDRUP);
+            assert_eq!(raw.weight().unwrap(), event.XWGTUP);
+            assert_eq!(raw.parse().unwrap(), event);
+        }
+        assert_eq!(expected.event().unwrap(), None);
+        assert_eq!(reader.progress().events, 10);
+    }
+}
@@ -0,0 +1,70 @@
+use std::io::{self, SeekFrom};
+
+use {HEPEUP, HEPRUP};
+
+/// Accumulated event weights for recomputing the cross sections when
+/// a `Writer` finishes
+pub(crate) struct CrossSectionSums<Stream> {
+    /// seek function of the output stream
+    pub(crate) seek: fn(&mut Stream, SeekFrom) -> io::Result<u64>,
+    /// position and content of the init block, once written
+    pub(crate) init: Option<(u64, HEPRUP)>,
+    events: u64,
+    /// sum of weights and of squared weights for each process
+    sums: Vec<(f64, f64)>,
+}
+
+impl<Stream> CrossSectionSums<Stream> {
+    pub(crate) fn new(seek: fn(&mut Stream, SeekFrom) -> io::Result<u64>) -> Self {
+        CrossSectionSums {
+            seek,
+            init: None,
+            events: 0,
+            sums: Vec::new(),
+        }
+    }
+
+    pub(crate) fn set_init(&mut self, offset: u64, heprup: &HEPRUP) {
+        self.sums = vec![(0., 0.); heprup.LPRUP.len()];
+        self.init = Some((offset, heprup.clone()));
+    }
+
+    /// Add the weight of an event
+    ///
+    /// Weights of events with unknown process IDs only count towards
+    /// the total number of events.
+    pub(crate) fn add(&mut self, event: &HEPEUP) {
+        self.events += 1;
+        let heprup = match self.init {
+            Some((_, ref heprup)) => heprup,
+            None => return,
+        };
+        if let Some(i) = heprup.LPRUP.iter().position(|&id| id == event.IDRUP) {
+            let w = event.XWGTUP;
+            self.sums[i].0 += w;
+            self.sums[i].1 += w * w;
+        }
+    }
+
+    /// The run information with cross sections computed from the weights
+    ///
+    /// For `IDWTUP` ±1, ±2, and ±4, the cross section of each process
+    /// is the sum of its weights divided by the total number of events,
+    /// and the error is the standard error of this mean. For unit
+    /// weights (`IDWTUP` ±3), the weights carry no information about the
+    /// cross section and the original values are kept.
+    pub(crate) fn heprup(&self) -> Option<(u64, HEPRUP)> {
+        let (offset, ref heprup) = *self.init.as_ref()?;
+        let mut heprup = heprup.clone();
+        if heprup.IDWTUP.abs() == 3 || self.events == 0 {
+            return Some((offset, heprup));
+        }
+        let n = self.events as f64;
+        for (i, &(sum, sum_squares)) in self.sums.iter().enumerate() {
+            let mean = sum / n;
+            heprup.XSECUP[i] = mean;
+            heprup.XERRUP[i] = ((sum_squares / n - mean * mean).max(0.) / n).sqrt();
+        }
+        Some((offset, heprup))
+    }
+}
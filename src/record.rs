@@ -0,0 +1,106 @@
+use HEPEUP;
+
+/// A particle of an `EventRecord`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct Particle {
+    /// Particle ID
+    pub id: i32,
+    /// Status code
+    pub status: i32,
+    /// Positions of the first and last mother, counting from 1, or 0
+    pub mothers: [i32; 2],
+    /// Momentum `[px, py, pz, E, m]` in GeV
+    pub momentum: [f64; 5],
+}
+
+/// Common interface of event records
+///
+/// Analysis code written against this trait instead of `HEPEUP` can be
+/// reused with other event formats that implement it.
+///
+/// # Example
+///
+/// ```rust
+/// use lhef::EventRecord;
+///
+/// fn count_final_state<E: EventRecord>(event: &E) -> usize {
+///     (0..event.num_particles())
+///         .filter(|&i| event.particle(i).status == 1)
+///         .count()
+/// }
+/// ```
+pub trait EventRecord {
+    /// Number of particles
+    fn num_particles(&self) -> usize;
+
+    /// Particle at position `i`, counting from 0
+    ///
+    /// Panics if `i` is not smaller than `num_particles`.
+    fn particle(&self, i: usize) -> Particle;
+
+    /// Event weight
+    ///
+    /// For `HEPEUP`, this is `XWGTUP`. Since `HEPEUP::weight` looks up
+    /// additional weights, call it as `EventRecord::weight(&event)`
+    /// outside of generic code.
+    fn weight(&self) -> f64;
+
+    /// Process ID
+    fn process_id(&self) -> i32;
+}
+
+impl EventRecord for HEPEUP {
+    fn num_particles(&self) -> usize {
+        self.NUP as usize
+    }
+
+    fn particle(&self, i: usize) -> Particle {
+        Particle {
+            id: self.IDUP[i],
+            status: self.ISTUP[i],
+            mothers: self.MOTHUP[i],
+            momentum: self.PUP[i],
+        }
+    }
+
+    fn weight(&self) -> f64 {
+        self.XWGTUP
+    }
+
+    fn process_id(&self) -> i32 {
+        self.IDRUP
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_util::MockEvent;
+
+    fn total_energy<E: EventRecord>(event: &E) -> f64 {
+        (0..event.num_particles())
+            .map(|i| event.particle(i))
+            .filter(|particle| particle.status == 1)
+            .map(|particle| particle.momentum[3])
+            .sum()
+    }
+
+    #[test]
+    fn hepeup() {
+        let event = MockEvent::drell_yan().with_weight(2.5).with_process_id(3).build();
+        assert_eq!(event.num_particles(), event.NUP as usize);
+        assert_eq!(EventRecord::weight(&event), 2.5);
+        assert_eq!(event.process_id(), 3);
+        let last = event.num_particles() - 1;
+        let particle = event.particle(last);
+        assert_eq!(particle.id, event.IDUP[last]);
+        assert_eq!(particle.mothers, event.MOTHUP[last]);
+        assert_eq!(particle.momentum, event.PUP[last]);
+        let expected: f64 = (0..event.IDUP.len())
+            .filter(|&i| event.ISTUP[i] == 1)
+            .map(|i| event.PUP[i][3])
+            .sum();
+        assert_eq!(total_energy(&event), expected);
+    }
+}
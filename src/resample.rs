@@ -0,0 +1,177 @@
+use std::error;
+use std::fmt;
+use std::io::{BufRead, Write};
+
+use random::Rng;
+use {Reader, Writer, HEPEUP};
+
+/// Resampling of weighted events to a target luminosity
+///
+/// Each event is repeated a Poisson-distributed number of times, with
+/// a mean proportional to its weight. The mean total number of events
+/// is the product of the integrated luminosity and the cross section,
+/// so the output resembles the data that would be observed. All
+/// output events have weight 1, or -1 for input events with negative
+/// weights, which have to be subtracted in an analysis.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # let (mut reader, mut writer): (lhef::Reader<std::io::Empty>, lhef::Writer<std::io::Sink>) = unimplemented!();
+/// let cross_section: f64 = reader.heprup().XSECUP.iter().sum();
+/// // sample normalised such that the sum of weights is the cross section
+/// let mut resampler = lhef::Resampler::new(100., cross_section, cross_section, 42).unwrap();
+/// writer.header(reader.header()).unwrap();
+/// writer.heprup(reader.heprup()).unwrap();
+/// let nevents = resampler.resample(&mut reader, &mut writer).unwrap();
+/// println!("Wrote {} pseudo-data events", nevents);
+/// ```
+#[derive(PartialEq, Debug, Clone)]
+pub struct Resampler {
+    /// Mean number of output events per unit input weight
+    events_per_weight: f64,
+    rng: Rng,
+}
+
+impl Resampler {
+    /// Set up resampling
+    ///
+    /// The cross section and luminosity have to be given in matching
+    /// units, e.g. pb and pb⁻¹. `sum_weights` is the sum of the
+    /// weights `XWGTUP` of all input events. The output is reproducible
+    /// for a given `seed`. Fails if the resulting number of events per
+    /// unit weight is not finite, e.g. because `sum_weights` is zero.
+    pub fn new(
+        luminosity: f64,
+        cross_section: f64,
+        sum_weights: f64,
+        seed: u64,
+    ) -> Result<Resampler, Box<dyn error::Error>> {
+        let events_per_weight = luminosity * cross_section / sum_weights;
+        if !events_per_weight.is_finite() {
+            return Err(Box::new(ResampleError::NonFiniteNormalisation(events_per_weight)));
+        }
+        Ok(Resampler {
+            events_per_weight,
+            rng: Rng::new(seed),
+        })
+    }
+
+    /// Draw the number of times an event is repeated in the output
+    ///
+    /// Events with a weight that is NaN or infinite are never repeated.
+    pub fn copies(&mut self, event: &HEPEUP) -> u64 {
+        self.rng.poisson(self.events_per_weight * event.XWGTUP.abs())
+    }
+
+    /// Resample all remaining events of `reader` into `writer`
+    ///
+    /// The header and run information have to be written beforehand.
+    /// Returns the number of events written.
+    pub fn resample<R: BufRead, W: Write>(
+        &mut self,
+        reader: &mut Reader<R>,
+        writer: &mut Writer<W>,
+    ) -> Result<u64, Box<dyn error::Error>> {
+        let mut written = 0;
+        while let Some(mut event) = reader.event()? {
+            let copies = self.copies(&event);
+            if copies == 0 {
+                continue;
+            }
+            event.XWGTUP = event.XWGTUP.signum();
+            for _ in 0..copies {
+                writer.event(&event)?;
+            }
+            written += copies;
+        }
+        Ok(written)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ResampleError {
+    NonFiniteNormalisation(f64),
+}
+
+impl fmt::Display for ResampleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::ResampleError::*;
+        match *self {
+            NonFiniteNormalisation(value) => {
+                write!(f, "Number of events per unit weight is {}", value)
+            }
+        }
+    }
+}
+
+impl error::Error for ResampleError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_util::{MockEvent, SyntheticEvents};
+
+    /// Write a sample with one event for each weight
+    fn sample(weights: &[f64]) -> Vec<u8> {
+        let mut output = Vec::new();
+        {
+            let mut writer = Writer::new(&mut output, "1.0").unwrap();
+            writer.heprup(&SyntheticEvents::new().heprup()).unwrap();
+            for &weight in weights {
+                writer.event(&MockEvent::dijet().with_weight(weight).build()).unwrap();
+            }
+            writer.finish().unwrap();
+        }
+        output
+    }
+
+    #[test]
+    fn resample() {
+        let input = sample(&[2., -1., 0.]);
+        let mut reader = Reader::new(input.as_slice()).unwrap();
+        let mut output = Vec::new();
+        let written = {
+            let mut writer = Writer::new(&mut output, "1.0").unwrap();
+            writer.heprup(reader.heprup()).unwrap();
+            let mut resampler = Resampler::new(50., 2., 1., 3).unwrap();
+            resampler.resample(&mut reader, &mut writer).unwrap()
+        };
+        let mut reread = Reader::new(output.as_slice()).unwrap();
+        let (mut positive, mut negative) = (0, 0);
+        while let Some(event) = reread.event().unwrap() {
+            if event.XWGTUP > 0. {
+                positive += 1;
+            } else {
+                assert_eq!(event.XWGTUP, -1.);
+                negative += 1;
+            }
+            assert_eq!(event.XWGTUP.abs(), 1.);
+        }
+        assert_eq!(positive + negative, written);
+        // means 200 and 100
+        assert!((positive as f64 - 200.).abs() < 5. * 200f64.sqrt());
+        assert!((negative as f64 - 100.).abs() < 5. * 100f64.sqrt());
+
+        let event = MockEvent::dijet().with_weight(2.).build();
+        let mut a = Resampler::new(1., 1., 1., 9).unwrap();
+        let mut b = Resampler::new(1., 1., 1., 9).unwrap();
+        let draws_a: Vec<_> = (0..10).map(|_| a.copies(&event)).collect();
+        let draws_b: Vec<_> = (0..10).map(|_| b.copies(&event)).collect();
+        assert_eq!(draws_a, draws_b);
+    }
+
+    #[test]
+    fn non_finite() {
+        assert!(Resampler::new(1., 1., 0., 1).is_err());
+        assert!(Resampler::new(f64::NAN, 1., 1., 1).is_err());
+        assert!(Resampler::new(1., f64::INFINITY, 1., 1).is_err());
+
+        let mut event = MockEvent::dijet().build();
+        let mut resampler = Resampler::new(1e3, 1., 1., 5).unwrap();
+        event.XWGTUP = f64::NAN;
+        assert_eq!(resampler.copies(&event), 0);
+        event.XWGTUP = f64::INFINITY;
+        assert_eq!(resampler.copies(&event), 0);
+    }
+}
@@ -0,0 +1,184 @@
+use std::f64::consts::PI;
+
+use {Weights, HEPEUP, HEPRUP};
+
+/// Approximate recomputation of scale-variation weights
+///
+/// For events without stored scale variations, the weight for
+/// renormalisation and factorisation scales multiplied by given
+/// factors is estimated by rescaling the event weight `XWGTUP`. The
+/// strong coupling `AQCDUP` is evolved with leading-order running, and
+/// the parton densities of the incoming partons are replaced by the
+/// ones at the new factorisation scale. The scales are taken from the
+/// `<scales>` tag if present and from `SCALUP` otherwise.
+///
+/// This neglects the scale dependence of everything except the
+/// couplings and parton densities, so it is only exact at leading
+/// order.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # let (heprup, event): (lhef::HEPRUP, lhef::HEPEUP) = unimplemented!();
+/// # fn xfx(id: i32, x: f64, q: f64) -> f64 { unimplemented!() }
+/// // two powers of αs, parton densities x f(x, Q) from an external library
+/// let rescaling = lhef::ScaleRescaling::new(&heprup, 2, xfx);
+/// if let Some(weights) = rescaling.seven_point(&event) {
+///     println!("{}", weights);
+/// }
+/// ```
+pub struct ScaleRescaling<F> {
+    /// Power of the strong coupling in the matrix element
+    pub alphas_power: u32,
+    /// Number of active quark flavours in the running of the strong
+    /// coupling
+    pub flavours: u32,
+    /// Energies of the two beams in GeV
+    pub beam_energies: [f64; 2],
+    /// Parton densities `x f(x, Q)` for a parton ID, momentum fraction
+    /// `x`, and scale `Q` in GeV
+    pub pdf: F,
+}
+
+/// Scale factors `[mur, muf]` of the 7-point variation
+const SEVEN_POINT: [[f64; 2]; 7] = [
+    [1., 1.],
+    [2., 1.],
+    [0.5, 1.],
+    [1., 2.],
+    [1., 0.5],
+    [2., 2.],
+    [0.5, 0.5],
+];
+
+impl<F: Fn(i32, f64, f64) -> f64> ScaleRescaling<F> {
+    /// Set up rescaling with five active flavours and the beam energies
+    /// of the run
+    pub fn new(heprup: &HEPRUP, alphas_power: u32, pdf: F) -> ScaleRescaling<F> {
+        ScaleRescaling {
+            alphas_power,
+            flavours: 5,
+            beam_energies: heprup.beam_energies(),
+            pdf,
+        }
+    }
+
+    /// Estimate the event weight for scales multiplied by the given
+    /// factors
+    ///
+    /// Returns `None` if the event has no strong coupling, a
+    /// non-positive scale, or not exactly two incoming partons, or if
+    /// a parton density at the original scale vanishes.
+    pub fn weight(&self, event: &HEPEUP, mur_factor: f64, muf_factor: f64) -> Option<f64> {
+        let (mur, muf) = scales(event);
+        if event.AQCDUP <= 0. || mur <= 0. || muf <= 0. {
+            return None;
+        }
+        let alphas = running_alphas(event.AQCDUP, mur, mur * mur_factor, self.flavours);
+        let mut ratio = (alphas / event.AQCDUP).powi(self.alphas_power as i32);
+        let incoming: Vec<_> = (0..event.IDUP.len()).filter(|&i| event.ISTUP[i] == -1).collect();
+        if incoming.len() != 2 {
+            return None;
+        }
+        for i in incoming {
+            let p = &event.PUP[i];
+            let beam = if p[2] >= 0. { 0 } else { 1 };
+            let x = p[3] / self.beam_energies[beam];
+            let id = event.IDUP[i];
+            let old = (self.pdf)(id, x, muf);
+            if old == 0. {
+                return None;
+            }
+            ratio *= (self.pdf)(id, x, muf * muf_factor) / old;
+        }
+        Some(event.XWGTUP * ratio)
+    }
+
+    /// Estimate the weights for the given scale factors `[mur, muf]`
+    ///
+    /// The weights are labelled as `MUR<mur>_MUF<muf>`, which can be
+    /// classified with `WeightConvention::Sherpa`. Returns `None` under
+    /// the same conditions as `weight`.
+    pub fn weights(&self, event: &HEPEUP, factors: &[[f64; 2]]) -> Option<Weights> {
+        let mut weights = Weights::default();
+        for &[mur, muf] in factors {
+            let id = format!("MUR{}_MUF{}", mur, muf);
+            weights.set(&id, self.weight(event, mur, muf)?);
+        }
+        Some(weights)
+    }
+
+    /// Estimate the weights of the 7-point scale variation
+    ///
+    /// See `weights`.
+    pub fn seven_point(&self, event: &HEPEUP) -> Option<Weights> {
+        self.weights(event, &SEVEN_POINT)
+    }
+}
+
+/// Renormalisation and factorisation scale of an event
+fn scales(event: &HEPEUP) -> (f64, f64) {
+    let scales = event.scales.as_ref();
+    let mur = scales.and_then(|scales| scales.mur).unwrap_or(event.SCALUP);
+    let muf = scales.and_then(|scales| scales.muf).unwrap_or(event.SCALUP);
+    (mur, muf)
+}
+
+/// Evolve the strong coupling from scale `from` to `to` at leading order
+fn running_alphas(alphas: f64, from: f64, to: f64, flavours: u32) -> f64 {
+    let beta0 = (33. - 2. * flavours as f64) / (12. * PI);
+    alphas / (1. + alphas * beta0 * (to * to / (from * from)).ln())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Reader;
+
+    const EVENTS: &str = r#"<LesHouchesEvents version="3.0">
+<init>
+2212 2212 6500 6500 0 0 0 0 3 1
+1 0 1 1
+</init>
+<event>
+3 1 2 100 0 0.1
+21 -1 0 0 0 0 0 0 6.5e2 6.5e2 0 0 9
+2 -1 0 0 0 0 0 0 -1.3e3 1.3e3 0 0 9
+2 1 1 2 0 0 0 0 -6.5e2 1.95e3 0 0 9
+<scales muf="50" mur="200"/>
+</event>
+<event>
+3 1 2 100 0 0
+21 -1 0 0 0 0 0 0 6.5e2 6.5e2 0 0 9
+2 -1 0 0 0 0 0 0 -1.3e3 1.3e3 0 0 9
+2 1 1 2 0 0 0 0 -6.5e2 1.95e3 0 0 9
+</event>
+</LesHouchesEvents>
+"#;
+
+    #[test]
+    fn rescale() {
+        let mut reader = Reader::new(EVENTS.as_bytes()).unwrap();
+        let event = reader.event().unwrap().unwrap();
+        // x f(x, Q) = x Q for gluons and Q for quarks
+        let pdf = |id: i32, x: f64, q: f64| if id == 21 { x * q } else { q };
+        let rescaling = ScaleRescaling::new(reader.heprup(), 2, pdf);
+        assert_eq!(rescaling.weight(&event, 1., 1.), Some(2.));
+        let muf = rescaling.weight(&event, 1., 2.).unwrap();
+        assert!((muf - 8.).abs() < 1e-12);
+        let mur = rescaling.weight(&event, 2., 1.).unwrap();
+        let beta0 = 23. / (12. * PI);
+        let expected = 2. / (1. + 0.1 * beta0 * 4f64.ln()).powi(2);
+        assert!((mur - expected).abs() < 1e-12);
+        assert!(mur < 2.);
+
+        let weights = rescaling.seven_point(&event).unwrap();
+        assert_eq!(weights.len(), 7);
+        assert_eq!(weights.get("MUR1_MUF1"), Some(2.));
+        assert_eq!(weights.get("MUR2_MUF1"), Some(mur));
+        assert_eq!(weights.weights[6].id.as_deref(), Some("MUR0.5_MUF0.5"));
+
+        let event = reader.event().unwrap().unwrap();
+        assert_eq!(rescaling.weight(&event, 2., 2.), None);
+    }
+}
@@ -0,0 +1,187 @@
+use std::cmp::Ordering;
+
+use HEPEUP;
+
+/// A resonance found in an event
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub struct Resonance {
+    /// Particle ID of the resonance
+    pub id: i32,
+    /// Position of the resonance in the event, counting from 0 as in
+    /// `PUP`, or `None` if it was reconstructed from its decay products
+    pub particle: Option<usize>,
+    /// Positions of the decay products, counting from 0 as in `PUP`
+    pub decay_products: Vec<usize>,
+    /// Invariant mass in GeV
+    pub mass: f64,
+}
+
+/// Reconstruction of resonances
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # let event: lhef::HEPEUP = unimplemented!();
+/// // Z boson, either stored in the event or decaying to leptons
+/// let decays: &[&[i32]] = &[&[11, -11], &[13, -13]];
+/// if let Some(z) = event.find_resonance(23, 91.1876, decays) {
+///     println!("Z boson with mass {} GeV", z.mass);
+/// }
+/// ```
+impl HEPEUP {
+    /// Get the resonances stored in the event with any of the given
+    /// IDs, including the corresponding antiparticles
+    ///
+    /// The decay products are the particles that list the resonance
+    /// as one of their mothers.
+    pub fn stored_resonances(&self, ids: &[i32]) -> Vec<Resonance> {
+        let mut resonances = Vec::new();
+        for (i, &id) in self.IDUP.iter().enumerate() {
+            if !ids.iter().any(|wanted| wanted.abs() == id.abs()) {
+                continue;
+            }
+            let position = i as i32 + 1;
+            let decay_products = self
+                .MOTHUP
+                .iter()
+                .enumerate()
+                .filter(|&(_, mothers)| mothers.contains(&position))
+                .map(|(j, _)| j)
+                .collect();
+            resonances.push(Resonance {
+                id,
+                particle: Some(i),
+                decay_products,
+                mass: self.mass(i),
+            });
+        }
+        resonances
+    }
+
+    /// Reconstruct candidates for a resonance from final-state
+    /// particles
+    ///
+    /// Each entry of `decays` lists the particle IDs of a decay mode.
+    /// All combinations of final-state particles matching one of the
+    /// decay modes are returned, ordered by how close their invariant
+    /// mass is to `mass`.
+    pub fn reconstruct_resonance(&self, id: i32, mass: f64, decays: &[&[i32]]) -> Vec<Resonance> {
+        let mut candidates = Vec::new();
+        for decay in decays {
+            let mut combinations = Vec::new();
+            self.match_decay(decay, &mut Vec::new(), &mut combinations);
+            for decay_products in combinations {
+                let candidate_mass = self.invariant_mass(&decay_products);
+                candidates.push(Resonance {
+                    id,
+                    particle: None,
+                    decay_products,
+                    mass: candidate_mass,
+                });
+            }
+        }
+        candidates.sort_by(|a, b| compare_distance(a, b, mass));
+        candidates
+    }
+
+    /// Find a resonance, preferring one stored in the event
+    ///
+    /// If there are stored resonances with the ID `id` or its
+    /// antiparticle, the one with the mass closest to `mass` is
+    /// returned. Otherwise, the best candidate reconstructed from the
+    /// given decay modes is returned, see `reconstruct_resonance`.
+    pub fn find_resonance(&self, id: i32, mass: f64, decays: &[&[i32]]) -> Option<Resonance> {
+        let stored = self.stored_resonances(&[id]);
+        let best_stored = stored.into_iter().min_by(|a, b| compare_distance(a, b, mass));
+        best_stored.or_else(|| self.reconstruct_resonance(id, mass, decays).into_iter().next())
+    }
+
+    /// Find all sets of distinct final-state particles with the IDs in
+    /// `decay`
+    fn match_decay(&self, decay: &[i32], chosen: &mut Vec<usize>, result: &mut Vec<Vec<usize>>) {
+        let (&id, rest) = match decay.split_first() {
+            Some(split) => split,
+            None => {
+                let mut combination = chosen.clone();
+                combination.sort_unstable();
+                if !result.contains(&combination) {
+                    result.push(combination);
+                }
+                return;
+            }
+        };
+        for i in 0..self.IDUP.len() {
+            if self.ISTUP[i] == 1 && self.IDUP[i] == id && !chosen.contains(&i) {
+                chosen.push(i);
+                self.match_decay(rest, chosen, result);
+                chosen.pop();
+            }
+        }
+    }
+}
+
+/// Compare how close the masses of two resonances are to `mass`
+fn compare_distance(a: &Resonance, b: &Resonance, mass: f64) -> Ordering {
+    let da = (a.mass - mass).abs();
+    let db = (b.mass - mass).abs();
+    da.partial_cmp(&db).unwrap_or(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod tests {
+    use Reader;
+
+    const EVENTS: &str = r#"<LesHouchesEvents version="1.0">
+<init>
+2212 2212 6500 6500 0 0 0 0 3 1
+1 0 1 1
+</init>
+<event>
+5 1 1 10 0 0.1
+21 -1 0 0 0 0 0 0 6e1 6e1 0 0 9
+21 -1 0 0 0 0 0 0 -6e1 6e1 0 0 9
+25 2 1 2 0 0 0 0 0 1.2e2 1.2e2 0 9
+22 1 3 3 0 0 0 0 6e1 6e1 0 0 9
+22 1 3 3 0 0 0 0 -6e1 6e1 0 0 9
+</event>
+<event>
+5 1 1 10 0 0.1
+21 -1 0 0 0 0 0 0 6e1 6e1 0 0 9
+21 -1 0 0 0 0 0 0 -6e1 6e1 0 0 9
+13 1 1 2 0 0 0 0 4.5e1 4.5e1 0 0 9
+-13 1 1 2 0 0 0 0 -4.5e1 4.5e1 0 0 9
+22 1 1 2 0 0 3e1 0 0 3e1 0 0 9
+</event>
+</LesHouchesEvents>
+"#;
+
+    #[test]
+    fn stored() {
+        let mut reader = Reader::new(EVENTS.as_bytes()).unwrap();
+        let event = reader.event().unwrap().unwrap();
+        let higgs = event.stored_resonances(&[25]);
+        assert_eq!(higgs.len(), 1);
+        assert_eq!(higgs[0].particle, Some(2));
+        assert_eq!(higgs[0].decay_products, [3, 4]);
+        assert_eq!(higgs[0].mass, 120.);
+        let found = event.find_resonance(25, 125., &[&[22, 22]]).unwrap();
+        assert_eq!(found, higgs[0]);
+    }
+
+    #[test]
+    fn reconstructed() {
+        let mut reader = Reader::new(EVENTS.as_bytes()).unwrap();
+        reader.event().unwrap();
+        let event = reader.event().unwrap().unwrap();
+        assert!(event.stored_resonances(&[23]).is_empty());
+        let decays: &[&[i32]] = &[&[11, -11], &[13, -13], &[13, -13, 22]];
+        let candidates = event.reconstruct_resonance(23, 91.1876, decays);
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(candidates[0].decay_products, [2, 3]);
+        assert_eq!(candidates[0].mass, 90.);
+        let z = event.find_resonance(23, 91.1876, decays).unwrap();
+        assert_eq!(z, candidates[0]);
+        assert_eq!(event.find_resonance(23, 91.1876, &[&[11, -11]]), None);
+    }
+}
@@ -0,0 +1,109 @@
+use std::borrow::Cow;
+use std::error;
+
+use fields::Fields;
+use {split_event_text, split_line, strip_event_tags, tags, EventContext, ParseError, EVENT_TAG};
+
+/// How the optional information `HEPEUP::info` of events is retained,
+/// see `ReaderBuilder::info_retention`
+///
+/// The information can span many lines, so copying it for every event
+/// is a noticeable cost for pipelines that never look at it. Tags
+/// with a dedicated representation in `HEPEUP`, like `<weights>`, are
+/// parsed regardless of this setting.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+pub enum InfoRetention {
+    /// Discard the information
+    Drop,
+    /// Keep the text of the most recent block in the reader, from
+    /// which the information can be borrowed with `Reader::block_info`
+    Borrow,
+    /// Copy the information into `HEPEUP::info`
+    #[default]
+    Own,
+}
+
+/// Get the information of the events in the text of a block
+///
+/// `text` is everything between the opening and closing tag of an
+/// `<event>`, or of an `<eventgroup>` if `group` is set. Tags with a
+/// dedicated representation in `HEPEUP` are removed, which is the only
+/// case in which the information is copied.
+pub(crate) fn block_info<'a>(
+    text: &'a str,
+    group: bool,
+    context: &EventContext,
+) -> Result<Vec<Cow<'a, str>>, Box<dyn error::Error>> {
+    if !group {
+        return Ok(vec![strip_event_tags(event_info(text)?, context)]);
+    }
+    let mut info = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let line = split_line(&mut rest);
+        if tags::opening_tag(line, EVENT_TAG).is_none() {
+            return Err(Box::new(ParseError::BadEventStart(line.to_owned())));
+        }
+        let (event_text, after) = split_event_text(rest)?;
+        rest = after;
+        info.push(strip_event_tags(event_info(event_text)?, context));
+    }
+    Ok(info)
+}
+
+/// Get the text following the particle lines of an event
+fn event_info(mut text: &str) -> Result<&str, Box<dyn error::Error>> {
+    let nparticles: usize = Fields::new(split_line(&mut text)).parse(|| "NUP".to_owned())?;
+    for _ in 0..nparticles {
+        split_line(&mut text);
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_util::{MockEvent, SyntheticEvents};
+    use {Reader, ReaderBuilder, Writer};
+
+    #[test]
+    fn retention() {
+        let events = [
+            MockEvent::dijet().with_info("# first\n").with_extra_weight("a", 1.).build(),
+            MockEvent::drell_yan().with_info("# second\n").build(),
+        ];
+        let mut output = Vec::new();
+        {
+            let mut writer = Writer::new(&mut output, "3.0").unwrap();
+            writer.heprup(&SyntheticEvents::new().heprup()).unwrap();
+            writer.event(&events[0]).unwrap();
+            writer.event_group(&events).unwrap();
+            writer.finish().unwrap();
+        }
+
+        let mut reader = Reader::new(output.as_slice()).unwrap();
+        assert_eq!(reader.event().unwrap().unwrap().info, "# first\n");
+        assert!(reader.block_info().unwrap().is_empty());
+
+        for retention in [InfoRetention::Drop, InfoRetention::Borrow] {
+            let mut reader = ReaderBuilder::new()
+                .info_retention(retention)
+                .build(output.as_slice())
+                .unwrap();
+            let event = reader.event().unwrap().unwrap();
+            assert!(event.info.is_empty());
+            assert_eq!(event.weights, events[0].weights);
+            let info = reader.block_info().unwrap();
+            if retention == InfoRetention::Borrow {
+                assert_eq!(info, ["# first\n"]);
+                reader.block().unwrap();
+                let info = reader.block_info().unwrap();
+                assert_eq!(info, ["# first\n", "# second\n"]);
+                assert!(matches!(info[1], Cow::Borrowed(_)));
+            } else {
+                assert!(info.is_empty());
+            }
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use std::error;
+
+use {Weights, HEPEUP};
+
+/// A closure that can add or modify the additional weights of an event
+pub(crate) type WeightHook =
+    Box<dyn FnMut(&HEPEUP, &mut Weights) -> Result<(), Box<dyn error::Error>> + Send + Sync>;
+
+/// Pass the weights of `event` to `hook` and store the result
+///
+/// Events without weights get a new, initially empty set of weights,
+/// which is kept only if the hook adds any.
+pub(crate) fn apply(
+    hook: &mut WeightHook,
+    event: &mut HEPEUP,
+) -> Result<(), Box<dyn error::Error>> {
+    let had_weights = event.weights.is_some();
+    let mut weights = event.weights.take().unwrap_or_default();
+    let result = hook(event, &mut weights);
+    if had_weights || !weights.is_empty() {
+        event.weights = Some(weights);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use {ReaderBuilder, Writer};
+
+    const EVENTS: &str = r#"<LesHouchesEvents version="3.0">
+<init>
+2212 2212 6500 6500 0 0 0 0 3 1
+1 0 1 1
+</init>
+<event>
+1 1 2 10 0 0.1
+21 -1 0 0 0 0 0 0 1e2 1e2 0 0 9
+<rwgt>
+<wgt id="a">3</wgt>
+</rwgt>
+</event>
+<eventgroup>
+<event>
+1 1 4 10 0 0.1
+21 -1 0 0 0 0 0 0 1e2 1e2 0 0 9
+</event>
+</eventgroup>
+</LesHouchesEvents>
+"#;
+
+    #[test]
+    fn reader_hook() {
+        let mut reader = ReaderBuilder::new()
+            .weight_hook(|event, weights| {
+                weights.set("double", 2. * event.XWGTUP);
+                if let Some(a) = weights.get("a") {
+                    weights.set("a", -a);
+                }
+                Ok(())
+            })
+            .build(EVENTS.as_bytes())
+            .unwrap();
+        let event = reader.event().unwrap().unwrap();
+        let weights = event.weights.unwrap();
+        assert_eq!(weights.get("a"), Some(-3.));
+        assert_eq!(weights.get("double"), Some(4.));
+        assert_eq!(weights.len(), 2);
+        let event = reader.event().unwrap().unwrap();
+        assert_eq!(event.weights.unwrap().get("double"), Some(8.));
+    }
+
+    #[test]
+    fn writer_hook() {
+        let mut reader = ReaderBuilder::new().build(EVENTS.as_bytes()).unwrap();
+        let mut output = Vec::new();
+        {
+            let mut writer = Writer::new(&mut output, "3.0").unwrap();
+            writer.set_weight_hook(|_, weights| {
+                if weights.is_empty() {
+                    return Err("no weights".into());
+                }
+                weights.set("b", 1.);
+                Ok(())
+            });
+            writer.heprup(reader.heprup()).unwrap();
+            let event = reader.event().unwrap().unwrap();
+            writer.event(&event).unwrap();
+            let event = reader.event().unwrap().unwrap();
+            assert!(writer.event(&event).is_err());
+            writer.finish().unwrap();
+        }
+        let mut reread = ReaderBuilder::new().build(output.as_slice()).unwrap();
+        let weights = reread.event().unwrap().unwrap().weights.unwrap();
+        assert_eq!(weights.values(), [3., 1.]);
+        assert_eq!(reread.event().unwrap(), None);
+    }
+
+    #[test]
+    fn failing_hook() {
+        let mut reader = ReaderBuilder::new()
+            .weight_hook(|_, _| Err("failed".into()))
+            .build(EVENTS.as_bytes())
+            .unwrap();
+        let err = reader.event().unwrap_err();
+        assert_eq!(err.to_string(), "failed");
+    }
+}
@@ -0,0 +1,224 @@
+use std::fmt;
+use std::str::FromStr;
+
+use tags::{self, Tag};
+
+pub(crate) const RUN_CARD_TAG: &str = "MGRunCard";
+
+/// Settings from a MadGraph `run_card.dat`
+///
+/// MadGraph copies the run card into the `<MGRunCard>` tag of the
+/// header. Each setting is written as `value = key ! comment`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct RunCard {
+    /// Settings in the order in which they appear
+    pub entries: Vec<RunCardEntry>,
+}
+
+/// A single run card setting
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub struct RunCardEntry {
+    /// Name of the setting, converted to lower case
+    pub key: String,
+    /// Value of the setting
+    pub value: String,
+    /// Comment at the end of the line
+    pub comment: Option<String>,
+}
+
+impl RunCard {
+    /// Parse the text of a run card
+    ///
+    /// Comment lines starting with `#` and lines without a `=` are
+    /// ignored.
+    pub fn parse(text: &str) -> RunCard {
+        let mut entries = Vec::new();
+        for line in text.lines() {
+            if line.trim_start().starts_with('#') {
+                continue;
+            }
+            let (setting, comment) = match line.find('!') {
+                Some(pos) => (&line[..pos], Some(line[pos + 1..].trim().to_owned())),
+                None => (line, None),
+            };
+            let mut parts = setting.splitn(2, '=');
+            let value = parts.next().unwrap().trim();
+            let key = match parts.next() {
+                Some(key) => key.trim(),
+                None => continue,
+            };
+            if key.is_empty() {
+                continue;
+            }
+            entries.push(RunCardEntry {
+                key: key.to_lowercase(),
+                value: value.to_owned(),
+                comment,
+            });
+        }
+        RunCard { entries }
+    }
+
+    /// Get the value of a setting as text, ignoring the case of `key`
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|entry| entry.key.eq_ignore_ascii_case(key))
+            .map(|entry| entry.value.as_str())
+    }
+
+    /// Get the value of a setting converted to type `T`
+    ///
+    /// Returns `None` if the setting is missing or cannot be converted.
+    /// Fortran-style exponents like `1d5` are accepted for numbers.
+    pub fn get_as<T: FromStr>(&self, key: &str) -> Option<T> {
+        let value = self.get(key)?;
+        value
+            .parse()
+            .ok()
+            .or_else(|| value.replace(['d', 'D'], "e").parse().ok())
+    }
+
+    /// Get the value of a logical setting
+    ///
+    /// Accepts both Python-style (`True`) and Fortran-style (`.true.`,
+    /// `T`) spellings.
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        let value = self.get(key)?.trim_matches('.').to_lowercase();
+        match value.as_str() {
+            "true" | "t" | "1" => Some(true),
+            "false" | "f" | "0" => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Number of requested events
+    pub fn nevents(&self) -> Option<u64> {
+        self.get_as("nevents")
+    }
+
+    /// Random seed
+    pub fn iseed(&self) -> Option<i64> {
+        self.get_as("iseed")
+    }
+
+    /// Energy of the first beam in GeV
+    pub fn ebeam1(&self) -> Option<f64> {
+        self.get_as("ebeam1")
+    }
+
+    /// Energy of the second beam in GeV
+    pub fn ebeam2(&self) -> Option<f64> {
+        self.get_as("ebeam2")
+    }
+
+    /// Type of the first beam
+    pub fn lpp1(&self) -> Option<i32> {
+        self.get_as("lpp1")
+    }
+
+    /// Type of the second beam
+    pub fn lpp2(&self) -> Option<i32> {
+        self.get_as("lpp2")
+    }
+
+    /// PDF set label, e.g. `lhapdf`
+    pub fn pdlabel(&self) -> Option<&str> {
+        self.get("pdlabel")
+    }
+
+    /// LHAPDF ID of the PDF set
+    pub fn lhaid(&self) -> Option<i32> {
+        self.get_as("lhaid")
+    }
+
+    /// Minimum transverse momentum of jets in GeV
+    pub fn ptj(&self) -> Option<f64> {
+        self.get_as("ptj")
+    }
+
+    /// Maximum absolute rapidity of jets
+    pub fn etaj(&self) -> Option<f64> {
+        self.get_as("etaj")
+    }
+
+    /// Minimum distance between jets
+    pub fn drjj(&self) -> Option<f64> {
+        self.get_as("drjj")
+    }
+
+    /// Matching scheme: 0 for none, 1 for MLM, 2 for CKKW
+    pub fn ickkw(&self) -> Option<i32> {
+        self.get_as("ickkw")
+    }
+
+    /// Merging scale in GeV
+    pub fn xqcut(&self) -> Option<f64> {
+        self.get_as("xqcut")
+    }
+
+    fn from_tag(tag: &Tag) -> RunCard {
+        RunCard::parse(tag.content())
+    }
+}
+
+/// Parse the MadGraph run card in the header
+pub(crate) fn parse_run_card(header: &str) -> Option<RunCard> {
+    tags::header_tag(header, &[RUN_CARD_TAG]).map(|tag| RunCard::from_tag(&tag))
+}
+
+/// Formats the settings in the run card format
+impl fmt::Display for RunCard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for entry in &self.entries {
+            write!(f, " {} = {}", entry.value, entry.key)?;
+            if let Some(ref comment) = entry.comment {
+                write!(f, " ! {}", comment)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_format() {
+        let text = "#*********************\n\
+                    # run_card.dat = MadEvent\n\
+                    \x20 tag_1     = run_tag ! name of the run\n\
+                    \x20 1000 = nevents ! Number of unweighted events requested\n\
+                    \x20    7000.0     = EBEAM1  ! beam 1 total energy in GeV\n\
+                    \x20 True = fixed_ren_scale  ! if .true. use fixed ren scale\n\
+                    \x20 .false. = use_syst\n\
+                    \x20 1d1 = ptj       ! minimum pt for the jets\n\
+                    \x20 no setting here\n";
+        let card = RunCard::parse(text);
+        assert_eq!(card.entries.len(), 6);
+        assert_eq!(card.get("run_tag"), Some("tag_1"));
+        assert_eq!(card.nevents(), Some(1000));
+        assert_eq!(card.ebeam1(), Some(7000.));
+        assert_eq!(card.ebeam2(), None);
+        assert_eq!(card.ptj(), Some(10.));
+        assert_eq!(card.get_bool("fixed_ren_scale"), Some(true));
+        assert_eq!(card.get_bool("use_syst"), Some(false));
+        assert_eq!(card.get_bool("run_tag"), None);
+        assert_eq!(card.get_as::<f64>("run_tag"), None);
+        assert_eq!(RunCard::parse(&card.to_string()), card);
+    }
+
+    #[test]
+    fn read_file() {
+        let reader = ::Reader::open("test_data/2j.lhe.gz").unwrap();
+        let run_card = reader.run_card().unwrap();
+        assert_eq!(run_card.ebeam1(), Some(7000.));
+        assert_eq!(run_card.nevents(), Some(1000));
+        assert_eq!(run_card.ptj(), Some(30.));
+        assert_eq!(run_card.get_bool("auto_ptj_mjj"), Some(true));
+    }
+}
@@ -0,0 +1,177 @@
+use std::error;
+use std::fmt;
+
+use tags::{self, Tag};
+use {parse, Attributes};
+
+pub(crate) const SCALES_TAG: &str = "scales";
+const SCALE_TAG: &str = "scale";
+
+/// Scale information from the LHEF 3.0 `<scales>` tag
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Scales {
+    /// Factorisation scale in GeV
+    pub muf: Option<f64>,
+    /// Renormalisation scale in GeV
+    pub mur: Option<f64>,
+    /// Starting scale for the parton shower in GeV
+    pub mups: Option<f64>,
+    /// Per-particle scale attributes like `pt_start_3`
+    pub particle_scales: Vec<ParticleScale>,
+    /// Scales given in nested `<scale>` tags
+    pub scales: Vec<Scale>,
+    /// Any other attributes
+    pub attr: Attributes,
+}
+
+/// A scale attribute of the form `<name>_<particle>`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub struct ParticleScale {
+    /// Name of the scale without the particle index, e.g. `pt_start`
+    pub name: String,
+    /// Index of the particle, starting from 1
+    pub particle: usize,
+    /// Value of the scale in GeV
+    pub value: f64,
+}
+
+/// A scale from a nested `<scale>` tag
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub struct Scale {
+    /// Scale type, e.g. `veto` or `start`
+    pub stype: String,
+    /// Index of the emitting particle, starting from 1
+    pub pos: Option<usize>,
+    /// Types of emitted particles, given as PDG IDs or names like `QCD`
+    pub etype: Option<String>,
+    /// Value of the scale in GeV
+    pub value: f64,
+}
+
+impl Scales {
+    /// Get the scale attribute with the given name for a particle,
+    /// where the particle index starts from 1
+    pub fn particle_scale(&self, name: &str, particle: usize) -> Option<f64> {
+        self.particle_scales
+            .iter()
+            .find(|s| s.name == name && s.particle == particle)
+            .map(|s| s.value)
+    }
+
+    pub(crate) fn from_tag(tag: &Tag) -> Result<Scales, Box<dyn error::Error>> {
+        let mut scales = Scales::default();
+        for &(key, value) in tag.attributes() {
+            let value_f64 = || parse::<f64>(key, Some(value.trim()));
+            match key {
+                "muf" => scales.muf = Some(value_f64()?),
+                "mur" => scales.mur = Some(value_f64()?),
+                "mups" => scales.mups = Some(value_f64()?),
+                _ => match split_particle_index(key) {
+                    Some((name, particle)) => {
+                        scales.particle_scales.push(ParticleScale {
+                            name: name.to_owned(),
+                            particle,
+                            value: value_f64()?,
+                        })
+                    }
+                    None => scales.attr.set(key, value),
+                },
+            }
+        }
+        for (_, scale) in tags::tags(tag.content()) {
+            if scale.name() != SCALE_TAG {
+                continue;
+            }
+            let stype = scale.attribute("stype").unwrap_or("veto").to_owned();
+            let pos = scale
+                .attribute("pos")
+                .map(|pos| parse::<usize>("pos", Some(pos.trim())))
+                .transpose()?;
+            let etype = scale.attribute("etype").map(|e| e.to_owned());
+            let value = parse::<f64>("scale", Some(scale.content().trim()))?;
+            scales.scales.push(Scale { stype, pos, etype, value });
+        }
+        Ok(scales)
+    }
+}
+
+fn split_particle_index(key: &str) -> Option<(&str, usize)> {
+    let pos = key.rfind('_')?;
+    let particle = key[pos + 1..].parse().ok()?;
+    Some((&key[..pos], particle))
+}
+
+/// Formats the scales as a `<scales>` tag
+impl fmt::Display for Scales {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<{}", SCALES_TAG)?;
+        let named = [("muf", self.muf), ("mur", self.mur), ("mups", self.mups)];
+        for &(name, value) in &named {
+            if let Some(value) = value {
+                write!(f, " {}=\"{:e}\"", name, value)?;
+            }
+        }
+        for scale in &self.particle_scales {
+            write!(f, " {}_{}=\"{:e}\"", scale.name, scale.particle, scale.value)?;
+        }
+        write!(f, "{}", self.attr)?;
+        if self.scales.is_empty() {
+            return write!(f, "/>");
+        }
+        writeln!(f, ">")?;
+        for scale in &self.scales {
+            write!(f, "<{} stype=\"{}\"", SCALE_TAG, scale.stype)?;
+            if let Some(pos) = scale.pos {
+                write!(f, " pos=\"{}\"", pos)?;
+            }
+            if let Some(ref etype) = scale.etype {
+                write!(f, " etype=\"{}\"", etype)?;
+            }
+            writeln!(f, ">{:e}</{}>", scale.value, SCALE_TAG)?;
+        }
+        write!(f, "</{}>", SCALES_TAG)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_scales(text: &str) -> Scales {
+        let (_, tag) = tags::tags(text).next().unwrap();
+        Scales::from_tag(&tag).unwrap()
+    }
+
+    #[test]
+    fn parse_and_format() {
+        let text = "<scales muf=\"91.2\" mur='45.6' pt_start_3=\"20\" foo=\"bar\">\n\
+                    <scale stype=\"veto\" pos=\"3\" etype=\"QCD\">15.5</scale>\n\
+                    </scales>";
+        let scales = parse_scales(text);
+        assert_eq!(scales.muf, Some(91.2));
+        assert_eq!(scales.mur, Some(45.6));
+        assert_eq!(scales.mups, None);
+        assert_eq!(scales.particle_scale("pt_start", 3), Some(20.));
+        assert_eq!(scales.attr.get("foo"), Some("bar"));
+        assert_eq!(
+            scales.scales,
+            [Scale {
+                stype: "veto".to_owned(),
+                pos: Some(3),
+                etype: Some("QCD".to_owned()),
+                value: 15.5
+            }]
+        );
+        assert_eq!(parse_scales(&scales.to_string()), scales);
+    }
+
+    #[test]
+    fn self_closing() {
+        let scales = parse_scales("<scales mups=\"-1\"/>");
+        assert_eq!(scales.mups, Some(-1.));
+        assert_eq!(scales.to_string(), "<scales mups=\"-1e0\"/>");
+    }
+}
@@ -0,0 +1,111 @@
+use HEPEUP;
+
+/// Selection of particles in an event by status and particle ID
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # let event: lhef::HEPEUP = unimplemented!();
+/// // final-state b quarks
+/// let b_quarks = lhef::ParticleSelection::final_state().with_ids(&[5]);
+/// let ht_b = event.ht_with(&b_quarks);
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub struct ParticleSelection {
+    /// Accepted status codes `ISTUP`, or all if empty
+    pub status: Vec<i32>,
+    /// Accepted particle IDs, including the corresponding
+    /// antiparticles, or all if empty
+    pub ids: Vec<i32>,
+}
+
+/// Quarks other than top, and gluons
+const PARTON_IDS: &[i32] = &[1, 2, 3, 4, 5, 21];
+
+impl ParticleSelection {
+    /// Select all particles
+    pub fn all() -> ParticleSelection {
+        ParticleSelection {
+            status: Vec::new(),
+            ids: Vec::new(),
+        }
+    }
+
+    /// Select all final-state particles, i.e. those with status 1
+    pub fn final_state() -> ParticleSelection {
+        ParticleSelection {
+            status: vec![1],
+            ids: Vec::new(),
+        }
+    }
+
+    /// Select final-state quarks other than the top quark, and gluons
+    pub fn partons() -> ParticleSelection {
+        ParticleSelection::final_state().with_ids(PARTON_IDS)
+    }
+
+    /// Restrict the selection to the given status codes
+    pub fn with_status(mut self, status: &[i32]) -> ParticleSelection {
+        self.status = status.to_vec();
+        self
+    }
+
+    /// Restrict the selection to the given particle IDs
+    ///
+    /// The corresponding antiparticles are selected as well.
+    pub fn with_ids(mut self, ids: &[i32]) -> ParticleSelection {
+        self.ids = ids.to_vec();
+        self
+    }
+
+    /// Check whether particle `i` of `event` is selected
+    ///
+    /// Particles are indexed from 0 as in `PUP`.
+    pub fn matches(&self, event: &HEPEUP, i: usize) -> bool {
+        let status_ok = self.status.is_empty() || self.status.contains(&event.ISTUP[i]);
+        let id = event.IDUP[i].abs();
+        let id_ok = self.ids.is_empty() || self.ids.iter().any(|selected| selected.abs() == id);
+        status_ok && id_ok
+    }
+
+    /// Get the indices of the selected particles in `event`
+    pub fn indices(&self, event: &HEPEUP) -> Vec<usize> {
+        (0..event.IDUP.len()).filter(|&i| self.matches(event, i)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Reader;
+
+    const EVENT: &str = r#"<LesHouchesEvents version="1.0">
+<init>
+2212 2212 6500 6500 0 0 0 0 3 1
+1 0 1 1
+</init>
+<event>
+5 1 1 10 0 0.1
+21 -1 0 0 0 0 0 0 5e2 5e2 0 0 9
+-5 1 1 1 0 0 3e1 -4e1 0 5e1 0 0 9
+6 2 1 1 0 0 0 6e1 8e1 2e2 1.73e2 0 9
+21 1 1 1 0 0 0 6e1 8e1 2e2 0 0 9
+11 1 3 3 0 0 0 6e1 8e1 2e2 0 0 9
+</event>
+</LesHouchesEvents>
+"#;
+
+    #[test]
+    fn select() {
+        let mut reader = Reader::new(EVENT.as_bytes()).unwrap();
+        let event = reader.event().unwrap().unwrap();
+        assert_eq!(ParticleSelection::all().indices(&event), [0, 1, 2, 3, 4]);
+        assert_eq!(ParticleSelection::final_state().indices(&event), [1, 3, 4]);
+        assert_eq!(ParticleSelection::partons().indices(&event), [1, 3]);
+        let b = ParticleSelection::all().with_ids(&[5]);
+        assert_eq!(b.indices(&event), [1]);
+        let gluons = ParticleSelection::all().with_ids(&[21]).with_status(&[-1]);
+        assert_eq!(gluons.indices(&event), [0]);
+    }
+}
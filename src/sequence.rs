@@ -0,0 +1,227 @@
+use std::collections::BTreeMap;
+use std::error;
+use std::fmt;
+
+use {EventSink, HEPEUP, HEPRUP};
+
+/// Writer restoring the original order of events processed in parallel
+///
+/// Events are passed to `event` together with their index, counting
+/// from 0, in any order. Each event is forwarded to the sink as soon as
+/// all events with smaller indices have been forwarded, so the output
+/// is the same regardless of the order in which parallel workers
+/// finish.
+///
+/// Events that arrive early are kept in a buffer with a fixed capacity.
+/// If it is full, `event` fails with `ReorderBufferFull` instead of
+/// using more memory. The error hands back the event, which can be
+/// passed again once the events preceding the buffered ones have been
+/// written. The capacity should be at least the number of events in
+/// flight.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # let heprup: lhef::HEPRUP = unimplemented!();
+/// # let results: std::sync::mpsc::Receiver<(u64, lhef::HEPEUP)> = unimplemented!();
+/// let file = std::fs::File::create("events.lhe").unwrap();
+/// let writer = lhef::Writer::new(std::io::BufWriter::new(file), "1.0").unwrap();
+/// let mut writer = lhef::SequencedWriter::new(writer, 1000);
+/// writer.heprup(&heprup).unwrap();
+/// for (index, event) in results {
+///     writer.event(index, event).unwrap();
+/// }
+/// writer.finish().unwrap();
+/// ```
+pub struct SequencedWriter<S: EventSink> {
+    sink: S,
+    next: u64,
+    capacity: usize,
+    pending: BTreeMap<u64, HEPEUP>,
+}
+
+impl<S: EventSink> SequencedWriter<S> {
+    /// Create a writer buffering at most `capacity` events
+    pub fn new(sink: S, capacity: usize) -> SequencedWriter<S> {
+        SequencedWriter {
+            sink,
+            next: 0,
+            capacity,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    /// Write a header
+    pub fn header(&mut self, header: &str) -> Result<(), Box<dyn error::Error>> {
+        self.sink.header(header)
+    }
+
+    /// Write the run information
+    pub fn heprup(&mut self, heprup: &HEPRUP) -> Result<(), Box<dyn error::Error>> {
+        self.sink.heprup(heprup)
+    }
+
+    /// Write the event with the given index once all preceding events
+    /// have been written
+    ///
+    /// If the event cannot be buffered, the error is a
+    /// `ReorderBufferFull` containing the event.
+    pub fn event(&mut self, index: u64, event: HEPEUP) -> Result<(), Box<dyn error::Error>> {
+        if index < self.next || self.pending.contains_key(&index) {
+            return Err(Box::new(SequenceError::Duplicate(index)));
+        }
+        if index > self.next {
+            if self.pending.len() >= self.capacity {
+                return Err(Box::new(ReorderBufferFull {
+                    index,
+                    event,
+                    capacity: self.capacity,
+                    next: self.next,
+                }));
+            }
+            self.pending.insert(index, event);
+            return Ok(());
+        }
+        self.sink.event(&event)?;
+        self.next += 1;
+        while let Some(event) = self.pending.remove(&self.next) {
+            self.sink.event(&event)?;
+            self.next += 1;
+        }
+        Ok(())
+    }
+
+    /// Index of the next event to be written
+    pub fn next_index(&self) -> u64 {
+        self.next
+    }
+
+    /// Number of events waiting for their predecessors
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Close the output
+    ///
+    /// This fails if any events are still waiting, i.e. if some index
+    /// was never passed to `event`.
+    pub fn finish(&mut self) -> Result<(), Box<dyn error::Error>> {
+        if !self.pending.is_empty() {
+            return Err(Box::new(SequenceError::Missing(self.next)));
+        }
+        self.sink.finish()
+    }
+
+    /// Get the underlying sink
+    pub fn into_inner(self) -> S {
+        self.sink
+    }
+}
+
+/// Error returned by `SequencedWriter::event` if the event arrived
+/// early and the reorder buffer is full
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # let mut writer: lhef::SequencedWriter<lhef::Writer<std::fs::File>> = unimplemented!();
+/// # let (index, event): (u64, lhef::HEPEUP) = unimplemented!();
+/// if let Err(err) = writer.event(index, event) {
+///     let full = err.downcast::<lhef::ReorderBufferFull>().unwrap();
+///     // write event `full.next` first, then pass `full.event` again
+/// }
+/// ```
+#[derive(PartialEq, Debug, Clone)]
+pub struct ReorderBufferFull {
+    /// Index of the rejected event
+    pub index: u64,
+    /// The rejected event
+    pub event: HEPEUP,
+    /// Capacity of the buffer
+    pub capacity: usize,
+    /// Index of the event the buffered events are waiting for
+    pub next: u64,
+}
+
+impl fmt::Display for ReorderBufferFull {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Reorder buffer is full with {} events waiting for event {}",
+            self.capacity, self.next
+        )
+    }
+}
+
+impl error::Error for ReorderBufferFull {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SequenceError {
+    Duplicate(u64),
+    Missing(u64),
+}
+
+impl fmt::Display for SequenceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::SequenceError::*;
+        match *self {
+            Duplicate(index) => write!(f, "Event {} was already written", index),
+            Missing(index) => write!(f, "Event {} is missing", index),
+        }
+    }
+}
+
+impl error::Error for SequenceError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_util::MockEvent;
+
+    #[derive(Default)]
+    struct Weights(Vec<f64>);
+
+    impl EventSink for Weights {
+        fn heprup(&mut self, _heprup: &HEPRUP) -> Result<(), Box<dyn error::Error>> {
+            Ok(())
+        }
+
+        fn event(&mut self, event: &HEPEUP) -> Result<(), Box<dyn error::Error>> {
+            self.0.push(event.XWGTUP);
+            Ok(())
+        }
+    }
+
+    fn event(index: u64) -> HEPEUP {
+        MockEvent::dijet().with_weight(index as f64).build()
+    }
+
+    #[test]
+    fn reorder() {
+        let mut writer = SequencedWriter::new(Weights::default(), 2);
+        for &index in &[2, 1, 0, 3, 5, 4] {
+            writer.event(index, event(index)).unwrap();
+        }
+        assert_eq!(writer.pending(), 0);
+        assert_eq!(writer.next_index(), 6);
+        assert!(writer.event(3, event(3)).is_err());
+        writer.finish().unwrap();
+        assert_eq!(writer.into_inner().0, [0., 1., 2., 3., 4., 5.]);
+    }
+
+    #[test]
+    fn bounded() {
+        let mut writer = SequencedWriter::new(Weights::default(), 2);
+        writer.event(1, event(1)).unwrap();
+        assert!(writer.event(1, event(1)).is_err());
+        writer.event(2, event(2)).unwrap();
+        let full = writer.event(3, event(3)).unwrap_err();
+        let full = full.downcast::<ReorderBufferFull>().unwrap();
+        assert_eq!((full.index, full.capacity, full.next), (3, 2, 0));
+        assert!(writer.finish().is_err());
+        writer.event(0, event(0)).unwrap();
+        writer.event(full.index, full.event).unwrap();
+        writer.finish().unwrap();
+        assert_eq!(writer.into_inner().0, [0., 1., 2., 3.]);
+    }
+}
@@ -0,0 +1,300 @@
+use std::f64::consts::PI;
+
+use {ParticleSelection, HEPEUP};
+
+/// Event-shape observables
+///
+/// These are mainly used for lepton collisions, where they are
+/// computed from the momenta of all final-state particles in the
+/// centre-of-mass frame.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub struct EventShapes {
+    /// Thrust, between 1/2 for spherical and 1 for pencil-like events
+    pub thrust: f64,
+    /// Unit vector along the thrust axis
+    pub thrust_axis: [f64; 3],
+    /// Sphericity, between 0 for pencil-like and 1 for spherical events
+    pub sphericity: f64,
+    /// Spherocity, between 0 for pencil-like and 1 for spherical events
+    ///
+    /// The minimisation over axes is performed numerically, so the
+    /// result is accurate to about 10⁻⁶.
+    pub spherocity: f64,
+}
+
+impl EventShapes {
+    /// Compute the event shapes from three-momenta
+    ///
+    /// All observables are 0 if there are no momenta.
+    pub fn new(momenta: &[[f64; 3]]) -> EventShapes {
+        let (thrust, thrust_axis) = thrust(momenta);
+        EventShapes {
+            thrust,
+            thrust_axis,
+            sphericity: sphericity(momenta),
+            spherocity: spherocity(momenta, thrust_axis),
+        }
+    }
+}
+
+/// Event shapes
+impl HEPEUP {
+    /// Compute the event shapes of all final-state particles
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # let event: lhef::HEPEUP = unimplemented!();
+    /// println!("Thrust: {}", event.event_shapes().thrust);
+    /// ```
+    pub fn event_shapes(&self) -> EventShapes {
+        self.event_shapes_with(&ParticleSelection::final_state())
+    }
+
+    /// Compute the event shapes of the selected particles
+    pub fn event_shapes_with(&self, selection: &ParticleSelection) -> EventShapes {
+        let momenta: Vec<_> = selection
+            .indices(self)
+            .into_iter()
+            .map(|i| [self.PUP[i][0], self.PUP[i][1], self.PUP[i][2]])
+            .collect();
+        EventShapes::new(&momenta)
+    }
+}
+
+fn dot(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: &[f64; 3], b: &[f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn norm(a: &[f64; 3]) -> f64 {
+    dot(a, a).sqrt()
+}
+
+fn normalised(a: &[f64; 3]) -> [f64; 3] {
+    let n = norm(a);
+    [a[0] / n, a[1] / n, a[2] / n]
+}
+
+/// Compute the thrust and its axis
+///
+/// The thrust axis is parallel to the sum of the momenta in one
+/// hemisphere. All hemisphere boundaries that make a difference are
+/// spanned by two of the momenta, so trying all pairs gives the exact
+/// result.
+fn thrust(momenta: &[[f64; 3]]) -> (f64, [f64; 3]) {
+    let total: f64 = momenta.iter().map(norm).sum();
+    if total == 0. {
+        return (0., [0., 0., 1.]);
+    }
+    let mut best = [0.; 3];
+    let mut consider = |candidate: [f64; 3]| {
+        if dot(&candidate, &candidate) > dot(&best, &best) {
+            best = candidate;
+        }
+    };
+    let hemisphere_sum = |n: &[f64; 3], skip: &[usize]| {
+        let mut sum = [0.; 3];
+        for (k, p) in momenta.iter().enumerate() {
+            if skip.contains(&k) {
+                continue;
+            }
+            let sign = if dot(p, n) >= 0. { 1. } else { -1. };
+            for c in 0..3 {
+                sum[c] += sign * p[c];
+            }
+        }
+        sum
+    };
+    for p in momenta {
+        consider(hemisphere_sum(p, &[]));
+    }
+    for i in 0..momenta.len() {
+        for j in i + 1..momenta.len() {
+            let n = cross(&momenta[i], &momenta[j]);
+            if norm(&n) == 0. {
+                continue;
+            }
+            let base = hemisphere_sum(&n, &[i, j]);
+            for &(si, sj) in &[(1., 1.), (1., -1.), (-1., 1.), (-1., -1.)] {
+                let mut candidate = base;
+                for c in 0..3 {
+                    candidate[c] += si * momenta[i][c] + sj * momenta[j][c];
+                }
+                consider(candidate);
+            }
+        }
+    }
+    let length = norm(&best);
+    if length == 0. {
+        return (0., [0., 0., 1.]);
+    }
+    (length / total, normalised(&best))
+}
+
+/// Compute the sphericity from the eigenvalues of the momentum tensor
+fn sphericity(momenta: &[[f64; 3]]) -> f64 {
+    let mut tensor = [[0.; 3]; 3];
+    let mut total = 0.;
+    for p in momenta {
+        for a in 0..3 {
+            for b in 0..3 {
+                tensor[a][b] += p[a] * p[b];
+            }
+        }
+        total += dot(p, p);
+    }
+    if total == 0. {
+        return 0.;
+    }
+    let largest = largest_eigenvalue(&tensor) / total;
+    1.5 * (1. - largest)
+}
+
+/// Largest eigenvalue of a symmetric positive semi-definite 3×3 matrix
+fn largest_eigenvalue(m: &[[f64; 3]; 3]) -> f64 {
+    let off_diagonal = m[0][1].powi(2) + m[0][2].powi(2) + m[1][2].powi(2);
+    let trace = m[0][0] + m[1][1] + m[2][2];
+    if off_diagonal == 0. {
+        return m[0][0].max(m[1][1]).max(m[2][2]);
+    }
+    let q = trace / 3.;
+    let diagonal = (m[0][0] - q).powi(2) + (m[1][1] - q).powi(2) + (m[2][2] - q).powi(2);
+    let p = ((diagonal + 2. * off_diagonal) / 6.).sqrt();
+    let mut b = [[0.; 3]; 3];
+    for i in 0..3 {
+        for j in 0..3 {
+            let delta = if i == j { q } else { 0. };
+            b[i][j] = (m[i][j] - delta) / p;
+        }
+    }
+    let det = b[0][0] * (b[1][1] * b[2][2] - b[1][2] * b[2][1])
+        - b[0][1] * (b[1][0] * b[2][2] - b[1][2] * b[2][0])
+        + b[0][2] * (b[1][0] * b[2][1] - b[1][1] * b[2][0]);
+    let phi = (det / 2.).clamp(-1., 1.).acos() / 3.;
+    q + 2. * p * phi.cos()
+}
+
+/// Compute the spherocity by minimising over axes numerically
+fn spherocity(momenta: &[[f64; 3]], thrust_axis: [f64; 3]) -> f64 {
+    let total: f64 = momenta.iter().map(norm).sum();
+    if total == 0. {
+        return 0.;
+    }
+    let transverse_sum = |n: &[f64; 3]| -> f64 {
+        let n = normalised(n);
+        momenta.iter().map(|p| norm(&cross(p, &n))).sum()
+    };
+    let mut starts = vec![thrust_axis];
+    starts.extend(momenta.iter().filter(|p| norm(p) > 0.).cloned());
+    let mut best = f64::INFINITY;
+    for start in starts {
+        let mut axis = normalised(&start);
+        let mut value = transverse_sum(&axis);
+        let mut step = 0.1;
+        while step > 1e-7 {
+            let mut improved = false;
+            for c in 0..3 {
+                for &sign in &[1., -1.] {
+                    let mut candidate = axis;
+                    candidate[c] += sign * step;
+                    let candidate_value = transverse_sum(&candidate);
+                    if candidate_value < value {
+                        axis = normalised(&candidate);
+                        value = candidate_value;
+                        improved = true;
+                    }
+                }
+            }
+            if !improved {
+                step /= 2.;
+            }
+        }
+        best = best.min(value);
+    }
+    (4. / PI).powi(2) * (best / total).powi(2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Reader;
+
+    const EVENT: &str = r#"<LesHouchesEvents version="1.0">
+<init>
+-11 11 45.6 45.6 0 0 0 0 3 1
+1 0 1 1
+</init>
+<event>
+5 1 1 91.2 0 0.1
+-11 -1 0 0 0 0 0 0 4.56e1 4.56e1 0 0 9
+11 -1 0 0 0 0 0 0 -4.56e1 4.56e1 0 0 9
+23 2 1 2 0 0 0 0 0 9.12e1 9.12e1 0 9
+1 1 3 3 501 0 0 0 4.56e1 4.56e1 0 0 9
+-1 1 3 3 0 501 0 0 -4.56e1 4.56e1 0 0 9
+</event>
+</LesHouchesEvents>
+"#;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-6, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn pencil() {
+        let shapes = EventShapes::new(&[[0., 0., 45.], [0., 0., -45.]]);
+        assert_close(shapes.thrust, 1.);
+        assert_close(shapes.thrust_axis[2].abs(), 1.);
+        assert_close(shapes.sphericity, 0.);
+        assert_close(shapes.spherocity, 0.);
+    }
+
+    #[test]
+    fn event() {
+        let mut reader = Reader::new(EVENT.as_bytes()).unwrap();
+        let event = reader.event().unwrap().unwrap();
+        let shapes = event.event_shapes();
+        assert_eq!(shapes, EventShapes::new(&[[0., 0., 45.6], [0., 0., -45.6]]));
+        assert_close(shapes.thrust, 1.);
+        let beams = ParticleSelection::all().with_status(&[-1]);
+        assert_eq!(event.event_shapes_with(&beams).thrust, 1.);
+    }
+
+    #[test]
+    fn three_jet() {
+        // symmetric three-particle event in the xy plane
+        let s = 3f64.sqrt() / 2.;
+        let momenta = [[1., 0., 0.], [-0.5, s, 0.], [-0.5, -s, 0.]];
+        let shapes = EventShapes::new(&momenta);
+        assert_close(shapes.thrust, 2. / 3.);
+        assert_close(shapes.thrust_axis[2], 0.);
+        assert_close(shapes.sphericity, 0.75);
+        // the spherocity is minimal along an axis through one particle
+        let transverse = 2. * s;
+        assert_close(shapes.spherocity, (4. / PI).powi(2) * (transverse / 3.).powi(2));
+    }
+
+    #[test]
+    fn isotropic() {
+        let momenta = [
+            [1., 0., 0.],
+            [-1., 0., 0.],
+            [0., 1., 0.],
+            [0., -1., 0.],
+            [0., 0., 1.],
+            [0., 0., -1.],
+        ];
+        let shapes = EventShapes::new(&momenta);
+        assert_close(shapes.thrust, 1. / 3f64.sqrt());
+        assert_close(shapes.sphericity, 1.);
+        assert_eq!(EventShapes::new(&[]).thrust, 0.);
+    }
+}
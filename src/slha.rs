@@ -0,0 +1,344 @@
+use std::error;
+use std::fmt;
+
+use tags::{self, Tag};
+use {parse, ParseError};
+
+pub(crate) const SLHA_TAG: &str = "slha";
+/// Tag used by older MadGraph versions
+const MG_PARAM_CARD_TAG: &str = "MGParamCard";
+
+/// Model parameters in the SUSY Les Houches Accord (SLHA) format
+///
+/// This is usually embedded in the header of files generated for
+/// models beyond the Standard Model, in a `<slha>` tag.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Slha {
+    /// Parameter blocks in the order in which they appear
+    pub blocks: Vec<SlhaBlock>,
+    /// Decay tables in the order in which they appear
+    pub decays: Vec<Decay>,
+}
+
+/// A `BLOCK` of parameters
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct SlhaBlock {
+    /// Name of the block, converted to upper case
+    pub name: String,
+    /// Further arguments in the block header other than the scale,
+    /// e.g. the PDG ID in `QNUMBERS`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub arguments: Vec<String>,
+    /// Scale given by `Q=` in the block header
+    pub scale: Option<f64>,
+    /// Entries in the block
+    pub entries: Vec<SlhaEntry>,
+}
+
+/// An entry in a parameter block
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub struct SlhaEntry {
+    /// Indices, e.g. the PDG ID in the `MASS` block
+    pub indices: Vec<i32>,
+    /// Value, which is usually a number
+    pub value: String,
+    /// Comment at the end of the line
+    pub comment: Option<String>,
+}
+
+/// A `DECAY` table
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub struct Decay {
+    /// PDG ID of the decaying particle
+    pub pdg_id: i32,
+    /// Total width in GeV
+    pub width: f64,
+    /// Decay channels
+    pub channels: Vec<DecayChannel>,
+}
+
+/// A decay channel with its branching ratio
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub struct DecayChannel {
+    /// Branching ratio
+    pub br: f64,
+    /// PDG IDs of the decay products
+    pub daughters: Vec<i32>,
+}
+
+impl Slha {
+    /// Parse SLHA text
+    ///
+    /// Block names are case-insensitive and converted to upper case.
+    /// Lines that are not inside a block or decay table are ignored.
+    pub fn parse(text: &str) -> Result<Slha, Box<dyn error::Error>> {
+        let mut slha = Slha::default();
+        let mut in_decay = false;
+        for line in text.lines() {
+            let (content, comment) = match line.find('#') {
+                Some(pos) => (&line[..pos], Some(line[pos + 1..].trim())),
+                None => (line, None),
+            };
+            let mut tokens = content.split_whitespace();
+            let first = match tokens.next() {
+                Some(first) => first,
+                None => continue,
+            };
+            if first.eq_ignore_ascii_case("block") {
+                let name = parse::<String>("block name", tokens.next())?;
+                let mut arguments = Vec::new();
+                let mut scale = None;
+                while let Some(token) = tokens.next() {
+                    let upper = token.to_uppercase();
+                    if upper == "Q" || upper.starts_with("Q=") {
+                        scale = Some(parse_scale(token, &mut tokens)?);
+                    } else {
+                        arguments.push(token.to_owned());
+                    }
+                }
+                slha.blocks.push(SlhaBlock {
+                    name: name.to_uppercase(),
+                    arguments,
+                    scale,
+                    entries: Vec::new(),
+                });
+                in_decay = false;
+            } else if first.eq_ignore_ascii_case("decay") {
+                slha.decays.push(Decay {
+                    pdg_id: parse("decay PDG ID", tokens.next())?,
+                    width: parse("decay width", tokens.next())?,
+                    channels: Vec::new(),
+                });
+                in_decay = true;
+            } else if in_decay {
+                let decay = slha.decays.last_mut().unwrap();
+                let br = parse("branching ratio", Some(first))?;
+                let nda = parse::<usize>("number of daughters", tokens.next())?;
+                let daughters = (0..nda)
+                    .map(|i| parse(&format!("daughter {}", i + 1), tokens.next()))
+                    .collect::<Result<_, _>>()?;
+                decay.channels.push(DecayChannel { br, daughters });
+            } else if let Some(block) = slha.blocks.last_mut() {
+                let tokens: Vec<_> = content.split_whitespace().collect();
+                let (value, indices) = tokens.split_last().unwrap();
+                let indices = indices
+                    .iter()
+                    .map(|index| parse::<i32>("block index", Some(index)))
+                    .collect::<Result<_, _>>()?;
+                block.entries.push(SlhaEntry {
+                    indices,
+                    value: (*value).to_owned(),
+                    comment: comment.map(|c| c.to_owned()),
+                });
+            }
+        }
+        Ok(slha)
+    }
+
+    /// Get the block with the given name, ignoring case
+    pub fn block(&self, name: &str) -> Option<&SlhaBlock> {
+        self.blocks
+            .iter()
+            .find(|block| block.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Get a numerical value from a block
+    pub fn get(&self, block: &str, indices: &[i32]) -> Option<f64> {
+        self.block(block).and_then(|block| block.get(indices))
+    }
+
+    /// Get the mass of the particle with the given PDG ID from the
+    /// `MASS` block
+    pub fn mass(&self, pdg_id: i32) -> Option<f64> {
+        self.get("MASS", &[pdg_id])
+    }
+
+    /// Get the decay table of the particle with the given PDG ID
+    pub fn decay(&self, pdg_id: i32) -> Option<&Decay> {
+        self.decays.iter().find(|decay| decay.pdg_id == pdg_id)
+    }
+
+    /// Get the total width of the particle with the given PDG ID
+    pub fn width(&self, pdg_id: i32) -> Option<f64> {
+        self.decay(pdg_id).map(|decay| decay.width)
+    }
+
+    fn from_tag(tag: &Tag) -> Result<Slha, Box<dyn error::Error>> {
+        Slha::parse(tag.content())
+    }
+}
+
+impl SlhaBlock {
+    /// Get the entry with the given indices
+    pub fn entry(&self, indices: &[i32]) -> Option<&SlhaEntry> {
+        self.entries.iter().find(|entry| entry.indices == indices)
+    }
+
+    /// Get the numerical value of the entry with the given indices
+    ///
+    /// Fortran-style exponents like `1.0D+02` are accepted.
+    pub fn get(&self, indices: &[i32]) -> Option<f64> {
+        self.entry(indices)
+            .and_then(|entry| entry.value.replace(['d', 'D'], "e").parse().ok())
+    }
+}
+
+fn parse_scale<'a, I: Iterator<Item = &'a str>>(
+    first: &'a str,
+    rest: &mut I,
+) -> Result<f64, Box<dyn error::Error>> {
+    // the scale can be written as "Q= 100", "Q=100", or "Q = 100"
+    let value = match first.to_uppercase().as_str() {
+        "Q" => {
+            let eq = rest.next();
+            if eq != Some("=") {
+                return Err(Box::new(ParseError::ConversionError(
+                    eq.unwrap_or("").to_owned(),
+                )));
+            }
+            rest.next()
+        }
+        "Q=" => rest.next(),
+        q if q.starts_with("Q=") => Some(&first[2..]),
+        _ => return Err(Box::new(ParseError::ConversionError(first.to_owned()))),
+    };
+    parse("block scale", value)
+}
+
+/// Parse the SLHA parameters in the header
+pub(crate) fn parse_slha(header: &str) -> Result<Option<Slha>, Box<dyn error::Error>> {
+    tags::header_tag(header, &[SLHA_TAG, MG_PARAM_CARD_TAG])
+        .map(|tag| Slha::from_tag(&tag))
+        .transpose()
+}
+
+/// Formats the parameters as SLHA text
+impl fmt::Display for Slha {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for block in &self.blocks {
+            write!(f, "BLOCK {}", block.name)?;
+            for argument in &block.arguments {
+                write!(f, " {}", argument)?;
+            }
+            if let Some(scale) = block.scale {
+                write!(f, " Q= {:e}", scale)?;
+            }
+            writeln!(f)?;
+            for entry in &block.entries {
+                for index in &entry.indices {
+                    write!(f, " {:>5}", index)?;
+                }
+                write!(f, " {}", entry.value)?;
+                if let Some(ref comment) = entry.comment {
+                    write!(f, " # {}", comment)?;
+                }
+                writeln!(f)?;
+            }
+        }
+        for decay in &self.decays {
+            writeln!(f, "DECAY {} {:e}", decay.pdg_id, decay.width)?;
+            for channel in &decay.channels {
+                write!(f, "   {:e} {}", channel.br, channel.daughters.len())?;
+                for daughter in &channel.daughters {
+                    write!(f, " {}", daughter)?;
+                }
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_util::SyntheticEvents;
+
+    const SLHA: &str = "# a comment\n\
+                        Block mass \n\
+                        \x20   6 1.730000e+02 # MT \n\
+                        \x20  24 80.419002 # w+ : cmath.sqrt(MZ__exp__2/2.)\n\
+                        BLOCK SPINFO\n\
+                        \x20   1 SOFTSUSY\n\
+                        Block yu Q= 4.6e+02\n\
+                        \x20 3 3 8.9D-01\n\
+                        DECAY   6 1.491500e+00 # WT \n\
+                        #  BR  NDA ID1 ID2\n\
+                        \x20  1.0 2 5 24\n\
+                        DECAY  22 0.000000 # a : 0.0\n";
+
+    #[test]
+    fn parse_and_format() {
+        let slha = Slha::parse(SLHA).unwrap();
+        assert_eq!(slha.blocks.len(), 3);
+        assert_eq!(slha.mass(6), Some(173.));
+        assert_eq!(slha.mass(24), Some(80.419002));
+        assert_eq!(slha.mass(5), None);
+        let mass = slha.block("MASS").unwrap();
+        assert_eq!(mass.entry(&[6]).unwrap().comment, Some("MT".to_owned()));
+        assert_eq!(slha.block("spinfo").unwrap().entry(&[1]).unwrap().value, "SOFTSUSY");
+        assert_eq!(slha.block("yu").unwrap().scale, Some(460.));
+        assert_eq!(slha.get("YU", &[3, 3]), Some(0.89));
+        assert_eq!(slha.width(6), Some(1.4915));
+        let top = slha.decay(6).unwrap();
+        assert_eq!(top.channels, [DecayChannel { br: 1., daughters: vec![5, 24] }]);
+        assert_eq!(slha.width(22), Some(0.));
+        assert_eq!(Slha::parse(&slha.to_string()).unwrap(), slha);
+    }
+
+    #[test]
+    fn qnumbers() {
+        let text = "BLOCK QNUMBERS 9000006 # zp\n\
+                    \x20   1 0 # 3 times electric charge\n\
+                    \x20   2 3 # number of spin states\n";
+        let slha = Slha::parse(text).unwrap();
+        let block = slha.block("qnumbers").unwrap();
+        assert_eq!(block.arguments, ["9000006"]);
+        assert_eq!(block.scale, None);
+        assert_eq!(block.get(&[2]), Some(3.));
+        assert_eq!(Slha::parse(&slha.to_string()).unwrap(), slha);
+
+        let read = |header: &str| {
+            let mut buf = Vec::new();
+            {
+                let mut writer = ::Writer::new(&mut buf, "3.0").unwrap();
+                let header = format!("<header>\n<slha>\n{}</slha>\n</header>\n", header);
+                writer.header(&header).unwrap();
+                writer.heprup(&SyntheticEvents::new().heprup()).unwrap();
+                writer.finish().unwrap();
+            }
+            ::Reader::new(buf.as_slice()).unwrap().slha().cloned()
+        };
+        assert_eq!(read(text).unwrap().block("QNUMBERS").unwrap().arguments, ["9000006"]);
+        assert_eq!(read(&text.replace("9000006", "9000006 Q= high")), None);
+    }
+
+    #[test]
+    fn header() {
+        let header = format!("<header>\n<slha>\n{}</slha>\n</header>\n", SLHA);
+        assert_eq!(parse_slha(&header).unwrap().unwrap().mass(6), Some(173.));
+        assert_eq!(parse_slha("<header>\n</header>\n").unwrap(), None);
+    }
+
+    #[test]
+    fn bad_input() {
+        assert!(Slha::parse("DECAY 6 wide").is_err());
+        assert!(Slha::parse("BLOCK mass\n 6.5 173").is_err());
+        assert!(Slha::parse("BLOCK yu Q= high").is_err());
+        assert!(Slha::parse("BLOCK yu Q 100").is_err());
+    }
+
+    #[test]
+    fn read_file() {
+        let reader = ::Reader::open("test_data/2j.lhe.gz").unwrap();
+        let slha = reader.slha().unwrap();
+        assert_eq!(slha.mass(25), Some(125.));
+        assert_eq!(slha.get("sminputs", &[3]), Some(0.118));
+        assert_eq!(slha.width(24), Some(2.0476));
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::VecDeque;
+use std::error;
+use std::ops::Deref;
+use std::str;
+
+use {
+    ends_in_comment, is_between_blocks, is_eventgroup_start, parse_event, parse_eventgroup,
+    starts_block, starts_eventgroup, tags, Attributes, Block, CancellationToken, ParseError, Reader,
+    EVENTGROUP_END, EVENT_END, EVENT_TAG, HEPEUP, LHEF_LAST_LINE,
+};
+
+/// Reader for LHEF data that is already in memory
+///
+/// Unlike `Reader`, this does not copy each line of an event before
+/// parsing it. Instead, events are parsed directly from the input. This
+/// is particularly efficient for memory-mapped files. The header and
+/// run information are available through the `Reader` methods. Since
+/// events are not copied, they must be valid UTF-8, whereas `Reader`
+/// decodes other lines as Latin-1.
+///
+/// # Example
+///
+/// Using the `memmap2` crate:
+///
+/// ```rust,ignore
+/// let file = std::fs::File::open("events.lhe").unwrap();
+/// // safety: the file must not be modified while it is mapped
+/// let data = unsafe { memmap2::Mmap::map(&file).unwrap() };
+/// let mut reader = lhef::SliceReader::new(&data).unwrap();
+/// println!("Run information: {:?}", reader.heprup());
+/// while let Some(event) = reader.event().unwrap() {
+///     println!("Found an event.");
+/// }
+/// ```
+pub struct SliceReader<'a> {
+    reader: Reader<&'a [u8]>,
+    pending: VecDeque<HEPEUP>,
+    finished: bool,
+}
+
+impl<'a> SliceReader<'a> {
+    /// Create a new reader for the given data
+    pub fn new(data: &'a [u8]) -> Result<SliceReader<'a>, Box<dyn error::Error>> {
+        let mut reader = Reader::new(data)?;
+        reader.total_bytes = Some(data.len() as u64);
+        Ok(SliceReader {
+            reader,
+            pending: VecDeque::new(),
+            finished: false,
+        })
+    }
+
+    /// Allow cancelling reading with the given token
+    ///
+    /// See `Reader::set_cancellation`.
+    pub fn set_cancellation(&mut self, token: Option<CancellationToken>) {
+        self.reader.set_cancellation(token)
+    }
+
+    /// Get the next event
+    ///
+    /// See `Reader::event`.
+    pub fn event(&mut self) -> Result<Option<HEPEUP>, Box<dyn error::Error>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(Some(event));
+            }
+            match self.block()? {
+                Some(Block::Event(event)) => return Ok(Some(event)),
+                Some(Block::EventGroup(events)) => self.pending = events.into(),
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Get the next event or group of events
+    ///
+    /// See `Reader::block`.
+    pub fn block(&mut self) -> Result<Option<Block>, Box<dyn error::Error>> {
+        if !self.pending.is_empty() {
+            let events = self.pending.drain(..).collect();
+            return Ok(Some(Block::EventGroup(events)));
+        }
+        self.reader.check_cancelled()?;
+        if self.finished {
+            return Ok(None);
+        }
+        let mut skipped = 0;
+        let mut in_comment = false;
+        let data = self.reader.stream;
+        let line = loop {
+            let line = self.next_line()?;
+            if line.is_empty() || !is_between_blocks(line, in_comment) {
+                break line;
+            }
+            in_comment = ends_in_comment(line, in_comment);
+            skipped += line.len();
+        };
+        self.reader.skipped = str::from_utf8(&data[..skipped])?.to_owned();
+        if let Some(attr) = tags::opening_tag(line, EVENT_TAG) {
+            let attr = Attributes::from(attr);
+            let text = self.block_text(EVENT_END, "event", starts_block)?;
+            let event = parse_event(text, attr, &self.reader.context)?;
+            self.reader.nevents += 1;
+            return Ok(Some(Block::Event(event)));
+        }
+        match line.trim() {
+            LHEF_LAST_LINE => {
+                self.finished = true;
+                Ok(None)
+            }
+            l if is_eventgroup_start(l) => {
+                let text = self.block_text(EVENTGROUP_END, "eventgroup", starts_eventgroup)?;
+                let events = parse_eventgroup(text, &self.reader.context)?;
+                self.reader.nevents += events.len() as u64;
+                Ok(Some(Block::EventGroup(events)))
+            }
+            _ => Err(Box::new(ParseError::BadEventStart(line.to_owned()))),
+        }
+    }
+
+    /// Get the next line, including the line break
+    fn next_line(&mut self) -> Result<&'a str, Box<dyn error::Error>> {
+        let data = self.reader.stream;
+        let len = line_len(data);
+        self.advance(len);
+        Ok(str::from_utf8(&data[..len])?)
+    }
+
+    /// Get the text of the current block up to the closing tag `end`
+    ///
+    /// See `Reader::read_block_text`.
+    fn block_text(
+        &mut self,
+        end: &str,
+        block: &'static str,
+        is_next_block: fn(&str) -> bool,
+    ) -> Result<&'a str, Box<dyn error::Error>> {
+        let data = self.reader.stream;
+        let mut len = 0;
+        let mut in_comment = false;
+        loop {
+            let line_len = line_len(&data[len..]);
+            if line_len == 0 {
+                self.advance(len);
+                return Err(Box::new(ParseError::EndOfFile(block)));
+            }
+            let line = str::from_utf8(&data[len..len + line_len])?;
+            if line.trim() == end {
+                self.advance(len + line_len);
+                return Ok(str::from_utf8(&data[..len])?);
+            }
+            if !in_comment && is_next_block(line) {
+                self.advance(len);
+                return Err(Box::new(ParseError::EndOfFile(block)));
+            }
+            in_comment = ends_in_comment(line, in_comment);
+            len += line_len;
+        }
+    }
+
+    /// Consume `len` bytes of the input
+    fn advance(&mut self, len: usize) {
+        self.reader.stream = &self.reader.stream[len..];
+        self.reader.bytes_read += len as u64;
+    }
+}
+
+/// Gives access to the header and run information
+impl<'a> Deref for SliceReader<'a> {
+    type Target = Reader<&'a [u8]>;
+
+    fn deref(&self) -> &Reader<&'a [u8]> {
+        &self.reader
+    }
+}
+
+/// Length of the first line in `data`, including the line break
+fn line_len(data: &[u8]) -> usize {
+    match data.iter().position(|&c| c == b'\n') {
+        Some(pos) => pos + 1,
+        None => data.len(),
+    }
+}
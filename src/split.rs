@@ -0,0 +1,251 @@
+use std::error;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use file;
+use {EventSink, Writer, HEPEUP, HEPRUP};
+
+/// Writer splitting the output into several numbered files
+///
+/// A new file is started once the current one contains the maximum
+/// number of events or has reached the maximum size. Each file is a
+/// complete event file with the same headers and run information, so
+/// the parts can for example be showered independently. The files are
+/// named after the given path with the part number, counting from 1,
+/// appended to the stem: `events.lhe.gz` is split into
+/// `events_1.lhe.gz`, `events_2.lhe.gz`, and so on. As with
+/// `Writer::create`, files ending in `.gz` are compressed, and their
+/// size refers to the compressed data.
+///
+/// Event groups are never split, so a part can contain slightly more
+/// than the maximum number of events. Because of buffering, the size of
+/// a file is only checked approximately.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # let heprup: lhef::HEPRUP = unimplemented!();
+/// # let events: Vec<lhef::HEPEUP> = unimplemented!();
+/// let mut writer = lhef::SplitWriter::create("events.lhe.gz", "3.0")
+///     .unwrap()
+///     .max_events(10000)
+///     .max_bytes(100 << 20);
+/// writer.heprup(&heprup).unwrap();
+/// for event in &events {
+///     writer.event(event).unwrap();
+/// }
+/// writer.finish().unwrap();
+/// println!("Wrote {} files", writer.paths().len());
+/// ```
+pub struct SplitWriter {
+    path: PathBuf,
+    version: String,
+    max_events: Option<u64>,
+    max_bytes: Option<u64>,
+    headers: Vec<String>,
+    heprup: Option<HEPRUP>,
+    writer: Writer<Box<dyn Write + Send>>,
+    written: Arc<AtomicU64>,
+    events: u64,
+    paths: Vec<PathBuf>,
+}
+
+impl SplitWriter {
+    /// Create the first part of the output for the given format version
+    ///
+    /// Without limits set by `max_events` or `max_bytes`, all events
+    /// are written to this part.
+    pub fn create<P: AsRef<Path>>(
+        path: P,
+        version: &str,
+    ) -> Result<SplitWriter, Box<dyn error::Error>> {
+        let path = path.as_ref().to_owned();
+        let written = Arc::new(AtomicU64::new(0));
+        let first = part_path(&path, 1);
+        let writer = Writer::new(file::create_counted(&first, written.clone())?, version)?;
+        Ok(SplitWriter {
+            path,
+            version: version.to_owned(),
+            max_events: None,
+            max_bytes: None,
+            headers: Vec::new(),
+            heprup: None,
+            writer,
+            written,
+            events: 0,
+            paths: vec![first],
+        })
+    }
+
+    /// Start a new file after the given number of events
+    pub fn max_events(mut self, events: u64) -> Self {
+        self.max_events = Some(events);
+        self
+    }
+
+    /// Start a new file once the current one has the given size
+    pub fn max_bytes(mut self, bytes: u64) -> Self {
+        self.max_bytes = Some(bytes);
+        self
+    }
+
+    /// Write a header to each file
+    pub fn header(&mut self, header: &str) -> Result<(), Box<dyn error::Error>> {
+        self.writer.header(header)?;
+        self.headers.push(header.to_owned());
+        Ok(())
+    }
+
+    /// Write the run information to each file
+    pub fn heprup(&mut self, heprup: &HEPRUP) -> Result<(), Box<dyn error::Error>> {
+        self.writer.heprup(heprup)?;
+        self.heprup = Some(heprup.clone());
+        Ok(())
+    }
+
+    /// Write an event, starting a new file if the current one is full
+    pub fn event(&mut self, event: &HEPEUP) -> Result<(), Box<dyn error::Error>> {
+        self.start_part_if_full()?;
+        self.writer.event(event)?;
+        self.events += 1;
+        Ok(())
+    }
+
+    /// Write a group of correlated events to the same file, starting a
+    /// new file if the current one is full
+    pub fn event_group(&mut self, events: &[HEPEUP]) -> Result<(), Box<dyn error::Error>> {
+        self.start_part_if_full()?;
+        self.writer.event_group(events)?;
+        self.events += events.len() as u64;
+        Ok(())
+    }
+
+    /// Close the current file
+    ///
+    /// As with `Writer::create`, a compressed stream is completed when
+    /// the writer is dropped.
+    pub fn finish(&mut self) -> Result<(), Box<dyn error::Error>> {
+        self.writer.finish()
+    }
+
+    /// The paths of all files started so far
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    fn start_part_if_full(&mut self) -> Result<(), Box<dyn error::Error>> {
+        let full = self.max_events.is_some_and(|max| self.events >= max)
+            || self.max_bytes.is_some_and(|max| self.written.load(Ordering::Relaxed) >= max);
+        if self.events == 0 || !full {
+            return Ok(());
+        }
+        self.writer.finish()?;
+        let path = part_path(&self.path, self.paths.len() + 1);
+        let written = Arc::new(AtomicU64::new(0));
+        let stream = file::create_counted(&path, written.clone())?;
+        // replacing the old writer completes the compressed stream
+        self.writer = Writer::new(stream, &self.version)?;
+        self.written = written;
+        self.events = 0;
+        self.paths.push(path);
+        for header in &self.headers {
+            self.writer.header(header)?;
+        }
+        if let Some(ref heprup) = self.heprup {
+            self.writer.heprup(heprup)?;
+        }
+        Ok(())
+    }
+}
+
+impl EventSink for SplitWriter {
+    fn header(&mut self, header: &str) -> Result<(), Box<dyn error::Error>> {
+        SplitWriter::header(self, header)
+    }
+
+    fn heprup(&mut self, heprup: &HEPRUP) -> Result<(), Box<dyn error::Error>> {
+        SplitWriter::heprup(self, heprup)
+    }
+
+    fn event(&mut self, event: &HEPEUP) -> Result<(), Box<dyn error::Error>> {
+        SplitWriter::event(self, event)
+    }
+
+    fn event_group(&mut self, events: &[HEPEUP]) -> Result<(), Box<dyn error::Error>> {
+        SplitWriter::event_group(self, events)
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn error::Error>> {
+        SplitWriter::finish(self)
+    }
+}
+
+/// Path of the part with the given number, e.g. `events_2.lhe.gz` for
+/// `events.lhe.gz`
+fn part_path(path: &Path, part: usize) -> PathBuf {
+    let name = path.file_name().map_or_else(String::new, |name| name.to_string_lossy().into());
+    let split = name.char_indices().skip(1).find(|&(_, c)| c == '.').map_or(name.len(), |(i, _)| i);
+    let (stem, extension) = name.split_at(split);
+    path.with_file_name(format!("{}_{}{}", stem, part, extension))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::process;
+    use test_util::SyntheticEvents;
+    use Reader;
+
+    #[test]
+    fn names() {
+        let path = Path::new("dir/events.lhe.gz");
+        assert_eq!(part_path(path, 2), Path::new("dir/events_2.lhe.gz"));
+        assert_eq!(part_path(Path::new(".events"), 1), Path::new(".events_1"));
+    }
+
+    #[test]
+    fn split() {
+        let sample = SyntheticEvents::new().num_events(7);
+        let path = env::temp_dir().join(format!("{}_lhef_split_test.lhe.gz", process::id()));
+        let paths = {
+            let mut writer = SplitWriter::create(&path, "3.0").unwrap().max_events(3);
+            writer.header(&sample.header()).unwrap();
+            writer.heprup(&sample.heprup()).unwrap();
+            for event in sample.events() {
+                writer.event(&event).unwrap();
+            }
+            writer.finish().unwrap();
+            writer.paths().to_vec()
+        };
+        assert_eq!(paths.len(), 3);
+        let mut events = sample.events();
+        for (i, path) in paths.iter().enumerate() {
+            let mut reader = Reader::open(path).unwrap();
+            assert_eq!(reader.heprup(), &sample.heprup());
+            let mut count = 0;
+            while let Some(event) = reader.event().unwrap() {
+                assert_eq!(Some(event), events.next());
+                count += 1;
+            }
+            assert_eq!(count, if i < 2 { 3 } else { 1 });
+            fs::remove_file(path).unwrap();
+        }
+
+        let path = env::temp_dir().join(format!("{}_lhef_split_test.lhe", process::id()));
+        let sample = SyntheticEvents::new().num_events(100);
+        let mut writer = SplitWriter::create(&path, "3.0").unwrap().max_bytes(1);
+        writer.heprup(&sample.heprup()).unwrap();
+        for event in sample.events() {
+            writer.event_group(&[event.clone(), event]).unwrap();
+        }
+        writer.finish().unwrap();
+        assert!(writer.paths().len() > 1);
+        for path in writer.paths() {
+            fs::remove_file(path).unwrap();
+        }
+    }
+}
@@ -0,0 +1,340 @@
+//! Statistical measures of weighted samples
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! # let events: Vec<lhef::HEPEUP> = unimplemented!();
+//! use lhef::stats;
+//!
+//! println!("Effective number of events: {}", stats::effective_entries(&events));
+//! for (process, summary) in stats::weight_summary_by_process(&events) {
+//!     println!("Process {}: {} effective events", process, summary.effective_entries());
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+
+use HEPEUP;
+
+/// Summary of the event weights of a sample
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Copy, Clone, Default)]
+pub struct WeightSummary {
+    /// Number of events
+    pub count: u64,
+    /// Number of events with negative weight
+    pub negative_count: u64,
+    /// Sum of weights
+    pub sum: f64,
+    /// Sum of squared weights
+    pub sum_squares: f64,
+}
+
+impl WeightSummary {
+    /// Create an empty summary
+    pub fn new() -> WeightSummary {
+        WeightSummary::default()
+    }
+
+    /// Add an event weight
+    pub fn add(&mut self, weight: f64) {
+        self.count += 1;
+        if weight < 0. {
+            self.negative_count += 1;
+        }
+        self.sum += weight;
+        self.sum_squares += weight * weight;
+    }
+
+    /// Combine with the summary of another sample
+    pub fn merge(&mut self, other: &WeightSummary) {
+        self.count += other.count;
+        self.negative_count += other.negative_count;
+        self.sum += other.sum;
+        self.sum_squares += other.sum_squares;
+    }
+
+    /// Kish effective number of events (Σw)² / Σw²
+    ///
+    /// This is the number of unweighted events with the same relative
+    /// statistical uncertainty of the total cross section. It is 0 for
+    /// an empty sample.
+    pub fn effective_entries(&self) -> f64 {
+        if self.sum_squares == 0. {
+            return 0.;
+        }
+        self.sum * self.sum / self.sum_squares
+    }
+
+    /// Ratio of the effective to the actual number of events
+    pub fn efficiency(&self) -> f64 {
+        if self.count == 0 {
+            return 0.;
+        }
+        self.effective_entries() / self.count as f64
+    }
+
+    /// Mean weight
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 {
+            return 0.;
+        }
+        self.sum / self.count as f64
+    }
+
+    /// Unbiased sample variance of the weights
+    pub fn variance(&self) -> f64 {
+        if self.count < 2 {
+            return 0.;
+        }
+        let n = self.count as f64;
+        (self.sum_squares - self.sum * self.sum / n).max(0.) / (n - 1.)
+    }
+
+    /// Fraction of events with negative weight
+    pub fn negative_fraction(&self) -> f64 {
+        if self.count == 0 {
+            return 0.;
+        }
+        self.negative_count as f64 / self.count as f64
+    }
+}
+
+impl<'a> Extend<&'a HEPEUP> for WeightSummary {
+    fn extend<I: IntoIterator<Item = &'a HEPEUP>>(&mut self, events: I) {
+        for event in events {
+            self.add(event.XWGTUP)
+        }
+    }
+}
+
+/// Running mean and variance of a weighted observable
+///
+/// The statistics are updated in a single pass with Welford's
+/// algorithm, generalised to weights by West (1979), which avoids the
+/// loss of precision of naive sums of squares. Negative weights are
+/// allowed as long as the accumulated weight does not vanish.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # let mut reader: lhef::Reader<std::io::Empty> = unimplemented!();
+/// use lhef::stats::RunningStats;
+///
+/// // mean and spread of the transverse momentum of the first outgoing particle
+/// let mut pt = RunningStats::new();
+/// while let Some(event) = reader.event().unwrap() {
+///     pt.add_event(&event, |event| Some(event.pt(2)));
+/// }
+/// println!("pt = {} ± {}", pt.mean(), pt.std_dev());
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Copy, Clone, Default)]
+pub struct RunningStats {
+    count: u64,
+    sum_weights: f64,
+    mean: f64,
+    /// weighted sum of squared deviations from the mean
+    m2: f64,
+}
+
+impl RunningStats {
+    /// Create empty statistics
+    pub fn new() -> RunningStats {
+        RunningStats::default()
+    }
+
+    /// Add a value with the given weight
+    pub fn add(&mut self, value: f64, weight: f64) {
+        self.count += 1;
+        self.sum_weights += weight;
+        if self.sum_weights == 0. {
+            return;
+        }
+        let delta = value - self.mean;
+        self.mean += delta * weight / self.sum_weights;
+        self.m2 += weight * delta * (value - self.mean);
+    }
+
+    /// Add an observable of an event with the event weight `XWGTUP`
+    ///
+    /// Nothing is added if `observable` returns `None`.
+    pub fn add_event<F>(&mut self, event: &HEPEUP, observable: F)
+    where
+        F: FnOnce(&HEPEUP) -> Option<f64>,
+    {
+        if let Some(value) = observable(event) {
+            self.add(value, event.XWGTUP)
+        }
+    }
+
+    /// Combine with the statistics of another sample
+    pub fn merge(&mut self, other: &RunningStats) {
+        let sum_weights = self.sum_weights + other.sum_weights;
+        if sum_weights == 0. {
+            *self = RunningStats {
+                count: self.count + other.count,
+                ..RunningStats::default()
+            };
+            return;
+        }
+        let delta = other.mean - self.mean;
+        self.m2 += other.m2 + delta * delta * self.sum_weights * other.sum_weights / sum_weights;
+        self.mean += delta * other.sum_weights / sum_weights;
+        self.sum_weights = sum_weights;
+        self.count += other.count;
+    }
+
+    /// Number of added values
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Sum of weights
+    pub fn sum_weights(&self) -> f64 {
+        self.sum_weights
+    }
+
+    /// Weighted mean
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// Weighted variance Σw(x - mean)² / Σw
+    pub fn variance(&self) -> f64 {
+        if self.sum_weights == 0. {
+            return 0.;
+        }
+        self.m2 / self.sum_weights
+    }
+
+    /// Weighted standard deviation
+    pub fn std_dev(&self) -> f64 {
+        self.variance().sqrt()
+    }
+}
+
+/// Summarise the weights `XWGTUP` of the given events
+pub fn weight_summary<'a, I: IntoIterator<Item = &'a HEPEUP>>(events: I) -> WeightSummary {
+    let mut summary = WeightSummary::new();
+    summary.extend(events);
+    summary
+}
+
+/// Summarise the weights of the given events separately for each
+/// process ID `IDRUP`
+pub fn weight_summary_by_process<'a, I>(events: I) -> BTreeMap<i32, WeightSummary>
+where
+    I: IntoIterator<Item = &'a HEPEUP>,
+{
+    let mut summaries = BTreeMap::new();
+    for event in events {
+        summaries
+            .entry(event.IDRUP)
+            .or_insert_with(WeightSummary::new)
+            .add(event.XWGTUP);
+    }
+    summaries
+}
+
+/// Kish effective number of events (Σw)² / Σw² of the given events
+///
+/// See `WeightSummary::effective_entries`.
+pub fn effective_entries<'a, I: IntoIterator<Item = &'a HEPEUP>>(events: I) -> f64 {
+    weight_summary(events).effective_entries()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Reader;
+
+    const EVENTS: &str = r#"<LesHouchesEvents version="1.0">
+<init>
+2212 2212 6500 6500 0 0 0 0 3 2
+1 0 1 1
+1 0 1 2
+</init>
+<event>
+1 1 2 10 0 0.1
+21 -1 0 0 0 0 0 0 1e2 1e2 0 0 9
+</event>
+<event>
+1 1 2 10 0 0.1
+21 -1 0 0 0 0 0 0 1e2 1e2 0 0 9
+</event>
+<event>
+1 2 -1 10 0 0.1
+21 -1 0 0 0 0 0 0 1e2 1e2 0 0 9
+</event>
+<event>
+1 2 3 10 0 0.1
+21 -1 0 0 0 0 0 0 1e2 1e2 0 0 9
+</event>
+</LesHouchesEvents>
+"#;
+
+    #[test]
+    fn summary() {
+        let mut reader = Reader::new(EVENTS.as_bytes()).unwrap();
+        let mut events = Vec::new();
+        while let Some(event) = reader.event().unwrap() {
+            events.push(event);
+        }
+        assert_eq!(effective_entries(&events), 36. / 18.);
+        let summary = weight_summary(&events);
+        assert_eq!(summary.count, 4);
+        assert_eq!(summary.negative_fraction(), 0.25);
+        assert_eq!(summary.mean(), 1.5);
+        assert_eq!(summary.variance(), (18. - 9.) / 3.);
+        assert_eq!(summary.efficiency(), 0.5);
+
+        let by_process = weight_summary_by_process(&events);
+        assert_eq!(by_process.len(), 2);
+        assert_eq!(by_process[&1].effective_entries(), 2.);
+        assert_eq!(by_process[&1].variance(), 0.);
+        assert_eq!(by_process[&2].effective_entries(), 0.4);
+        let mut merged = by_process[&1];
+        merged.merge(&by_process[&2]);
+        assert_eq!(merged, summary);
+        assert_eq!(WeightSummary::new().effective_entries(), 0.);
+    }
+
+    #[test]
+    fn running() {
+        let mut stats = RunningStats::new();
+        for &(x, w) in &[(1., 1.), (2., 2.), (4., 1.)] {
+            stats.add(x, w);
+        }
+        assert_eq!(stats.count(), 3);
+        assert_eq!(stats.sum_weights(), 4.);
+        assert_eq!(stats.mean(), 2.25);
+        let expected = (1.25f64.powi(2) + 2. * 0.25f64.powi(2) + 1.75f64.powi(2)) / 4.;
+        assert!((stats.variance() - expected).abs() < 1e-12);
+
+        let mut first = RunningStats::new();
+        first.add(1., 1.);
+        first.add(2., 2.);
+        let mut second = RunningStats::new();
+        second.add(4., 1.);
+        first.merge(&second);
+        assert_eq!(first.count(), 3);
+        assert_eq!(first.mean(), 2.25);
+        assert!((first.variance() - expected).abs() < 1e-12);
+
+        // a large offset does not spoil the precision
+        let mut shifted = RunningStats::new();
+        for &(x, w) in &[(1., 1.), (2., 2.), (4., 1.)] {
+            shifted.add(x + 1e9, w);
+        }
+        assert!((shifted.variance() - expected).abs() < 1e-6);
+
+        let mut reader = Reader::new(EVENTS.as_bytes()).unwrap();
+        let mut weights = RunningStats::new();
+        while let Some(event) = reader.event().unwrap() {
+            weights.add_event(&event, |event| Some(event.XWGTUP));
+        }
+        assert_eq!(weights.sum_weights(), 6.);
+        assert_eq!(weights.mean(), 18. / 6.);
+    }
+}
@@ -0,0 +1,283 @@
+use initrwgt::{InitRwgt, WeightInfo};
+
+/// Naming convention of a generator for its additional weights
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum WeightConvention {
+    /// MadGraph: scale factors and PDF in `MUR`, `MUF`, and `PDF`
+    /// attributes, or as `mur=... muf=... pdf=...` in the description
+    MadGraph,
+    /// POWHEG-BOX: `muR=... muF=...` or `renscfact=... facscfact=...`
+    /// and `pdf=...` or `lhapdf=...` in the description
+    Powheg,
+    /// Sherpa: scale factors and PDF encoded in the weight ID, e.g.
+    /// `MUR0.5_MUF0.5_PDF303200`
+    Sherpa,
+}
+
+/// The systematic variation described by an additional weight
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub enum Variation {
+    /// The nominal weight
+    Nominal,
+    /// A variation of the renormalisation and factorisation scales by
+    /// the given factors
+    Scale {
+        /// Renormalisation scale factor
+        mur: f64,
+        /// Factorisation scale factor
+        muf: f64,
+    },
+    /// A PDF member
+    Pdf {
+        /// LHAPDF ID of the member
+        lhapdf_id: i32,
+    },
+    /// Anything else
+    Other,
+}
+
+/// A scale variation weight
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub struct ScaleVariation {
+    /// Weight ID
+    pub id: String,
+    /// Renormalisation scale factor
+    pub mur: f64,
+    /// Factorisation scale factor
+    pub muf: f64,
+}
+
+/// A PDF variation weight
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub struct PdfVariation {
+    /// Weight ID
+    pub id: String,
+    /// LHAPDF ID of the PDF member
+    pub lhapdf_id: i32,
+}
+
+/// Additional weights classified into systematic variations
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct SystematicWeights {
+    /// ID of the nominal weight
+    pub nominal: Option<String>,
+    /// Scale variations
+    pub scale: Vec<ScaleVariation>,
+    /// PDF members
+    pub pdf: Vec<PdfVariation>,
+    /// IDs of all weights that could not be classified
+    pub other: Vec<String>,
+}
+
+impl WeightConvention {
+    /// Determine the variation described by a weight declaration
+    pub fn classify(self, weight: &WeightInfo) -> Variation {
+        if self == WeightConvention::Sherpa {
+            return classify_sherpa(&weight.id);
+        }
+        let mut values = key_values(&weight.description);
+        if self == WeightConvention::MadGraph {
+            // attributes take precedence over the description
+            for (key, value) in weight.attr.iter() {
+                values.insert(0, (key.to_lowercase(), value.to_owned()));
+            }
+        }
+        let get = |keys: &[&str]| {
+            values
+                .iter()
+                .find(|(key, _)| keys.contains(&key.as_str()))
+                .map(|(_, value)| value.as_str())
+        };
+        let (mur_keys, muf_keys, pdf_keys): (&[&str], &[&str], &[&str]) = match self {
+            WeightConvention::MadGraph => (&["mur"], &["muf"], &["pdf", "lhapdf"]),
+            WeightConvention::Powheg => (
+                &["mur", "renscfact"],
+                &["muf", "facscfact"],
+                &["pdf", "lhapdf", "pdfset"],
+            ),
+            WeightConvention::Sherpa => unreachable!(),
+        };
+        let mur = get(mur_keys).and_then(parse_number);
+        let muf = get(muf_keys).and_then(parse_number);
+        let pdf = get(pdf_keys).and_then(|pdf| pdf.parse::<i32>().ok());
+        variation(mur, muf, pdf)
+    }
+}
+
+/// Determine the variation from a Sherpa weight ID
+///
+/// IDs with components other than `MUR`, `MUF`, and `PDF`, for example
+/// `ME_ONLY_MUR2_MUF2_PDF303200`, are not classified.
+fn classify_sherpa(id: &str) -> Variation {
+    let (mut mur, mut muf, mut pdf) = (None, None, None);
+    for component in id.split('_') {
+        if let Some(value) = component.strip_prefix("MUR") {
+            mur = Some(parse_number(value));
+        } else if let Some(value) = component.strip_prefix("MUF") {
+            muf = Some(parse_number(value));
+        } else if let Some(value) = component.strip_prefix("PDF") {
+            pdf = Some(value.parse::<i32>().ok());
+        } else {
+            return Variation::Other;
+        }
+    }
+    match (mur, muf, pdf) {
+        (Some(None), _, _) | (_, Some(None), _) | (_, _, Some(None)) => Variation::Other,
+        (mur, muf, pdf) => variation(mur.flatten(), muf.flatten(), pdf.flatten()),
+    }
+}
+
+/// Combine the parsed scale factors and PDF into a variation
+fn variation(mur: Option<f64>, muf: Option<f64>, pdf: Option<i32>) -> Variation {
+    if mur.is_none() && muf.is_none() {
+        return match pdf {
+            Some(lhapdf_id) => Variation::Pdf { lhapdf_id },
+            None => Variation::Other,
+        };
+    }
+    let mur = mur.unwrap_or(1.);
+    let muf = muf.unwrap_or(1.);
+    match pdf {
+        _ if mur != 1. || muf != 1. => Variation::Scale { mur, muf },
+        Some(lhapdf_id) => Variation::Pdf { lhapdf_id },
+        None => Variation::Nominal,
+    }
+}
+
+impl SystematicWeights {
+    /// Classify all declared weights according to the given convention
+    ///
+    /// If several weights are classified as nominal, the first one is
+    /// used and the others are treated as unclassified.
+    pub fn new(initrwgt: &InitRwgt, convention: WeightConvention) -> SystematicWeights {
+        let mut result = SystematicWeights::default();
+        for weight in initrwgt.weights() {
+            result.push(weight.id.clone(), convention.classify(weight));
+        }
+        result
+    }
+
+    fn push(&mut self, id: String, variation: Variation) {
+        match variation {
+            Variation::Nominal if self.nominal.is_none() => self.nominal = Some(id),
+            Variation::Scale { mur, muf } => self.scale.push(ScaleVariation { id, mur, muf }),
+            Variation::Pdf { lhapdf_id } => self.pdf.push(PdfVariation { id, lhapdf_id }),
+            _ => self.other.push(id),
+        }
+    }
+}
+
+/// Split text of the form `key1=value1 key2 = value2` into pairs with
+/// lower-case keys
+fn key_values(text: &str) -> Vec<(String, String)> {
+    let normalised = text.replace('=', " = ");
+    let words: Vec<_> = normalised.split_whitespace().collect();
+    let mut result = Vec::new();
+    for i in 1..words.len().saturating_sub(1) {
+        if words[i] == "=" && words[i - 1] != "=" && words[i + 1] != "=" {
+            result.push((words[i - 1].to_lowercase(), words[i + 1].to_owned()));
+        }
+    }
+    result
+}
+
+/// Parse a number, also accepting Fortran-style exponents like `1d0`
+fn parse_number(text: &str) -> Option<f64> {
+    text.parse()
+        .ok()
+        .or_else(|| text.replace(['d', 'D'], "e").parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use initrwgt::WeightEntry;
+    use Attributes;
+
+    fn weight(id: &str, attr: &[(&str, &str)], description: &str) -> WeightInfo {
+        WeightInfo {
+            id: id.to_owned(),
+            attr: Attributes::from(attr.to_vec()),
+            description: description.to_owned(),
+        }
+    }
+
+    #[test]
+    fn powheg() {
+        let initrwgt = InitRwgt {
+            entries: vec![
+                weight("11", &[], " muR=0.10000E+01 muF=0.10000E+01 "),
+                weight("12", &[], " muR=0.20000E+01 muF=0.10000E+01 "),
+                weight("13", &[], " renscfact=0.5d0 facscfact=0.5d0 "),
+                weight("2001", &[], " pdf=260001 "),
+                weight("2002", &[], " lhapdf = 260002 "),
+                weight("3001", &[], " some other weight "),
+            ]
+            .into_iter()
+            .map(WeightEntry::Weight)
+            .collect(),
+        };
+        let systematics = SystematicWeights::new(&initrwgt, WeightConvention::Powheg);
+        assert_eq!(systematics.nominal, Some("11".to_owned()));
+        assert_eq!(
+            systematics.scale,
+            [
+                ScaleVariation { id: "12".to_owned(), mur: 2., muf: 1. },
+                ScaleVariation { id: "13".to_owned(), mur: 0.5, muf: 0.5 },
+            ]
+        );
+        let pdf: Vec<_> = systematics.pdf.iter().map(|p| p.lhapdf_id).collect();
+        assert_eq!(pdf, [260001, 260002]);
+        assert_eq!(systematics.other, ["3001"]);
+    }
+
+    #[test]
+    fn madgraph() {
+        let convention = WeightConvention::MadGraph;
+        let scale = weight("1002", &[("MUR", "2.0"), ("MUF", "1.0")], "mur=1 muf=1");
+        assert_eq!(convention.classify(&scale), Variation::Scale { mur: 2., muf: 1. });
+        let pdf = weight("2001", &[], "PDF=260001 MemberID=1");
+        assert_eq!(convention.classify(&pdf), Variation::Pdf { lhapdf_id: 260001 });
+        let nominal = weight("1001", &[], " mur=1.0 muf=1.0 ");
+        assert_eq!(convention.classify(&nominal), Variation::Nominal);
+        let renscfact = weight("1", &[], "renscfact=2d0");
+        assert_eq!(convention.classify(&renscfact), Variation::Other);
+    }
+
+    #[test]
+    fn sherpa() {
+        let initrwgt = InitRwgt {
+            entries: vec![
+                weight("MUR1_MUF1_PDF303200", &[], ""),
+                weight("MUR0.5_MUF0.5_PDF303200", &[], ""),
+                weight("MUR2_MUF1_PDF303200", &[], ""),
+                weight("MUR1_MUF1_PDF303201", &[], ""),
+                weight("ME_ONLY_MUR2_MUF2_PDF303200", &[], ""),
+                weight("MURx_MUF1_PDF303200", &[], ""),
+            ]
+            .into_iter()
+            .map(WeightEntry::Weight)
+            .collect(),
+        };
+        let systematics = SystematicWeights::new(&initrwgt, WeightConvention::Sherpa);
+        assert_eq!(systematics.nominal, None);
+        assert_eq!(
+            systematics.scale,
+            [
+                ScaleVariation { id: "MUR0.5_MUF0.5_PDF303200".to_owned(), mur: 0.5, muf: 0.5 },
+                ScaleVariation { id: "MUR2_MUF1_PDF303200".to_owned(), mur: 2., muf: 1. },
+            ]
+        );
+        let pdf: Vec<_> = systematics.pdf.iter().map(|p| p.lhapdf_id).collect();
+        assert_eq!(pdf, [303200, 303201]);
+        assert_eq!(
+            systematics.other,
+            ["ME_ONLY_MUR2_MUF2_PDF303200", "MURx_MUF1_PDF303200"]
+        );
+    }
+}
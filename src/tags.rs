@@ -0,0 +1,376 @@
+use std::ops::Range;
+
+const COMMENT_START: &str = "<!--";
+const COMMENT_END: &str = "-->";
+pub(crate) const HEADER_TAG: &str = "header";
+
+/// An XML-like tag found in the optional information of a LHEF block
+///
+/// This is deliberately minimal: attribute values are returned verbatim
+/// and the content of the tag, including any nested tags, is available
+/// as unparsed text.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Tag<'a> {
+    name: &'a str,
+    attributes: Vec<(&'a str, &'a str)>,
+    content: &'a str,
+}
+
+impl<'a> Tag<'a> {
+    /// Get the tag name
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Get the value of the attribute with the given name
+    pub fn attribute(&self, name: &str) -> Option<&'a str> {
+        self.attributes
+            .iter()
+            .find(|&&(key, _)| key == name)
+            .map(|&(_, value)| value)
+    }
+
+    /// Get all attributes in the order in which they appear
+    pub fn attributes(&self) -> &[(&'a str, &'a str)] {
+        &self.attributes
+    }
+
+    /// Get the text between the opening and closing tag
+    ///
+    /// The content of self-closing tags is empty.
+    pub fn content(&self) -> &'a str {
+        self.content
+    }
+}
+
+/// Iterator over the top-level tags in a text
+///
+/// Yields each tag together with the byte range it occupies.
+/// Comments, stray closing tags, and unterminated tags are treated as
+/// plain text and skipped.
+pub(crate) struct Tags<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+pub(crate) fn tags(text: &str) -> Tags<'_> {
+    Tags { text, pos: 0 }
+}
+
+impl<'a> Iterator for Tags<'a> {
+    type Item = (Range<usize>, Tag<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(offset) = self.text[self.pos..].find('<') {
+            let start = self.pos + offset;
+            let rest = &self.text[start..];
+            if rest.starts_with(COMMENT_START) {
+                self.pos = match rest.find(COMMENT_END) {
+                    Some(end) => start + end + COMMENT_END.len(),
+                    None => self.text.len(),
+                };
+                continue;
+            }
+            self.pos = start + 1;
+            if let Some((len, tag)) = parse_tag(rest) {
+                self.pos = start + len;
+                return Some((start..start + len, tag));
+            }
+        }
+        self.pos = self.text.len();
+        None
+    }
+}
+
+/// Parse the tag at the beginning of `text`
+///
+/// Returns the length of the complete element and the tag.
+fn parse_tag(text: &str) -> Option<(usize, Tag<'_>)> {
+    debug_assert!(text.starts_with('<'));
+    let name_len = text[1..]
+        .find(|c: char| !is_name_char(c))
+        .unwrap_or(text.len() - 1);
+    if name_len == 0 {
+        return None;
+    }
+    let name = &text[1..1 + name_len];
+    let (attr_len, attributes, self_closing) =
+        parse_attributes_until_close(&text[1 + name_len..])?;
+    let content_start = 1 + name_len + attr_len;
+    if self_closing {
+        return Some((content_start, Tag { name, attributes, content: "" }));
+    }
+    let content_len = find_closing_tag(&text[content_start..], name)?;
+    let content = &text[content_start..content_start + content_len];
+    let len = content_start + content_len + name.len() + 3;
+    Some((len, Tag { name, attributes, content }))
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-' || c == ':' || c == '.'
+}
+
+type Attributes<'a> = Vec<(&'a str, &'a str)>;
+
+/// Parse attributes up to and including the end of the opening tag
+///
+/// Returns the number of consumed bytes, the attributes, and whether
+/// the tag is self-closing.
+fn parse_attributes_until_close(text: &str) -> Option<(usize, Attributes<'_>, bool)> {
+    let mut attributes = Vec::new();
+    let mut pos = 0;
+    loop {
+        pos += whitespace_len(&text[pos..]);
+        let rest = &text[pos..];
+        if rest.starts_with("/>") {
+            return Some((pos + 2, attributes, true));
+        }
+        if rest.starts_with('>') {
+            return Some((pos + 1, attributes, false));
+        }
+        let (len, key, value) = parse_attribute(rest)?;
+        attributes.push((key, value));
+        pos += len;
+    }
+}
+
+/// Parse the line as an opening tag `<name ...>` with optional attributes
+///
+/// Returns `None` if the line is not such a tag.
+pub(crate) fn opening_tag<'a>(line: &'a str, name: &str) -> Option<Attributes<'a>> {
+    let line = line.trim();
+    let rest = line.strip_prefix('<')?.strip_prefix(name)?;
+    let inner = rest.strip_suffix('>')?;
+    if !inner.is_empty() && !inner.starts_with(char::is_whitespace) {
+        return None;
+    }
+    parse_attributes(inner)
+}
+
+/// Rewrite a line consisting of one of the tags `names` in the
+/// canonical form
+///
+/// The tag name is matched case-insensitively and whitespace around the
+/// name, e.g. in `< /Event >`, is removed. Attributes are kept
+/// verbatim. Returns `None` if the line is not one of these tags.
+pub(crate) fn normalize_tag(line: &str, names: &[&str]) -> Option<String> {
+    let inner = line.trim().strip_prefix('<')?.strip_suffix('>')?.trim();
+    let (slash, inner) = match inner.strip_prefix('/') {
+        Some(inner) => ("/", inner.trim_start()),
+        None => ("", inner),
+    };
+    let name_len = inner.find(|c: char| !is_name_char(c)).unwrap_or(inner.len());
+    let name = names.iter().find(|name| name.eq_ignore_ascii_case(&inner[..name_len]))?;
+    let rest = &inner[name_len..];
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let newline = if line.ends_with('\n') { "\n" } else { "" };
+    let separator = if rest.is_empty() { "" } else { " " };
+    Some(format!("<{}{}{}{}>{}", slash, name, separator, rest.trim(), newline))
+}
+
+/// Get the name of the opening tag at the start of the line
+///
+/// Returns `None` if the line does not start with an opening tag.
+pub(crate) fn tag_name(line: &str) -> Option<&str> {
+    let rest = line.trim_start().strip_prefix('<')?;
+    let len = rest.find(|c: char| !is_name_char(c)).unwrap_or(rest.len());
+    if len == 0 {
+        return None;
+    }
+    Some(&rest[..len])
+}
+
+/// Parse all attributes in `text`, e.g. the inside of an opening tag
+pub(crate) fn parse_attributes(text: &str) -> Option<Attributes<'_>> {
+    let mut attributes = Vec::new();
+    let mut pos = 0;
+    loop {
+        pos += whitespace_len(&text[pos..]);
+        if pos == text.len() {
+            return Some(attributes);
+        }
+        let (len, key, value) = parse_attribute(&text[pos..])?;
+        attributes.push((key, value));
+        pos += len;
+    }
+}
+
+/// Parse a single `key="value"` or `key='value'` pair
+fn parse_attribute(text: &str) -> Option<(usize, &str, &str)> {
+    let key_len = text.find(|c: char| !is_name_char(c))?;
+    if key_len == 0 {
+        return None;
+    }
+    let key = &text[..key_len];
+    let mut pos = key_len + whitespace_len(&text[key_len..]);
+    if !text[pos..].starts_with('=') {
+        return None;
+    }
+    pos += 1;
+    pos += whitespace_len(&text[pos..]);
+    let quote = text[pos..].chars().next()?;
+    if quote != '"' && quote != '\'' {
+        return None;
+    }
+    pos += 1;
+    let value_len = text[pos..].find(quote)?;
+    let value = &text[pos..pos + value_len];
+    Some((pos + value_len + 1, key, value))
+}
+
+fn whitespace_len(text: &str) -> usize {
+    text.len() - text.trim_start().len()
+}
+
+/// Find the position of `</name>` matching an already opened tag
+fn find_closing_tag(text: &str, name: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut pos = 0;
+    while let Some(offset) = text[pos..].find('<') {
+        let start = pos + offset;
+        let rest = &text[start + 1..];
+        if let Some(rest) = rest.strip_prefix('/') {
+            if rest.starts_with(name) && rest[name.len()..].starts_with('>') {
+                if depth == 0 {
+                    return Some(start);
+                }
+                depth -= 1;
+            }
+        } else if rest.starts_with(name)
+            && !rest[name.len()..].starts_with(is_name_char)
+        {
+            let self_closing = rest
+                .find('>')
+                .map(|end| rest[..end].ends_with('/'))
+                .unwrap_or(false);
+            if !self_closing {
+                depth += 1;
+            }
+        }
+        pos = start + 1;
+    }
+    None
+}
+
+/// Find the first tag with one of the given names in a LHEF header
+///
+/// Such tags are usually nested inside a `<header>` tag, but are also
+/// found at the top level.
+pub(crate) fn header_tag<'a>(header: &'a str, names: &[&str]) -> Option<Tag<'a>> {
+    for (_, tag) in tags(header) {
+        if names.contains(&tag.name()) {
+            return Some(tag);
+        }
+        if tag.name() == HEADER_TAG {
+            if let Some(tag) = header_tag(tag.content(), names) {
+                return Some(tag);
+            }
+        }
+    }
+    None
+}
+
+/// Remove the given byte ranges from `text`
+///
+/// The ranges have to be sorted and must not overlap. Lines that only
+/// contained removed text are dropped completely.
+pub(crate) fn remove_ranges(text: &str, ranges: &[Range<usize>]) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut pos = 0;
+    for range in ranges {
+        result.push_str(&text[pos..range.start]);
+        pos = range.end;
+        let line_start = result.rfind('\n').map(|n| n + 1).unwrap_or(0);
+        if result[line_start..].trim().is_empty() {
+            let rest = &text[pos..];
+            let line_end = rest.find('\n').map(|n| n + 1).unwrap_or(rest.len());
+            if rest[..line_end].trim().is_empty() {
+                result.truncate(line_start);
+                pos += line_end;
+            }
+        }
+    }
+    result.push_str(&text[pos..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_tags() {
+        let text = "free text\n<!-- <ignored/> -->\n\
+                    <scales mups='-1' muf = \"2\"/>\n\
+                    <rwgt>\n<wgt id=\"1\">1.</wgt>\n</rwgt>\n";
+        let found: Vec<_> = tags(text).map(|(_, tag)| tag).collect();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].name(), "scales");
+        assert_eq!(found[0].attribute("mups"), Some("-1"));
+        assert_eq!(found[0].attribute("muf"), Some("2"));
+        assert_eq!(found[0].content(), "");
+        assert_eq!(found[1].name(), "rwgt");
+        assert_eq!(found[1].content(), "\n<wgt id=\"1\">1.</wgt>\n");
+    }
+
+    #[test]
+    fn nested_tags_of_same_name() {
+        let text = "<a><a>x</a><a/></a>tail";
+        let (range, tag) = tags(text).next().unwrap();
+        assert_eq!(range, 0..text.len() - 4);
+        assert_eq!(tag.content(), "<a>x</a><a/>");
+    }
+
+    #[test]
+    fn opening() {
+        assert_eq!(opening_tag(" <event>\n", "event"), Some(vec![]));
+        assert_eq!(
+            opening_tag("<event npLO='1' npNLO=\"-1\">", "event"),
+            Some(vec![("npLO", "1"), ("npNLO", "-1")])
+        );
+        assert_eq!(opening_tag("<eventgroup>", "event"), None);
+        assert_eq!(opening_tag("<event npLO>", "event"), None);
+    }
+
+    #[test]
+    fn normalize() {
+        let names = ["event", "LesHouchesEvents"];
+        assert_eq!(normalize_tag("<Event>\n", &names).unwrap(), "<event>\n");
+        assert_eq!(normalize_tag(" < /EVENT >", &names).unwrap(), "</event>");
+        assert_eq!(
+            normalize_tag("<event  npLO='1' >\n", &names).unwrap(),
+            "<event npLO='1'>\n"
+        );
+        assert_eq!(
+            normalize_tag("</lesHouchesEvents>", &names).unwrap(),
+            "</LesHouchesEvents>"
+        );
+        assert_eq!(normalize_tag("<eventgroup>", &names), None);
+        assert_eq!(normalize_tag("<Event", &names), None);
+        assert_eq!(normalize_tag("1 2 3", &names), None);
+    }
+
+    #[test]
+    fn name() {
+        assert_eq!(tag_name("  <weights>1 2</weights>\n"), Some("weights"));
+        assert_eq!(tag_name("<mg:info a='1'/>"), Some("mg:info"));
+        assert_eq!(tag_name("</event>"), None);
+        assert_eq!(tag_name("<!-- comment -->"), None);
+        assert_eq!(tag_name("1 2 3"), None);
+    }
+
+    #[test]
+    fn unterminated_tags_are_text() {
+        assert_eq!(tags("<a> no end").count(), 0);
+        assert_eq!(tags("a < b").count(), 0);
+    }
+
+    #[test]
+    fn remove() {
+        let text = "keep\n<a/>\nkeep <b/> too\n";
+        let ranges: Vec<_> = tags(text).map(|(range, _)| range).collect();
+        assert_eq!(remove_ranges(text, &ranges), "keep\nkeep  too\n");
+    }
+}
@@ -0,0 +1,100 @@
+use std::cmp;
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// Size of the chunks read when scanning backwards
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+/// Read the end of a stream, starting at the `n`th to last event
+///
+/// Events are recognised by lines starting with `<event>` or `<event `.
+/// If there are fewer than `n` events, the text from the first event
+/// onwards is returned, or an empty string if there is no event at all.
+pub(crate) fn read_last_events<Stream: Read + Seek>(
+    stream: &mut Stream,
+    n: usize,
+) -> io::Result<String> {
+    read_last_events_in_chunks(stream, n, CHUNK_SIZE)
+}
+
+fn read_last_events_in_chunks<Stream: Read + Seek>(
+    stream: &mut Stream,
+    n: usize,
+    chunk_size: u64,
+) -> io::Result<String> {
+    let mut start = stream.seek(SeekFrom::End(0))?;
+    let mut tail = Vec::new();
+    let starts = loop {
+        let len = cmp::min(chunk_size, start);
+        start -= len;
+        stream.seek(SeekFrom::Start(start))?;
+        let mut chunk = Vec::with_capacity(len as usize + tail.len());
+        stream.by_ref().take(len).read_to_end(&mut chunk)?;
+        chunk.append(&mut tail);
+        tail = chunk;
+        let starts = event_starts(&tail, start == 0);
+        if starts.len() >= n || start == 0 {
+            break starts;
+        }
+    };
+    let first = match n {
+        0 => tail.len(),
+        n => *starts.get(starts.len().saturating_sub(n)).unwrap_or(&tail.len()),
+    };
+    tail.drain(..first);
+    String::from_utf8(tail).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// Find the positions of all lines starting an event
+///
+/// The first line is only considered if `at_line_start` is true, since
+/// it may be the end of a longer line otherwise.
+fn event_starts(text: &[u8], at_line_start: bool) -> Vec<usize> {
+    const START: &[u8] = b"<event";
+    let mut starts = Vec::new();
+    let mut pos = if at_line_start { 0 } else { next_line(text, 0) };
+    while pos < text.len() {
+        let rest = &text[pos..];
+        if rest.starts_with(START) {
+            match rest.get(START.len()) {
+                Some(b'>') | Some(b' ') | Some(b'\t') => starts.push(pos),
+                _ => {}
+            }
+        }
+        pos = next_line(text, pos);
+    }
+    starts
+}
+
+/// Get the start of the line after the one containing `pos`
+fn next_line(text: &[u8], pos: usize) -> usize {
+    match text[pos..].iter().position(|&c| c == b'\n') {
+        Some(len) => pos + len + 1,
+        None => text.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn last_events() {
+        let text = "<LesHouchesEvents version=\"1.0\">\n\
+                    <event>\n1\n</event>\n\
+                    <eventgroup>\n<event a=\"1\">\n2\n</event>\n</eventgroup>\n\
+                    <event>\n3\n</event>\n\
+                    </LesHouchesEvents>\n";
+        let mut stream = Cursor::new(text);
+        let last = read_last_events(&mut stream, 2).unwrap();
+        assert!(last.starts_with("<event a=\"1\">\n2\n"));
+        let all = read_last_events(&mut stream, 5).unwrap();
+        assert!(all.starts_with("<event>\n1\n"));
+        assert_eq!(read_last_events(&mut stream, 0).unwrap(), "");
+        for chunk_size in 1..10 {
+            let last = read_last_events_in_chunks(&mut stream, 2, chunk_size).unwrap();
+            assert!(last.starts_with("<event a=\"1\">\n2\n"));
+        }
+        assert_eq!(event_starts(b"x<event>\n<eventgroup>\n<event>", false), [22]);
+    }
+}
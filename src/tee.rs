@@ -0,0 +1,291 @@
+use std::error;
+use std::io::{BufRead, Write};
+
+use {Block, Reader, Writer, HEPEUP, HEPRUP};
+
+/// Destination for the header, run information, and events of an
+/// event file
+///
+/// This is implemented by `Writer` and `Tee`. Other output formats, for
+/// example a JSON Lines mirror of the events, can implement it to be
+/// used as a sink of a `Tee`. Only `heprup` and `event` have to be
+/// implemented; by default, headers are ignored and the events of a
+/// group are passed to `event` one by one.
+pub trait EventSink {
+    /// Write a header
+    fn header(&mut self, _header: &str) -> Result<(), Box<dyn error::Error>> {
+        Ok(())
+    }
+
+    /// Write the run information
+    fn heprup(&mut self, heprup: &HEPRUP) -> Result<(), Box<dyn error::Error>>;
+
+    /// Write an event
+    fn event(&mut self, event: &HEPEUP) -> Result<(), Box<dyn error::Error>>;
+
+    /// Write a group of correlated events
+    fn event_group(&mut self, events: &[HEPEUP]) -> Result<(), Box<dyn error::Error>> {
+        for event in events {
+            self.event(event)?;
+        }
+        Ok(())
+    }
+
+    /// Close the output
+    fn finish(&mut self) -> Result<(), Box<dyn error::Error>> {
+        Ok(())
+    }
+}
+
+impl<Stream: Write> EventSink for Writer<Stream> {
+    fn header(&mut self, header: &str) -> Result<(), Box<dyn error::Error>> {
+        Writer::header(self, header)
+    }
+
+    fn heprup(&mut self, heprup: &HEPRUP) -> Result<(), Box<dyn error::Error>> {
+        Writer::heprup(self, heprup)
+    }
+
+    fn event(&mut self, event: &HEPEUP) -> Result<(), Box<dyn error::Error>> {
+        Writer::event(self, event)
+    }
+
+    fn event_group(&mut self, events: &[HEPEUP]) -> Result<(), Box<dyn error::Error>> {
+        Writer::event_group(self, events)
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn error::Error>> {
+        Writer::finish(self)
+    }
+}
+
+impl<S: EventSink + ?Sized> EventSink for &mut S {
+    fn header(&mut self, header: &str) -> Result<(), Box<dyn error::Error>> {
+        (**self).header(header)
+    }
+
+    fn heprup(&mut self, heprup: &HEPRUP) -> Result<(), Box<dyn error::Error>> {
+        (**self).heprup(heprup)
+    }
+
+    fn event(&mut self, event: &HEPEUP) -> Result<(), Box<dyn error::Error>> {
+        (**self).event(event)
+    }
+
+    fn event_group(&mut self, events: &[HEPEUP]) -> Result<(), Box<dyn error::Error>> {
+        (**self).event_group(events)
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn error::Error>> {
+        (**self).finish()
+    }
+}
+
+type Filter<'a> = Box<dyn FnMut(&HEPEUP) -> bool + 'a>;
+
+/// Write the same events to several sinks
+///
+/// Each sink can have a filter selecting the events it receives, so a
+/// single pass over the input can for example produce a full copy
+/// together with a skim. An `<eventgroup>` is passed on as a whole if
+/// any of its events passes the filter, since the events in a group
+/// are correlated. Errors of any sink are passed on immediately.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use std::fs::File;
+/// use std::io::{BufReader, BufWriter};
+///
+/// let input = BufReader::new(File::open("events.lhe").unwrap());
+/// let mut reader = lhef::Reader::new(input).unwrap();
+/// let version = reader.version().to_owned();
+/// let copy = BufWriter::new(File::create("copy.lhe").unwrap());
+/// let skim = BufWriter::new(File::create("skim.lhe").unwrap());
+/// let mut tee = lhef::Tee::new()
+///     .with_sink(lhef::Writer::new(copy, &version).unwrap())
+///     .with_filtered_sink(
+///         lhef::Writer::new(skim, &version).unwrap(),
+///         |event| event.XWGTUP > 0.,
+///     );
+/// tee.copy_from(&mut reader).unwrap();
+/// ```
+#[derive(Default)]
+pub struct Tee<'a> {
+    sinks: Vec<(Box<dyn EventSink + 'a>, Option<Filter<'a>>)>,
+}
+
+impl<'a> Tee<'a> {
+    /// Create a `Tee` without any sinks
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a sink receiving all events
+    pub fn with_sink<S: EventSink + 'a>(mut self, sink: S) -> Self {
+        self.sinks.push((Box::new(sink), None));
+        self
+    }
+
+    /// Add a sink receiving only the events for which `filter` returns
+    /// `true`
+    pub fn with_filtered_sink<S, F>(mut self, sink: S, filter: F) -> Self
+    where
+        S: EventSink + 'a,
+        F: FnMut(&HEPEUP) -> bool + 'a,
+    {
+        self.sinks.push((Box::new(sink), Some(Box::new(filter))));
+        self
+    }
+
+    /// Number of sinks
+    pub fn len(&self) -> usize {
+        self.sinks.len()
+    }
+
+    /// Whether there are no sinks
+    pub fn is_empty(&self) -> bool {
+        self.sinks.is_empty()
+    }
+
+    /// Write an event or a group of events
+    pub fn block(&mut self, block: &Block) -> Result<(), Box<dyn error::Error>> {
+        match *block {
+            Block::Event(ref event) => self.event(event),
+            Block::EventGroup(ref events) => self.event_group(events),
+        }
+    }
+
+    /// Write the header, run information, and all remaining events of
+    /// `reader` to all sinks and close them
+    pub fn copy_from<Stream: BufRead>(
+        &mut self,
+        reader: &mut Reader<Stream>,
+    ) -> Result<(), Box<dyn error::Error>> {
+        self.header(reader.header())?;
+        self.heprup(reader.heprup())?;
+        while let Some(block) = reader.block()? {
+            self.block(&block)?;
+        }
+        self.finish()
+    }
+}
+
+impl<'a> EventSink for Tee<'a> {
+    fn header(&mut self, header: &str) -> Result<(), Box<dyn error::Error>> {
+        for (sink, _) in &mut self.sinks {
+            sink.header(header)?;
+        }
+        Ok(())
+    }
+
+    fn heprup(&mut self, heprup: &HEPRUP) -> Result<(), Box<dyn error::Error>> {
+        for (sink, _) in &mut self.sinks {
+            sink.heprup(heprup)?;
+        }
+        Ok(())
+    }
+
+    fn event(&mut self, event: &HEPEUP) -> Result<(), Box<dyn error::Error>> {
+        for (sink, filter) in &mut self.sinks {
+            if filter.as_mut().is_none_or(|filter| filter(event)) {
+                sink.event(event)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn event_group(&mut self, events: &[HEPEUP]) -> Result<(), Box<dyn error::Error>> {
+        for (sink, filter) in &mut self.sinks {
+            if filter.as_mut().is_none_or(|filter| events.iter().any(filter)) {
+                sink.event_group(events)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn error::Error>> {
+        for (sink, _) in &mut self.sinks {
+            sink.finish()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_util::{MockEvent, SyntheticEvents};
+
+    /// Sink recording the weights of the events
+    #[derive(Default)]
+    struct Weights(Vec<f64>);
+
+    impl EventSink for Weights {
+        fn heprup(&mut self, _heprup: &HEPRUP) -> Result<(), Box<dyn error::Error>> {
+            Ok(())
+        }
+
+        fn event(&mut self, event: &HEPEUP) -> Result<(), Box<dyn error::Error>> {
+            self.0.push(event.XWGTUP);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn tee() {
+        let sample = SyntheticEvents::new().num_events(20).to_bytes();
+        let mut copy = Vec::new();
+        let mut skim = Vec::new();
+        let mut weights = Weights::default();
+        {
+            let mut reader = Reader::new(sample.as_slice()).unwrap();
+            let mut tee = Tee::new()
+                .with_sink(Writer::new(&mut copy, "1.0").unwrap())
+                .with_filtered_sink(Writer::new(&mut skim, "1.0").unwrap(), |event| {
+                    event.PUP[2][2] > 0.
+                })
+                .with_sink(&mut weights);
+            assert_eq!(tee.len(), 3);
+            tee.copy_from(&mut reader).unwrap();
+        }
+        assert_eq!(copy, sample);
+
+        let mut reader = Reader::new(sample.as_slice()).unwrap();
+        let heprup = reader.heprup().clone();
+        let mut expected = Vec::new();
+        let mut selected = Vec::new();
+        while let Some(event) = reader.event().unwrap() {
+            expected.push(event.XWGTUP);
+            if event.PUP[2][2] > 0. {
+                selected.push(event);
+            }
+        }
+        assert_eq!(weights.0, expected);
+        assert!(!selected.is_empty() && selected.len() < 20);
+        let mut reader = Reader::new(skim.as_slice()).unwrap();
+        assert_eq!(reader.heprup(), &heprup);
+        for event in selected {
+            assert_eq!(reader.event().unwrap(), Some(event));
+        }
+        assert_eq!(reader.event().unwrap(), None);
+    }
+
+    #[test]
+    fn event_group() {
+        let group = [MockEvent::dijet().with_weight(1.).build(), MockEvent::dijet().build()];
+        let mut all = Weights::default();
+        let mut none = Weights::default();
+        let mut some = Weights::default();
+        {
+            let mut tee = Tee::new()
+                .with_sink(&mut all)
+                .with_filtered_sink(&mut none, |_| false)
+                .with_filtered_sink(&mut some, |event| event.XWGTUP == 1.);
+            tee.block(&Block::EventGroup(group.to_vec())).unwrap();
+        }
+        assert_eq!(all.0.len(), 2);
+        assert!(none.0.is_empty());
+        assert_eq!(some.0.len(), 2);
+    }
+}
@@ -0,0 +1,471 @@
+//! Synthetic events for tests and benchmarks
+//!
+//! Tests and benchmarks often need event files of a given size and
+//! shape. `SyntheticEvents` generates them in memory, so that no
+//! fixtures have to be shipped. The same settings and seed always
+//! produce the same output. Single events for unit tests can be
+//! created with `MockEvent`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use lhef::test_util::SyntheticEvents;
+//!
+//! let sample = SyntheticEvents::new()
+//!     .num_events(100)
+//!     .multiplicity(8)
+//!     .num_weights(3)
+//!     .to_bytes();
+//! let mut reader = lhef::Reader::new(sample.as_slice()).unwrap();
+//! let mut count = 0;
+//! while let Some(event) = reader.event().unwrap() {
+//!     assert_eq!(event.NUP, 8);
+//!     count += 1;
+//! }
+//! assert_eq!(count, 100);
+//! ```
+
+use std::error;
+use std::f64::consts::PI;
+use std::io::Write;
+
+use random::Rng;
+use {Attributes, Extensions, Weight, WeightFormat, Weights, Writer, HEPEUP, HEPRUP};
+
+const BEAM_ENERGY: f64 = 6500.;
+const CROSS_SECTION: f64 = 100.;
+const OUTGOING_IDS: [i32; 11] = [21, 1, -1, 2, -2, 3, -3, 4, -4, 5, -5];
+
+/// Generator of synthetic event samples
+///
+/// The events describe proton-proton collisions at 13 TeV with
+/// massless partons in the final state. Energy and momentum are
+/// conserved, and the event weights fluctuate around the mean expected
+/// for a cross section of 100 pb. Additional weights are written in
+/// `<rwgt>` tags and declared in the header.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct SyntheticEvents {
+    num_events: usize,
+    multiplicity: usize,
+    num_weights: usize,
+    seed: u64,
+}
+
+impl Default for SyntheticEvents {
+    fn default() -> SyntheticEvents {
+        SyntheticEvents {
+            num_events: 1000,
+            multiplicity: 4,
+            num_weights: 0,
+            seed: 0,
+        }
+    }
+}
+
+impl SyntheticEvents {
+    /// Create a generator for 1000 events with 4 particles each and
+    /// no additional weights
+    pub fn new() -> SyntheticEvents {
+        SyntheticEvents::default()
+    }
+
+    /// Set the number of events
+    pub fn num_events(mut self, num_events: usize) -> SyntheticEvents {
+        self.num_events = num_events;
+        self
+    }
+
+    /// Set the number of particles in each event, including the two
+    /// incoming ones
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are fewer than three particles.
+    pub fn multiplicity(mut self, multiplicity: usize) -> SyntheticEvents {
+        assert!(multiplicity >= 3, "Events need at least three particles");
+        self.multiplicity = multiplicity;
+        self
+    }
+
+    /// Set the number of additional weights in each event
+    pub fn num_weights(mut self, num_weights: usize) -> SyntheticEvents {
+        self.num_weights = num_weights;
+        self
+    }
+
+    /// Set the seed of the random number generator
+    pub fn seed(mut self, seed: u64) -> SyntheticEvents {
+        self.seed = seed;
+        self
+    }
+
+    /// The header, declaring the additional weights if there are any
+    pub fn header(&self) -> String {
+        if self.num_weights == 0 {
+            return String::new();
+        }
+        let mut header = String::from("<header>\n<initrwgt>\n<weightgroup name=\"synthetic\">\n");
+        for id in 1..=self.num_weights {
+            header += &format!("<weight id=\"{}\">variation {}</weight>\n", id, id);
+        }
+        header + "</weightgroup>\n</initrwgt>\n</header>"
+    }
+
+    /// The run information
+    pub fn heprup(&self) -> HEPRUP {
+        HEPRUP {
+            IDBMUP: [2212, 2212],
+            EBMUP: [BEAM_ENERGY, BEAM_ENERGY],
+            PDFGUP: [0, 0],
+            PDFSUP: [0, 0],
+            IDWTUP: 4,
+            NPRUP: 1,
+            XSECUP: vec![CROSS_SECTION],
+            XERRUP: vec![CROSS_SECTION / (self.num_events.max(1) as f64).sqrt()],
+            XMAXUP: vec![1.5 * self.mean_weight()],
+            LPRUP: vec![1],
+            info: String::new(),
+            extensions: Extensions::new(),
+        }
+    }
+
+    /// The events
+    pub fn events(&self) -> impl Iterator<Item = HEPEUP> {
+        let generator = self.clone();
+        let mut rng = Rng::new(self.seed);
+        (0..self.num_events).map(move |_| generator.event(&mut rng))
+    }
+
+    /// Write the complete sample
+    pub fn write<Stream: Write>(&self, stream: Stream) -> Result<(), Box<dyn error::Error>> {
+        let version = if self.num_weights == 0 { "1.0" } else { "3.0" };
+        let mut writer = Writer::new(stream, version)?;
+        let header = self.header();
+        if !header.is_empty() {
+            writer.header(&header)?;
+        }
+        writer.heprup(&self.heprup())?;
+        for event in self.events() {
+            writer.event(&event)?;
+        }
+        writer.finish()
+    }
+
+    /// Write the complete sample to a buffer
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.write(&mut buf).expect("Writing to a buffer does not fail");
+        buf
+    }
+
+    fn mean_weight(&self) -> f64 {
+        CROSS_SECTION / self.num_events.max(1) as f64
+    }
+
+    fn event(&self, rng: &mut Rng) -> HEPEUP {
+        let n = self.multiplicity;
+        let mut event = HEPEUP {
+            NUP: n as i32,
+            IDRUP: 1,
+            XWGTUP: self.mean_weight() * (0.5 + rng.uniform()),
+            SCALUP: 0.,
+            AQEDUP: 1. / 128.,
+            AQCDUP: 0.118,
+            IDUP: vec![21, 21],
+            ISTUP: vec![-1, -1],
+            MOTHUP: vec![[0, 0]; 2],
+            ICOLUP: vec![[0, 0]; n],
+            PUP: Vec::with_capacity(n),
+            VTIMUP: vec![0.; n],
+            SPINUP: vec![9.; n],
+            info: String::new(),
+            attr: Attributes::new(),
+            scales: None,
+            clustering: None,
+            mgrwt: None,
+            weights: None,
+            comment_lines: None,
+            extensions: Extensions::new(),
+        };
+        let mut outgoing = Vec::with_capacity(n - 2);
+        let (mut px_sum, mut py_sum) = (0., 0.);
+        for _ in 2..n - 1 {
+            let pt = 10. + 90. * rng.uniform();
+            let phi = 2. * PI * rng.uniform();
+            let rapidity = 4. * rng.uniform() - 2.;
+            let (px, py) = (pt * phi.cos(), pt * phi.sin());
+            px_sum += px;
+            py_sum += py;
+            outgoing.push(massless(px, py, rapidity));
+        }
+        // the last particle balances the transverse momentum
+        outgoing.push(massless(-px_sum, -py_sum, 4. * rng.uniform() - 2.));
+        let energy: f64 = outgoing.iter().map(|p| p[3]).sum();
+        let pz: f64 = outgoing.iter().map(|p| p[2]).sum();
+        let (e1, e2) = ((energy + pz) / 2., (energy - pz) / 2.);
+        event.PUP.push([0., 0., e1, e1, 0.]);
+        event.PUP.push([0., 0., -e2, e2, 0.]);
+        event.SCALUP = (energy * energy - pz * pz).sqrt();
+        for p in outgoing {
+            let id = OUTGOING_IDS[(rng.next_u64() % OUTGOING_IDS.len() as u64) as usize];
+            event.IDUP.push(id);
+            event.ISTUP.push(1);
+            event.MOTHUP.push([1, 2]);
+            event.PUP.push(p);
+        }
+        if self.num_weights > 0 {
+            let weights = (1..=self.num_weights)
+                .map(|id| Weight {
+                    id: Some(id.to_string()),
+                    value: event.XWGTUP * (0.8 + 0.4 * rng.uniform()),
+                })
+                .collect();
+            event.weights = Some(Weights {
+                weights,
+                format: WeightFormat::Rwgt,
+            });
+        }
+        event
+    }
+}
+
+/// Builder for single events in unit tests
+///
+/// Each constructor starts from a simple, physically consistent
+/// process, which can then be modified. The momenta of the incoming
+/// partons are chosen to conserve energy and longitudinal momentum.
+///
+/// # Example
+///
+/// ```rust
+/// use lhef::test_util::MockEvent;
+///
+/// let event = MockEvent::dijet().with_weight(0.5).build();
+/// assert_eq!(event.XWGTUP, 0.5);
+/// let final_state = lhef::ParticleSelection::final_state();
+/// assert_eq!(final_state.indices(&event).len(), 2);
+/// ```
+#[derive(PartialEq, Debug, Clone)]
+pub struct MockEvent {
+    event: HEPEUP,
+}
+
+impl MockEvent {
+    /// An event with only the two given incoming partons
+    pub fn new(incoming: [i32; 2]) -> MockEvent {
+        let mut mock = MockEvent {
+            event: HEPEUP {
+                NUP: 0,
+                IDRUP: 1,
+                XWGTUP: 1.,
+                SCALUP: 0.,
+                AQEDUP: 1. / 128.,
+                AQCDUP: 0.118,
+                IDUP: Vec::new(),
+                ISTUP: Vec::new(),
+                MOTHUP: Vec::new(),
+                ICOLUP: Vec::new(),
+                PUP: Vec::new(),
+                VTIMUP: Vec::new(),
+                SPINUP: Vec::new(),
+                info: String::new(),
+                attr: Attributes::new(),
+                scales: None,
+                clustering: None,
+                mgrwt: None,
+                weights: None,
+                comment_lines: None,
+                extensions: Extensions::new(),
+            },
+        };
+        for &id in &incoming {
+            mock.push(id, -1, [0, 0], [0, 0], [0.; 4]);
+        }
+        mock
+    }
+
+    /// Gluon scattering into two back-to-back gluons with a transverse
+    /// momentum of 100 GeV
+    pub fn dijet() -> MockEvent {
+        let mut mock = MockEvent::new([21, 21]);
+        mock.event.ICOLUP = vec![[501, 502], [503, 501]];
+        mock.push(21, 1, [1, 2], [503, 504], [100., 0., 50., 0.]);
+        mock.push(21, 1, [1, 2], [504, 502], [-100., 0., -20., 0.]);
+        mock.event.SCALUP = 100.;
+        mock
+    }
+
+    /// Production of a Z boson at rest decaying into a muon pair
+    pub fn drell_yan() -> MockEvent {
+        const Z_MASS: f64 = 91.1876;
+        let mut mock = MockEvent::new([2, -2]);
+        mock.event.ICOLUP = vec![[501, 0], [0, 501]];
+        mock.push(23, 2, [1, 2], [0, 0], [0., 0., 0., Z_MASS]);
+        let p = Z_MASS / 2.;
+        mock.push(13, 1, [3, 3], [0, 0], [0., p, 0., 0.]);
+        mock.push(-13, 1, [3, 3], [0, 0], [0., -p, 0., 0.]);
+        mock.event.SCALUP = Z_MASS;
+        mock
+    }
+
+    /// Add an outgoing particle with the given momentum `[px, py, pz]`
+    /// and mass, originating from the incoming partons
+    pub fn with_particle(mut self, id: i32, momentum: [f64; 3], mass: f64) -> MockEvent {
+        let [px, py, pz] = momentum;
+        self.push(id, 1, [1, 2], [0, 0], [px, py, pz, mass]);
+        self
+    }
+
+    /// Set the event weight `XWGTUP`
+    pub fn with_weight(mut self, weight: f64) -> MockEvent {
+        self.event.XWGTUP = weight;
+        self
+    }
+
+    /// Add an additional weight with the given ID
+    pub fn with_extra_weight(mut self, id: &str, value: f64) -> MockEvent {
+        self.event
+            .weights
+            .get_or_insert_with(Weights::default)
+            .set(id, value);
+        self
+    }
+
+    /// Set the process ID `IDRUP`
+    pub fn with_process_id(mut self, id: i32) -> MockEvent {
+        self.event.IDRUP = id;
+        self
+    }
+
+    /// Set the scale `SCALUP`
+    pub fn with_scale(mut self, scale: f64) -> MockEvent {
+        self.event.SCALUP = scale;
+        self
+    }
+
+    /// Set the optional event information
+    pub fn with_info(mut self, info: &str) -> MockEvent {
+        self.event.info = info.to_owned();
+        self
+    }
+
+    /// Create the event
+    pub fn build(self) -> HEPEUP {
+        let mut event = self.event;
+        let (mut energy, mut pz) = (0., 0.);
+        for (p, &status) in event.PUP.iter().zip(&event.ISTUP) {
+            if status == 1 {
+                energy += p[3];
+                pz += p[2];
+            }
+        }
+        let (e1, e2) = ((energy + pz) / 2., (energy - pz) / 2.);
+        event.PUP[0] = [0., 0., e1, e1, 0.];
+        event.PUP[1] = [0., 0., -e2, e2, 0.];
+        event
+    }
+
+    fn push(&mut self, id: i32, status: i32, mothers: [i32; 2], colours: [i32; 2], p: [f64; 4]) {
+        let [px, py, pz, mass] = p;
+        let energy = (px * px + py * py + pz * pz + mass * mass).sqrt();
+        let event = &mut self.event;
+        event.NUP += 1;
+        event.IDUP.push(id);
+        event.ISTUP.push(status);
+        event.MOTHUP.push(mothers);
+        event.ICOLUP.push(colours);
+        event.PUP.push([px, py, pz, energy, mass]);
+        event.VTIMUP.push(0.);
+        event.SPINUP.push(9.);
+    }
+}
+
+/// Massless momentum with the given transverse components and rapidity
+fn massless(px: f64, py: f64, rapidity: f64) -> [f64; 5] {
+    let pt = px.hypot(py);
+    [px, py, pt * rapidity.sinh(), pt * rapidity.cosh(), 0.]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {ParticleSelection, Reader, Validator};
+
+    #[test]
+    fn sample() {
+        let generator = SyntheticEvents::new()
+            .num_events(20)
+            .multiplicity(6)
+            .num_weights(2)
+            .seed(3);
+        let sample = generator.to_bytes();
+        assert_eq!(sample, generator.to_bytes());
+        assert_ne!(sample, generator.clone().seed(4).to_bytes());
+
+        let mut reader = Reader::new(sample.as_slice()).unwrap();
+        assert_eq!(reader.heprup(), &generator.heprup());
+        assert_eq!(reader.initrwgt().unwrap().weights().len(), 2);
+        let validator = Validator::for_run(reader.heprup());
+        let mut events = generator.events();
+        while let Some(event) = reader.event().unwrap() {
+            assert_eq!(Some(&event), events.next().as_ref());
+            assert!(validator.validate(&event).is_empty());
+            assert_eq!(event.weights.as_ref().unwrap().len(), 2);
+            let outgoing = &event.PUP[2..];
+            for i in 0..4 {
+                let total: f64 = outgoing.iter().map(|p| p[i]).sum();
+                let incoming = event.PUP[0][i] + event.PUP[1][i];
+                assert!((total - incoming).abs() < 1e-9 * event.PUP[0][3].max(1.));
+            }
+        }
+        assert_eq!(events.next(), None);
+
+        let plain = SyntheticEvents::new().num_events(3).to_bytes();
+        let mut reader = Reader::new(plain.as_slice()).unwrap();
+        assert_eq!(reader.version(), "1.0");
+        let event = reader.event().unwrap().unwrap();
+        assert_eq!(event.NUP, 4);
+        assert_eq!(event.weights, None);
+    }
+
+    fn assert_conserved(event: &HEPEUP) {
+        for i in 0..4 {
+            let incoming = event.PUP[0][i] + event.PUP[1][i];
+            let final_state = ParticleSelection::final_state().indices(event);
+            let outgoing: f64 = final_state.iter().map(|&p| event.PUP[p][i]).sum();
+            assert!((incoming - outgoing).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn mock() {
+        let dijet = MockEvent::dijet()
+            .with_weight(0.5)
+            .with_extra_weight("up", 0.6)
+            .with_process_id(3)
+            .build();
+        assert_eq!(dijet.NUP, 4);
+        assert_eq!(dijet.XWGTUP, 0.5);
+        assert_eq!(dijet.IDRUP, 3);
+        assert_eq!(dijet.weights.as_ref().unwrap().get("up"), Some(0.6));
+        assert!(Validator::new().validate(&dijet).is_empty());
+        assert!((dijet.pt(2) - 100.).abs() < 1e-9);
+        assert_conserved(&dijet);
+
+        let drell_yan = MockEvent::drell_yan().with_particle(22, [0., 0., 10.], 0.).build();
+        assert_eq!(drell_yan.NUP, 6);
+        assert!(Validator::new().validate(&drell_yan).is_empty());
+        assert!((drell_yan.invariant_mass(&[3, 4]) - 91.1876).abs() < 1e-9);
+        assert_conserved(&drell_yan);
+
+        let mut output = Vec::new();
+        {
+            let mut writer = Writer::new(&mut output, "3.0").unwrap();
+            writer.heprup(&SyntheticEvents::new().heprup()).unwrap();
+            writer.event(&dijet).unwrap();
+            writer.finish().unwrap();
+        }
+        let mut reader = Reader::new(output.as_slice()).unwrap();
+        assert_eq!(reader.event().unwrap(), Some(dijet));
+    }
+}
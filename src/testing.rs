@@ -0,0 +1,189 @@
+//! Random generation of run information and events for property tests
+//!
+//! With the `testing` feature, `HEPRUP` and `HEPEUP` implement
+//! [proptest](https://crates.io/crates/proptest)'s `Arbitrary`. The
+//! generated values are consistent: the number of particles matches
+//! `NUP`, mothers precede their daughters, masses agree with the
+//! energies and momenta, and the first two particles are incoming
+//! partons along the beam axis.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use proptest::prelude::*;
+//!
+//! proptest! {
+//!     #[test]
+//!     fn analysis_accepts_any_event(event in any::<lhef::HEPEUP>()) {
+//!         my_analysis(&event);
+//!     }
+//! }
+//! ```
+
+use proptest::arbitrary::Arbitrary;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::sample::select;
+
+use {Attributes, Extensions, HEPEUP, HEPRUP};
+
+const BEAM_IDS: &[i32] = &[2212, -2212, 11, -11];
+const PARTON_IDS: &[i32] = &[1, -1, 2, -2, 3, -3, 4, -4, 5, -5, 21];
+const OUTGOING_IDS: &[i32] = &[
+    1, -1, 2, -2, 3, -3, 4, -4, 5, -5, 6, -6, 11, -11, 12, -12, 13, -13, 14, -14, 15, -15, 16,
+    -16, 21, 22, 23, 24, -24, 25,
+];
+
+impl Arbitrary for HEPRUP {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<HEPRUP>;
+
+    fn arbitrary_with(_: ()) -> BoxedStrategy<HEPRUP> {
+        // cross section, relative error, ratio of maximum weight to
+        // cross section, and process ID
+        let process = (1e-6..1e6f64, 0. ..1f64, 0. ..10f64, 1..10_000i32);
+        (
+            [select(BEAM_IDS), select(BEAM_IDS)],
+            [1. ..1e4f64, 1. ..1e4f64],
+            [0..3i32, 0..3i32],
+            [0..400_000i32, 0..400_000i32],
+            select(&[1, 2, 3, 4, -1, -2, -3, -4][..]),
+            vec(process, 1..5),
+        )
+            .prop_map(|(beams, energies, pdf_groups, pdf_sets, weighting, processes)| {
+                HEPRUP {
+                    IDBMUP: beams,
+                    EBMUP: energies,
+                    PDFGUP: pdf_groups,
+                    PDFSUP: pdf_sets,
+                    IDWTUP: weighting,
+                    NPRUP: processes.len() as i32,
+                    XSECUP: processes.iter().map(|p| p.0).collect(),
+                    XERRUP: processes.iter().map(|p| p.0 * p.1).collect(),
+                    XMAXUP: processes.iter().map(|p| p.0 * p.2).collect(),
+                    LPRUP: processes.iter().map(|p| p.3).collect(),
+                    info: String::new(),
+                    extensions: Extensions::new(),
+                }
+            })
+            .boxed()
+    }
+}
+
+/// ID, whether the particle is an intermediate resonance, fractions
+/// determining the mothers, momentum, mass, and colour tag of an
+/// outgoing particle
+type Outgoing = (i32, bool, (f64, f64), [f64; 3], f64, i32);
+
+fn outgoing() -> impl Strategy<Value = Outgoing> {
+    (
+        select(OUTGOING_IDS),
+        any::<bool>(),
+        (0. ..1f64, 0. ..1f64),
+        [-1e3..1e3f64, -1e3..1e3f64, -1e3..1e3f64],
+        select(&[0., 0.105, 1.5, 4.7, 80.4, 91.2, 125., 173.][..]),
+        501..504i32,
+    )
+}
+
+impl Arbitrary for HEPEUP {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<HEPEUP>;
+
+    fn arbitrary_with(_: ()) -> BoxedStrategy<HEPEUP> {
+        (
+            1..100i32,
+            -1e3..1e3f64,
+            1. ..1e3f64,
+            (0.007..0.008f64, 0.1..0.13f64),
+            [select(PARTON_IDS), select(PARTON_IDS)],
+            [1. ..1e3f64, 1. ..1e3f64],
+            vec(outgoing(), 1..11),
+        )
+            .prop_map(|(id, weight, scale, (alpha_qed, alpha_qcd), partons, energies, outgoing)| {
+                let n = 2 + outgoing.len();
+                let mut event = HEPEUP {
+                    NUP: n as i32,
+                    IDRUP: id,
+                    XWGTUP: weight,
+                    SCALUP: scale,
+                    AQEDUP: alpha_qed,
+                    AQCDUP: alpha_qcd,
+                    IDUP: Vec::with_capacity(n),
+                    ISTUP: Vec::with_capacity(n),
+                    MOTHUP: Vec::with_capacity(n),
+                    ICOLUP: Vec::with_capacity(n),
+                    PUP: Vec::with_capacity(n),
+                    VTIMUP: vec![0.; n],
+                    SPINUP: vec![9.; n],
+                    info: String::new(),
+                    attr: Attributes::new(),
+                    scales: None,
+                    clustering: None,
+                    mgrwt: None,
+                    weights: None,
+                    comment_lines: None,
+                    extensions: Extensions::new(),
+                };
+                let incoming = partons.iter().zip(&energies).zip(&[1., -1.]);
+                for (i, ((&id, &energy), direction)) in incoming.enumerate() {
+                    event.IDUP.push(id);
+                    event.ISTUP.push(-1);
+                    event.MOTHUP.push([0, 0]);
+                    event.ICOLUP.push(colours(id, 501 + i as i32));
+                    event.PUP.push([0., 0., direction * energy, energy, 0.]);
+                }
+                for (id, intermediate, mothers, p, mass, tag) in outgoing {
+                    // mothers are among the preceding particles, counting from 1
+                    let preceding = event.IDUP.len() as f64;
+                    let first = 1 + (mothers.0 * preceding) as i32;
+                    let last = first + (mothers.1 * (preceding + 1. - f64::from(first))) as i32;
+                    let energy = (p[0] * p[0] + p[1] * p[1] + p[2] * p[2] + mass * mass).sqrt();
+                    event.IDUP.push(id);
+                    event.ISTUP.push(if intermediate { 2 } else { 1 });
+                    event.MOTHUP.push([first, last]);
+                    event.ICOLUP.push(colours(id, tag));
+                    event.PUP.push([p[0], p[1], p[2], energy, mass]);
+                }
+                event
+            })
+            .boxed()
+    }
+}
+
+/// Colour and anticolour tags of a particle
+fn colours(id: i32, tag: i32) -> [i32; 2] {
+    match id {
+        21 => [tag, tag + 1],
+        1..=6 => [tag, 0],
+        -6..=-1 => [0, tag],
+        _ => [0, 0],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {Reader, Validator, Writer};
+
+    proptest! {
+        #[test]
+        fn consistent(event in any::<HEPEUP>()) {
+            prop_assert_eq!(Validator::new().validate(&event), vec![]);
+        }
+
+        #[test]
+        fn roundtrip(heprup in any::<HEPRUP>(), event in any::<HEPEUP>()) {
+            let mut output = Vec::new();
+            {
+                let mut writer = Writer::new(&mut output, "1.0").unwrap();
+                writer.heprup(&heprup).unwrap();
+                writer.event(&event).unwrap();
+                writer.finish().unwrap();
+            }
+            let mut reader = Reader::new(output.as_slice()).unwrap();
+            prop_assert_eq!(reader.heprup(), &heprup);
+            prop_assert_eq!(reader.event().unwrap(), Some(event));
+        }
+    }
+}
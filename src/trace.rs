@@ -0,0 +1,19 @@
+//! Instrumentation with the `tracing` crate
+//!
+//! The macros expand to nothing unless the `tracing` feature is enabled.
+
+/// Enter a span until the end of the current block
+macro_rules! span {
+    ($level:ident, $($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = ::tracing::span!(::tracing::Level::$level, $($arg)*).entered();
+    };
+}
+
+/// Record an event
+macro_rules! event {
+    ($level:ident, $($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        ::tracing::event!(::tracing::Level::$level, $($arg)*);
+    };
+}
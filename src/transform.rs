@@ -0,0 +1,287 @@
+use std::ops::Mul;
+
+use HEPEUP;
+
+/// A Lorentz transformation, composed of rotations and boosts
+///
+/// Transformations act on momenta `[px, py, pz, E, m]`, leaving the
+/// mass unchanged. They are combined with `then`, or equivalently by
+/// multiplication, where `b * a` first applies `a` and then `b`.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # let mut event: lhef::HEPEUP = unimplemented!();
+/// use lhef::LorentzTransform;
+/// use std::f64::consts::FRAC_PI_2;
+///
+/// // rotate the z axis onto the x axis, then boost along x
+/// let transform = LorentzTransform::rotation([0., 1., 0.], FRAC_PI_2)
+///     .then(&LorentzTransform::boost([0.3, 0., 0.]));
+/// event.transform(&transform);
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct LorentzTransform {
+    /// Matrix acting on `(px, py, pz, E)`
+    matrix: [[f64; 4]; 4],
+}
+
+impl Default for LorentzTransform {
+    fn default() -> LorentzTransform {
+        LorentzTransform::identity()
+    }
+}
+
+impl LorentzTransform {
+    /// The transformation that leaves all momenta unchanged
+    pub fn identity() -> LorentzTransform {
+        let mut matrix = [[0.; 4]; 4];
+        for (i, row) in matrix.iter_mut().enumerate() {
+            row[i] = 1.;
+        }
+        LorentzTransform { matrix }
+    }
+
+    /// A boost by the velocity `beta` in units of the speed of light
+    ///
+    /// A particle at rest acquires the velocity `beta`.
+    pub fn boost(beta: [f64; 3]) -> LorentzTransform {
+        let mut result = LorentzTransform::identity();
+        let beta2 = beta.iter().map(|b| b * b).sum::<f64>();
+        if beta2 == 0. {
+            return result;
+        }
+        let gamma = 1. / (1. - beta2).sqrt();
+        let m = &mut result.matrix;
+        m[3][3] = gamma;
+        for i in 0..3 {
+            m[i][3] = gamma * beta[i];
+            m[3][i] = gamma * beta[i];
+            for j in 0..3 {
+                m[i][j] += (gamma - 1.) * beta[i] * beta[j] / beta2;
+            }
+        }
+        result
+    }
+
+    /// A rotation by `angle` around `axis`
+    ///
+    /// The rotation is counterclockwise when looking against the
+    /// direction of the axis, which need not be normalised.
+    pub fn rotation(axis: [f64; 3], angle: f64) -> LorentzTransform {
+        let mut result = LorentzTransform::identity();
+        let norm = axis.iter().map(|a| a * a).sum::<f64>().sqrt();
+        if norm == 0. {
+            return result;
+        }
+        let n = [axis[0] / norm, axis[1] / norm, axis[2] / norm];
+        let (sin, cos) = angle.sin_cos();
+        let cross = [
+            [0., -n[2], n[1]],
+            [n[2], 0., -n[0]],
+            [-n[1], n[0], 0.],
+        ];
+        let m = &mut result.matrix;
+        for i in 0..3 {
+            for j in 0..3 {
+                let delta = if i == j { 1. } else { 0. };
+                m[i][j] = cos * delta + sin * cross[i][j] + (1. - cos) * n[i] * n[j];
+            }
+        }
+        result
+    }
+
+    /// The transformation that first applies `self` and then `next`
+    pub fn then(&self, next: &LorentzTransform) -> LorentzTransform {
+        let mut matrix = [[0.; 4]; 4];
+        for (i, row) in matrix.iter_mut().enumerate() {
+            for (j, entry) in row.iter_mut().enumerate() {
+                *entry = (0..4).map(|k| next.matrix[i][k] * self.matrix[k][j]).sum();
+            }
+        }
+        LorentzTransform { matrix }
+    }
+
+    /// The transformation that undoes `self`
+    pub fn inverse(&self) -> LorentzTransform {
+        // Λ⁻¹ = η Λᵀ η with the metric η = diag(-1, -1, -1, 1)
+        let mut matrix = [[0.; 4]; 4];
+        for (i, row) in matrix.iter_mut().enumerate() {
+            for (j, entry) in row.iter_mut().enumerate() {
+                let sign = if (i == 3) == (j == 3) { 1. } else { -1. };
+                *entry = sign * self.matrix[j][i];
+            }
+        }
+        LorentzTransform { matrix }
+    }
+
+    /// Transform a momentum `[px, py, pz, E, m]` in place
+    pub fn apply(&self, p: &mut [f64; 5]) {
+        let old = [p[0], p[1], p[2], p[3]];
+        for (new, row) in p.iter_mut().zip(&self.matrix) {
+            *new = row.iter().zip(&old).map(|(m, p)| m * p).sum();
+        }
+    }
+}
+
+impl Mul for LorentzTransform {
+    type Output = LorentzTransform;
+
+    fn mul(self, rhs: LorentzTransform) -> LorentzTransform {
+        rhs.then(&self)
+    }
+}
+
+/// Lorentz transformations of whole events
+impl HEPEUP {
+    /// Apply a Lorentz transformation to all particles
+    pub fn transform(&mut self, transform: &LorentzTransform) {
+        for p in self.PUP.iter_mut() {
+            transform.apply(p);
+        }
+    }
+
+    /// Get a copy of the event with a Lorentz transformation applied to
+    /// all particles
+    pub fn transformed(&self, transform: &LorentzTransform) -> HEPEUP {
+        let mut event = self.clone();
+        event.transform(transform);
+        event
+    }
+
+    /// Boost all particles by the velocity `beta` in units of the speed
+    /// of light
+    ///
+    /// A particle at rest acquires the velocity `beta`.
+    pub fn boost(&mut self, beta: [f64; 3]) {
+        self.transform(&LorentzTransform::boost(beta))
+    }
+
+    /// Velocity of the centre-of-mass frame of the incoming particles
+    ///
+    /// Returns `None` if there are no incoming particles, i.e. ones
+    /// with status -1, or if their total momentum is not time-like.
+    pub fn partonic_cm_velocity(&self) -> Option<[f64; 3]> {
+        let mut total = [0.; 4];
+        let incoming = self.ISTUP.iter().zip(&self.PUP).filter(|&(&status, _)| status == -1);
+        for (_, p) in incoming {
+            for (t, p) in total.iter_mut().zip(p) {
+                *t += p;
+            }
+        }
+        let [px, py, pz, e] = total;
+        if e <= 0. || px * px + py * py + pz * pz >= e * e {
+            return None;
+        }
+        Some([px / e, py / e, pz / e])
+    }
+
+    /// Boost all particles to the centre-of-mass frame of the incoming
+    /// particles
+    ///
+    /// Returns whether the boost was performed, see
+    /// `partonic_cm_velocity`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # let mut event: lhef::HEPEUP = unimplemented!();
+    /// if event.boost_to_partonic_cm() {
+    ///     println!("Rapidity of the first outgoing particle: {}", event.rapidity(2));
+    /// }
+    /// ```
+    pub fn boost_to_partonic_cm(&mut self) -> bool {
+        match self.partonic_cm_velocity() {
+            Some(beta) => {
+                self.boost([-beta[0], -beta[1], -beta[2]]);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Reader;
+
+    const EVENT: &str = r#"<LesHouchesEvents version="1.0">
+<init>
+2212 2212 6500 6500 0 0 0 0 3 1
+1 0 1 1
+</init>
+<event>
+4 1 1 10 0 0.1
+21 -1 0 0 0 0 0 0 3e2 3e2 0 0 9
+21 -1 0 0 0 0 0 0 -1e2 1e2 0 0 9
+11 1 1 2 0 0 3e1 -4e1 2e2 2.0615528e2 0 0 9
+-11 1 1 2 0 0 -3e1 4e1 0 5e1 0 0 9
+</event>
+</LesHouchesEvents>
+"#;
+
+    #[test]
+    fn partonic_cm() {
+        let mut reader = Reader::new(EVENT.as_bytes()).unwrap();
+        let event = reader.event().unwrap().unwrap();
+        assert_eq!(event.partonic_cm_velocity(), Some([0., 0., 0.5]));
+        let mut boosted = event.clone();
+        assert!(boosted.boost_to_partonic_cm());
+        let [e1, e2] = [boosted.PUP[0][3], boosted.PUP[1][3]];
+        assert!((e1 - e2).abs() < 1e-9);
+        assert!((e1 + e2 - 400. * 0.75f64.sqrt()).abs() < 1e-9);
+        assert!((boosted.PUP[0][2] + boosted.PUP[1][2]).abs() < 1e-9);
+        for i in 0..4 {
+            assert!((boosted.mass(i) - event.mass(i)).abs() < 1e-4);
+            assert!((boosted.pt(i) - event.pt(i)).abs() < 1e-9);
+        }
+        boosted.boost([0., 0., 0.5]);
+        for (b, p) in boosted.PUP.iter().zip(&event.PUP) {
+            for k in 0..4 {
+                assert!((b[k] - p[k]).abs() < 1e-9);
+            }
+        }
+    }
+
+    fn assert_close(a: &[f64; 5], b: &[f64; 5]) {
+        for k in 0..5 {
+            assert!((a[k] - b[k]).abs() < 1e-9, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn lorentz_transform() {
+        use std::f64::consts::FRAC_PI_2;
+        let rotation = LorentzTransform::rotation([0., 0., 2.], FRAC_PI_2);
+        let mut p = [1., 0., 5., 7., 1.];
+        rotation.apply(&mut p);
+        assert_close(&p, &[0., 1., 5., 7., 1.]);
+
+        let boost = LorentzTransform::boost([0., 0.6, 0.]);
+        let mut q = [0., 0., 0., 2., 2.];
+        boost.apply(&mut q);
+        assert_close(&q, &[0., 1.5, 0., 2.5, 2.]);
+
+        let combined = rotation.then(&boost);
+        assert_eq!(combined, boost * rotation);
+        let mut r = [1., 0., 0., 1., 0.];
+        combined.apply(&mut r);
+        assert_close(&r, &[0., 2., 0., 2., 0.]);
+        combined.inverse().apply(&mut r);
+        assert_close(&r, &[1., 0., 0., 1., 0.]);
+
+        let mut reader = Reader::new(EVENT.as_bytes()).unwrap();
+        let event = reader.event().unwrap().unwrap();
+        let transformed = event.transformed(&combined);
+        for i in 0..4 {
+            assert!((transformed.mass(i) - event.mass(i)).abs() < 1e-4);
+        }
+        let back = transformed.transformed(&combined.inverse());
+        for (b, p) in back.PUP.iter().zip(&event.PUP) {
+            assert_close(b, p);
+        }
+        assert_eq!(LorentzTransform::default(), LorentzTransform::identity());
+    }
+}
@@ -0,0 +1,415 @@
+use std::fmt;
+
+use pdg;
+use {HEPEUP, HEPRUP};
+
+const BEAM_ENERGY_TOLERANCE: f64 = 1e-6;
+
+/// A problem found when validating an event
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub enum Issue {
+    /// The number of particles `NUP` does not match the number of
+    /// particle lines
+    ///
+    /// When reading, particle lines beyond the declared number end up
+    /// at the start of the optional event information, where they are
+    /// detected.
+    ParticleCount {
+        /// The declared number of particles
+        declared: i32,
+        /// The number of particle lines found
+        found: usize,
+    },
+    /// A mother index is neither 0 nor the position of a particle in
+    /// the event
+    MotherOutOfRange {
+        /// Position of the particle, counting from 1 as in `MOTHUP`
+        particle: usize,
+        /// The invalid mother index
+        mother: i32,
+    },
+    /// A particle is its own mother
+    SelfMother {
+        /// Position of the particle, counting from 1 as in `MOTHUP`
+        particle: usize,
+    },
+    /// The mothers of a particle violate the ordering conventions
+    ///
+    /// Mothers have to precede their daughters, and the first mother
+    /// must not come after the last one.
+    MotherOrder {
+        /// Position of the particle, counting from 1 as in `MOTHUP`
+        particle: usize,
+        /// The mother indices
+        mothers: [i32; 2],
+    },
+    /// The mass of a particle differs from the one computed from its
+    /// energy and momentum
+    Mass {
+        /// Position of the particle, counting from 1 as in `MOTHUP`
+        particle: usize,
+        /// The mass given in `PUP`
+        mass: f64,
+        /// The mass computed from the energy and momentum, negative for
+        /// space-like momenta
+        computed: f64,
+    },
+    /// An incoming particle cannot originate from the corresponding beam
+    BeamParticle {
+        /// Position of the particle, counting from 1 as in `MOTHUP`
+        particle: usize,
+        /// Particle ID of the incoming particle
+        id: i32,
+        /// Particle ID of the beam
+        beam: i32,
+    },
+    /// An incoming particle has more energy than the corresponding beam
+    BeamEnergy {
+        /// Position of the particle, counting from 1 as in `MOTHUP`
+        particle: usize,
+        /// Energy of the incoming particle
+        energy: f64,
+        /// Energy of the beam
+        beam_energy: f64,
+    },
+    /// A particle ID is not valid, see `PdgIdCheck`
+    PdgId {
+        /// Position of the particle, counting from 1 as in `MOTHUP`
+        particle: usize,
+        /// The invalid ID
+        id: i32,
+    },
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Issue::ParticleCount { declared, found } => write!(
+                f,
+                "Event declares {} particles, but has {} particle lines",
+                declared, found
+            ),
+            Issue::MotherOutOfRange { particle, mother } => write!(
+                f,
+                "Particle {} has mother {}, which is not in the event",
+                particle, mother
+            ),
+            Issue::SelfMother { particle } => {
+                write!(f, "Particle {} is its own mother", particle)
+            }
+            Issue::MotherOrder { particle, mothers } => write!(
+                f,
+                "Particle {} has mothers {} and {} in the wrong order",
+                particle, mothers[0], mothers[1]
+            ),
+            Issue::Mass { particle, mass, computed } => write!(
+                f,
+                "Particle {} has mass {:e}, but its momentum gives {:e}",
+                particle, mass, computed
+            ),
+            Issue::BeamParticle { particle, id, beam } => write!(
+                f,
+                "Incoming particle {} with ID {} cannot originate from beam particle {}",
+                particle, id, beam
+            ),
+            Issue::BeamEnergy { particle, energy, beam_energy } => write!(
+                f,
+                "Incoming particle {} has energy {:e}, more than the beam energy {:e}",
+                particle, energy, beam_energy
+            ),
+            Issue::PdgId { particle, id } => write!(
+                f,
+                "Particle {} has ID {}, which is not a valid PDG ID",
+                particle, id
+            ),
+        }
+    }
+}
+
+/// How a `Validator` checks particle IDs
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+pub enum PdgIdCheck {
+    /// Do not check particle IDs
+    Off,
+    /// Check that IDs conform to the PDG numbering scheme
+    ///
+    /// This catches corrupted IDs like 0, but accepts IDs of unknown
+    /// particles as long as they have a valid structure.
+    #[default]
+    Scheme,
+    /// Additionally require that the particle is known to the
+    /// [particle_id](https://crates.io/crates/particle_id) crate
+    ///
+    /// This requires the `particle_id` feature.
+    #[cfg(feature = "particle_id")]
+    Known,
+}
+
+impl PdgIdCheck {
+    fn accepts(self, id: i32) -> bool {
+        match self {
+            PdgIdCheck::Off => true,
+            PdgIdCheck::Scheme => pdg::is_valid(id),
+            #[cfg(feature = "particle_id")]
+            PdgIdCheck::Known => {
+                pdg::is_valid(id) && ::particle_id::ParticleID::new(id).name().is_some()
+            }
+        }
+    }
+}
+
+/// Checks for the consistency of events
+///
+/// # Example
+///
+/// ```rust,no_run
+/// let file = std::fs::File::open("events.lhe").unwrap();
+/// let file = std::io::BufReader::new(file);
+/// let mut reader = lhef::Reader::new(file).unwrap();
+/// let validator = lhef::Validator::new();
+/// while let Some(event) = reader.event().unwrap() {
+///     for issue in validator.validate(&event) {
+///         eprintln!("{}", issue);
+///     }
+/// }
+/// ```
+#[derive(PartialEq, Debug, Clone)]
+pub struct Validator {
+    /// Tolerance for the difference between the given squared mass
+    /// and the one computed from energy and momentum, relative to the
+    /// squared energy
+    pub mass_tolerance: f64,
+    /// IDs and energies of the two beams, if the incoming particles
+    /// should be checked against them
+    ///
+    /// Incoming particles moving in positive z direction are assigned
+    /// to the first beam and all others to the second one.
+    pub beams: Option<[(i32, f64); 2]>,
+    /// How particle IDs are checked
+    pub pdg_ids: PdgIdCheck,
+}
+
+impl Default for Validator {
+    fn default() -> Validator {
+        Validator {
+            mass_tolerance: 1e-4,
+            beams: None,
+            pdg_ids: PdgIdCheck::default(),
+        }
+    }
+}
+
+impl Validator {
+    /// Create a validator performing all checks
+    pub fn new() -> Validator {
+        Validator::default()
+    }
+
+    /// Create a validator that also checks events against the beams
+    /// of the given run
+    pub fn for_run(heprup: &HEPRUP) -> Validator {
+        Validator {
+            beams: Some([
+                (heprup.IDBMUP[0], heprup.EBMUP[0]),
+                (heprup.IDBMUP[1], heprup.EBMUP[1]),
+            ]),
+            ..Validator::default()
+        }
+    }
+
+    /// Check an event, returning all problems found
+    pub fn validate(&self, event: &HEPEUP) -> Vec<Issue> {
+        let mut issues = Vec::new();
+        issues.extend(check_particle_count(event));
+        check_mothers(event, &mut issues);
+        self.check_masses(event, &mut issues);
+        self.check_beams(event, &mut issues);
+        for (i, &id) in event.IDUP.iter().enumerate() {
+            if !self.pdg_ids.accepts(id) {
+                issues.push(Issue::PdgId { particle: i + 1, id });
+            }
+        }
+        issues
+    }
+
+    fn check_beams(&self, event: &HEPEUP, issues: &mut Vec<Issue>) {
+        let beams = match self.beams {
+            Some(ref beams) => beams,
+            None => return,
+        };
+        let incoming = event.ISTUP.iter().zip(&event.IDUP).zip(&event.PUP);
+        for (i, ((&status, &id), p)) in incoming.enumerate() {
+            if status != -1 {
+                continue;
+            }
+            let particle = i + 1;
+            let (beam, beam_energy) = if p[2] >= 0. { beams[0] } else { beams[1] };
+            if !originates_from(id, beam) {
+                issues.push(Issue::BeamParticle { particle, id, beam });
+            }
+            let energy = p[3];
+            if energy > beam_energy * (1. + BEAM_ENERGY_TOLERANCE) {
+                issues.push(Issue::BeamEnergy { particle, energy, beam_energy });
+            }
+        }
+    }
+
+    fn check_masses(&self, event: &HEPEUP, issues: &mut Vec<Issue>) {
+        for (i, p) in event.PUP.iter().enumerate() {
+            let [px, py, pz, e, mass] = *p;
+            let computed_sq = e * e - px * px - py * py - pz * pz;
+            if (computed_sq - mass * mass).abs() > self.mass_tolerance * e * e {
+                issues.push(Issue::Mass {
+                    particle: i + 1,
+                    mass,
+                    computed: computed_sq.signum() * computed_sq.abs().sqrt(),
+                });
+            }
+        }
+    }
+}
+
+fn check_particle_count(event: &HEPEUP) -> Option<Issue> {
+    let extra = event
+        .info
+        .lines()
+        .take_while(|line| is_particle_line(line))
+        .count();
+    let found = event.IDUP.len() + extra;
+    if found as i64 == i64::from(event.NUP) {
+        None
+    } else {
+        Some(Issue::ParticleCount {
+            declared: event.NUP,
+            found,
+        })
+    }
+}
+
+fn check_mothers(event: &HEPEUP, issues: &mut Vec<Issue>) {
+    let nparticles = event.MOTHUP.len() as i32;
+    for (i, mothers) in event.MOTHUP.iter().enumerate() {
+        let particle = i + 1;
+        let mut valid = true;
+        for &mother in mothers {
+            if mother < 0 || mother > nparticles {
+                issues.push(Issue::MotherOutOfRange { particle, mother });
+                valid = false;
+            } else if mother as usize == particle {
+                issues.push(Issue::SelfMother { particle });
+                valid = false;
+            }
+        }
+        if !valid {
+            continue;
+        }
+        let [first, last] = *mothers;
+        let after_daughter = mothers.iter().any(|&m| m as usize > particle);
+        if after_daughter || (last != 0 && first > last) {
+            issues.push(Issue::MotherOrder { particle, mothers: *mothers });
+        }
+    }
+}
+
+/// Check if a particle can be extracted from a beam
+///
+/// Hadrons and photons can emit partons, and any beam can emit photons.
+fn originates_from(id: i32, beam: i32) -> bool {
+    let is_parton = (1..=6).contains(&id.abs()) || id == 21;
+    let has_partons = beam.abs() > 100 || beam == 22;
+    id == beam || id == 22 || (is_parton && has_partons)
+}
+
+/// Check if a line has the format of a particle line inside an event
+fn is_particle_line(line: &str) -> bool {
+    let entries: Vec<_> = line.split_whitespace().collect();
+    entries.len() == 13
+        && entries[..6].iter().all(|e| e.parse::<i32>().is_ok())
+        && entries[6..].iter().all(|e| e.parse::<f64>().is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_util::{MockEvent, SyntheticEvents};
+
+    #[test]
+    fn particle_count() {
+        let validator = Validator::new();
+        let mut event = MockEvent::dijet().build();
+        assert!(validator.validate(&event).is_empty());
+        event.info = "21 1 1 2 0 0 0 0 0 0 0 0 9\n# 1 2 3\n".to_owned();
+        assert_eq!(
+            validator.validate(&event),
+            [Issue::ParticleCount { declared: 4, found: 5 }]
+        );
+    }
+
+    #[test]
+    fn mothers() {
+        let mut event = MockEvent::dijet().build();
+        event.MOTHUP[2] = [2, 1];
+        event.MOTHUP[3] = [4, 5];
+        assert_eq!(
+            Validator::new().validate(&event),
+            [
+                Issue::MotherOrder { particle: 3, mothers: [2, 1] },
+                Issue::SelfMother { particle: 4 },
+                Issue::MotherOutOfRange { particle: 4, mother: 5 },
+            ]
+        );
+    }
+
+    #[test]
+    fn masses() {
+        let mut event = MockEvent::drell_yan().build();
+        event.PUP[2][4] = 100.;
+        let issues = Validator::new().validate(&event);
+        assert_eq!(issues.len(), 1);
+        match issues[0] {
+            Issue::Mass { particle, mass, computed } => {
+                assert_eq!(particle, 3);
+                assert_eq!(mass, 100.);
+                assert!((computed - 91.1876).abs() < 1e-10);
+            }
+            _ => panic!("unexpected issue {:?}", issues[0]),
+        }
+    }
+
+    #[test]
+    fn beams() {
+        let validator = Validator::for_run(&SyntheticEvents::new().heprup());
+        let event = MockEvent::new([11, -2])
+            .with_particle(21, [0., 0., 1e3], 0.)
+            .with_particle(21, [0., 0., -7e3], 0.)
+            .build();
+        assert_eq!(
+            validator.validate(&event),
+            [
+                Issue::BeamParticle { particle: 1, id: 11, beam: 2212 },
+                Issue::BeamEnergy { particle: 2, energy: 7000., beam_energy: 6500. },
+            ]
+        );
+        assert!(Validator::new().validate(&event).is_empty());
+    }
+
+    #[test]
+    fn pdg_ids() {
+        let mut validator = Validator::new();
+        let mut event = MockEvent::dijet().with_particle(9000005, [0.; 3], 0.).build();
+        event.IDUP[0] = 0;
+        event.IDUP[2] = -21;
+        assert_eq!(
+            validator.validate(&event),
+            [Issue::PdgId { particle: 1, id: 0 }, Issue::PdgId { particle: 3, id: -21 }]
+        );
+        validator.pdg_ids = PdgIdCheck::Off;
+        assert!(validator.validate(&event).is_empty());
+        #[cfg(feature = "particle_id")]
+        {
+            validator.pdg_ids = PdgIdCheck::Known;
+            assert_eq!(validator.validate(&event).len(), 3);
+        }
+    }
+}
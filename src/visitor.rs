@@ -0,0 +1,167 @@
+use std::error;
+use std::io::BufRead;
+
+use {Reader, HEPEUP, HEPRUP};
+
+/// Receiver for the contents of an event file, see `parse_with`
+///
+/// This is a push-based alternative to reading events with a `Reader`,
+/// for tools that only react to the contents of a file. Only `on_event`
+/// has to be implemented; by default, everything else is ignored and
+/// the events of a group are passed to `on_event` one by one. An error
+/// returned by any method stops the parsing.
+pub trait LheVisitor {
+    /// Called with the header, which can be empty
+    fn on_header(&mut self, _header: &str) -> Result<(), Box<dyn error::Error>> {
+        Ok(())
+    }
+
+    /// Called with the run information from the `<init>` block
+    fn on_init(&mut self, _heprup: &HEPRUP) -> Result<(), Box<dyn error::Error>> {
+        Ok(())
+    }
+
+    /// Called for each event
+    fn on_event(&mut self, event: &HEPEUP) -> Result<(), Box<dyn error::Error>>;
+
+    /// Called for the events of an `<eventgroup>`
+    fn on_event_group(&mut self, events: &[HEPEUP]) -> Result<(), Box<dyn error::Error>> {
+        for event in events {
+            self.on_event(event)?;
+        }
+        Ok(())
+    }
+
+    /// Called with the complete text of a tag between the events that
+    /// is neither an event nor an event group
+    fn on_unknown_tag(&mut self, _tag: &str) -> Result<(), Box<dyn error::Error>> {
+        Ok(())
+    }
+}
+
+/// Parse an event file, passing its contents to `visitor`
+///
+/// This is a shorthand for creating a `Reader` and calling
+/// `Reader::visit`.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use lhef::{LheVisitor, HEPEUP};
+///
+/// struct SumWeights(f64);
+///
+/// impl LheVisitor for SumWeights {
+///     fn on_event(&mut self, event: &HEPEUP) -> Result<(), Box<dyn std::error::Error>> {
+///         self.0 += event.XWGTUP;
+///         Ok(())
+///     }
+/// }
+///
+/// let file = std::fs::File::open("events.lhe").unwrap();
+/// let mut sum = SumWeights(0.);
+/// lhef::parse_with(std::io::BufReader::new(file), &mut sum).unwrap();
+/// println!("Sum of weights: {}", sum.0);
+/// ```
+pub fn parse_with<Stream, V>(stream: Stream, visitor: &mut V) -> Result<(), Box<dyn error::Error>>
+where
+    Stream: BufRead,
+    V: LheVisitor + ?Sized,
+{
+    Reader::new(stream)?.visit(visitor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_util::{MockEvent, SyntheticEvents};
+    use Writer;
+
+    #[derive(Default)]
+    struct Recorder {
+        header: String,
+        nprup: i32,
+        events: Vec<HEPEUP>,
+        groups: usize,
+        tags: Vec<String>,
+    }
+
+    impl LheVisitor for Recorder {
+        fn on_header(&mut self, header: &str) -> Result<(), Box<dyn error::Error>> {
+            self.header = header.to_owned();
+            Ok(())
+        }
+
+        fn on_init(&mut self, heprup: &HEPRUP) -> Result<(), Box<dyn error::Error>> {
+            self.nprup = heprup.NPRUP;
+            Ok(())
+        }
+
+        fn on_event(&mut self, event: &HEPEUP) -> Result<(), Box<dyn error::Error>> {
+            self.events.push(event.clone());
+            Ok(())
+        }
+
+        fn on_event_group(&mut self, events: &[HEPEUP]) -> Result<(), Box<dyn error::Error>> {
+            self.groups += 1;
+            self.events.extend_from_slice(events);
+            Ok(())
+        }
+
+        fn on_unknown_tag(&mut self, tag: &str) -> Result<(), Box<dyn error::Error>> {
+            self.tags.push(tag.to_owned());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn visit() {
+        let sample = SyntheticEvents::new().num_events(5).to_bytes();
+        let mut recorder = Recorder::default();
+        parse_with(sample.as_slice(), &mut recorder).unwrap();
+        let mut reader = Reader::new(sample.as_slice()).unwrap();
+        assert_eq!(recorder.header, reader.header());
+        assert_eq!(recorder.nprup, reader.heprup().NPRUP);
+        for event in &recorder.events {
+            assert_eq!(Some(event), reader.event().unwrap().as_ref());
+        }
+        assert_eq!(reader.event().unwrap(), None);
+        assert_eq!(recorder.events.len(), 5);
+        assert_eq!(recorder.groups, 0);
+        assert!(recorder.tags.is_empty());
+    }
+
+    #[test]
+    fn event_groups() {
+        let events = [MockEvent::dijet().build(), MockEvent::drell_yan().build()];
+        let mut output = Vec::new();
+        {
+            let mut writer = Writer::new(&mut output, "3.0").unwrap();
+            writer.heprup(&SyntheticEvents::new().heprup()).unwrap();
+            writer.event_group(&events).unwrap();
+            writer.event(&events[0]).unwrap();
+            writer.finish().unwrap();
+        }
+        let mut recorder = Recorder::default();
+        parse_with(output.as_slice(), &mut recorder).unwrap();
+        assert_eq!(recorder.groups, 1);
+        assert_eq!(recorder.events, [&events[..], &events[..1]].concat());
+    }
+
+    #[test]
+    fn unknown_tags() {
+        let sample = String::from_utf8(SyntheticEvents::new().num_events(2).to_bytes()).unwrap();
+        let sample = sample.replacen(
+            "</event>\n",
+            "</event>\n<mg:info a='1'/>\n<note>\nsome text\n</note>\n",
+            1,
+        );
+        let mut reader = Reader::new(sample.as_bytes()).unwrap();
+        assert!(reader.event().unwrap().is_some());
+        assert!(reader.event().is_err());
+        let mut recorder = Recorder::default();
+        parse_with(sample.as_bytes(), &mut recorder).unwrap();
+        assert_eq!(recorder.events.len(), 2);
+        assert_eq!(recorder.tags, ["<mg:info a='1'/>\n", "<note>\nsome text\n</note>\n"]);
+    }
+}
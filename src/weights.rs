@@ -0,0 +1,187 @@
+use std::error;
+use std::fmt;
+
+use parse;
+use tags::{self, Tag};
+
+pub(crate) const RWGT_TAG: &str = "rwgt";
+pub(crate) const WEIGHTS_TAG: &str = "weights";
+const WGT_TAG: &str = "wgt";
+
+/// Additional event weights
+///
+/// LHEF 3.0 allows two encodings: a `<rwgt>` tag with one `<wgt id="...">`
+/// tag per weight, and a compact `<weights>` tag with space-separated
+/// values in the order of the `<initrwgt>` declarations. Both are parsed
+/// into the same representation. For the compact form, the weight IDs are
+/// taken from the declarations in the header, if present.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Weights {
+    /// The weights in the order in which they appear in the event
+    pub weights: Vec<Weight>,
+    /// The encoding used in the event file
+    pub format: WeightFormat,
+}
+
+/// A single additional event weight
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub struct Weight {
+    /// Weight ID, corresponding to a `<weight>` declaration in `<initrwgt>`
+    pub id: Option<String>,
+    /// Value of the weight
+    pub value: f64,
+}
+
+/// Encoding of additional event weights
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum WeightFormat {
+    /// A `<rwgt>` tag containing `<wgt>` tags
+    #[default]
+    Rwgt,
+    /// A compact `<weights>` tag
+    Compact,
+}
+
+impl Weights {
+    pub(crate) fn from_rwgt(tag: &Tag) -> Result<Weights, Box<dyn error::Error>> {
+        let weights = tags::tags(tag.content())
+            .filter(|(_, wgt)| wgt.name() == WGT_TAG)
+            .map(|(_, wgt)| {
+                Ok(Weight {
+                    id: wgt.attribute("id").map(|id| id.trim().to_owned()),
+                    value: parse("wgt", Some(wgt.content().trim()))?,
+                })
+            })
+            .collect::<Result<_, Box<dyn error::Error>>>()?;
+        Ok(Weights {
+            weights,
+            format: WeightFormat::Rwgt,
+        })
+    }
+
+    pub(crate) fn from_compact(
+        tag: &Tag,
+        ids: &[String],
+    ) -> Result<Weights, Box<dyn error::Error>> {
+        let weights = tag
+            .content()
+            .split_whitespace()
+            .enumerate()
+            .map(|(i, value)| {
+                Ok(Weight {
+                    id: ids.get(i).cloned(),
+                    value: parse("weights", Some(value))?,
+                })
+            })
+            .collect::<Result<_, Box<dyn error::Error>>>()?;
+        Ok(Weights {
+            weights,
+            format: WeightFormat::Compact,
+        })
+    }
+
+    /// Get the value of the weight with the given ID
+    pub fn get(&self, id: &str) -> Option<f64> {
+        self.weights
+            .iter()
+            .find(|weight| weight.id.as_deref() == Some(id))
+            .map(|weight| weight.value)
+    }
+
+    /// Set the value of the weight with the given ID
+    ///
+    /// If there is no such weight, it is appended.
+    pub fn set(&mut self, id: &str, value: f64) {
+        match self.weights.iter_mut().find(|weight| weight.id.as_deref() == Some(id)) {
+            Some(weight) => weight.value = value,
+            None => self.weights.push(Weight {
+                id: Some(id.to_owned()),
+                value,
+            }),
+        }
+    }
+
+    /// The weight values in order
+    pub fn values(&self) -> Vec<f64> {
+        self.weights.iter().map(|weight| weight.value).collect()
+    }
+
+    /// Number of weights
+    pub fn len(&self) -> usize {
+        self.weights.len()
+    }
+
+    /// Whether there are no weights
+    pub fn is_empty(&self) -> bool {
+        self.weights.is_empty()
+    }
+}
+
+/// Formats the weights as a `<rwgt>` or `<weights>` tag,
+/// depending on `format`
+///
+/// Weights without an ID are written in the compact form
+/// in any case.
+impl fmt::Display for Weights {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let all_ids = self.weights.iter().all(|weight| weight.id.is_some());
+        if self.format == WeightFormat::Rwgt && all_ids {
+            writeln!(f, "<{}>", RWGT_TAG)?;
+            for weight in &self.weights {
+                if let Some(ref id) = weight.id {
+                    writeln!(f, "<{} id=\"{}\"> {:e} </{}>", WGT_TAG, id, weight.value, WGT_TAG)?;
+                }
+            }
+            write!(f, "</{}>", RWGT_TAG)
+        } else {
+            write!(f, "<{}>", WEIGHTS_TAG)?;
+            for (i, weight) in self.weights.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " ")?;
+                }
+                write!(f, "{:e}", weight.value)?;
+            }
+            write!(f, "</{}>", WEIGHTS_TAG)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first_tag(text: &str) -> Tag<'_> {
+        tags::tags(text).next().unwrap().1
+    }
+
+    #[test]
+    fn rwgt() {
+        let text = "<rwgt>\n\
+                    <wgt id='1001'> +1.2e+01 </wgt>\n\
+                    <wgt id='1002'> -3.5 </wgt>\n\
+                    </rwgt>";
+        let weights = Weights::from_rwgt(&first_tag(text)).unwrap();
+        assert_eq!(weights.format, WeightFormat::Rwgt);
+        assert_eq!(weights.values(), [12., -3.5]);
+        assert_eq!(weights.get("1002"), Some(-3.5));
+        assert_eq!(weights.get("1003"), None);
+        let formatted = weights.to_string();
+        assert_eq!(Weights::from_rwgt(&first_tag(&formatted)).unwrap(), weights);
+    }
+
+    #[test]
+    fn compact() {
+        let ids = ["1001".to_owned(), "1002".to_owned()];
+        let text = "<weights> 12 -3.5 7 </weights>";
+        let weights = Weights::from_compact(&first_tag(text), &ids).unwrap();
+        assert_eq!(weights.format, WeightFormat::Compact);
+        assert_eq!(weights.get("1002"), Some(-3.5));
+        assert_eq!(weights.weights[2], Weight { id: None, value: 7. });
+        let formatted = weights.to_string();
+        assert_eq!(Weights::from_compact(&first_tag(&formatted), &ids).unwrap(), weights);
+        assert!(Weights::from_compact(&first_tag("<weights>1 x</weights>"), &ids).is_err());
+    }
+}
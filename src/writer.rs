@@ -0,0 +1,968 @@
+use std::borrow::{Borrow, Cow};
+use std::error;
+use std::fmt;
+use std::mem;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use file;
+use recompute::CrossSectionSums;
+use reweight::{self, WeightHook};
+use {Attributes, Block, GeneratorInfo, Reader, Weights, HEPEUP, HEPRUP};
+use {EVENTGROUP_END, EVENT_END, INIT_END, LHEF_LAST_LINE, LHEF_TAG};
+
+/// Number of bytes at the end of a file searched for the last line
+/// when appending
+const TAIL_SIZE: u64 = 64 * 1024;
+
+/// Width of the cross sections in an init block that is rewritten
+/// later, enough for any `f64` in scientific notation
+const PADDED_WIDTH: usize = 24;
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+enum WriterState {
+    ExpectingHeaderOrInit,
+    ExpectingEvent,
+    Finished,
+}
+
+/// Writer for the LHEF format
+///
+/// The output has to be written in order: first an optional header,
+/// then the run information, then the events.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # let heprup: lhef::HEPRUP = unimplemented!();
+/// # let hepeup: lhef::HEPEUP = unimplemented!();
+/// let file = std::fs::File::create("events.lhe").unwrap();
+/// let file = std::io::BufWriter::new(file);
+/// let mut writer = lhef::Writer::new(file, "1.0").unwrap();
+/// writer.header("<!-- a comment -->").unwrap();
+/// writer.heprup(&heprup).unwrap();
+/// writer.event(&hepeup).unwrap();
+/// writer.finish().unwrap();
+/// ```
+pub struct Writer<Stream: Write> {
+    stream: Stream,
+    state: WriterState,
+    weight_hook: Option<WeightHook>,
+    cross_sections: Option<CrossSectionSums<Stream>>,
+    generators: Vec<GeneratorInfo>,
+    weight_ids: Option<Vec<String>>,
+    event_number: Option<u64>,
+    batch: Vec<u8>,
+}
+
+impl<Stream: Write> Writer<Stream> {
+    /// Create a new LHEF writer for the given format version
+    ///
+    /// Supported versions are "1.0", "2.0", and "3.0".
+    pub fn new(stream: Stream, version: &str) -> Result<Writer<Stream>, Box<dyn error::Error>> {
+        Self::with_root_attributes(stream, version, &Attributes::new())
+    }
+
+    /// Create a new LHEF writer with additional attributes of the
+    /// `<LesHouchesEvents>` tag
+    ///
+    /// The attributes are written after the version, in order. This
+    /// can be used to keep the root tag of an input file intact, see
+    /// `Reader::root_attributes`. A `version` attribute in
+    /// `attributes` is ignored.
+    pub fn with_root_attributes(
+        mut stream: Stream,
+        version: &str,
+        attributes: &Attributes,
+    ) -> Result<Writer<Stream>, Box<dyn error::Error>> {
+        match version {
+            "1.0" | "2.0" | "3.0" => {}
+            _ => {
+                return Err(Box::new(WriteError::UnsupportedVersion(
+                    version.to_owned(),
+                )))
+            }
+        };
+        let mut root = Attributes::new();
+        root.set("version", version);
+        for (key, value) in attributes.iter().filter(|&(key, _)| key != "version") {
+            root.set(key, value);
+        }
+        writeln!(stream, "<{}{}>", LHEF_TAG, root)?;
+        Ok(Writer {
+            stream,
+            state: WriterState::ExpectingHeaderOrInit,
+            weight_hook: None,
+            cross_sections: None,
+            generators: Vec::new(),
+            weight_ids: None,
+            event_number: None,
+            batch: Vec::new(),
+        })
+    }
+
+    /// Write a header
+    ///
+    /// The header is written verbatim, so it should consist of comments
+    /// and `<header>` blocks. Headers have to be written before the run
+    /// information.
+    pub fn header(&mut self, header: &str) -> Result<(), Box<dyn error::Error>> {
+        self.expect(WriterState::ExpectingHeaderOrInit, "header")?;
+        write_with_newline(&mut self.stream, header)?;
+        Ok(())
+    }
+
+    /// Write the run information
+    pub fn heprup(&mut self, heprup: &HEPRUP) -> Result<(), Box<dyn error::Error>> {
+        self.expect(WriterState::ExpectingHeaderOrInit, "init")?;
+        check_entries(
+            "NPRUP",
+            heprup.NPRUP,
+            &[
+                ("XSECUP", heprup.XSECUP.len()),
+                ("XERRUP", heprup.XERRUP.len()),
+                ("XMAXUP", heprup.XMAXUP.len()),
+                ("LPRUP", heprup.LPRUP.len()),
+            ],
+        )?;
+        let mut heprup = Cow::Borrowed(heprup);
+        if !self.generators.is_empty() {
+            let info = &mut heprup.to_mut().info;
+            if !info.is_empty() && !info.ends_with('\n') {
+                info.push('\n');
+            }
+            for generator in &self.generators {
+                info.push_str(&generator.to_string());
+                info.push('\n');
+            }
+        }
+        match self.cross_sections {
+            Some(ref mut sums) => {
+                let offset = (sums.seek)(&mut self.stream, SeekFrom::Current(0))?;
+                write_init(&mut self.stream, &heprup, PADDED_WIDTH)?;
+                sums.set_init(offset, &heprup);
+            }
+            None => write_init(&mut self.stream, &heprup, 0)?,
+        }
+        self.state = WriterState::ExpectingEvent;
+        Ok(())
+    }
+
+    /// Call `hook` with each event and its additional weights before
+    /// writing it
+    ///
+    /// The hook can add or modify weights, which only affects the
+    /// output. Events without weights are passed an empty set of
+    /// weights. If the hook returns an error, the event is not written
+    /// and the error is passed on. New weights should be declared in
+    /// the `<initrwgt>` block of the header.
+    pub fn set_weight_hook<F>(&mut self, hook: F)
+    where
+        F: FnMut(&HEPEUP, &mut Weights) -> Result<(), Box<dyn error::Error>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.weight_hook = Some(Box::new(hook));
+    }
+
+    /// Write an event
+    pub fn event(&mut self, hepeup: &HEPEUP) -> Result<(), Box<dyn error::Error>> {
+        self.expect(WriterState::ExpectingEvent, "event")?;
+        let event = self.prepare(hepeup, 0)?;
+        write_event(&mut self.stream, &event)?;
+        add_weight(&mut self.cross_sections, &event);
+        self.advance_event_number(1);
+        Ok(())
+    }
+
+    /// Write a group of correlated events into an `<eventgroup>`
+    pub fn event_group(
+        &mut self,
+        events: &[HEPEUP],
+    ) -> Result<(), Box<dyn error::Error>> {
+        self.expect(WriterState::ExpectingEvent, "eventgroup")?;
+        let events = events
+            .iter()
+            .enumerate()
+            .map(|(i, event)| self.prepare(event, i as u64))
+            .collect::<Result<Vec<_>, _>>()?;
+        write_event_group(&mut self.stream, &events)?;
+        events.iter().for_each(|event| add_weight(&mut self.cross_sections, event));
+        self.advance_event_number(events.len() as u64);
+        Ok(())
+    }
+
+    /// Write a batch of events
+    ///
+    /// The events are formatted into an internal buffer, which is then
+    /// written to the output in a single call. This is considerably
+    /// faster than writing the events one by one to outputs where each
+    /// write is expensive, like network or compressed streams. If any
+    /// event is rejected, for example by the weight hook, none of the
+    /// events are written.
+    pub fn write_events(&mut self, events: &[HEPEUP]) -> Result<(), Box<dyn error::Error>> {
+        self.expect(WriterState::ExpectingEvent, "event")?;
+        let mut batch = mem::take(&mut self.batch);
+        batch.clear();
+        let mut prepared = Vec::with_capacity(events.len());
+        for (i, event) in events.iter().enumerate() {
+            let event = self.prepare(event, i as u64)?;
+            write_event(&mut batch, &event)?;
+            prepared.push(event);
+        }
+        let result = self.stream.write_all(&batch);
+        self.batch = batch;
+        result?;
+        for event in &prepared {
+            add_weight(&mut self.cross_sections, event);
+        }
+        self.advance_event_number(prepared.len() as u64);
+        Ok(())
+    }
+
+    /// Write an event or a group of events
+    pub fn block(&mut self, block: &Block) -> Result<(), Box<dyn error::Error>> {
+        match *block {
+            Block::Event(ref event) => self.event(event),
+            Block::EventGroup(ref events) => self.event_group(events),
+        }
+    }
+
+    /// Close the output
+    ///
+    /// This is done automatically when the writer is dropped, but
+    /// calling `finish` explicitly allows handling errors.
+    pub fn finish(&mut self) -> Result<(), Box<dyn error::Error>> {
+        self.expect(WriterState::ExpectingEvent, "closing tag")?;
+        writeln!(self.stream, "{}", LHEF_LAST_LINE)?;
+        if let Some(sums) = self.cross_sections.take() {
+            rewrite_init(&mut self.stream, &sums)?;
+        }
+        self.stream.flush()?;
+        self.state = WriterState::Finished;
+        Ok(())
+    }
+
+    /// Add `<generator>` tags to the init block
+    pub(crate) fn add_generators(&mut self, generators: Vec<GeneratorInfo>) {
+        self.generators.extend(generators);
+    }
+
+    /// Require each event to have exactly the weights with the given
+    /// IDs, in this order
+    pub(crate) fn expect_weights(&mut self, ids: Vec<String>) {
+        self.weight_ids = Some(ids);
+    }
+
+    pub(crate) fn set_boxed_weight_hook(&mut self, hook: WeightHook) {
+        self.weight_hook = Some(hook);
+    }
+
+    /// Number the events consecutively, starting from `first`
+    pub(crate) fn number_events(&mut self, first: u64) {
+        self.event_number = Some(first);
+    }
+
+    /// Apply the weight hook and the event numbering, and check the
+    /// weights
+    ///
+    /// `position` is the position of the event among those written
+    /// together. The numbering only advances with
+    /// `advance_event_number` once the events have been written.
+    fn prepare<'a>(
+        &mut self,
+        event: &'a HEPEUP,
+        position: u64,
+    ) -> Result<Cow<'a, HEPEUP>, Box<dyn error::Error>> {
+        check_entries(
+            "NUP",
+            event.NUP,
+            &[
+                ("IDUP", event.IDUP.len()),
+                ("ISTUP", event.ISTUP.len()),
+                ("MOTHUP", event.MOTHUP.len()),
+                ("ICOLUP", event.ICOLUP.len()),
+                ("PUP", event.PUP.len()),
+                ("VTIMUP", event.VTIMUP.len()),
+                ("SPINUP", event.SPINUP.len()),
+            ],
+        )?;
+        let mut event = Cow::Borrowed(event);
+        if let Some(ref mut hook) = self.weight_hook {
+            reweight::apply(hook, event.to_mut())?;
+        }
+        if let Some(number) = self.event_number {
+            if event.event_number().is_none() {
+                event.to_mut().set_event_number(number + position);
+            }
+        }
+        check_weights(&self.weight_ids, &event)?;
+        Ok(event)
+    }
+
+    fn advance_event_number(&mut self, events: u64) {
+        if let Some(ref mut number) = self.event_number {
+            *number += events;
+        }
+    }
+
+    fn expect(
+        &self,
+        state: WriterState,
+        what: &'static str,
+    ) -> Result<(), WriteError> {
+        if self.state == state {
+            Ok(())
+        } else {
+            Err(WriteError::BadState(self.state, what))
+        }
+    }
+}
+
+impl Writer<Box<dyn Write + Send>> {
+    /// Create the LHEF file at `path` for the given format version
+    ///
+    /// The output is buffered. If the file name ends in `.gz`, it is
+    /// compressed with gzip. The compressed stream is completed when
+    /// the writer is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # let heprup: lhef::HEPRUP = unimplemented!();
+    /// let mut writer = lhef::Writer::create("events.lhe.gz", "3.0").unwrap();
+    /// writer.heprup(&heprup).unwrap();
+    /// writer.finish().unwrap();
+    /// ```
+    pub fn create<P: AsRef<Path>>(path: P, version: &str) -> Result<Self, Box<dyn error::Error>> {
+        Writer::new(file::create(path.as_ref())?, version)
+    }
+}
+
+impl<Stream: Read + Write + Seek> Writer<BufWriter<Stream>> {
+    /// Create a writer appending events to an existing event file
+    ///
+    /// The header and init block of the file are parsed to check that
+    /// it is a valid event file. New events are then written after the
+    /// last event, replacing the closing `</LesHouchesEvents>` tag,
+    /// which is written again by `finish`. Files that end after a
+    /// complete event, for example because the generator was stopped,
+    /// can be extended as well. Content following the closing tag is
+    /// not supported.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # let hepeup: lhef::HEPEUP = unimplemented!();
+    /// let file = std::fs::OpenOptions::new()
+    ///     .read(true)
+    ///     .write(true)
+    ///     .open("events.lhe")
+    ///     .unwrap();
+    /// let mut writer = lhef::Writer::append(file).unwrap();
+    /// writer.event(&hepeup).unwrap();
+    /// writer.finish().unwrap();
+    /// ```
+    pub fn append(
+        mut stream: Stream,
+    ) -> Result<Writer<BufWriter<Stream>>, Box<dyn error::Error>> {
+        stream.seek(SeekFrom::Start(0))?;
+        Reader::new(BufReader::new(&mut stream))?;
+        let (pos, newline) = append_position(&mut stream)?;
+        stream.seek(SeekFrom::Start(pos))?;
+        let mut stream = BufWriter::new(stream);
+        if newline {
+            writeln!(stream)?;
+        }
+        Ok(Writer {
+            stream,
+            state: WriterState::ExpectingEvent,
+            weight_hook: None,
+            cross_sections: None,
+            generators: Vec::new(),
+            weight_ids: None,
+            event_number: None,
+            batch: Vec::new(),
+        })
+    }
+}
+
+impl<Stream: Write + Seek> Writer<Stream> {
+    /// Compute the cross sections from the written events
+    ///
+    /// When finishing, the cross sections `XSECUP` and their errors
+    /// `XERRUP` in the init block are replaced by the values obtained
+    /// from the weights of the written events. For `IDWTUP` ±1, ±2, and
+    /// ±4, the cross section of each process is the sum of the weights
+    /// of its events divided by the total number of events, and the
+    /// error is the standard error of this mean. Events inside an
+    /// `<eventgroup>` are counted individually. Unit weights (`IDWTUP`
+    /// ±3) do not determine the cross section, so the values passed to
+    /// `heprup` are kept.
+    ///
+    /// This has to be enabled before writing the run information.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # let heprup: lhef::HEPRUP = unimplemented!();
+    /// # let events: Vec<lhef::HEPEUP> = unimplemented!();
+    /// let file = std::fs::File::create("events.lhe").unwrap();
+    /// let file = std::io::BufWriter::new(file);
+    /// let mut writer = lhef::Writer::new(file, "1.0").unwrap();
+    /// writer.recompute_cross_sections().unwrap();
+    /// // the cross sections in heprup are only placeholders
+    /// writer.heprup(&heprup).unwrap();
+    /// for event in &events {
+    ///     writer.event(event).unwrap();
+    /// }
+    /// writer.finish().unwrap();
+    /// ```
+    pub fn recompute_cross_sections(&mut self) -> Result<(), Box<dyn error::Error>> {
+        self.expect(WriterState::ExpectingHeaderOrInit, "init")?;
+        self.cross_sections = Some(CrossSectionSums::new(Stream::seek));
+        Ok(())
+    }
+}
+
+/// Find the position at which further events can be written
+///
+/// This is the start of the closing tag, or the end of the last
+/// complete block if the closing tag is missing. In the latter case,
+/// the second return value indicates that a line break has to be
+/// written first.
+fn append_position<Stream: Read + Seek>(
+    stream: &mut Stream,
+) -> Result<(u64, bool), Box<dyn error::Error>> {
+    let end = stream.seek(SeekFrom::End(0))?;
+    let start = end.saturating_sub(TAIL_SIZE);
+    stream.seek(SeekFrom::Start(start))?;
+    let mut tail = Vec::new();
+    stream.read_to_end(&mut tail)?;
+    let content_end = tail
+        .iter()
+        .rposition(|c| !c.is_ascii_whitespace())
+        .map_or(0, |pos| pos + 1);
+    let line_start = match tail[..content_end].iter().rposition(|&c| c == b'\n') {
+        Some(pos) => pos + 1,
+        None if start == 0 => 0,
+        None => return Err(Box::new(WriteError::CannotAppend(String::new()))),
+    };
+    let last_line = String::from_utf8_lossy(&tail[line_start..content_end]);
+    match last_line.trim() {
+        LHEF_LAST_LINE => Ok((start + line_start as u64, false)),
+        EVENT_END | EVENTGROUP_END | INIT_END => Ok((start + content_end as u64, true)),
+        line => Err(Box::new(WriteError::CannotAppend(line.to_owned()))),
+    }
+}
+
+impl<Stream: Write> Drop for Writer<Stream> {
+    fn drop(&mut self) {
+        if self.state == WriterState::ExpectingEvent {
+            let _ = self.finish();
+        }
+    }
+}
+
+fn write_with_newline<W: Write>(stream: &mut W, text: &str) -> std::io::Result<()> {
+    stream.write_all(text.as_bytes())?;
+    if !text.is_empty() && !text.ends_with('\n') {
+        stream.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Check that the weights of an event match the declared ones
+fn check_weights(ids: &Option<Vec<String>>, event: &HEPEUP) -> Result<(), WriteError> {
+    let ids = match *ids {
+        Some(ref ids) => ids,
+        None => return Ok(()),
+    };
+    let weights = event.weights.as_ref().map_or(&[][..], |weights| &weights.weights[..]);
+    if weights.len() != ids.len() {
+        return Err(WriteError::WeightCount(ids.len(), weights.len()));
+    }
+    for (weight, id) in weights.iter().zip(ids) {
+        match weight.id {
+            Some(ref found) if found != id => {
+                return Err(WriteError::WeightId(id.clone(), found.clone()))
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Check that the entries have as many elements as given by `count`
+fn check_entries(
+    count: &'static str,
+    value: i32,
+    entries: &[(&'static str, usize)],
+) -> Result<(), WriteError> {
+    for &(name, len) in entries {
+        if value < 0 || value as usize != len {
+            return Err(WriteError::EntryCount(count, value, name, len));
+        }
+    }
+    Ok(())
+}
+
+fn add_weight<Stream>(sums: &mut Option<CrossSectionSums<Stream>>, event: &HEPEUP) {
+    if let Some(ref mut sums) = *sums {
+        sums.add(event)
+    }
+}
+
+/// Rewrite the init block with the cross sections computed from the
+/// written events
+fn rewrite_init<Stream: Write>(
+    stream: &mut Stream,
+    sums: &CrossSectionSums<Stream>,
+) -> std::io::Result<()> {
+    let (offset, heprup) = match sums.heprup() {
+        Some(init) => init,
+        None => return Ok(()),
+    };
+    let end = (sums.seek)(stream, SeekFrom::Current(0))?;
+    (sums.seek)(stream, SeekFrom::Start(offset))?;
+    write_init(stream, &heprup, PADDED_WIDTH)?;
+    (sums.seek)(stream, SeekFrom::Start(end))?;
+    Ok(())
+}
+
+/// Write the init block
+///
+/// The cross sections and their errors are padded to `width`
+/// characters, so that they can be replaced later.
+fn write_init<W: Write>(stream: &mut W, heprup: &HEPRUP, width: usize) -> std::io::Result<()> {
+    writeln!(stream, "{}", ::INIT_START)?;
+    writeln!(
+        stream,
+        "{} {} {:e} {:e} {} {} {} {} {} {}",
+        heprup.IDBMUP[0],
+        heprup.IDBMUP[1],
+        heprup.EBMUP[0],
+        heprup.EBMUP[1],
+        heprup.PDFGUP[0],
+        heprup.PDFGUP[1],
+        heprup.PDFSUP[0],
+        heprup.PDFSUP[1],
+        heprup.IDWTUP,
+        heprup.NPRUP
+    )?;
+    for i in 0..heprup.NPRUP as usize {
+        writeln!(
+            stream,
+            "{:<width$} {:<width$} {:e} {}",
+            format!("{:e}", heprup.XSECUP[i]),
+            format!("{:e}", heprup.XERRUP[i]),
+            heprup.XMAXUP[i],
+            heprup.LPRUP[i],
+            width = width
+        )?;
+    }
+    write_with_newline(stream, &heprup.info)?;
+    writeln!(stream, "{}", INIT_END)
+}
+
+fn write_event<W: Write>(stream: &mut W, hepeup: &HEPEUP) -> std::io::Result<()> {
+    writeln!(stream, "<{}{}>", ::EVENT_TAG, hepeup.attr)?;
+    writeln!(
+        stream,
+        "{} {} {:e} {:e} {:e} {:e}",
+        hepeup.NUP,
+        hepeup.IDRUP,
+        hepeup.XWGTUP,
+        hepeup.SCALUP,
+        hepeup.AQEDUP,
+        hepeup.AQCDUP
+    )?;
+    for i in 0..hepeup.NUP as usize {
+        let p = &hepeup.PUP[i];
+        writeln!(
+            stream,
+            "{} {} {} {} {} {} {:e} {:e} {:e} {:e} {:e} {:e} {:e}",
+            hepeup.IDUP[i],
+            hepeup.ISTUP[i],
+            hepeup.MOTHUP[i][0],
+            hepeup.MOTHUP[i][1],
+            hepeup.ICOLUP[i][0],
+            hepeup.ICOLUP[i][1],
+            p[0],
+            p[1],
+            p[2],
+            p[3],
+            p[4],
+            hepeup.VTIMUP[i],
+            hepeup.SPINUP[i]
+        )?;
+    }
+    write_with_newline(stream, &hepeup.info)?;
+    if let Some(ref comment_lines) = hepeup.comment_lines {
+        writeln!(stream, "{}", comment_lines)?;
+    }
+    if let Some(ref scales) = hepeup.scales {
+        writeln!(stream, "{}", scales)?;
+    }
+    if let Some(ref clustering) = hepeup.clustering {
+        writeln!(stream, "{}", clustering)?;
+    }
+    if let Some(ref mgrwt) = hepeup.mgrwt {
+        writeln!(stream, "{}", mgrwt)?;
+    }
+    if let Some(ref weights) = hepeup.weights {
+        writeln!(stream, "{}", weights)?;
+    }
+    writeln!(stream, "{}", ::EVENT_END)
+}
+
+fn write_event_group<W: Write, E: Borrow<HEPEUP>>(
+    stream: &mut W,
+    events: &[E],
+) -> std::io::Result<()> {
+    writeln!(stream, "<eventgroup>")?;
+    for event in events {
+        write_event(stream, event.borrow())?;
+    }
+    writeln!(stream, "{}", ::EVENTGROUP_END)
+}
+
+#[derive(Debug)]
+enum WriteError {
+    BadState(WriterState, &'static str),
+    UnsupportedVersion(String),
+    CannotAppend(String),
+    WeightCount(usize, usize),
+    WeightId(String, String),
+    EntryCount(&'static str, i32, &'static str, usize),
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::WriteError::*;
+        match *self {
+            BadState(state, what) => {
+                let expected = match state {
+                    WriterState::ExpectingHeaderOrInit => "a header or the init block",
+                    WriterState::ExpectingEvent => "an event or the closing tag",
+                    WriterState::Finished => "nothing, output is already finished",
+                };
+                write!(f, "Cannot write {}, expected {}", what, expected)
+            }
+            UnsupportedVersion(ref version) => write!(
+                f,
+                "Unsupported version {}, only 1.0, 2.0, 3.0 are supported",
+                version
+            ),
+            CannotAppend(ref line) => write!(
+                f,
+                "Cannot append events after '{}', expected the closing tag or a complete event",
+                line
+            ),
+            WeightCount(declared, found) => write!(
+                f,
+                "Event has {} weights, but {} were declared",
+                found, declared
+            ),
+            WeightId(ref declared, ref found) => write!(
+                f,
+                "Found event weight '{}' where '{}' was declared",
+                found, declared
+            ),
+            EntryCount(count, value, name, len) => write!(
+                f,
+                "{} is {}, but {} has {} entries",
+                count, value, name, len
+            ),
+        }
+    }
+}
+
+impl error::Error for WriteError {}
+
+#[cfg(test)]
+mod tests {
+    extern crate flate2;
+    use super::*;
+
+    use std::fs::File;
+    use std::io::BufReader;
+    use self::flate2::bufread::GzDecoder;
+    use Reader;
+
+    fn roundtrip(path: &str) {
+        let file = File::open(path).expect("file not found");
+        let reader = BufReader::new(GzDecoder::new(BufReader::new(file)));
+        let mut lhef = Reader::new(reader).unwrap();
+        let mut blocks = Vec::new();
+        while let Some(block) = lhef.block().unwrap() {
+            blocks.push(block);
+        }
+
+        let mut output = Vec::new();
+        {
+            let mut writer =
+                Writer::with_root_attributes(&mut output, lhef.version(), lhef.root_attributes())
+                    .unwrap();
+            writer.header(lhef.header()).unwrap();
+            writer.heprup(lhef.heprup()).unwrap();
+            for block in &blocks {
+                writer.block(block).unwrap();
+            }
+            writer.finish().unwrap();
+        }
+
+        let mut reread = Reader::new(output.as_slice()).unwrap();
+        assert_eq!(reread.version(), lhef.version());
+        assert_eq!(reread.root_attributes(), lhef.root_attributes());
+        assert_eq!(reread.header(), lhef.header());
+        assert_eq!(reread.heprup(), lhef.heprup());
+        for block in blocks {
+            assert_eq!(reread.block().unwrap(), Some(block));
+        }
+        assert_eq!(reread.block().unwrap(), None);
+    }
+
+    #[test]
+    fn roundtrip_2j() {
+        roundtrip("test_data/2j.lhe.gz");
+    }
+
+    #[test]
+    fn roundtrip_hejfog() {
+        roundtrip("test_data/HEJFOG.lhe.gz");
+    }
+
+    #[test]
+    fn append() {
+        use std::io::Cursor;
+        use test_util::MockEvent;
+
+        let first = MockEvent::dijet().with_weight(1.).build();
+        let second = MockEvent::drell_yan().with_weight(2.).build();
+        let mut output = Vec::new();
+        {
+            let mut writer = Writer::new(&mut output, "1.0").unwrap();
+            writer.header("<header>\n</header>").unwrap();
+            writer.heprup(&::test_util::SyntheticEvents::new().heprup()).unwrap();
+            writer.event(&first).unwrap();
+            writer.finish().unwrap();
+        }
+        let complete = output.clone();
+        let closing = LHEF_LAST_LINE.len() + 1;
+        let truncated = output[..output.len() - closing].to_vec();
+
+        for file in [complete, truncated] {
+            let mut file = Cursor::new(file);
+            {
+                let mut writer = Writer::append(&mut file).unwrap();
+                writer.event(&second).unwrap();
+                writer.event_group(&[first.clone(), second.clone()]).unwrap();
+                writer.finish().unwrap();
+            }
+            let mut reader = Reader::new(file.get_ref().as_slice()).unwrap();
+            assert_eq!(reader.header(), "<header>\n</header>\n");
+            assert_eq!(reader.event().unwrap().as_ref(), Some(&first));
+            assert_eq!(reader.event().unwrap().as_ref(), Some(&second));
+            assert_eq!(
+                reader.block().unwrap(),
+                Some(Block::EventGroup(vec![first.clone(), second.clone()]))
+            );
+            assert_eq!(reader.event().unwrap(), None);
+            assert_eq!(reader.trailing().unwrap(), Some(""));
+        }
+
+        let mut partial = output[..output.len() - closing - 3].to_vec();
+        assert!(Writer::append(Cursor::new(&mut partial)).is_err());
+        assert!(Writer::append(Cursor::new(b"<event>".to_vec())).is_err());
+    }
+
+    #[test]
+    fn batch() {
+        use test_util::SyntheticEvents;
+        use WriterBuilder;
+
+        let sample = SyntheticEvents::new().num_events(10);
+        let events: Vec<_> = sample.events().collect();
+        let mut output = Vec::new();
+        {
+            let mut writer = Writer::new(&mut output, "1.0").unwrap();
+            writer.header(&sample.header()).unwrap();
+            writer.heprup(&sample.heprup()).unwrap();
+            writer.write_events(&events[..3]).unwrap();
+            writer.write_events(&events[3..]).unwrap();
+            writer.write_events(&[]).unwrap();
+            writer.finish().unwrap();
+        }
+        assert_eq!(output, sample.to_bytes());
+
+        let mut output = Vec::new();
+        {
+            let mut writer = WriterBuilder::new()
+                .version("1.0")
+                .weight_hook(|event, _| {
+                    if event.XWGTUP < 0. {
+                        return Err("negative weight".into());
+                    }
+                    Ok(())
+                })
+                .build(&mut output)
+                .unwrap();
+            writer.heprup(&sample.heprup()).unwrap();
+            let mut batch = events[..3].to_vec();
+            batch[2].XWGTUP = -1.;
+            assert!(writer.write_events(&batch).is_err());
+            writer.finish().unwrap();
+        }
+        let mut reader = Reader::new(output.as_slice()).unwrap();
+        assert_eq!(reader.event().unwrap(), None);
+    }
+
+    #[test]
+    fn recompute() {
+        use std::io::Cursor;
+        use test_util::{MockEvent, SyntheticEvents};
+
+        let mut heprup = SyntheticEvents::new().heprup();
+        heprup.NPRUP = 2;
+        heprup.XSECUP = vec![0., 1.];
+        heprup.XERRUP = vec![0., 1.];
+        heprup.XMAXUP = vec![3., 3.];
+        heprup.LPRUP = vec![1, 2];
+        let weights = [(1, 1.), (1, 3.), (2, -1.), (7, 5.)];
+        let write = |heprup: &HEPRUP| {
+            let mut output = Cursor::new(Vec::new());
+            {
+                let mut writer = Writer::new(&mut output, "1.0").unwrap();
+                writer.recompute_cross_sections().unwrap();
+                writer.heprup(heprup).unwrap();
+                for &(id, weight) in &weights[..2] {
+                    let event = MockEvent::dijet().with_process_id(id).with_weight(weight);
+                    writer.event(&event.build()).unwrap();
+                }
+                let group: Vec<_> = weights[2..]
+                    .iter()
+                    .map(|&(id, weight)| {
+                        MockEvent::dijet().with_process_id(id).with_weight(weight).build()
+                    })
+                    .collect();
+                writer.event_group(&group).unwrap();
+                assert!(writer.recompute_cross_sections().is_err());
+                writer.finish().unwrap();
+            }
+            let output = output.into_inner();
+            let mut reader = Reader::new(output.as_slice()).unwrap();
+            let mut nevents = 0;
+            while reader.event().unwrap().is_some() {
+                nevents += 1;
+            }
+            assert_eq!(nevents, 4);
+            reader.heprup().clone()
+        };
+
+        let recomputed = write(&heprup);
+        assert_eq!(recomputed.XSECUP, vec![1., -0.25]);
+        let errors = [(10. / 4. - 1f64) / 4., (1. / 4. - 1f64 / 16.) / 4.];
+        assert_eq!(recomputed.XERRUP, vec![errors[0].sqrt(), errors[1].sqrt()]);
+        assert_eq!(recomputed.XMAXUP, heprup.XMAXUP);
+
+        heprup.IDWTUP = -3;
+        assert_eq!(write(&heprup), heprup);
+    }
+
+    #[test]
+    fn root_attributes() {
+        use std::io::Cursor;
+        use test_util::SyntheticEvents;
+
+        let mut attributes = Attributes::new();
+        attributes.set("xmlns", "urn:lhef");
+        attributes.set("version", "1.0");
+        attributes.set("generator", "\"quoted\"");
+        let mut output = Vec::new();
+        {
+            let mut writer = Writer::with_root_attributes(&mut output, "3.0", &attributes).unwrap();
+            writer.heprup(&SyntheticEvents::new().heprup()).unwrap();
+            writer.finish().unwrap();
+        }
+        let reader = Reader::new(output.as_slice()).unwrap();
+        assert_eq!(reader.version(), "3.0");
+        attributes.remove("version");
+        assert_eq!(reader.root_attributes(), &attributes);
+        let checkpoint = reader.checkpoint();
+        let resumed = Reader::resume(Cursor::new(output), checkpoint).unwrap();
+        assert_eq!(resumed.root_attributes(), &attributes);
+    }
+
+    #[test]
+    fn files() {
+        use std::env;
+        use std::fs;
+        use std::process;
+        use test_util::SyntheticEvents;
+        use ReaderBuilder;
+
+        let sample = SyntheticEvents::new().num_events(3).to_bytes();
+        for name in &["lhef_files_test.lhe", "lhef_files_test.lhe.gz"] {
+            let path = env::temp_dir().join(format!("{}_{}", process::id(), name));
+            {
+                let mut reader = Reader::new(sample.as_slice()).unwrap();
+                let mut writer = Writer::create(&path, reader.version()).unwrap();
+                writer.header(reader.header()).unwrap();
+                writer.heprup(reader.heprup()).unwrap();
+                while let Some(block) = reader.block().unwrap() {
+                    writer.block(&block).unwrap();
+                }
+                writer.finish().unwrap();
+            }
+            let compressed = fs::read(&path).unwrap().starts_with(&[0x1f, 0x8b]);
+            assert_eq!(compressed, name.ends_with(".gz"));
+            let builder = ReaderBuilder::new().background_decompression(true);
+            for mut reader in [Reader::open(&path).unwrap(), builder.open(&path).unwrap()] {
+                let mut expected = Reader::new(sample.as_slice()).unwrap();
+                assert_eq!(reader.heprup(), expected.heprup());
+                while let Some(block) = expected.block().unwrap() {
+                    assert_eq!(reader.block().unwrap(), Some(block));
+                }
+                assert_eq!(reader.block().unwrap(), None);
+            }
+            fs::remove_file(&path).unwrap();
+        }
+        assert!(Reader::open(env::temp_dir().join("lhef_files_test_missing.lhe")).is_err());
+    }
+
+    #[test]
+    fn wrong_order() {
+        let mut output = Vec::new();
+        let mut writer = Writer::new(&mut output, "3.0").unwrap();
+        assert!(writer.finish().is_err());
+        assert!(Writer::new(Vec::new(), "4.0").is_err());
+    }
+
+    #[test]
+    fn inconsistent_counts() {
+        use test_util::{MockEvent, SyntheticEvents};
+
+        let mut output = Vec::new();
+        let mut writer = Writer::new(&mut output, "3.0").unwrap();
+        let mut heprup = SyntheticEvents::new().heprup();
+        heprup.NPRUP += 1;
+        let err = writer.heprup(&heprup).unwrap_err();
+        assert_eq!(err.to_string(), "NPRUP is 2, but XSECUP has 1 entries");
+        heprup.NPRUP -= 1;
+        writer.heprup(&heprup).unwrap();
+
+        let mut event = MockEvent::drell_yan().build();
+        event.NUP += 1;
+        assert!(writer.event(&event).is_err());
+        assert!(writer.write_events(&[event.clone()]).is_err());
+        event.NUP = -1;
+        assert!(writer.event(&event).is_err());
+        event.NUP = 5;
+        event.SPINUP.pop();
+        let err = writer.event(&event).unwrap_err();
+        assert_eq!(err.to_string(), "NUP is 5, but SPINUP has 4 entries");
+        writer.finish().unwrap();
+    }
+}
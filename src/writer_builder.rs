@@ -0,0 +1,373 @@
+use std::collections::HashSet;
+use std::error;
+use std::fmt;
+use std::io::Write;
+use std::ops::Range;
+
+use initrwgt::INITRWGT_TAG;
+use reweight::WeightHook;
+use tags::{self, HEADER_TAG};
+use {Attributes, GeneratorInfo, InitRwgt, Provenance, Weights, Writer, HEPEUP};
+use {COMMENT_END, COMMENT_START, HEADER_END, HEADER_START};
+
+/// Builder for a `Writer` with a header assembled from parts
+///
+/// The header consists of the original banner, followed by the added
+/// comments and a `<header>` block with the weight declarations and
+/// the provenance record. The
+/// `<generator>` tags are added to the init block, where version 3.0
+/// expects them.
+///
+/// If weights are declared, each event written afterwards must carry
+/// exactly the declared weights in the order of declaration, otherwise
+/// writing the event fails. Weights added by a weight hook are taken
+/// into account.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # let reader: lhef::Reader<std::io::BufReader<std::fs::File>> = unimplemented!();
+/// # let initrwgt: lhef::InitRwgt = unimplemented!();
+/// let file = std::fs::File::create("events.lhe").unwrap();
+/// let file = std::io::BufWriter::new(file);
+/// let mut writer = lhef::WriterBuilder::new()
+///     .banner(reader.header())
+///     .comment("reweighted with my_tool")
+///     .generator(lhef::GeneratorInfo {
+///         name: Some("my_tool".to_owned()),
+///         version: Some("1.0".to_owned()),
+///         text: String::new(),
+///     })
+///     .initrwgt(initrwgt)
+///     .build(file)
+///     .unwrap();
+/// ```
+pub struct WriterBuilder {
+    version: String,
+    root_attributes: Attributes,
+    banner: String,
+    comments: Vec<String>,
+    generators: Vec<GeneratorInfo>,
+    initrwgt: Option<InitRwgt>,
+    provenance: Option<Provenance>,
+    first_event_number: Option<u64>,
+    weight_hook: Option<WeightHook>,
+}
+
+impl Default for WriterBuilder {
+    fn default() -> Self {
+        WriterBuilder {
+            version: "3.0".to_owned(),
+            root_attributes: Attributes::new(),
+            banner: String::new(),
+            comments: Vec::new(),
+            generators: Vec::new(),
+            initrwgt: None,
+            provenance: None,
+            first_event_number: None,
+            weight_hook: None,
+        }
+    }
+}
+
+impl WriterBuilder {
+    /// Create a builder for a version 3.0 writer with an empty header
+    pub fn new() -> WriterBuilder {
+        WriterBuilder::default()
+    }
+
+    /// Set the LHEF version of the output
+    pub fn version(mut self, version: &str) -> WriterBuilder {
+        self.version = version.to_owned();
+        self
+    }
+
+    /// Add attributes to the `<LesHouchesEvents>` tag
+    ///
+    /// See `Writer::with_root_attributes`.
+    pub fn root_attributes(mut self, attributes: Attributes) -> WriterBuilder {
+        self.root_attributes = attributes;
+        self
+    }
+
+    /// Start the header with an existing banner, for example the header
+    /// returned by `Reader::header`
+    ///
+    /// The banner is copied verbatim, except that any `<initrwgt>` tag
+    /// is removed if new weights are declared with `initrwgt`.
+    pub fn banner(mut self, banner: &str) -> WriterBuilder {
+        self.banner = banner.to_owned();
+        self
+    }
+
+    /// Add an XML comment to the header
+    ///
+    /// Since comments must not contain `--`, any occurrence is
+    /// replaced by `- -`.
+    pub fn comment(mut self, comment: &str) -> WriterBuilder {
+        self.comments.push(comment.replace("--", "- -"));
+        self
+    }
+
+    /// Add a `<generator>` tag to the init block
+    pub fn generator(mut self, generator: GeneratorInfo) -> WriterBuilder {
+        self.generators.push(generator);
+        self
+    }
+
+    /// Declare the additional event weights
+    pub fn initrwgt(mut self, initrwgt: InitRwgt) -> WriterBuilder {
+        self.initrwgt = Some(initrwgt);
+        self
+    }
+
+    /// Record the processing step in the header
+    ///
+    /// Records of earlier steps in the banner are kept.
+    pub fn provenance(mut self, provenance: Provenance) -> WriterBuilder {
+        self.provenance = Some(provenance);
+        self
+    }
+
+    /// Number the written events consecutively, starting from `first`
+    ///
+    /// The number is stored in an attribute of the `<event>` tag, see
+    /// `HEPEUP::event_number`. Events inside an `<eventgroup>` are
+    /// numbered individually. Events that already have a number keep
+    /// it, but still advance the count.
+    pub fn number_events(mut self, first: u64) -> WriterBuilder {
+        self.first_event_number = Some(first);
+        self
+    }
+
+    /// Call `hook` with each event and its additional weights before
+    /// writing it
+    ///
+    /// See `Writer::set_weight_hook`.
+    pub fn weight_hook<F>(mut self, hook: F) -> WriterBuilder
+    where
+        F: FnMut(&HEPEUP, &mut Weights) -> Result<(), Box<dyn error::Error>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.weight_hook = Some(Box::new(hook));
+        self
+    }
+
+    /// The assembled header
+    pub fn header(&self) -> String {
+        let mut header = match self.initrwgt {
+            Some(_) => remove_initrwgt(&self.banner),
+            None => self.banner.clone(),
+        };
+        if !header.is_empty() && !header.ends_with('\n') {
+            header.push('\n');
+        }
+        for comment in &self.comments {
+            header.push_str(&format!("{}\n{}\n{}\n", COMMENT_START, comment, COMMENT_END));
+        }
+        if self.initrwgt.is_none() && self.provenance.is_none() {
+            return header;
+        }
+        header.push_str(HEADER_START);
+        header.push('\n');
+        if let Some(ref initrwgt) = self.initrwgt {
+            header.push_str(&format!("{}\n", initrwgt));
+        }
+        if let Some(ref provenance) = self.provenance {
+            header.push_str(&format!("{}\n", provenance));
+        }
+        header.push_str(HEADER_END);
+        header.push('\n');
+        header
+    }
+
+    /// Create a writer, writing the header to `stream`
+    pub fn build<Stream: Write>(
+        self,
+        stream: Stream,
+    ) -> Result<Writer<Stream>, Box<dyn error::Error>> {
+        let ids = match self.initrwgt {
+            Some(ref initrwgt) => Some(declared_ids(initrwgt)?),
+            None => None,
+        };
+        let header = self.header();
+        let mut writer =
+            Writer::with_root_attributes(stream, &self.version, &self.root_attributes)?;
+        writer.header(&header)?;
+        writer.add_generators(self.generators);
+        if let Some(ids) = ids {
+            writer.expect_weights(ids);
+        }
+        if let Some(first) = self.first_event_number {
+            writer.number_events(first);
+        }
+        if let Some(hook) = self.weight_hook {
+            writer.set_boxed_weight_hook(hook);
+        }
+        Ok(writer)
+    }
+}
+
+/// The IDs of the declared weights, which have to be unique
+fn declared_ids(initrwgt: &InitRwgt) -> Result<Vec<String>, HeaderError> {
+    let ids: Vec<_> = initrwgt.weights().iter().map(|weight| weight.id.clone()).collect();
+    let mut seen = HashSet::new();
+    match ids.iter().find(|id| !seen.insert(id.as_str())) {
+        Some(id) => Err(HeaderError::DuplicateWeight(id.clone())),
+        None => Ok(ids),
+    }
+}
+
+/// Remove all `<initrwgt>` tags, including the ones inside `<header>`
+/// blocks
+fn remove_initrwgt(banner: &str) -> String {
+    let mut ranges = Vec::new();
+    collect_initrwgt(banner, 0, &mut ranges);
+    tags::remove_ranges(banner, &ranges)
+}
+
+fn collect_initrwgt(text: &str, offset: usize, ranges: &mut Vec<Range<usize>>) {
+    for (range, tag) in tags::tags(text) {
+        if tag.name() == INITRWGT_TAG {
+            ranges.push(offset + range.start..offset + range.end);
+        } else if tag.name() == HEADER_TAG {
+            let content = tag.content();
+            let start = content.as_ptr() as usize - text.as_ptr() as usize;
+            collect_initrwgt(content, offset + start, ranges);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum HeaderError {
+    DuplicateWeight(String),
+}
+
+impl fmt::Display for HeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::HeaderError::*;
+        match *self {
+            DuplicateWeight(ref id) => write!(f, "Weight '{}' is declared twice", id),
+        }
+    }
+}
+
+impl error::Error for HeaderError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_util::{MockEvent, SyntheticEvents};
+    use {Attributes, Reader, WeightEntry, WeightInfo};
+
+    const BANNER: &str = "<!--
+original comment
+-->
+<header>
+<MGVersion>3.5.0</MGVersion>
+<initrwgt>
+<weight id=\"old\">old weight</weight>
+</initrwgt>
+</header>
+";
+
+    fn initrwgt(ids: &[&str]) -> InitRwgt {
+        InitRwgt {
+            entries: ids
+                .iter()
+                .map(|&id| {
+                    WeightEntry::Weight(WeightInfo {
+                        id: id.to_owned(),
+                        attr: Attributes::new(),
+                        description: String::new(),
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn compose() {
+        let generator = GeneratorInfo {
+            name: Some("test".to_owned()),
+            version: Some("0.1".to_owned()),
+            text: String::new(),
+        };
+        let builder = WriterBuilder::new()
+            .banner(BANNER)
+            .comment("added -- by test")
+            .generator(generator.clone())
+            .initrwgt(initrwgt(&["a", "b"]))
+            .weight_hook(|event, weights| {
+                weights.set("b", 2. * event.XWGTUP);
+                Ok(())
+            });
+        let header = builder.header();
+        assert!(header.starts_with("<!--\noriginal comment\n-->\n<header>\n<MGVersion>"));
+        assert!(!header.contains("old"));
+        assert!(header.contains("<!--\nadded - - by test\n-->\n"));
+
+        let mut output = Vec::new();
+        {
+            let mut writer = builder.build(&mut output).unwrap();
+            writer.heprup(&SyntheticEvents::new().heprup()).unwrap();
+            let event = MockEvent::dijet().with_extra_weight("a", 1.).build();
+            writer.event(&event).unwrap();
+            let event = MockEvent::dijet().with_extra_weight("c", 1.).build();
+            assert!(writer.event(&event).is_err());
+            assert!(writer.event(&MockEvent::dijet().build()).is_err());
+            writer.finish().unwrap();
+        }
+
+        let mut reader = Reader::new(output.as_slice()).unwrap();
+        assert_eq!(reader.header(), header);
+        assert_eq!(reader.generators(), [generator]);
+        let ids: Vec<_> = reader.initrwgt().unwrap().weights().iter().map(|w| &w.id).collect();
+        assert_eq!(ids, ["a", "b"]);
+        let event = reader.event().unwrap().unwrap();
+        assert_eq!(event.weights.unwrap().values(), [1., 2. * event.XWGTUP]);
+        assert_eq!(reader.event().unwrap(), None);
+    }
+
+    #[test]
+    fn event_numbers() {
+        let mut output = Vec::new();
+        {
+            let mut writer = WriterBuilder::new()
+                .number_events(1)
+                .weight_hook(|event, _| match event.XWGTUP < 0. {
+                    true => Err("negative weight".into()),
+                    false => Ok(()),
+                })
+                .build(&mut output)
+                .unwrap();
+            writer.heprup(&SyntheticEvents::new().heprup()).unwrap();
+            writer.event(&MockEvent::dijet().build()).unwrap();
+            let mut event = MockEvent::dijet().build();
+            event.set_event_number(17);
+            let group = [event, MockEvent::dijet().build()];
+            writer.event_group(&group).unwrap();
+            // rejected events do not use up numbers
+            let rejected = MockEvent::dijet().with_weight(-1.).build();
+            assert!(writer.event(&rejected).is_err());
+            let batch = [MockEvent::dijet().build(), rejected];
+            assert!(writer.write_events(&batch).is_err());
+            writer.write_events(&batch[..1]).unwrap();
+            writer.finish().unwrap();
+        }
+        let mut reader = Reader::new(output.as_slice()).unwrap();
+        let mut numbers = Vec::new();
+        while let Some(event) = reader.event().unwrap() {
+            numbers.push(event.event_number());
+        }
+        assert_eq!(numbers, [Some(1), Some(17), Some(3), Some(4)]);
+    }
+
+    #[test]
+    fn duplicate_weight() {
+        let builder = WriterBuilder::new().initrwgt(initrwgt(&["a", "a"]));
+        assert!(builder.build(Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,132 @@
+use std::error;
+use std::fmt;
+
+use tags::{self, Tag};
+use {parse, ParseError};
+
+pub(crate) const XSECINFO_TAG: &str = "xsecinfo";
+
+/// Cross section information from the LHEF 3.0 `<xsecinfo>` tag
+///
+/// When present, this should be preferred over the cross sections in
+/// `HEPRUP`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct XSecInfo {
+    /// Number of events in the file
+    pub neve: u64,
+    /// Number of attempts needed to generate the events
+    pub ntries: Option<u64>,
+    /// Total cross section in pb
+    pub totxsec: f64,
+    /// Error of the total cross section in pb
+    pub xsecerr: Option<f64>,
+    /// Maximum event weight
+    pub maxweight: Option<f64>,
+    /// Mean event weight
+    pub meanweight: Option<f64>,
+    /// Whether there are events with negative weights
+    pub negweights: bool,
+    /// Whether the events carry additional weights
+    pub varweights: bool,
+    /// Name of the weight this information refers to
+    pub weightname: Option<String>,
+}
+
+impl XSecInfo {
+    fn from_tag(tag: &Tag) -> Result<XSecInfo, Box<dyn error::Error>> {
+        let attr = |name| tag.attribute(name).map(str::trim);
+        let optional_f64 = |name| attr(name).map(|v| parse::<f64>(name, Some(v))).transpose();
+        Ok(XSecInfo {
+            neve: parse("neve", attr("neve"))?,
+            ntries: attr("ntries").map(|v| parse("ntries", Some(v))).transpose()?,
+            totxsec: parse("totxsec", attr("totxsec"))?,
+            xsecerr: optional_f64("xsecerr")?,
+            maxweight: optional_f64("maxweight")?,
+            meanweight: optional_f64("meanweight")?,
+            negweights: attr("negweights").map(parse_bool).transpose()?.unwrap_or(false),
+            varweights: attr("varweights").map(parse_bool).transpose()?.unwrap_or(false),
+            weightname: tag.attribute("weightname").map(|name| name.to_owned()),
+        })
+    }
+}
+
+pub(crate) fn parse_bool(text: &str) -> Result<bool, Box<dyn error::Error>> {
+    match text.to_lowercase().as_str() {
+        "yes" | "true" | "on" | "1" => Ok(true),
+        "no" | "false" | "off" | "0" => Ok(false),
+        _ => Err(Box::new(ParseError::ConversionError(text.to_owned()))),
+    }
+}
+
+/// Parse all `<xsecinfo>` tags in the optional run information
+pub(crate) fn parse_xsecinfo(info: &str) -> Result<Vec<XSecInfo>, Box<dyn error::Error>> {
+    tags::tags(info)
+        .filter(|(_, tag)| tag.name() == XSECINFO_TAG)
+        .map(|(_, tag)| XSecInfo::from_tag(&tag))
+        .collect()
+}
+
+/// Formats the information as a `<xsecinfo>` tag
+impl fmt::Display for XSecInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<{} neve=\"{}\"", XSECINFO_TAG, self.neve)?;
+        if let Some(ntries) = self.ntries {
+            write!(f, " ntries=\"{}\"", ntries)?;
+        }
+        write!(f, " totxsec=\"{:e}\"", self.totxsec)?;
+        let optional = [
+            ("xsecerr", self.xsecerr),
+            ("maxweight", self.maxweight),
+            ("meanweight", self.meanweight),
+        ];
+        for &(name, value) in &optional {
+            if let Some(value) = value {
+                write!(f, " {}=\"{:e}\"", name, value)?;
+            }
+        }
+        if self.negweights {
+            write!(f, " negweights=\"yes\"")?;
+        }
+        if self.varweights {
+            write!(f, " varweights=\"yes\"")?;
+        }
+        if let Some(ref weightname) = self.weightname {
+            write!(f, " weightname=\"{}\"", weightname)?;
+        }
+        write!(f, "/>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_format() {
+        let info = "<xsecinfo neve=\"10000\" totxsec=\"1.5e3\" maxweight=\"2.5\" \
+                    negweights=\"yes\" varweights='on'/>";
+        let xsecinfo = parse_xsecinfo(info).unwrap();
+        assert_eq!(
+            xsecinfo,
+            [XSecInfo {
+                neve: 10000,
+                ntries: None,
+                totxsec: 1500.,
+                xsecerr: None,
+                maxweight: Some(2.5),
+                meanweight: None,
+                negweights: true,
+                varweights: true,
+                weightname: None,
+            }]
+        );
+        assert_eq!(parse_xsecinfo(&xsecinfo[0].to_string()).unwrap(), xsecinfo);
+    }
+
+    #[test]
+    fn missing_required() {
+        assert!(parse_xsecinfo("<xsecinfo neve=\"1\"/>").is_err());
+        assert!(parse_xsecinfo("<xsecinfo neve=\"1\" totxsec=\"1\" negweights=\"maybe\"/>").is_err());
+    }
+}